@@ -0,0 +1,203 @@
+use roxmltree::{Document, Node};
+
+pub const DAV: &str = "DAV:";
+pub const CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
+pub const CALENDARSERVER: &str = "http://calendarserver.org/ns/";
+pub const APPLE_ICAL: &str = "http://apple.com/ns/ical/";
+
+// Prefixes declared on every multistatus, so property values can use them
+const PREFIXES: [(&str, &str); 4] = [("d", DAV), ("c", CALDAV), ("cs", CALENDARSERVER), ("ical", APPLE_ICAL)];
+
+/// A property as (namespace, local name).
+pub type PropName = (String, String);
+
+/// A property this server can answer, with its value as XML.
+pub struct Prop {
+    pub namespace: &'static str,
+    pub name: &'static str,
+    pub value: String,
+}
+
+impl Prop {
+    pub fn new(namespace: &'static str, name: &'static str, value: impl Into<String>) -> Self {
+        Self { namespace, name, value: value.into() }
+    }
+
+    fn is(&self, (namespace, name): &PropName) -> bool {
+        self.namespace == namespace && self.name == name
+    }
+}
+
+/// Which properties a PROPFIND or REPORT asked for.
+pub enum PropRequest {
+    /// `allprop`, `propname` or an empty body
+    All,
+    Names(Vec<PropName>),
+}
+
+pub enum Report {
+    Multiget { props: PropRequest, hrefs: Vec<String> },
+    /// Filters are ignored beyond the component: every task matches
+    Query { props: PropRequest, todos: bool },
+    /// Not advertised in `supported-report-set`, answered with 403
+    Unsupported,
+}
+
+#[derive(Debug)]
+pub struct InvalidXml;
+
+fn parse(body: &[u8]) -> Result<Option<Document<'_>>, InvalidXml> {
+    let text = std::str::from_utf8(body).map_err(|_| InvalidXml)?;
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    Document::parse(text).map(Some).map_err(|_| InvalidXml)
+}
+
+fn is(node: &Node, namespace: &str, name: &str) -> bool {
+    node.is_element() && node.tag_name().namespace() == Some(namespace) && node.tag_name().name() == name
+}
+
+// Helper: Children of the first `<d:prop>` under `root`
+fn requested_props(root: Node) -> PropRequest {
+    let Some(prop) = root.children().find(|n| is(n, DAV, "prop")) else {
+        return PropRequest::All;
+    };
+
+    PropRequest::Names(
+        prop.children()
+            .filter(Node::is_element)
+            .map(|n| {
+                let tag = n.tag_name();
+                (tag.namespace().unwrap_or_default().to_string(), tag.name().to_string())
+            })
+            .collect(),
+    )
+}
+
+pub fn parse_propfind(body: &[u8]) -> Result<PropRequest, InvalidXml> {
+    Ok(match parse(body)? {
+        Some(doc) => requested_props(doc.root_element()),
+        None => PropRequest::All,
+    })
+}
+
+pub fn parse_report(body: &[u8]) -> Result<Report, InvalidXml> {
+    let doc = parse(body)?.ok_or(InvalidXml)?;
+    let root = doc.root_element();
+
+    if is(&root, CALDAV, "calendar-multiget") {
+        let hrefs = root
+            .children()
+            .filter(|n| is(n, DAV, "href"))
+            .filter_map(|n| n.text())
+            .map(|href| href.trim().to_string())
+            .collect();
+        return Ok(Report::Multiget { props: requested_props(root), hrefs });
+    }
+
+    if is(&root, CALDAV, "calendar-query") {
+        let components: Vec<&str> = root
+            .descendants()
+            .filter(|n| is(n, CALDAV, "comp-filter"))
+            .filter_map(|n| n.attribute("name"))
+            .collect();
+        // Only the VCALENDAR filter (or none) still means "everything"
+        let todos = components.iter().all(|c| c.eq_ignore_ascii_case("VCALENDAR"))
+            || components.iter().any(|c| c.eq_ignore_ascii_case("VTODO"));
+        return Ok(Report::Query { props: requested_props(root), todos });
+    }
+
+    Ok(Report::Unsupported)
+}
+
+/// `displayname` and `calendar-color` from a MKCALENDAR body, if present.
+pub fn parse_mkcalendar(body: &[u8]) -> Result<(Option<String>, Option<String>), InvalidXml> {
+    let Some(doc) = parse(body)? else {
+        return Ok((None, None));
+    };
+    let text = |namespace: &str, name: &str| {
+        doc.descendants()
+            .find(|n| is(n, namespace, name))
+            .and_then(|n| n.text())
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+    };
+    Ok((text(DAV, "displayname"), text(APPLE_ICAL, "calendar-color")))
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+pub fn href(path: &str) -> String {
+    format!("<d:href>{}</d:href>", escape(path))
+}
+
+/// Body of a 207 Multi-Status response.
+pub struct Multistatus {
+    out: String,
+}
+
+impl Default for Multistatus {
+    fn default() -> Self {
+        let namespaces: String = PREFIXES
+            .iter()
+            .map(|(prefix, namespace)| format!(" xmlns:{}=\"{}\"", prefix, namespace))
+            .collect();
+        Self {
+            out: format!("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<d:multistatus{}>", namespaces),
+        }
+    }
+}
+
+impl Multistatus {
+    /// One resource: requested properties we have under 200, the rest under 404. With
+    /// `All`, everything but the properties clients must ask for by name.
+    pub fn response(&mut self, path: &str, props: Vec<Prop>, request: &PropRequest) {
+        self.out.push_str(&format!("<d:response>{}", href(path)));
+
+        let (found, missing): (Vec<&Prop>, Vec<&PropName>) = match request {
+            PropRequest::All => (props.iter().filter(|p| p.name != "calendar-data").collect(), Vec::new()),
+            PropRequest::Names(names) => (
+                props.iter().filter(|p| names.iter().any(|n| p.is(n))).collect(),
+                names.iter().filter(|n| !props.iter().any(|p| p.is(n))).collect(),
+            ),
+        };
+
+        if !found.is_empty() {
+            self.out.push_str("<d:propstat><d:prop>");
+            for prop in found {
+                let prefix = PREFIXES.iter().find(|(_, ns)| *ns == prop.namespace).map_or("d", |(p, _)| p);
+                if prop.value.is_empty() {
+                    self.out.push_str(&format!("<{}:{}/>", prefix, prop.name));
+                } else {
+                    self.out.push_str(&format!("<{0}:{1}>{2}</{0}:{1}>", prefix, prop.name, prop.value));
+                }
+            }
+            self.out.push_str("</d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat>");
+        }
+
+        if !missing.is_empty() {
+            self.out.push_str("<d:propstat><d:prop>");
+            for (namespace, name) in missing {
+                self.out.push_str(&format!("<x:{} xmlns:x=\"{}\"/>", name, escape(namespace)));
+            }
+            self.out.push_str("</d:prop><d:status>HTTP/1.1 404 Not Found</d:status></d:propstat>");
+        }
+
+        self.out.push_str("</d:response>");
+    }
+
+    pub fn not_found(&mut self, path: &str) {
+        self.out.push_str(&format!(
+            "<d:response>{}<d:status>HTTP/1.1 404 Not Found</d:status></d:response>",
+            href(path)
+        ));
+    }
+
+    pub fn finish(mut self) -> String {
+        self.out.push_str("</d:multistatus>");
+        self.out
+    }
+}
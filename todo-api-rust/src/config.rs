@@ -1,5 +1,11 @@
 use std::env;
 
+use crate::logging::LogFormat;
+use crate::mail::TransportKind;
+use crate::oidc::{self, OidcProvider};
+use crate::password::HashParams;
+use crate::reminders::ReminderChannel;
+
 #[derive(Clone)]
 pub struct Config {
     pub database_url: String,
@@ -7,38 +13,131 @@ pub struct Config {
     pub jwt_expiration: i64,
     pub host: String,
     pub port: u16,
+    pub public_url: String,
+    pub invite_url: String,
     pub smtp_host: String,
     pub smtp_port: u16,
     pub smtp_user: String,
     pub smtp_pass: String,
     pub smtp_from: String,
+    pub log_format: LogFormat,
+    pub mail_transport: TransportKind,
+    pub mail_dir: String,
+    pub mail_templates_dir: Option<String>,
+    pub account_deletion_grace_days: i64,
+    pub password_hashing: HashParams,
+    pub password_blocklist: Option<String>,
+    pub oidc_providers: Vec<OidcProvider>,
+    pub webhook_allow_private_targets: bool,
+    pub inbound_email_domain: String,
+    pub inbound_email_secret: Option<String>,
+    pub inbound_smtp_listen: Option<String>,
+    pub inbound_email_max_bytes: usize,
+    pub reminder_channels: Vec<ReminderChannel>,
+    pub vapid_private_key: Option<String>,
+    pub vapid_subject: String,
+    pub push_test_sink: bool,
 }
 
 impl Config {
     pub fn from_env() -> Self {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    /// Builds the configuration from `var`, which returns a variable's value when set.
+    /// Tests pass a fixed table here instead of touching the process environment.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        // Numeric variable, falling back to `default` when unset or invalid
+        let env_number = |name: &str, default: u32| -> u32 {
+            var(name).and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        let port = var("PORT")
+            .unwrap_or_else(|| "8080".to_string())
+            .parse()
+            .unwrap_or(8080);
+        let smtp_user = var("SMTP_USER").unwrap_or_default();
+        let defaults = HashParams::default();
+        // Base for links sent by email and OIDC redirects, e.g. https://api.example.com
+        let public_url = var("PUBLIC_URL")
+            .map(|url| url.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| format!("http://localhost:{}", port));
+        // Without SMTP credentials mail goes to stdout, as in development
+        let mail_transport = var("MAIL_TRANSPORT")
+            .and_then(|t| t.parse().ok())
+            .unwrap_or(if smtp_user.is_empty() { TransportKind::Stdout } else { TransportKind::Smtp });
+
         Self {
-            database_url: env::var("DATABASE_URL")
+            database_url: var("DATABASE_URL")
                 .expect("DATABASE_URL must be set"),
-            jwt_secret: env::var("JWT_SECRET")
-                .unwrap_or_else(|_| "default_secret_change_me".to_string()),
-            jwt_expiration: env::var("JWT_EXPIRATION")
-                .unwrap_or_else(|_| "2592000".to_string())
+            jwt_secret: var("JWT_SECRET")
+                .unwrap_or_else(|| "default_secret_change_me".to_string()),
+            jwt_expiration: var("JWT_EXPIRATION")
+                .unwrap_or_else(|| "2592000".to_string())
                 .parse()
                 .unwrap_or(2592000),
-            host: env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
-            port: env::var("PORT")
-                .unwrap_or_else(|_| "8080".to_string())
-                .parse()
-                .unwrap_or(8080),
-            smtp_host: env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string()),
-            smtp_port: env::var("SMTP_PORT")
-                .unwrap_or_else(|_| "587".to_string())
+            host: var("HOST").unwrap_or_else(|| "0.0.0.0".to_string()),
+            port,
+            oidc_providers: oidc::providers_from_vars(&var, &public_url),
+            // Only for local testing: lets webhooks reach localhost and private networks
+            webhook_allow_private_targets: var("WEBHOOK_ALLOW_PRIVATE_TARGETS")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            // `none` turns the reminder dispatcher off
+            reminder_channels: var("REMINDER_CHANNELS")
+                .map(|channels| ReminderChannel::parse_list(&channels))
+                .unwrap_or_else(|| ReminderChannel::ALL.to_vec()),
+            vapid_private_key: var("VAPID_PRIVATE_KEY").filter(|k| !k.is_empty()),
+            // Push services contact this if the server misbehaves; a URL or mailto:
+            vapid_subject: var("VAPID_SUBJECT")
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| public_url.clone()),
+            // Only for local testing: serves a fake push service under /push/sink
+            push_test_sink: var("PUSH_TEST_SINK")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            // Where invitation emails send people, e.g. a page with the app downloads
+            invite_url: var("INVITE_URL")
+                .filter(|url| !url.is_empty())
+                .unwrap_or_else(|| public_url.clone()),
+            public_url,
+            inbound_email_domain: var("INBOUND_EMAIL_DOMAIN")
+                .unwrap_or_else(|| "inbox.localhost".to_string()),
+            inbound_email_secret: var("INBOUND_EMAIL_SECRET").filter(|s| !s.is_empty()),
+            inbound_smtp_listen: var("INBOUND_SMTP_LISTEN").filter(|a| !a.is_empty()),
+            inbound_email_max_bytes: env_number("INBOUND_EMAIL_MAX_BYTES", 10 * 1024 * 1024) as usize,
+            smtp_host: var("SMTP_HOST").unwrap_or_else(|| "localhost".to_string()),
+            smtp_port: var("SMTP_PORT")
+                .unwrap_or_else(|| "587".to_string())
                 .parse()
                 .unwrap_or(587),
-            smtp_user: env::var("SMTP_USER").unwrap_or_default(),
-            smtp_pass: env::var("SMTP_PASS").unwrap_or_default(),
-            smtp_from: env::var("SMTP_FROM")
-                .unwrap_or_else(|_| "MyTudo <noreply@localhost>".to_string()),
+            smtp_user,
+            smtp_pass: var("SMTP_PASS").unwrap_or_default(),
+            smtp_from: var("SMTP_FROM")
+                .unwrap_or_else(|| "MyTudo <noreply@localhost>".to_string()),
+            mail_transport,
+            mail_dir: var("MAIL_DIR").unwrap_or_else(|| "mail".to_string()),
+            mail_templates_dir: var("MAIL_TEMPLATES_DIR").filter(|d| !d.is_empty()),
+            account_deletion_grace_days: var("ACCOUNT_DELETION_GRACE_DAYS")
+                .unwrap_or_else(|| "7".to_string())
+                .parse()
+                .unwrap_or(7),
+            password_hashing: HashParams {
+                memory_kib: env_number("ARGON2_MEMORY_KIB", defaults.memory_kib),
+                iterations: env_number("ARGON2_ITERATIONS", defaults.iterations),
+                parallelism: env_number("ARGON2_PARALLELISM", defaults.parallelism),
+            },
+            password_blocklist: var("PASSWORD_BLOCKLIST").filter(|p| !p.is_empty()),
+            log_format: var("LOG_FORMAT")
+                .and_then(|f| f.parse().ok())
+                .unwrap_or_default(),
         }
     }
 }
+
+#[cfg(test)]
+impl Config {
+    /// Defaults plus the required `DATABASE_URL`, for tests that never reach the database.
+    pub fn for_tests() -> Self {
+        Self::from_vars(|name| (name == "DATABASE_URL").then(|| "mysql://test@localhost/test".to_string()))
+    }
+}
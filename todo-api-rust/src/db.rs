@@ -1,3 +0,0 @@
-use sqlx::MySqlPool;
-
-pub type DbPool = MySqlPool;
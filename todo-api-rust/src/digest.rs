@@ -0,0 +1,293 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc};
+use minijinja::context;
+use serde::Serialize;
+use sqlx::{FromRow, MySqlPool};
+
+use crate::config::Config;
+use crate::i18n::{self, Locale};
+use crate::mail::{self, MailKind};
+use crate::models::UserSettings;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+const BATCH_SIZE: i64 = 100;
+// A digest this late describes a day that is mostly over; it is skipped, not sent
+const MAX_LATENESS_HOURS: i64 = 6;
+// Enough for a summary; the rest is in the app
+const MAX_TASKS: i64 = 100;
+
+// Someone whose digest is due, with the settings it is written in
+#[derive(Debug, FromRow)]
+struct Recipient {
+    user_id: String,
+    username: String,
+    email: String,
+    digest_next_at: DateTime<Utc>,
+    #[sqlx(flatten)]
+    settings: UserSettings,
+}
+
+#[derive(Debug, FromRow, Serialize)]
+struct PendingTask {
+    title: String,
+    list: String,
+    #[serde(skip)]
+    due: DateTime<Utc>,
+}
+
+/// When the digest is next due after `after`: `default_reminder_time` in the user's time
+/// zone, today or tomorrow. A time skipped by a DST change moves to the next day.
+pub fn next_run(settings: &UserSettings, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let tz = settings.tz();
+    let today = after.with_timezone(&tz).date_naive();
+
+    (0..=2)
+        .filter_map(|days| today.checked_add_days(Days::new(days)))
+        .filter_map(|date| tz.from_local_datetime(&date.and_time(settings.default_reminder_time)).earliest())
+        .map(|at| at.with_timezone(&Utc))
+        .find(|at| *at > after)
+}
+
+/// Sends the daily digest to users who turned on `digest_email`: their overdue tasks and
+/// the ones due today, in their time zone and locale. `user_settings.digest_next_at` holds
+/// when each one is due; settings changes and every send move it forward.
+pub async fn run(pool: MySqlPool, config: Config) {
+    loop {
+        let sent = match send_batch(&pool, &config).await {
+            Ok(count) => count,
+            Err(e) => {
+                tracing::error!(error = %e, "Digest batch failed");
+                0
+            }
+        };
+
+        // A full batch likely means more is waiting
+        if sent < BATCH_SIZE as usize {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+async fn send_batch(pool: &MySqlPool, config: &Config) -> Result<usize, sqlx::Error> {
+    let now = Utc::now();
+
+    let due: Vec<Recipient> = sqlx::query_as(
+        r#"
+        SELECT u.id AS user_id, u.username, u.email, s.digest_next_at,
+               s.time_zone, s.locale, s.week_start, s.default_reminder_time, s.reminder_email,
+               s.digest_email, s.updated_at
+        FROM user_settings s
+        JOIN users u ON s.user_id = u.id
+        WHERE s.digest_email = 1 AND s.digest_next_at <= ?
+            AND u.is_verified = 1 AND u.deletion_scheduled_at IS NULL
+        ORDER BY s.digest_next_at
+        LIMIT ?
+        "#
+    )
+    .bind(now)
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    let count = due.len();
+    for recipient in &due {
+        // Another instance may have got there first, or the settings changed meanwhile
+        if !claim(pool, recipient, now).await? {
+            continue;
+        }
+        if recipient.digest_next_at < now - TimeDelta::hours(MAX_LATENESS_HOURS) {
+            tracing::info!(user_id = %recipient.user_id, "Skipped a digest that is too late");
+            continue;
+        }
+        send(pool, config, recipient).await;
+    }
+
+    Ok(count)
+}
+
+// Helper: Moves the digest to its next day, if it still is due at the time that was read.
+// `updated_at` is kept so the move doesn't look like a settings change.
+async fn claim(pool: &MySqlPool, recipient: &Recipient, now: DateTime<Utc>) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE user_settings SET digest_next_at = ?, updated_at = updated_at
+        WHERE user_id = ? AND digest_email = 1 AND digest_next_at = ?
+        "#
+    )
+    .bind(next_run(&recipient.settings, now))
+    .bind(&recipient.user_id)
+    .bind(recipient.digest_next_at)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Helper: Queues one digest. Failures are logged; the next one goes out tomorrow.
+async fn send(pool: &MySqlPool, config: &Config, recipient: &Recipient) {
+    let settings = &recipient.settings;
+    let tz = settings.tz();
+    // The day it was due, even when the digest goes out a little late
+    let today = recipient.digest_next_at.with_timezone(&tz).date_naive();
+    let start_of = |date: NaiveDate| {
+        tz.from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|dt| dt.with_timezone(&Utc))
+    };
+    let (Some(start), Some(end)) = (start_of(today), today.succ_opt().and_then(start_of)) else {
+        return;
+    };
+
+    // Same dates as the `due` filter of GET /tasks
+    let tasks: Vec<PendingTask> = match sqlx::query_as(
+        r#"
+        SELECT t.title, l.name AS list, COALESCE(t.due_at, t.reminder) AS due
+        FROM tasks t
+        JOIN todo_lists l ON t.list_id = l.id
+        WHERE l.user_id = ? AND t.deleted_at IS NULL AND l.deleted_at IS NULL AND t.completed = 0
+            AND COALESCE(t.due_at, t.reminder) < ?
+        ORDER BY due
+        LIMIT ?
+        "#
+    )
+    .bind(&recipient.user_id)
+    .bind(end)
+    .bind(MAX_TASKS)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to load tasks for a digest");
+            return;
+        }
+    };
+
+    // Nothing pending, nothing to summarize
+    if tasks.is_empty() {
+        return;
+    }
+
+    let (overdue, due_today): (Vec<PendingTask>, Vec<PendingTask>) =
+        tasks.into_iter().partition(|task| task.due < start);
+    let locale = Locale::from_tag(&settings.locale).unwrap_or(i18n::DEFAULT_LOCALE);
+    let context = context! {
+        name => &recipient.username,
+        overdue => overdue,
+        due_today => due_today,
+    };
+
+    if let Err(e) = mail::send(pool, config, MailKind::Digest, locale, &recipient.email, context).await {
+        tracing::warn!(error = ?e, "Failed to queue a digest email");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Timelike};
+
+    use super::*;
+    use crate::test_support;
+
+    fn settings(time_zone: &str, time: &str) -> UserSettings {
+        UserSettings {
+            time_zone: time_zone.to_string(),
+            default_reminder_time: time.parse().unwrap(),
+            ..UserSettings::defaults()
+        }
+    }
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn runs_at_the_default_reminder_time() {
+        let cases = [
+            // Later today, or tomorrow once the time has passed
+            ("UTC", "09:00", "2030-03-05T08:00:00Z", "2030-03-05T09:00:00Z"),
+            ("UTC", "09:00", "2030-03-05T09:00:00Z", "2030-03-06T09:00:00Z"),
+            // 09:00 in São Paulo is 12:00 UTC; at 01:00 UTC it is still the 4th there
+            ("America/Sao_Paulo", "09:00", "2030-03-05T01:00:00Z", "2030-03-05T12:00:00Z"),
+            ("Asia/Tokyo", "07:30", "2030-03-05T23:00:00Z", "2030-03-06T22:30:00Z"),
+            // 02:30 doesn't exist on the day clocks go forward in New York
+            ("America/New_York", "02:30", "2030-03-10T05:00:00Z", "2030-03-11T06:30:00Z"),
+        ];
+
+        for (time_zone, time, after, expected) in cases {
+            assert_eq!(
+                next_run(&settings(time_zone, time), utc(after)),
+                Some(utc(expected)),
+                "{} at {} after {}",
+                time_zone,
+                time,
+                after
+            );
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn sends_one_digest_a_day() {
+        let (pool, config) = test_support::database().await;
+        // DATETIME columns keep whole seconds
+        let now = Utc::now().with_nanosecond(0).unwrap();
+        let (user_id, email) = test_support::create_user(&pool, "digest", "!", true).await;
+        let list_id = test_support::create_list(&pool, &user_id, "Home").await;
+
+        // The digest was due a minute ago, in English and São Paulo time
+        sqlx::query(
+            r#"
+            INSERT INTO user_settings (user_id, time_zone, locale, default_reminder_time, digest_email, digest_next_at)
+            VALUES (?, 'America/Sao_Paulo', 'en', ?, 1, ?)
+            "#
+        )
+        .bind(&user_id)
+        .bind((now - Duration::minutes(1)).with_timezone(&chrono_tz::America::Sao_Paulo).time())
+        .bind(now - Duration::minutes(1))
+        .execute(&pool)
+        .await
+        .unwrap();
+        for (title, completed, due_at) in [
+            ("Pay rent", false, now - Duration::days(2)),
+            ("Water plants", true, now - Duration::days(1)),
+            ("Plan trip", false, now + Duration::days(3)),
+        ] {
+            sqlx::query("INSERT INTO tasks (id, list_id, title, completed, due_at, created_at) VALUES (?, ?, ?, ?, ?, NOW())")
+                .bind(uuid::Uuid::new_v4().to_string())
+                .bind(&list_id)
+                .bind(title)
+                .bind(completed)
+                .bind(due_at)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        // A second pass finds nothing due
+        for _ in 0..2 {
+            send_batch(&pool, &config).await.unwrap();
+        }
+        let sent: Vec<(String, String)> =
+            sqlx::query_as("SELECT subject, text_body FROM mail_outbox WHERE recipient = ? AND kind = 'digest'")
+                .bind(&email)
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        let next_at: Option<DateTime<Utc>> =
+            sqlx::query_scalar("SELECT digest_next_at FROM user_settings WHERE user_id = ?")
+                .bind(&user_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        sqlx::query("DELETE FROM mail_outbox WHERE recipient = ?").bind(&email).execute(&pool).await.unwrap();
+        test_support::delete_user(&pool, &user_id).await;
+
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, "MyTudo - Your summary: 1 pending tasks");
+        assert!(sent[0].1.contains("Overdue:\n- Pay rent (Home)"));
+        assert!(!sent[0].1.contains("Water plants") && !sent[0].1.contains("Plan trip"));
+        assert_eq!(next_at, Some(now - Duration::minutes(1) + Duration::days(1)));
+    }
+}
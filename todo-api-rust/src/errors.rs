@@ -1,43 +1,124 @@
 use actix_web::{HttpResponse, http::StatusCode};
 use serde::Serialize;
+use std::collections::BTreeMap;
 use std::fmt;
+use utoipa::ToSchema;
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+use crate::i18n::{self, Msg};
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldError {
+    pub code: String,
+    pub message: String,
+}
+
+pub type FieldErrors = BTreeMap<String, Vec<FieldError>>;
 
 #[derive(Debug)]
 pub struct ApiError {
+    pub code: Msg,
     pub message: String,
     pub status: StatusCode,
+    pub details: Option<FieldErrors>,
 }
 
 impl ApiError {
-    pub fn new(message: impl Into<String>, status: StatusCode) -> Self {
+    pub fn new(code: Msg, status: StatusCode) -> Self {
         Self {
-            message: message.into(),
+            code,
+            message: code.into(),
             status,
+            details: None,
         }
     }
 
-    pub fn bad_request(message: impl Into<String>) -> Self {
-        Self::new(message, StatusCode::BAD_REQUEST)
+    pub fn bad_request(code: Msg) -> Self {
+        Self::new(code, StatusCode::BAD_REQUEST)
     }
 
-    pub fn unauthorized(message: impl Into<String>) -> Self {
-        Self::new(message, StatusCode::UNAUTHORIZED)
+    pub fn unauthorized(code: Msg) -> Self {
+        Self::new(code, StatusCode::UNAUTHORIZED)
     }
 
-    pub fn forbidden(message: impl Into<String>) -> Self {
-        Self::new(message, StatusCode::FORBIDDEN)
+    pub fn forbidden(code: Msg) -> Self {
+        Self::new(code, StatusCode::FORBIDDEN)
     }
 
-    pub fn not_found(message: impl Into<String>) -> Self {
-        Self::new(message, StatusCode::NOT_FOUND)
+    pub fn not_found(code: Msg) -> Self {
+        Self::new(code, StatusCode::NOT_FOUND)
     }
 
-    pub fn conflict(message: impl Into<String>) -> Self {
-        Self::new(message, StatusCode::CONFLICT)
+    pub fn conflict(code: Msg) -> Self {
+        Self::new(code, StatusCode::CONFLICT)
     }
 
     pub fn internal(message: impl Into<String>) -> Self {
-        Self::new(message, StatusCode::INTERNAL_SERVER_ERROR)
+        Self {
+            code: Msg::InternalError,
+            message: message.into(),
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            details: None,
+        }
+    }
+
+    /// Replaces the catalog text, e.g. with a message that has placeholders filled in.
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+}
+
+// Flattens `validator` errors into `field -> [{code, message}]`. Nested structs and lists
+// use dotted paths such as `tasks[2].title`.
+fn collect_field_errors(prefix: &str, errors: &ValidationErrors, out: &mut FieldErrors) {
+    let locale = i18n::current();
+
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() { field.to_string() } else { format!("{}.{}", prefix, field) };
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                let entries = out.entry(path).or_default();
+                for error in errors {
+                    let message = Msg::from_code(&error.code)
+                        .map(|msg| msg.text(locale).to_string())
+                        .or_else(|| error.message.as_ref().map(|m| m.to_string()))
+                        .unwrap_or_else(|| Msg::ValidationFailed.text(locale).to_string());
+                    entries.push(FieldError {
+                        code: error.code.to_string(),
+                        message,
+                    });
+                }
+            }
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(&path, errors, out),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect_field_errors(&format!("{}[{}]", path, index), errors, out);
+                }
+            }
+        }
+    }
+}
+
+impl From<ValidationErrors> for ApiError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut details = FieldErrors::new();
+        collect_field_errors("", &errors, &mut details);
+
+        // Surface the first field message so clients that only show `message` stay useful
+        let message = details
+            .values()
+            .flat_map(|errors| errors.iter())
+            .map(|e| e.message.clone())
+            .next()
+            .unwrap_or_else(|| Msg::ValidationFailed.into());
+
+        Self {
+            code: Msg::ValidationFailed,
+            message,
+            status: StatusCode::BAD_REQUEST,
+            details: Some(details),
+        }
     }
 }
 
@@ -48,25 +129,49 @@ impl fmt::Display for ApiError {
 }
 
 impl actix_web::error::ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
     fn error_response(&self) -> HttpResponse {
-        let response = ApiResponse::<()>::error(&self.message);
+        if self.status.is_server_error() {
+            tracing::error!(error = %self.message, "Internal error");
+        }
+
+        let mut response = ApiResponse::<()>::error(&self.message);
+        response.code = Some(self.code);
+        response.details = self.details.clone();
         HttpResponse::build(self.status).json(response)
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ApiResponse<T: Serialize> {
     pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<Msg>,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<BTreeMap<String, Vec<FieldError>>>)]
+    pub details: Option<FieldErrors>,
     pub data: Option<T>,
     pub timestamp: String,
 }
 
+/// Stands in for `data` in the schema of responses that never carry any.
+#[derive(Serialize, ToSchema)]
+pub struct NoData;
+
+/// Schema of responses without `data`: errors and message-only successes.
+pub type MessageResponse = ApiResponse<NoData>;
+
 impl<T: Serialize> ApiResponse<T> {
     pub fn success(message: impl Into<String>, data: T) -> Self {
         Self {
             success: true,
+            code: None,
             message: message.into(),
+            details: None,
             data: Some(data),
             timestamp: chrono::Utc::now().to_rfc3339(),
         }
@@ -75,7 +180,9 @@ impl<T: Serialize> ApiResponse<T> {
     pub fn success_no_data(message: impl Into<String>) -> ApiResponse<()> {
         ApiResponse {
             success: true,
+            code: None,
             message: message.into(),
+            details: None,
             data: None,
             timestamp: chrono::Utc::now().to_rfc3339(),
         }
@@ -84,7 +191,9 @@ impl<T: Serialize> ApiResponse<T> {
     pub fn error(message: impl Into<String>) -> ApiResponse<()> {
         ApiResponse {
             success: false,
+            code: None,
             message: message.into(),
+            details: None,
             data: None,
             timestamp: chrono::Utc::now().to_rfc3339(),
         }
@@ -0,0 +1,196 @@
+use std::time::Duration;
+
+use actix_web::web::Bytes;
+use actix_web::HttpRequest;
+use futures_util::Stream;
+use serde::Serialize;
+use sqlx::{Executor, MySql, MySqlPool};
+use tokio::sync::broadcast;
+
+use crate::metrics::METRICS;
+use crate::models::WebhookEvent;
+use crate::webhooks;
+
+/// Header clients send on writes and on `/events` so a device doesn't hear its own changes.
+pub const DEVICE_ID_HEADER: &str = "X-Device-Id";
+
+// Events buffered per stream; a stream that falls further behind is told to resync
+const CHANNEL_CAPACITY: usize = 1024;
+// Comment line sent on idle streams so proxies keep the connection open
+const KEEPALIVE: Duration = Duration::from_secs(25);
+
+/// One row of `sync_log`, as pushed to `/events`. `cursor` is the row id.
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    pub entity_type: &'static str,
+    pub entity_id: String,
+    pub action: &'static str,
+    pub cursor: u64,
+    #[serde(skip)]
+    pub user_id: String,
+    #[serde(skip)]
+    pub device_id: Option<String>,
+}
+
+/// In-process fan-out of committed changes to open `/events` streams. Every stream sees
+/// every change and keeps those addressed to its user, so this only reaches clients
+/// connected to the same instance.
+#[derive(Clone)]
+pub struct EventHub {
+    sender: broadcast::Sender<Change>,
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+}
+
+impl EventHub {
+    /// Call after the change is committed. Lists are not shared yet, so the owner's
+    /// sessions are the only recipients.
+    pub fn publish(&self, change: Change) {
+        // No receivers just means nobody is listening
+        let _ = self.sender.send(change);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Change> {
+        self.sender.subscribe()
+    }
+}
+
+/// `X-Device-Id`, if present and short enough to fit `sync_log.device_id`.
+pub fn device_id(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(DEVICE_ID_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= 100)
+        .map(str::to_string)
+}
+
+/// Writes a `sync_log` row and returns the change to publish once the surrounding
+/// transaction (if any) commits.
+pub async fn record<'c, E: Executor<'c, Database = MySql>>(
+    executor: E,
+    user_id: &str,
+    device_id: Option<&str>,
+    entity_type: &'static str,
+    entity_id: &str,
+    action: &'static str,
+) -> Result<Change, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO sync_log (user_id, entity_type, entity_id, action, device_id) VALUES (?, ?, ?, ?, ?)"
+    )
+    .bind(user_id)
+    .bind(entity_type)
+    .bind(entity_id)
+    .bind(action)
+    .bind(device_id)
+    .execute(executor)
+    .await?;
+
+    Ok(Change {
+        entity_type,
+        entity_id: entity_id.to_string(),
+        action,
+        cursor: result.last_insert_id(),
+        user_id: user_id.to_string(),
+        device_id: device_id.map(str::to_string),
+    })
+}
+
+/// Records a committed change, pushes it to the user's other sessions and queues the
+/// matching webhooks. Failures are logged; the write they describe already happened.
+pub async fn log_sync(
+    pool: &MySqlPool,
+    events: &EventHub,
+    device_id: Option<&str>,
+    user_id: &str,
+    entity_type: &'static str,
+    entity_id: &str,
+    action: &'static str,
+) {
+    match record(pool, user_id, device_id, entity_type, entity_id, action).await {
+        Ok(change) => events.publish(change),
+        Err(e) => tracing::warn!(error = %e, "Failed to write sync_log"),
+    }
+
+    if let Some(event) = WebhookEvent::from_change(entity_type, action) {
+        webhooks::notify(pool, user_id, event, entity_id).await;
+    }
+}
+
+// Decrements the open streams gauge when the client goes away and the stream is dropped
+struct StreamGuard;
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        METRICS.event_stream_closed();
+    }
+}
+
+struct StreamState {
+    receiver: broadcast::Receiver<Change>,
+    user_id: String,
+    device_id: Option<String>,
+    keepalive: tokio::time::Interval,
+    _guard: StreamGuard,
+}
+
+fn frame(event: &str, id: Option<u64>, data: &str) -> Bytes {
+    let id = id.map(|id| format!("id: {}\n", id)).unwrap_or_default();
+    Bytes::from(format!("event: {}\n{}data: {}\n\n", event, id, data))
+}
+
+/// Server-Sent Events for `user_id`: `change` for each change made elsewhere, `resync`
+/// when events were dropped and the client should pull.
+pub fn stream(
+    hub: &EventHub,
+    user_id: String,
+    device_id: Option<String>,
+) -> impl Stream<Item = Result<Bytes, actix_web::Error>> {
+    METRICS.event_stream_opened();
+
+    let mut keepalive = tokio::time::interval(KEEPALIVE);
+    keepalive.reset();
+
+    let state = StreamState {
+        receiver: hub.subscribe(),
+        user_id,
+        device_id,
+        keepalive,
+        _guard: StreamGuard,
+    };
+
+    // Tells EventSource how long to wait before reconnecting
+    let hello = futures_util::stream::once(async { Ok(Bytes::from_static(b"retry: 5000\n\n")) });
+
+    let events = futures_util::stream::unfold(state, |mut state| async move {
+        loop {
+            let bytes = tokio::select! {
+                received = state.receiver.recv() => match received {
+                    Ok(change) => {
+                        let mine = change.user_id == state.user_id;
+                        let same_device = state.device_id.is_some() && change.device_id == state.device_id;
+                        if !mine || same_device {
+                            continue;
+                        }
+                        let data = serde_json::to_string(&change).unwrap_or_default();
+                        frame("change", Some(change.cursor), &data)
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "Event stream lagged");
+                        frame("resync", None, "{}")
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+                _ = state.keepalive.tick() => Bytes::from_static(b": keepalive\n\n"),
+            };
+            return Some((Ok(bytes), state));
+        }
+    });
+
+    futures_util::StreamExt::chain(hello, events)
+}
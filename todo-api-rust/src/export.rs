@@ -0,0 +1,190 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+
+use crate::errors::ApiError;
+use crate::i18n::{self, Locale, Msg};
+use crate::models::{ExportFormat, ExportedList, ExportedTask, ListExport};
+
+/// `format` of the JSON export, and its current `version`.
+pub const JSON_FORMAT: &str = "mytudo.list";
+pub const JSON_VERSION: u32 = 1;
+
+/// Columns of the CSV export, the same names `POST /import` reads by default.
+const CSV_COLUMNS: [&str; 10] = [
+    "id",
+    "list",
+    "title",
+    "description",
+    "completed",
+    "completed_at",
+    "due_at",
+    "reminder",
+    "recurrence",
+    "created_at",
+];
+
+fn export_error(e: impl std::fmt::Display) -> ApiError {
+    ApiError::internal(format!("Export error: {}", e))
+}
+
+/// One list and its tasks as a file of `format`. Times are shown in `tz` in Markdown and
+/// HTML, and kept in UTC in CSV and JSON.
+pub fn render(
+    format: ExportFormat,
+    list: ExportedList,
+    tasks: Vec<ExportedTask>,
+    tz: Tz,
+    exported_at: DateTime<Utc>,
+) -> Result<Vec<u8>, ApiError> {
+    match format {
+        ExportFormat::Markdown => Ok(markdown(&list, &tasks, tz).into_bytes()),
+        ExportFormat::Csv => csv(&list, &tasks),
+        ExportFormat::Json => {
+            let export = ListExport {
+                format: JSON_FORMAT,
+                version: JSON_VERSION,
+                exported_at,
+                list,
+                // Older rows store "" for no description
+                tasks: tasks
+                    .into_iter()
+                    .map(|task| ExportedTask {
+                        description: task.description.filter(|d| !d.trim().is_empty()),
+                        ..task
+                    })
+                    .collect(),
+            };
+            serde_json::to_vec_pretty(&export).map_err(export_error)
+        }
+        ExportFormat::Html => Ok(html(&list, &tasks, tz, exported_at).into_bytes()),
+    }
+}
+
+// Helper: Local date and time in the request's locale
+fn local_time(at: DateTime<Utc>, tz: Tz) -> String {
+    let pattern = match i18n::current() {
+        Locale::PtBr => "%d/%m/%Y %H:%M",
+        Locale::En => "%Y-%m-%d %H:%M",
+    };
+    at.with_timezone(&tz).format(pattern).to_string()
+}
+
+fn description(task: &ExportedTask) -> Option<&str> {
+    task.description.as_deref().map(str::trim).filter(|d| !d.is_empty())
+}
+
+fn markdown(list: &ExportedList, tasks: &[ExportedTask], tz: Tz) -> String {
+    let mut out = format!("# {}\n\n", list.name);
+
+    for task in tasks {
+        let mark = if task.completed { "x" } else { " " };
+        let _ = write!(out, "- [{}] {}", mark, task.title.replace('\n', " "));
+        if let Some(due_at) = task.due_at {
+            let _ = write!(out, " ({})", local_time(due_at, tz));
+        }
+        out.push('\n');
+
+        // Indented under the item, so it stays part of it
+        if let Some(description) = description(task) {
+            for line in description.lines() {
+                let _ = writeln!(out, "  {}", line);
+            }
+        }
+    }
+
+    out
+}
+
+fn csv(list: &ExportedList, tasks: &[ExportedTask]) -> Result<Vec<u8>, ApiError> {
+    let time = |at: Option<DateTime<Utc>>| at.map(|at| at.to_rfc3339()).unwrap_or_default();
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(CSV_COLUMNS).map_err(export_error)?;
+
+    for task in tasks {
+        writer
+            .write_record([
+                task.id.as_str(),
+                list.name.as_str(),
+                task.title.as_str(),
+                description(task).unwrap_or_default(),
+                if task.completed { "true" } else { "false" },
+                &time(task.completed_at),
+                &time(task.due_at),
+                &time(task.reminder),
+                task.recurrence.as_deref().unwrap_or_default(),
+                &task.created_at.to_rfc3339(),
+            ])
+            .map_err(export_error)?;
+    }
+
+    writer.into_inner().map_err(export_error)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// Printable page: checkboxes to tick on paper, no scripts or external assets
+fn html(list: &ExportedList, tasks: &[ExportedTask], tz: Tz, exported_at: DateTime<Utc>) -> String {
+    let done = tasks.iter().filter(|t| t.completed).count();
+
+    let mut items = String::new();
+    for task in tasks {
+        let (class, mark) = if task.completed { (" class=\"done\"", "☑") } else { ("", "☐") };
+        let _ = write!(
+            items,
+            "<li{}><span class=\"box\">{}</span><span class=\"title\">{}</span>",
+            class,
+            mark,
+            escape(&task.title)
+        );
+        if let Some(due_at) = task.due_at {
+            let _ = write!(items, "<span class=\"due\">{}</span>", escape(&local_time(due_at, tz)));
+        }
+        if let Some(description) = description(task) {
+            let _ = write!(items, "<div class=\"description\">{}</div>", escape(description));
+        }
+        items.push_str("</li>\n");
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="{lang}">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{name}</title>
+<style>
+body {{ font-family: -apple-system, "Segoe UI", Roboto, Helvetica, Arial, sans-serif; max-width: 720px; margin: 2rem auto; padding: 0 1rem; color: #111; }}
+h1 {{ border-left: 6px solid {color}; padding-left: .6rem; margin-bottom: .2rem; }}
+.progress {{ color: #555; margin-top: 0; }}
+ul {{ list-style: none; padding: 0; }}
+li {{ padding: .45rem 0; border-bottom: 1px solid #ddd; break-inside: avoid; }}
+.box {{ display: inline-block; width: 1.6em; font-size: 1.1em; }}
+.done .title {{ text-decoration: line-through; color: #777; }}
+.due {{ color: #555; font-size: .9em; margin-left: .6rem; }}
+.description {{ margin: .2rem 0 0 1.6em; color: #444; font-size: .9em; white-space: pre-wrap; }}
+footer {{ margin-top: 2rem; color: #777; font-size: .8em; }}
+@media print {{ body {{ margin: 0; max-width: none; }} }}
+</style>
+</head>
+<body>
+<h1>{name}</h1>
+<p class="progress">{progress}</p>
+<ul>
+{items}</ul>
+<footer>{footer}</footer>
+</body>
+</html>
+"#,
+        lang = i18n::current().tag(),
+        name = escape(&list.name),
+        color = escape(&list.color),
+        progress = escape(&Msg::ExportProgress.with(&[("done", &done.to_string()), ("total", &tasks.len().to_string())])),
+        items = items,
+        footer = escape(&Msg::ExportFooter.with(&[("date", &local_time(exported_at, tz))])),
+    )
+}
@@ -0,0 +1,400 @@
+use std::io::{Cursor, Write};
+
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use sqlx::MySqlPool;
+use validator::Validate;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::config::Config;
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::handlers::settings::load_settings;
+use crate::i18n::Msg;
+use crate::middleware::jwt::get_auth_user;
+use crate::models::*;
+use crate::password;
+
+// Helper: Load the authenticated user's row
+async fn load_user(pool: &MySqlPool, user_id: &str) -> Result<User, ApiError> {
+    let user: Option<User> = sqlx::query_as(
+        "SELECT * FROM users WHERE id = ?"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    user.ok_or_else(|| ApiError::not_found(Msg::UserNotFound))
+}
+
+// Helper: File inside the export archive
+fn add_file(zip: &mut ZipWriter<Cursor<Vec<u8>>>, name: &str, data: &[u8]) -> Result<(), ApiError> {
+    zip.start_file(name, SimpleFileOptions::default())
+        .and_then(|_| zip.write_all(data).map_err(Into::into))
+        .map_err(|e| ApiError::internal(format!("Export error: {}", e)))
+}
+
+// Helper: Pretty JSON file inside the export archive
+fn add_json<T: Serialize>(zip: &mut ZipWriter<Cursor<Vec<u8>>>, name: &str, value: &T) -> Result<(), ApiError> {
+    let json = serde_json::to_vec_pretty(value)
+        .map_err(|e| ApiError::internal(format!("Export error: {}", e)))?;
+
+    add_file(zip, name, &json)
+}
+
+// Helper: Where an attachment goes in the archive. The id keeps equal names apart; the
+// filename is the user's, so it can't add folders.
+fn attachment_path(attachment: &TaskAttachment) -> String {
+    let filename: String = attachment
+        .filename
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\') || c.is_control() { '_' } else { c })
+        .collect();
+    format!("attachments/{}/{}", attachment.id, filename)
+}
+
+// DELETE /auth/me
+#[utoipa::path(
+    delete,
+    path = "/auth/me",
+    tag = "auth",
+    request_body = DeleteAccountRequest,
+    responses(
+        (status = 200, description = "Deletion scheduled after the grace period", body = ApiResponse<AccountDeletionResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing token or wrong password", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_account(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    body: web::Json<DeleteAccountRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+
+    let user = load_user(pool.get_ref(), &claims.user_id).await?;
+
+    let valid = password::verify(&body.password, &user.password_hash).await?;
+
+    if !valid {
+        return Err(ApiError::unauthorized(Msg::WrongPassword));
+    }
+
+    // Asking again keeps the original date
+    let deletion_scheduled_at = match user.deletion_scheduled_at {
+        Some(scheduled) => scheduled,
+        None => {
+            let scheduled = Utc::now() + Duration::days(config.account_deletion_grace_days);
+
+            sqlx::query("UPDATE users SET deletion_scheduled_at = ? WHERE id = ?")
+                .bind(scheduled)
+                .bind(&user.id)
+                .execute(pool.get_ref())
+                .await
+                .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+            scheduled
+        }
+    };
+
+    tracing::info!(%deletion_scheduled_at, "Account deletion scheduled");
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(
+        Msg::AccountDeletionScheduled,
+        AccountDeletionResponse { deletion_scheduled_at },
+    )))
+}
+
+// POST /auth/me/restore
+#[utoipa::path(
+    post,
+    path = "/auth/me/restore",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Scheduled deletion cancelled", body = ApiResponse<UserPublic>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn restore_account(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+
+    sqlx::query("UPDATE users SET deletion_scheduled_at = NULL WHERE id = ?")
+        .bind(&claims.user_id)
+        .execute(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let user = load_user(pool.get_ref(), &claims.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(
+        Msg::AccountDeletionCancelled,
+        UserPublic::from(user),
+    )))
+}
+
+// GET /auth/export
+#[utoipa::path(
+    get,
+    path = "/auth/export",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Zip with profile.json, lists.json, tasks.json, reminders.json, attachments.json, activity.json and the attached files", body = String, content_type = "application/zip"),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn export_data(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    let user_id = &claims.user_id;
+
+    let user = load_user(pool.get_ref(), user_id).await?;
+    let settings = load_settings(pool.get_ref(), user_id).await?;
+
+    // Soft-deleted rows are the user's data too
+    let lists: Vec<TodoList> = sqlx::query_as(
+        r#"
+        SELECT id, user_id, name, color, created_at, updated_at, deleted_at
+        FROM todo_lists
+        WHERE user_id = ?
+        ORDER BY created_at
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let tasks: Vec<Task> = sqlx::query_as(
+        r#"
+        SELECT t.id, t.list_id, t.title, t.description, t.completed, t.reminder, t.due_at, t.recurrence, t.created_at, t.updated_at, t.deleted_at
+        FROM tasks t
+        JOIN todo_lists l ON t.list_id = l.id
+        WHERE l.user_id = ?
+        ORDER BY t.created_at
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    // Snooze and dismissal included, so an import can keep reminders from ringing again
+    let reminders: Vec<TaskReminderResponse> = sqlx::query_as::<_, TaskReminder>(&format!(
+        r#"
+        SELECT {}
+        FROM task_reminders r
+        JOIN tasks t ON r.task_id = t.id
+        JOIN todo_lists l ON t.list_id = l.id
+        WHERE l.user_id = ?
+        ORDER BY r.created_at, r.id
+        "#,
+        REMINDER_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?
+    .into_iter()
+    .map(Into::into)
+    .collect();
+
+    let attachments: Vec<TaskAttachment> = sqlx::query_as(
+        r#"
+        SELECT a.id, a.task_id, a.filename, a.content_type, a.size, a.created_at
+        FROM task_attachments a
+        JOIN tasks t ON a.task_id = t.id
+        JOIN todo_lists l ON t.list_id = l.id
+        WHERE l.user_id = ?
+        ORDER BY a.created_at, a.id
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let activity: Vec<ActivityEntry> = sqlx::query_as(
+        r#"
+        SELECT entity_type, entity_id, action, synced_at, device_id
+        FROM sync_log
+        WHERE user_id = ?
+        ORDER BY synced_at, id
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let exported_at = Utc::now();
+    let profile = ExportProfile {
+        user: user.into(),
+        settings,
+        exported_at,
+    };
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    add_json(&mut zip, "profile.json", &profile)?;
+    add_json(&mut zip, "lists.json", &lists)?;
+    add_json(&mut zip, "tasks.json", &tasks)?;
+    add_json(&mut zip, "reminders.json", &reminders)?;
+    add_json(&mut zip, "attachments.json", &attachments)?;
+    add_json(&mut zip, "activity.json", &activity)?;
+
+    // One at a time, so only the archive itself is held in memory
+    for attachment in &attachments {
+        let data: Vec<u8> = sqlx::query_scalar("SELECT data FROM task_attachments WHERE id = ?")
+            .bind(&attachment.id)
+            .fetch_one(pool.get_ref())
+            .await
+            .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+        add_file(&mut zip, &attachment_path(attachment), &data)?;
+    }
+    let archive = zip
+        .finish()
+        .map_err(|e| ApiError::internal(format!("Export error: {}", e)))?
+        .into_inner();
+
+    let filename = format!("mytudo-export-{}.zip", exported_at.format("%Y%m%d"));
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(filename)],
+        })
+        .body(archive))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use actix_web::http::StatusCode;
+    use actix_web::test::{call_service, init_service, read_body, TestRequest};
+    use actix_web::App;
+    use zip::ZipArchive;
+
+    use super::*;
+    use crate::middleware::jwt::create_token;
+    use crate::test_support;
+
+    // Helper: One JSON file of the archive
+    fn entry(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> serde_json::Value {
+        let mut json = String::new();
+        archive.by_name(name).unwrap().read_to_string(&mut json).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn exports_everything_the_user_owns() {
+        let (pool, config) = test_support::database().await;
+        let (user_id, email) = test_support::create_user(&pool, "export", "!", true).await;
+        let token = create_token(&config, &user_id, &email).unwrap();
+        let list_id = test_support::create_list(&pool, &user_id, "Home").await;
+        let task_id = uuid::Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO tasks (id, list_id, title, due_at, created_at) VALUES (?, ?, 'Pay rent', '2030-03-05 12:00:00', NOW())")
+            .bind(&task_id)
+            .bind(&list_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        // One snoozed, one dismissed
+        for (minutes, snoozed_until, dismissed_at) in [
+            (60, Some("2030-03-05 11:30:00"), None),
+            (1440, None, Some("2030-03-04 12:00:00")),
+        ] {
+            sqlx::query(
+                r#"
+                INSERT INTO task_reminders (id, task_id, minutes_before_due, snoozed_until, dismissed_at, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, NOW(), NOW())
+                "#
+            )
+            .bind(uuid::Uuid::new_v4().to_string())
+            .bind(&task_id)
+            .bind(minutes)
+            .bind(snoozed_until)
+            .bind(dismissed_at)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        let attachment_id = uuid::Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO task_attachments (id, task_id, filename, content_type, size, data, created_at)
+            VALUES (?, ?, '../receipt.txt', 'text/plain', 4, 'paid', NOW())
+            "#
+        )
+        .bind(&attachment_id)
+        .bind(&task_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(config))
+                .route("/auth/export", web::get().to(export_data)),
+        )
+        .await;
+
+        let res = call_service(
+            &app,
+            TestRequest::get()
+                .uri("/auth/export")
+                .insert_header(("Authorization", format!("Bearer {}", token)))
+                .to_request(),
+        )
+        .await;
+        let status = res.status();
+        let body = read_body(res).await.to_vec();
+        test_support::delete_user(&pool, &user_id).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let mut archive = ZipArchive::new(Cursor::new(body)).unwrap();
+        assert_eq!(entry(&mut archive, "profile.json")["user"]["email"], email.as_str());
+        assert_eq!(entry(&mut archive, "tasks.json")[0]["title"], "Pay rent");
+
+        let reminders = entry(&mut archive, "reminders.json");
+        let reminders = reminders.as_array().unwrap();
+        assert_eq!(reminders.len(), 2);
+        let snoozed = reminders.iter().find(|r| r["minutes_before_due"] == 60).unwrap();
+        assert_eq!(snoozed["task_id"], task_id.as_str());
+        assert_eq!(snoozed["snoozed_until"], "2030-03-05T11:30:00Z");
+        assert_eq!(snoozed["fires_at"], "2030-03-05T11:30:00Z");
+        let dismissed = reminders.iter().find(|r| r["minutes_before_due"] == 1440).unwrap();
+        assert_eq!(dismissed["dismissed_at"], "2030-03-04T12:00:00Z");
+        assert!(dismissed["fires_at"].is_null());
+
+        let attachments = entry(&mut archive, "attachments.json");
+        assert_eq!(attachments[0]["task_id"], task_id.as_str());
+        assert_eq!(attachments[0]["filename"], "../receipt.txt");
+        assert_eq!(attachments[0]["content_type"], "text/plain");
+        assert_eq!(attachments[0]["size"], 4);
+        let mut data = String::new();
+        archive
+            .by_name(&format!("attachments/{}/.._receipt.txt", attachment_id))
+            .unwrap()
+            .read_to_string(&mut data)
+            .unwrap();
+        assert_eq!(data, "paid");
+    }
+}
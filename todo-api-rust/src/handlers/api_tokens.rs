@@ -0,0 +1,177 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
+use rand::Rng;
+use sqlx::MySqlPool;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::i18n::Msg;
+use crate::middleware::api_token::{hash_token, TOKEN_PREFIX};
+use crate::middleware::jwt::get_auth_user;
+use crate::models::*;
+
+// Characters of the secret kept in clear so users can tell their tokens apart
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+// Helper: Generate UUID
+fn generate_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+// Helper: `mtd_` followed by 24 random bytes as hex
+fn generate_token() -> String {
+    let bytes: [u8; 24] = rand::thread_rng().gen();
+    let secret: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}{}", TOKEN_PREFIX, secret)
+}
+
+// GET /auth/tokens
+#[utoipa::path(
+    get,
+    path = "/auth/tokens",
+    tag = "tokens",
+    responses(
+        (status = 200, description = "Active tokens, newest first", body = ApiResponse<Vec<ApiTokenResponse>>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_tokens(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+
+    let tokens: Vec<ApiToken> = sqlx::query_as(
+        r#"
+        SELECT id, user_id, name, token_prefix, scopes, expires_at, last_used_at, created_at
+        FROM api_tokens
+        WHERE user_id = ? AND revoked_at IS NULL
+        ORDER BY created_at DESC
+        "#
+    )
+    .bind(&claims.user_id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let tokens: Vec<ApiTokenResponse> = tokens.into_iter().map(Into::into).collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::ApiTokensLoaded, tokens)))
+}
+
+// POST /auth/tokens
+#[utoipa::path(
+    post,
+    path = "/auth/tokens",
+    tag = "tokens",
+    request_body = CreateApiTokenRequest,
+    responses(
+        (status = 201, description = "Token created; the secret is only returned here", body = ApiResponse<CreateApiTokenResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_token(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    body: web::Json<CreateApiTokenRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+
+    // Stored in the canonical order, without repeats
+    let requested: Vec<Scope> = body.scopes.iter().filter_map(|s| s.parse().ok()).collect();
+    let scopes = Scope::ALL
+        .into_iter()
+        .filter(|scope| requested.contains(scope))
+        .map(Scope::as_str)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let now = Utc::now();
+    let token = generate_token();
+    let api_token = ApiToken {
+        id: generate_uuid(),
+        user_id: claims.user_id,
+        name: body.name.trim().to_string(),
+        token_prefix: token[..DISPLAY_PREFIX_LEN].to_string(),
+        scopes,
+        expires_at: body.expires_in_days.map(|days| now + Duration::days(days.into())),
+        last_used_at: None,
+        created_at: now,
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO api_tokens (id, user_id, name, token_hash, token_prefix, scopes, expires_at, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&api_token.id)
+    .bind(&api_token.user_id)
+    .bind(&api_token.name)
+    .bind(hash_token(&token))
+    .bind(&api_token.token_prefix)
+    .bind(&api_token.scopes)
+    .bind(api_token.expires_at)
+    .bind(api_token.created_at)
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    tracing::info!(token_id = %api_token.id, scopes = %api_token.scopes, "API token created");
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(
+        Msg::ApiTokenCreated,
+        CreateApiTokenResponse {
+            token,
+            api_token: api_token.into(),
+        },
+    )))
+}
+
+// DELETE /auth/tokens/{id}
+#[utoipa::path(
+    delete,
+    path = "/auth/tokens/{id}",
+    tag = "tokens",
+    params(("id" = String, Path, description = "Token ID")),
+    responses(
+        (status = 200, description = "Token revoked", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Token not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn revoke_token(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    let token_id = path.into_inner();
+
+    let result = sqlx::query(
+        "UPDATE api_tokens SET revoked_at = ? WHERE id = ? AND user_id = ? AND revoked_at IS NULL"
+    )
+    .bind(Utc::now())
+    .bind(&token_id)
+    .bind(&claims.user_id)
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found(Msg::ApiTokenNotFound));
+    }
+
+    tracing::info!(%token_id, "API token revoked");
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data(Msg::ApiTokenRevoked)))
+}
@@ -0,0 +1,177 @@
+use actix_web::http::header::{Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue};
+use actix_web::{web, HttpRequest, HttpResponse};
+use sqlx::MySqlPool;
+
+use crate::config::Config;
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::events::{self, EventHub};
+use crate::i18n::Msg;
+use crate::middleware::jwt::get_auth_user;
+use crate::models::*;
+
+// Helper: Whether the task exists and belongs to the user
+async fn owns_task(pool: &MySqlPool, task_id: &str, user_id: &str) -> Result<bool, ApiError> {
+    let task: Option<(String,)> = sqlx::query_as(
+        r#"
+        SELECT t.id
+        FROM tasks t
+        JOIN todo_lists l ON t.list_id = l.id
+        WHERE t.id = ? AND l.user_id = ? AND t.deleted_at IS NULL
+        "#
+    )
+    .bind(task_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    Ok(task.is_some())
+}
+
+// GET /tasks/{id}/attachments
+#[utoipa::path(
+    get,
+    path = "/tasks/{id}/attachments",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task id")),
+    responses(
+        (status = 200, description = "Attachments of the task, without their contents", body = ApiResponse<Vec<TaskAttachment>>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Task not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_attachments(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+
+    if !owns_task(pool.get_ref(), &path, &claims.user_id).await? {
+        return Err(ApiError::not_found(Msg::TaskNotFound));
+    }
+
+    let attachments: Vec<TaskAttachment> = sqlx::query_as(
+        r#"
+        SELECT id, task_id, filename, content_type, size, created_at
+        FROM task_attachments
+        WHERE task_id = ?
+        ORDER BY created_at, filename
+        "#
+    )
+    .bind(path.as_str())
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::AttachmentsLoaded, attachments)))
+}
+
+// GET /tasks/{id}/attachments/{attachment_id}
+#[utoipa::path(
+    get,
+    path = "/tasks/{id}/attachments/{attachment_id}",
+    tag = "tasks",
+    params(
+        ("id" = String, Path, description = "Task id"),
+        ("attachment_id" = String, Path, description = "Attachment id"),
+    ),
+    responses(
+        (status = 200, description = "The file, as a download", body = String, content_type = "application/octet-stream"),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn download_attachment(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    let (task_id, attachment_id) = path.into_inner();
+
+    if !owns_task(pool.get_ref(), &task_id, &claims.user_id).await? {
+        return Err(ApiError::not_found(Msg::TaskNotFound));
+    }
+
+    let file: Option<(String, String, Vec<u8>)> = sqlx::query_as(
+        "SELECT filename, content_type, data FROM task_attachments WHERE id = ? AND task_id = ?"
+    )
+    .bind(&attachment_id)
+    .bind(&task_id)
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let (filename, content_type, data) = file.ok_or_else(|| ApiError::not_found(Msg::AttachmentNotFound))?;
+
+    // Always a download: an attached HTML page must not run on the API's origin
+    let fallback: String = filename.chars().map(|c| if c.is_ascii() && c != '"' { c } else { '_' }).collect();
+    let disposition = ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![
+            DispositionParam::Filename(fallback),
+            DispositionParam::FilenameExt(ExtendedValue {
+                charset: Charset::Ext("UTF-8".to_string()),
+                language_tag: None,
+                value: filename.into_bytes(),
+            }),
+        ],
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(disposition)
+        .insert_header(("X-Content-Type-Options", "nosniff"))
+        .body(data))
+}
+
+// DELETE /tasks/{id}/attachments/{attachment_id}
+#[utoipa::path(
+    delete,
+    path = "/tasks/{id}/attachments/{attachment_id}",
+    tag = "tasks",
+    params(
+        ("id" = String, Path, description = "Task id"),
+        ("attachment_id" = String, Path, description = "Attachment id"),
+    ),
+    responses(
+        (status = 200, description = "Attachment deleted", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_attachment(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    events: web::Data<EventHub>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    let (task_id, attachment_id) = path.into_inner();
+
+    if !owns_task(pool.get_ref(), &task_id, &claims.user_id).await? {
+        return Err(ApiError::not_found(Msg::TaskNotFound));
+    }
+
+    let result = sqlx::query("DELETE FROM task_attachments WHERE id = ? AND task_id = ?")
+        .bind(&attachment_id)
+        .bind(&task_id)
+        .execute(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found(Msg::AttachmentNotFound));
+    }
+
+    events::log_sync(pool.get_ref(), &events, events::device_id(&req).as_deref(), &claims.user_id, "task", &task_id, "update").await;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data(Msg::AttachmentDeleted)))
+}
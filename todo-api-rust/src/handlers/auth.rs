@@ -1,12 +1,22 @@
+use actix_web::http::StatusCode;
 use actix_web::{web, HttpRequest, HttpResponse};
 use chrono::{Duration, Utc};
+use minijinja::context;
 use rand::Rng;
 use sqlx::MySqlPool;
+use validator::Validate;
 
 use crate::config::Config;
-use crate::errors::{ApiError, ApiResponse};
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::handlers::settings::user_locale;
+use crate::i18n::{self, Locale, Msg};
+use crate::mail::{self, MailKind};
 use crate::middleware::jwt::{create_token, get_auth_user};
 use crate::models::*;
+use crate::password;
+
+// How long the old address can undo a confirmed email change
+const REVERT_LINK_DAYS: i64 = 7;
 
 // Helper: Generate UUID
 fn generate_uuid() -> String {
@@ -19,144 +29,82 @@ fn generate_verification_code() -> String {
     format!("{:06}", code)
 }
 
-// Helper: Send verification email (simplified - log only in this version)
-async fn send_verification_email(config: &Config, email: &str, username: &str, code: &str) {
-    log::info!("📧 Sending verification email to {} ({}): Code = {}", email, username, code);
-    
-    // If SMTP is configured, send real email
-    if !config.smtp_user.is_empty() {
-        use lettre::{Message, SmtpTransport, Transport};
-        use lettre::transport::smtp::authentication::Credentials;
-        
-        let html_body = format!(r#"
-        <html>
-        <head>
-            <style>
-                body {{ font-family: Arial, sans-serif; background: #f5f5f5; padding: 20px; }}
-                .container {{ max-width: 400px; margin: 0 auto; background: white; border-radius: 16px; padding: 32px; }}
-                .logo {{ text-align: center; color: #7C6FFF; font-size: 28px; font-weight: bold; margin-bottom: 24px; }}
-                .code {{ text-align: center; font-size: 36px; font-weight: bold; color: #333; letter-spacing: 8px; background: #f0f0f0; padding: 16px; border-radius: 8px; margin: 24px 0; }}
-                .text {{ color: #666; text-align: center; line-height: 1.6; }}
-                .footer {{ text-align: center; color: #999; font-size: 12px; margin-top: 24px; }}
-            </style>
-        </head>
-        <body>
-            <div class='container'>
-                <div class='logo'>MyTudo</div>
-                <p class='text'>Olá <strong>{}</strong>!</p>
-                <p class='text'>Use o código abaixo para ativar sua conta:</p>
-                <div class='code'>{}</div>
-                <p class='text'>Este código expira em <strong>15 minutos</strong>.</p>
-                <p class='footer'>Se você não solicitou este código, ignore este email.</p>
-            </div>
-        </body>
-        </html>
-        "#, username, code);
-
-        let email_result = Message::builder()
-            .from(config.smtp_from.parse().unwrap_or_else(|_| "noreply@localhost".parse().unwrap()))
-            .to(email.parse().unwrap())
-            .subject(format!("MyTudo - Código de Verificação: {}", code))
-            .header(lettre::message::header::ContentType::TEXT_HTML)
-            .body(html_body);
-
-        if let Ok(email_msg) = email_result {
-            let creds = Credentials::new(config.smtp_user.clone(), config.smtp_pass.clone());
-            
-            let mailer = SmtpTransport::relay(&config.smtp_host)
-                .ok()
-                .map(|m| m.port(config.smtp_port).credentials(creds).build());
-
-            if let Some(mailer) = mailer {
-                match mailer.send(&email_msg) {
-                    Ok(_) => log::info!("✅ Email sent successfully to {}", email),
-                    Err(e) => log::error!("❌ Failed to send email: {:?}", e),
-                }
-            }
-        }
-    }
+// Helper: Random token for links sent by email
+fn generate_revert_token() -> String {
+    let bytes: [u8; 32] = rand::thread_rng().gen();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
-// Helper: Send password reset email
-async fn send_password_reset_email(config: &Config, email: &str, username: &str, code: &str) {
-    log::info!("📧 Sending password reset email to {} ({}): Code = {}", email, username, code);
-    
-    if !config.smtp_user.is_empty() {
-        use lettre::{Message, SmtpTransport, Transport};
-        use lettre::transport::smtp::authentication::Credentials;
-        
-        let html_body = format!(r#"
-        <html>
-        <head>
-            <style>
-                body {{ font-family: Arial, sans-serif; background: #f5f5f5; padding: 20px; }}
-                .container {{ max-width: 400px; margin: 0 auto; background: white; border-radius: 16px; padding: 32px; }}
-                .logo {{ text-align: center; color: #7C6FFF; font-size: 28px; font-weight: bold; margin-bottom: 24px; }}
-                .code {{ text-align: center; font-size: 36px; font-weight: bold; color: #333; letter-spacing: 8px; background: #f0f0f0; padding: 16px; border-radius: 8px; margin: 24px 0; }}
-                .text {{ color: #666; text-align: center; line-height: 1.6; }}
-                .footer {{ text-align: center; color: #999; font-size: 12px; margin-top: 24px; }}
-            </style>
-        </head>
-        <body>
-            <div class='container'>
-                <div class='logo'>MyTudo</div>
-                <p class='text'>Olá <strong>{}</strong>!</p>
-                <p class='text'>Use o código abaixo para recuperar sua senha:</p>
-                <div class='code'>{}</div>
-                <p class='text'>Este código expira em <strong>15 minutos</strong>.</p>
-                <p class='footer'>Se você não solicitou a recuperação de senha, ignore este email.</p>
-            </div>
-        </body>
-        </html>
-        "#, username, code);
-
-        let email_result = Message::builder()
-            .from(config.smtp_from.parse().unwrap_or_else(|_| "noreply@localhost".parse().unwrap()))
-            .to(email.parse().unwrap())
-            .subject(format!("MyTudo - Recuperar Senha: {}", code))
-            .header(lettre::message::header::ContentType::TEXT_HTML)
-            .body(html_body);
-
-        if let Ok(email_msg) = email_result {
-            let creds = Credentials::new(config.smtp_user.clone(), config.smtp_pass.clone());
-            
-            let mailer = SmtpTransport::relay(&config.smtp_host)
-                .ok()
-                .map(|m| m.port(config.smtp_port).credentials(creds).build());
-
-            if let Some(mailer) = mailer {
-                match mailer.send(&email_msg) {
-                    Ok(_) => log::info!("✅ Password reset email sent to {}", email),
-                    Err(e) => log::error!("❌ Failed to send email: {:?}", e),
-                }
-            }
+// Helper: Check if another account already uses this email
+async fn email_taken(pool: &MySqlPool, email: &str, user_id: &str) -> Result<bool, ApiError> {
+    let existing: Option<(String,)> = sqlx::query_as(
+        "SELECT id FROM users WHERE email = ? AND id != ?"
+    )
+    .bind(email)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    Ok(existing.is_some())
+}
+
+// Helper: Store a fresh hash after login. Failures only cost the upgrade, not the login.
+async fn rehash_password(pool: &MySqlPool, config: &Config, user_id: &str, plain: &str) {
+    let password_hash = match password::hash(plain, config.password_hashing).await {
+        Ok(hash) => hash,
+        Err(e) => {
+            tracing::warn!(error = %e.message, "Password rehash failed");
+            return;
         }
+    };
+
+    if let Err(e) = sqlx::query("UPDATE users SET password_hash = ? WHERE id = ?")
+        .bind(&password_hash)
+        .bind(user_id)
+        .execute(pool)
+        .await
+    {
+        tracing::warn!(error = %e, "Password rehash failed");
     }
 }
 
+// Helper: Queue the verification email
+async fn send_verification_email(pool: &MySqlPool, config: &Config, locale: Locale, email: &str, username: &str, code: &str) -> Result<(), ApiError> {
+    let context = context! { name => username, code => code };
+    mail::send(pool, config, MailKind::Verification, locale, email, context).await
+}
+
+// Helper: Queue the password reset email
+async fn send_password_reset_email(pool: &MySqlPool, config: &Config, locale: Locale, email: &str, username: &str, code: &str) -> Result<(), ApiError> {
+    let context = context! { name => username, code => code };
+    mail::send(pool, config, MailKind::PasswordReset, locale, email, context).await
+}
+
 // POST /auth/register
+#[utoipa::path(
+    post,
+    path = "/auth/register",
+    tag = "auth",
+    request_body = RegisterRequest,
+    responses(
+        (status = 201, description = "Verification code sent", body = ApiResponse<RegisterResponse>),
+        (status = 200, description = "Pending account, code resent", body = ApiResponse<RegisterResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 409, description = "Email already registered", body = MessageResponse),
+    )
+)]
 pub async fn register(
     pool: web::Data<MySqlPool>,
     config: web::Data<Config>,
     body: web::Json<RegisterRequest>,
 ) -> Result<HttpResponse, ApiError> {
+    body.validate()?;
+
     let username = body.username.trim();
     let email = body.email.trim().to_lowercase();
     let password = &body.password;
 
-    // Validations
-    if username.is_empty() || email.is_empty() || password.is_empty() {
-        return Err(ApiError::bad_request("Preencha todos os campos"));
-    }
-
-    if !email.contains('@') {
-        return Err(ApiError::bad_request("Email inválido"));
-    }
-
-    if password.len() < 6 {
-        return Err(ApiError::bad_request("Senha deve ter no mínimo 6 caracteres"));
-    }
-
     // Check if email exists
     let existing: Option<(String, bool)> = sqlx::query_as(
         "SELECT id, is_verified FROM users WHERE email = ?"
@@ -168,7 +116,7 @@ pub async fn register(
 
     if let Some((id, is_verified)) = existing {
         if is_verified {
-            return Err(ApiError::conflict("Email já cadastrado"));
+            return Err(ApiError::conflict(Msg::EmailAlreadyRegistered));
         } else {
             // User exists but not verified, resend code
             let code = generate_verification_code();
@@ -184,10 +132,10 @@ pub async fn register(
             .await
             .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-            send_verification_email(&config, &email, username, &code).await;
+            send_verification_email(pool.get_ref(), &config, user_locale(pool.get_ref(), &id).await, &email, username, &code).await?;
 
             return Ok(HttpResponse::Ok().json(ApiResponse::success(
-                "Código reenviado para o email",
+                Msg::CodeResent,
                 RegisterResponse {
                     email,
                     requires_verification: true,
@@ -196,10 +144,11 @@ pub async fn register(
         }
     }
 
+    password::check_personal_info("password", password, username, &email)?;
+
     // Create new user
     let id = generate_uuid();
-    let password_hash = bcrypt::hash(password, bcrypt::DEFAULT_COST)
-        .map_err(|_| ApiError::internal("Failed to hash password"))?;
+    let password_hash = password::hash(password, config.password_hashing).await?;
     let code = generate_verification_code();
     let now = Utc::now();
     let expires_at = now + Duration::minutes(15);
@@ -221,10 +170,10 @@ pub async fn register(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    send_verification_email(&config, &email, username, &code).await;
+    send_verification_email(pool.get_ref(), &config, i18n::current(), &email, username, &code).await?;
 
     Ok(HttpResponse::Created().json(ApiResponse::success(
-        "Código de verificação enviado para o email",
+        Msg::VerificationCodeSent,
         RegisterResponse {
             email,
             requires_verification: true,
@@ -233,18 +182,27 @@ pub async fn register(
 }
 
 // POST /auth/verify
+#[utoipa::path(
+    post,
+    path = "/auth/verify",
+    tag = "auth",
+    request_body = VerifyEmailRequest,
+    responses(
+        (status = 200, description = "Account activated", body = ApiResponse<VerifyResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    )
+)]
 pub async fn verify_email(
     pool: web::Data<MySqlPool>,
     config: web::Data<Config>,
     body: web::Json<VerifyEmailRequest>,
 ) -> Result<HttpResponse, ApiError> {
+    body.validate()?;
+
     let email = body.email.trim().to_lowercase();
     let code = body.code.trim();
 
-    if email.is_empty() || code.is_empty() {
-        return Err(ApiError::bad_request("Email e código são obrigatórios"));
-    }
-
     let user: Option<User> = sqlx::query_as(
         "SELECT * FROM users WHERE email = ?"
     )
@@ -253,20 +211,20 @@ pub async fn verify_email(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let user = user.ok_or_else(|| ApiError::not_found("Email não encontrado"))?;
+    let user = user.ok_or_else(|| ApiError::not_found(Msg::EmailNotFound))?;
 
     if user.is_verified {
-        return Err(ApiError::bad_request("Email já verificado"));
+        return Err(ApiError::bad_request(Msg::EmailAlreadyVerified));
     }
 
     let stored_code = user.verification_code.as_deref().unwrap_or("");
     if stored_code != code {
-        return Err(ApiError::bad_request("Código inválido"));
+        return Err(ApiError::bad_request(Msg::InvalidCode));
     }
 
     if let Some(expires_at) = user.code_expires_at {
         if expires_at < Utc::now() {
-            return Err(ApiError::bad_request("Código expirado. Solicite um novo."));
+            return Err(ApiError::bad_request(Msg::CodeExpired));
         }
     }
 
@@ -282,7 +240,7 @@ pub async fn verify_email(
     let token = create_token(&config, &user.id, &user.email)?;
 
     Ok(HttpResponse::Ok().json(ApiResponse::success(
-        "Conta ativada com sucesso!",
+        Msg::AccountActivated,
         VerifyResponse {
             user: user.into(),
             token,
@@ -291,16 +249,25 @@ pub async fn verify_email(
 }
 
 // POST /auth/resend-code
+#[utoipa::path(
+    post,
+    path = "/auth/resend-code",
+    tag = "auth",
+    request_body = ResendCodeRequest,
+    responses(
+        (status = 200, description = "New code sent", body = MessageResponse),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    )
+)]
 pub async fn resend_code(
     pool: web::Data<MySqlPool>,
     config: web::Data<Config>,
     body: web::Json<ResendCodeRequest>,
 ) -> Result<HttpResponse, ApiError> {
-    let email = body.email.trim().to_lowercase();
+    body.validate()?;
 
-    if email.is_empty() {
-        return Err(ApiError::bad_request("Email é obrigatório"));
-    }
+    let email = body.email.trim().to_lowercase();
 
     let user: Option<User> = sqlx::query_as(
         "SELECT * FROM users WHERE email = ?"
@@ -310,10 +277,10 @@ pub async fn resend_code(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let user = user.ok_or_else(|| ApiError::not_found("Email não encontrado"))?;
+    let user = user.ok_or_else(|| ApiError::not_found(Msg::EmailNotFound))?;
 
     if user.is_verified {
-        return Err(ApiError::bad_request("Email já verificado"));
+        return Err(ApiError::bad_request(Msg::EmailAlreadyVerified));
     }
 
     let code = generate_verification_code();
@@ -329,22 +296,31 @@ pub async fn resend_code(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    send_verification_email(&config, &email, &user.username, &code).await;
+    send_verification_email(pool.get_ref(), &config, user_locale(pool.get_ref(), &user.id).await, &email, &user.username, &code).await?;
 
-    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data("Novo código enviado para o email")))
+    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data(Msg::NewCodeSent)))
 }
 
 // POST /auth/forgot-password
+#[utoipa::path(
+    post,
+    path = "/auth/forgot-password",
+    tag = "auth",
+    request_body = ForgotPasswordRequest,
+    responses(
+        (status = 200, description = "Reset code sent", body = ApiResponse<ForgotPasswordResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    )
+)]
 pub async fn forgot_password(
     pool: web::Data<MySqlPool>,
     config: web::Data<Config>,
     body: web::Json<ForgotPasswordRequest>,
 ) -> Result<HttpResponse, ApiError> {
-    let email = body.email.trim().to_lowercase();
+    body.validate()?;
 
-    if email.is_empty() {
-        return Err(ApiError::bad_request("Email é obrigatório"));
-    }
+    let email = body.email.trim().to_lowercase();
 
     let user: Option<User> = sqlx::query_as(
         "SELECT * FROM users WHERE email = ?"
@@ -354,7 +330,7 @@ pub async fn forgot_password(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let user = user.ok_or_else(|| ApiError::not_found("Email não encontrado"))?;
+    let user = user.ok_or_else(|| ApiError::not_found(Msg::EmailNotFound))?;
 
     let code = generate_verification_code();
     let expires_at = Utc::now() + Duration::minutes(15);
@@ -369,26 +345,35 @@ pub async fn forgot_password(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    send_password_reset_email(&config, &email, &user.username, &code).await;
+    send_password_reset_email(pool.get_ref(), &config, user_locale(pool.get_ref(), &user.id).await, &email, &user.username, &code).await?;
 
     Ok(HttpResponse::Ok().json(ApiResponse::success(
-        "Código de recuperação enviado para o email",
+        Msg::ResetCodeSent,
         ForgotPasswordResponse { email },
     )))
 }
 
 // POST /auth/verify-reset-code
+#[utoipa::path(
+    post,
+    path = "/auth/verify-reset-code",
+    tag = "auth",
+    request_body = VerifyResetCodeRequest,
+    responses(
+        (status = 200, description = "Code verified", body = ApiResponse<VerifyResetCodeResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    )
+)]
 pub async fn verify_reset_code(
     pool: web::Data<MySqlPool>,
     body: web::Json<VerifyResetCodeRequest>,
 ) -> Result<HttpResponse, ApiError> {
+    body.validate()?;
+
     let email = body.email.trim().to_lowercase();
     let code = body.code.trim();
 
-    if email.is_empty() || code.is_empty() {
-        return Err(ApiError::bad_request("Email e código são obrigatórios"));
-    }
-
     let user: Option<User> = sqlx::query_as(
         "SELECT * FROM users WHERE email = ?"
     )
@@ -397,16 +382,16 @@ pub async fn verify_reset_code(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let user = user.ok_or_else(|| ApiError::not_found("Email não encontrado"))?;
+    let user = user.ok_or_else(|| ApiError::not_found(Msg::EmailNotFound))?;
 
     let stored_code = user.verification_code.as_deref().unwrap_or("");
     if stored_code != code {
-        return Err(ApiError::bad_request("Código inválido"));
+        return Err(ApiError::bad_request(Msg::InvalidCode));
     }
 
     if let Some(expires_at) = user.code_expires_at {
         if expires_at < Utc::now() {
-            return Err(ApiError::bad_request("Código expirado. Solicite um novo."));
+            return Err(ApiError::bad_request(Msg::CodeExpired));
         }
     }
 
@@ -423,32 +408,33 @@ pub async fn verify_reset_code(
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
     Ok(HttpResponse::Ok().json(ApiResponse::success(
-        "Código verificado",
+        Msg::CodeVerified,
         VerifyResetCodeResponse { reset_token },
     )))
 }
 
 // POST /auth/reset-password
+#[utoipa::path(
+    post,
+    path = "/auth/reset-password",
+    tag = "auth",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 200, description = "Password reset", body = MessageResponse),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    )
+)]
 pub async fn reset_password(
     pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
     body: web::Json<ResetPasswordRequest>,
 ) -> Result<HttpResponse, ApiError> {
+    body.validate()?;
+
     let email = body.email.trim().to_lowercase();
     let reset_token = &body.reset_token;
     let new_password = &body.new_password;
-    let confirm_password = &body.confirm_password;
-
-    if email.is_empty() || reset_token.is_empty() || new_password.is_empty() || confirm_password.is_empty() {
-        return Err(ApiError::bad_request("Preencha todos os campos"));
-    }
-
-    if new_password != confirm_password {
-        return Err(ApiError::bad_request("As senhas não coincidem"));
-    }
-
-    if new_password.len() < 6 {
-        return Err(ApiError::bad_request("Senha deve ter no mínimo 6 caracteres"));
-    }
 
     let user: Option<User> = sqlx::query_as(
         "SELECT * FROM users WHERE email = ?"
@@ -458,15 +444,16 @@ pub async fn reset_password(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let user = user.ok_or_else(|| ApiError::not_found("Email não encontrado"))?;
+    let user = user.ok_or_else(|| ApiError::not_found(Msg::EmailNotFound))?;
 
     let stored_token = user.verification_code.as_deref().unwrap_or("");
     if stored_token != reset_token {
-        return Err(ApiError::bad_request("Token inválido"));
+        return Err(ApiError::bad_request(Msg::InvalidResetToken));
     }
 
-    let password_hash = bcrypt::hash(new_password, bcrypt::DEFAULT_COST)
-        .map_err(|_| ApiError::internal("Failed to hash password"))?;
+    password::check_personal_info("new_password", new_password, &user.username, &user.email)?;
+
+    let password_hash = password::hash(new_password, config.password_hashing).await?;
 
     sqlx::query(
         "UPDATE users SET password_hash = ?, verification_code = NULL, code_expires_at = NULL WHERE id = ?"
@@ -477,22 +464,32 @@ pub async fn reset_password(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data("Senha alterada com sucesso!")))
+    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data(Msg::PasswordReset)))
 }
 
 // POST /auth/login
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Logged in", body = ApiResponse<LoginResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 403, description = "Email not verified", body = MessageResponse),
+    )
+)]
 pub async fn login(
     pool: web::Data<MySqlPool>,
     config: web::Data<Config>,
     body: web::Json<LoginRequest>,
 ) -> Result<HttpResponse, ApiError> {
+    body.validate()?;
+
     let email = body.email.trim().to_lowercase();
     let password = &body.password;
 
-    if email.is_empty() || password.is_empty() {
-        return Err(ApiError::bad_request("Preencha email e senha"));
-    }
-
     let user: Option<User> = sqlx::query_as(
         "SELECT * FROM users WHERE email = ?"
     )
@@ -501,13 +498,17 @@ pub async fn login(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let user = user.ok_or_else(|| ApiError::unauthorized("Email ou senha incorretos"))?;
+    let user = user.ok_or_else(|| ApiError::unauthorized(Msg::InvalidCredentials))?;
 
-    let valid = bcrypt::verify(password, &user.password_hash)
-        .map_err(|_| ApiError::internal("Password verification failed"))?;
+    let valid = password::verify(password, &user.password_hash).await?;
 
     if !valid {
-        return Err(ApiError::unauthorized("Email ou senha incorretos"));
+        return Err(ApiError::unauthorized(Msg::InvalidCredentials));
+    }
+
+    // Upgrade bcrypt and outdated Argon2 hashes while the plain password is at hand
+    if password::needs_rehash(&user.password_hash, config.password_hashing) {
+        rehash_password(pool.get_ref(), &config, &user.id, password).await;
     }
 
     if !user.is_verified {
@@ -525,15 +526,15 @@ pub async fn login(
         .await
         .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-        send_verification_email(&config, &email, &user.username, &code).await;
+        send_verification_email(pool.get_ref(), &config, user_locale(pool.get_ref(), &user.id).await, &email, &user.username, &code).await?;
 
-        return Err(ApiError::forbidden("Conta não verificada. Código enviado para o email."));
+        return Err(ApiError::forbidden(Msg::AccountNotVerified));
     }
 
     let token = create_token(&config, &user.id, &user.email)?;
 
     Ok(HttpResponse::Ok().json(ApiResponse::success(
-        "Login realizado com sucesso",
+        Msg::LoggedIn,
         LoginResponse {
             user: user.into(),
             token,
@@ -542,6 +543,17 @@ pub async fn login(
 }
 
 // GET /auth/me
+#[utoipa::path(
+    get,
+    path = "/auth/me",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Current user", body = ApiResponse<UserPublic>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn me(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
@@ -557,12 +569,24 @@ pub async fn me(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let user = user.ok_or_else(|| ApiError::not_found("Usuário não encontrado"))?;
+    let user = user.ok_or_else(|| ApiError::not_found(Msg::UserNotFound))?;
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success("Dados do usuário", UserPublic::from(user))))
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::UserData, UserPublic::from(user))))
 }
 
 // PUT /auth/update-username
+#[utoipa::path(
+    put,
+    path = "/auth/update-username",
+    tag = "auth",
+    request_body = UpdateUsernameRequest,
+    responses(
+        (status = 200, description = "Username updated", body = ApiResponse<UpdateUsernameResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn update_username(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
@@ -570,15 +594,9 @@ pub async fn update_username(
     body: web::Json<UpdateUsernameRequest>,
 ) -> Result<HttpResponse, ApiError> {
     let claims = get_auth_user(&req, &config)?;
-    let username = body.username.trim();
+    body.validate()?;
 
-    if username.is_empty() {
-        return Err(ApiError::bad_request("Nome de usuário é obrigatório"));
-    }
-
-    if username.len() < 3 {
-        return Err(ApiError::bad_request("Nome deve ter no mínimo 3 caracteres"));
-    }
+    let username = body.username.trim();
 
     sqlx::query("UPDATE users SET username = ? WHERE id = ?")
         .bind(username)
@@ -588,7 +606,7 @@ pub async fn update_username(
         .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
     Ok(HttpResponse::Ok().json(ApiResponse::success(
-        "Nome atualizado com sucesso",
+        Msg::UsernameUpdated,
         UpdateUsernameResponse {
             username: username.to_string(),
         },
@@ -596,6 +614,19 @@ pub async fn update_username(
 }
 
 // PUT /auth/update-email
+#[utoipa::path(
+    put,
+    path = "/auth/update-email",
+    tag = "auth",
+    request_body = UpdateEmailRequest,
+    responses(
+        (status = 200, description = "Confirmation code sent to the new email", body = ApiResponse<EmailChangeResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 409, description = "Email already in use", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn update_email(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
@@ -603,21 +634,11 @@ pub async fn update_email(
     body: web::Json<UpdateEmailRequest>,
 ) -> Result<HttpResponse, ApiError> {
     let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+
     let new_email = body.email.trim().to_lowercase();
     let password = &body.password;
 
-    if new_email.is_empty() {
-        return Err(ApiError::bad_request("Email é obrigatório"));
-    }
-
-    if !new_email.contains('@') {
-        return Err(ApiError::bad_request("Email inválido"));
-    }
-
-    if password.is_empty() {
-        return Err(ApiError::bad_request("Senha atual é obrigatória"));
-    }
-
     let user: Option<User> = sqlx::query_as(
         "SELECT * FROM users WHERE id = ?"
     )
@@ -626,48 +647,349 @@ pub async fn update_email(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let user = user.ok_or_else(|| ApiError::not_found("Usuário não encontrado"))?;
+    let user = user.ok_or_else(|| ApiError::not_found(Msg::UserNotFound))?;
 
-    let valid = bcrypt::verify(password, &user.password_hash)
-        .map_err(|_| ApiError::internal("Password verification failed"))?;
+    let valid = password::verify(password, &user.password_hash).await?;
 
     if !valid {
-        return Err(ApiError::unauthorized("Senha incorreta"));
+        return Err(ApiError::unauthorized(Msg::WrongPassword));
     }
 
-    // Check if email is already in use
-    let existing: Option<(String,)> = sqlx::query_as(
-        "SELECT id FROM users WHERE email = ? AND id != ?"
+    if email_taken(pool.get_ref(), &new_email, &claims.user_id).await? {
+        return Err(ApiError::conflict(Msg::EmailInUse));
+    }
+
+    // Only the latest request can be confirmed
+    sqlx::query("DELETE FROM email_changes WHERE user_id = ? AND confirmed_at IS NULL")
+        .bind(&claims.user_id)
+        .execute(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let code = generate_verification_code();
+    let now = Utc::now();
+    let code_expires_at = now + Duration::minutes(15);
+
+    sqlx::query(
+        r#"
+        INSERT INTO email_changes (id, user_id, old_email, new_email, code, code_expires_at, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#
     )
+    .bind(generate_uuid())
+    .bind(&claims.user_id)
+    .bind(&user.email)
     .bind(&new_email)
+    .bind(&code)
+    .bind(code_expires_at)
+    .bind(now)
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let locale = user_locale(pool.get_ref(), &user.id).await;
+    let context = context! { name => &user.username, email => &new_email, code => &code };
+    mail::send(pool.get_ref(), &config, MailKind::EmailChange, locale, &new_email, context).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(
+        Msg::EmailChangeRequested,
+        EmailChangeResponse {
+            pending_email: new_email,
+            code_expires_at,
+        },
+    )))
+}
+
+// POST /auth/confirm-email-change
+#[utoipa::path(
+    post,
+    path = "/auth/confirm-email-change",
+    tag = "auth",
+    request_body = ConfirmEmailChangeRequest,
+    responses(
+        (status = 200, description = "Email updated, new token issued", body = ApiResponse<UpdateEmailResponse>),
+        (status = 400, description = "Invalid or expired code", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "No pending email change", body = MessageResponse),
+        (status = 409, description = "Email already in use", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn confirm_email_change(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    body: web::Json<ConfirmEmailChangeRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+
+    let change: Option<EmailChange> = sqlx::query_as(
+        r#"
+        SELECT id, user_id, old_email, new_email, code, code_expires_at
+        FROM email_changes
+        WHERE user_id = ? AND confirmed_at IS NULL
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#
+    )
     .bind(&claims.user_id)
     .fetch_optional(pool.get_ref())
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    if existing.is_some() {
-        return Err(ApiError::conflict("Email já está em uso"));
+    let change = change.ok_or_else(|| ApiError::not_found(Msg::NoPendingEmailChange))?;
+
+    if change.code != body.code.trim() {
+        return Err(ApiError::bad_request(Msg::InvalidCode));
     }
 
+    let now = Utc::now();
+    if change.code_expires_at < now {
+        return Err(ApiError::bad_request(Msg::CodeExpired));
+    }
+
+    // The address may have been registered since the request
+    if email_taken(pool.get_ref(), &change.new_email, &change.user_id).await? {
+        return Err(ApiError::conflict(Msg::EmailInUse));
+    }
+
+    let revert_token = generate_revert_token();
+    let revert_expires_at = now + Duration::days(REVERT_LINK_DAYS);
+
+    let mut tx = pool.begin().await
+        .map_err(|e| ApiError::internal(format!("Transaction error: {}", e)))?;
+
     sqlx::query("UPDATE users SET email = ? WHERE id = ?")
-        .bind(&new_email)
-        .bind(&claims.user_id)
-        .execute(pool.get_ref())
+        .bind(&change.new_email)
+        .bind(&change.user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    sqlx::query(
+        "UPDATE email_changes SET confirmed_at = ?, revert_token = ?, revert_expires_at = ? WHERE id = ?"
+    )
+    .bind(now)
+    .bind(&revert_token)
+    .bind(revert_expires_at)
+    .bind(&change.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    tx.commit().await
+        .map_err(|e| ApiError::internal(format!("Commit error: {}", e)))?;
+
+    let username: Option<(String,)> = sqlx::query_as("SELECT username FROM users WHERE id = ?")
+        .bind(&change.user_id)
+        .fetch_optional(pool.get_ref())
         .await
         .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let token = create_token(&config, &claims.user_id, &new_email)?;
+    // Notify the old address, with a way back in case the account was taken over
+    let locale = user_locale(pool.get_ref(), &change.user_id).await;
+    let context = context! {
+        name => username.map(|(name,)| name).unwrap_or_default(),
+        email => &change.new_email,
+        revert_link => format!("{}/auth/revert-email-change?token={}", config.public_url, revert_token),
+    };
+    mail::send(pool.get_ref(), &config, MailKind::EmailChanged, locale, &change.old_email, context).await?;
+
+    let token = create_token(&config, &change.user_id, &change.new_email)?;
 
     Ok(HttpResponse::Ok().json(ApiResponse::success(
-        "Email atualizado com sucesso",
+        Msg::EmailUpdated,
         UpdateEmailResponse {
-            email: new_email,
+            email: change.new_email,
             token,
         },
     )))
 }
 
+// Helper: Confirmed change a revert token still undoes
+async fn revertable_change(pool: &MySqlPool, token: &str) -> Result<Option<RevertableEmailChange>, ApiError> {
+    sqlx::query_as(
+        r#"
+        SELECT id, user_id, old_email, confirmed_at
+        FROM email_changes
+        WHERE revert_token = ? AND reverted_at IS NULL AND revert_expires_at > ?
+        "#
+    )
+    .bind(token.trim())
+    .bind(Utc::now())
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))
+}
+
+// Helper: The page behind the revert link, in the request's locale. The token travels in
+// the URL, so the page is kept out of frames and referrers.
+fn revert_page(config: &Config, status: StatusCode, context: minijinja::Value) -> Result<HttpResponse, ApiError> {
+    let html = mail::templates::render_page(config, "revert_email_change", i18n::current(), context)?;
+    Ok(HttpResponse::build(status)
+        .content_type("text/html; charset=utf-8")
+        .insert_header(("X-Frame-Options", "DENY"))
+        .insert_header(("Content-Security-Policy", "frame-ancestors 'none'"))
+        .insert_header(("Referrer-Policy", "no-referrer"))
+        .insert_header(("Cache-Control", "no-store"))
+        .body(html))
+}
+
+// Helper: Page explaining why the revert can't go ahead
+fn revert_error_page(config: &Config, status: StatusCode, msg: Msg) -> Result<HttpResponse, ApiError> {
+    revert_page(config, status, context! { error => msg.text(i18n::current()) })
+}
+
+// GET /auth/revert-email-change
+#[utoipa::path(
+    get,
+    path = "/auth/revert-email-change",
+    tag = "auth",
+    params(RevertEmailChangeRequest),
+    responses(
+        (status = 200, description = "Confirmation page that posts the token back", content_type = "text/html", body = String),
+        (status = 400, description = "Invalid or expired link", content_type = "text/html", body = String),
+    )
+)]
+pub async fn revert_email_change_page(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    query: web::Query<RevertEmailChangeRequest>,
+) -> Result<HttpResponse, ApiError> {
+    // Opening the link changes nothing, so mail scanners that follow it are harmless
+    let change = match query.validate() {
+        Ok(()) => revertable_change(pool.get_ref(), &query.token).await?,
+        Err(_) => None,
+    };
+    let Some(change) = change else {
+        return revert_error_page(&config, StatusCode::BAD_REQUEST, Msg::InvalidRevertLink);
+    };
+
+    revert_page(&config, StatusCode::OK, context! {
+        email => change.old_email,
+        token => query.token.trim(),
+        action => format!("{}/auth/revert-email-change", config.public_url),
+    })
+}
+
+// POST /auth/revert-email-change
+#[utoipa::path(
+    post,
+    path = "/auth/revert-email-change",
+    tag = "auth",
+    request_body(content = RevertEmailChangeRequest, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Previous email restored; sessions, access tokens and the password revoked", content_type = "text/html", body = String),
+        (status = 400, description = "Invalid or expired link", content_type = "text/html", body = String),
+        (status = 409, description = "Previous email now belongs to another account", content_type = "text/html", body = String),
+    )
+)]
+pub async fn revert_email_change(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    form: web::Form<RevertEmailChangeRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let change = match form.validate() {
+        Ok(()) => revertable_change(pool.get_ref(), &form.token).await?,
+        Err(_) => None,
+    };
+    let Some(change) = change else {
+        return revert_error_page(&config, StatusCode::BAD_REQUEST, Msg::InvalidRevertLink);
+    };
+
+    if email_taken(pool.get_ref(), &change.old_email, &change.user_id).await? {
+        return revert_error_page(&config, StatusCode::CONFLICT, Msg::EmailInUse);
+    }
+
+    // Whoever changed the email may know the password, so it is replaced by one nobody
+    // knows and a reset code goes to the restored address
+    let secret: [u8; 32] = rand::thread_rng().gen();
+    let secret: String = secret.iter().map(|b| format!("{:02x}", b)).collect();
+    let password_hash = password::hash(&secret, config.password_hashing).await?;
+    let code = generate_verification_code();
+    let now = Utc::now();
+
+    let mut tx = pool.begin().await
+        .map_err(|e| ApiError::internal(format!("Transaction error: {}", e)))?;
+
+    // Login JWTs issued before `sessions_revoked_at` are refused by `get_auth_user`
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET email = ?, password_hash = ?, verification_code = ?, code_expires_at = ?, sessions_revoked_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(&change.old_email)
+    .bind(&password_hash)
+    .bind(&code)
+    .bind(now + Duration::minutes(15))
+    .bind(now)
+    .bind(&change.user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    sqlx::query("UPDATE api_tokens SET revoked_at = ? WHERE user_id = ? AND revoked_at IS NULL")
+        .bind(now)
+        .bind(&change.user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    // Providers linked since the change could log the other party back in
+    sqlx::query("DELETE FROM user_identities WHERE user_id = ? AND created_at >= ?")
+        .bind(&change.user_id)
+        .bind(change.confirmed_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    sqlx::query("UPDATE email_changes SET reverted_at = ?, revert_token = NULL WHERE id = ?")
+        .bind(now)
+        .bind(&change.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    // Drop anything the other party may have started since
+    sqlx::query("DELETE FROM email_changes WHERE user_id = ? AND confirmed_at IS NULL")
+        .bind(&change.user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    tx.commit().await
+        .map_err(|e| ApiError::internal(format!("Commit error: {}", e)))?;
+
+    tracing::info!(user_id = %change.user_id, "Email change reverted, sessions revoked");
+
+    let username: Option<(String,)> = sqlx::query_as("SELECT username FROM users WHERE id = ?")
+        .bind(&change.user_id)
+        .fetch_optional(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+    let locale = user_locale(pool.get_ref(), &change.user_id).await;
+    let username = username.map(|(name,)| name).unwrap_or_default();
+    send_password_reset_email(pool.get_ref(), &config, locale, &change.old_email, &username, &code).await?;
+
+    revert_page(&config, StatusCode::OK, context! { done => true, email => change.old_email })
+}
+
 // PUT /auth/update-password
+#[utoipa::path(
+    put,
+    path = "/auth/update-password",
+    tag = "auth",
+    request_body = UpdatePasswordRequest,
+    responses(
+        (status = 200, description = "Password updated", body = MessageResponse),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn update_password(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
@@ -675,21 +997,10 @@ pub async fn update_password(
     body: web::Json<UpdatePasswordRequest>,
 ) -> Result<HttpResponse, ApiError> {
     let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+
     let current_password = &body.current_password;
     let new_password = &body.new_password;
-    let confirm_password = &body.confirm_password;
-
-    if current_password.is_empty() || new_password.is_empty() || confirm_password.is_empty() {
-        return Err(ApiError::bad_request("Preencha todos os campos"));
-    }
-
-    if new_password != confirm_password {
-        return Err(ApiError::bad_request("As senhas não coincidem"));
-    }
-
-    if new_password.len() < 6 {
-        return Err(ApiError::bad_request("Senha deve ter no mínimo 6 caracteres"));
-    }
 
     let user: Option<User> = sqlx::query_as(
         "SELECT * FROM users WHERE id = ?"
@@ -699,17 +1010,17 @@ pub async fn update_password(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let user = user.ok_or_else(|| ApiError::not_found("Usuário não encontrado"))?;
+    let user = user.ok_or_else(|| ApiError::not_found(Msg::UserNotFound))?;
 
-    let valid = bcrypt::verify(current_password, &user.password_hash)
-        .map_err(|_| ApiError::internal("Password verification failed"))?;
+    let valid = password::verify(current_password, &user.password_hash).await?;
 
     if !valid {
-        return Err(ApiError::unauthorized("Senha atual incorreta"));
+        return Err(ApiError::unauthorized(Msg::WrongCurrentPassword));
     }
 
-    let password_hash = bcrypt::hash(new_password, bcrypt::DEFAULT_COST)
-        .map_err(|_| ApiError::internal("Failed to hash password"))?;
+    password::check_personal_info("new_password", new_password, &user.username, &user.email)?;
+
+    let password_hash = password::hash(new_password, config.password_hashing).await?;
 
     sqlx::query("UPDATE users SET password_hash = ? WHERE id = ?")
         .bind(&password_hash)
@@ -718,5 +1029,43 @@ pub async fn update_password(
         .await
         .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data("Senha atualizada com sucesso")))
+    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data(Msg::PasswordUpdated)))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+
+    use super::*;
+    use crate::test_support::lazy_pool;
+
+    // Requests rejected before any query, so the pool never connects
+    #[actix_web::test]
+    async fn revert_link_without_token_shows_an_error_page() {
+        let config = Config::for_tests();
+        let pool = lazy_pool(&config);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(web::Data::new(config))
+                .route("/auth/revert-email-change", web::get().to(revert_email_change_page))
+                .route("/auth/revert-email-change", web::post().to(revert_email_change)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/auth/revert-email-change?token=%20").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(res.headers().get("X-Frame-Options").unwrap(), "DENY");
+        let body = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        assert!(body.contains(Msg::InvalidRevertLink.text(Locale::default())));
+        assert!(!body.contains("<form"));
+
+        let req = test::TestRequest::post()
+            .uri("/auth/revert-email-change")
+            .set_form([("token", "")])
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
 }
@@ -0,0 +1,775 @@
+use actix_web::http::{header, Method, StatusCode};
+use actix_web::{web, HttpRequest, HttpResponse};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use sqlx::MySqlPool;
+
+use crate::caldav::{self, InvalidXml, Multistatus, Prop, Report, APPLE_ICAL, CALDAV, CALENDARSERVER, DAV};
+use crate::errors::ApiError;
+use crate::events::{self, EventHub};
+use crate::handlers::lists::delete_list_tasks;
+use crate::handlers::settings::load_settings;
+use crate::i18n::Msg;
+use crate::ics;
+use crate::middleware::api_token::{find_token, ApiTokenAuth, TOKEN_PREFIX};
+use crate::models::*;
+use crate::webhooks;
+
+const PRINCIPAL: &str = "/dav/principal/";
+const HOME: &str = "/dav/calendars/";
+const ALLOW: &str = "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, REPORT, MKCALENDAR";
+const DEFAULT_COLOR: &str = "#3B82F6";
+const DEFAULT_LIST_NAME: &str = "Lista";
+
+// A task served as a VTODO resource, with the ETag of its current rendering
+struct CalendarObject {
+    task: Task,
+    data: String,
+    etag: String,
+}
+
+impl CalendarObject {
+    fn new(task: Task, tz: Tz) -> Self {
+        let data = ics::render_todo(&task, tz);
+        let etag = format!("\"{:x}\"", md5::compute(&data));
+        Self { task, data, etag }
+    }
+
+    fn path(&self) -> String {
+        format!("{}{}/{}.ics", HOME, self.task.list_id, self.task.id)
+    }
+}
+
+// Helper: Personal access token sent as the Basic auth password. The username is ignored.
+async fn authenticate(req: &HttpRequest, pool: &MySqlPool) -> Option<ApiTokenAuth> {
+    let encoded = req
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Basic ")?;
+    let decoded = base64::engine::general_purpose::STANDARD.decode(encoded.trim()).ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (_, password) = decoded.split_once(':')?;
+
+    if !password.starts_with(TOKEN_PREFIX) {
+        return None;
+    }
+
+    find_token(pool, password).await
+}
+
+// Helper: 401 that makes clients ask for credentials
+fn challenge() -> HttpResponse {
+    HttpResponse::Unauthorized()
+        .insert_header((header::WWW_AUTHENTICATE, "Basic realm=\"MyTudo\", charset=\"UTF-8\""))
+        .finish()
+}
+
+fn require(auth: &ApiTokenAuth, scopes: &[Scope]) -> Result<(), ApiError> {
+    if scopes.iter().all(|scope| auth.scopes.contains(scope)) {
+        Ok(())
+    } else {
+        Err(ApiError::forbidden(Msg::InsufficientScope))
+    }
+}
+
+fn options() -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(("DAV", "1, 3, calendar-access"))
+        .insert_header((header::ALLOW, ALLOW))
+        .finish()
+}
+
+fn not_allowed() -> HttpResponse {
+    HttpResponse::MethodNotAllowed()
+        .insert_header((header::ALLOW, ALLOW))
+        .finish()
+}
+
+fn multistatus(body: Multistatus) -> HttpResponse {
+    HttpResponse::build(StatusCode::MULTI_STATUS)
+        .content_type("application/xml; charset=utf-8")
+        .body(body.finish())
+}
+
+fn invalid_xml(_: InvalidXml) -> ApiError {
+    ApiError::bad_request(Msg::InvalidRequestBody)
+}
+
+// Helper: `Depth: 0` or anything deeper, which is answered as 1
+fn shallow(req: &HttpRequest) -> bool {
+    req.headers().get("Depth").and_then(|h| h.to_str().ok()) == Some("0")
+}
+
+fn calendar_path(list_id: &str) -> String {
+    format!("{}{}/", HOME, list_id)
+}
+
+// Helper: Ids clients may choose for lists and tasks; they end up in hrefs as they are
+fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 36
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+// Helper: Task id from `{id}.ics` or a full href ending in it
+fn resource_id(href: &str) -> Option<&str> {
+    let name = href.trim_end_matches('/').rsplit('/').next()?;
+    name.strip_suffix(".ics").filter(|id| valid_id(id))
+}
+
+// Helper: If-Match / If-None-Match against the current ETag (`None` if there is no resource)
+fn check_preconditions(req: &HttpRequest, etag: Option<&str>) -> Result<(), ApiError> {
+    let tags = |name: header::HeaderName| {
+        req.headers()
+            .get(name)
+            .and_then(|h| h.to_str().ok())
+            .map(|v| v.split(',').map(str::trim).map(str::to_string).collect::<Vec<_>>())
+    };
+    let matches = |tags: &[String]| {
+        tags.iter().any(|tag| (tag == "*" && etag.is_some()) || Some(tag.as_str()) == etag)
+    };
+
+    let failed = tags(header::IF_MATCH).is_some_and(|t| !matches(&t))
+        || tags(header::IF_NONE_MATCH).is_some_and(|t| matches(&t));
+
+    if failed {
+        return Err(ApiError::new(Msg::PreconditionFailed, StatusCode::PRECONDITION_FAILED));
+    }
+    Ok(())
+}
+
+// Helper: Collection tag that changes whenever the list or any of its tasks does
+fn ctag<'a>(list: &TodoList, objects: impl Iterator<Item = &'a CalendarObject>) -> String {
+    let mut context = md5::Context::new();
+    context.consume(list.name.as_bytes());
+    context.consume(list.color.as_bytes());
+    for object in objects {
+        context.consume(object.etag.as_bytes());
+    }
+    format!("\"{:x}\"", context.compute())
+}
+
+fn principal_props(auth: &ApiTokenAuth) -> Vec<Prop> {
+    vec![
+        Prop::new(DAV, "resourcetype", "<d:collection/><d:principal/>"),
+        Prop::new(DAV, "displayname", caldav::escape(&auth.email)),
+        Prop::new(DAV, "current-user-principal", caldav::href(PRINCIPAL)),
+        Prop::new(DAV, "principal-URL", caldav::href(PRINCIPAL)),
+        Prop::new(CALDAV, "calendar-home-set", caldav::href(HOME)),
+        Prop::new(CALDAV, "calendar-user-address-set", caldav::href(&format!("mailto:{}", auth.email))),
+    ]
+}
+
+fn home_props() -> Vec<Prop> {
+    vec![
+        Prop::new(DAV, "resourcetype", "<d:collection/>"),
+        Prop::new(DAV, "displayname", "MyTudo"),
+        Prop::new(DAV, "current-user-principal", caldav::href(PRINCIPAL)),
+        Prop::new(DAV, "owner", caldav::href(PRINCIPAL)),
+    ]
+}
+
+fn calendar_props(list: &TodoList, ctag: String, auth: &ApiTokenAuth) -> Vec<Prop> {
+    let mut privileges = String::from("<d:privilege><d:read/></d:privilege>");
+    if auth.scopes.contains(&Scope::TasksWrite) {
+        privileges.push_str("<d:privilege><d:write-content/></d:privilege><d:privilege><d:bind/></d:privilege><d:privilege><d:unbind/></d:privilege>");
+    }
+
+    vec![
+        Prop::new(DAV, "resourcetype", "<d:collection/><c:calendar/>"),
+        Prop::new(DAV, "displayname", caldav::escape(&list.name)),
+        Prop::new(DAV, "current-user-principal", caldav::href(PRINCIPAL)),
+        Prop::new(DAV, "owner", caldav::href(PRINCIPAL)),
+        Prop::new(DAV, "current-user-privilege-set", privileges),
+        Prop::new(
+            DAV,
+            "supported-report-set",
+            "<d:supported-report><d:report><c:calendar-multiget/></d:report></d:supported-report>\
+             <d:supported-report><d:report><c:calendar-query/></d:report></d:supported-report>",
+        ),
+        Prop::new(CALDAV, "supported-calendar-component-set", "<c:comp name=\"VTODO\"/>"),
+        Prop::new(CALENDARSERVER, "getctag", caldav::escape(&ctag)),
+        Prop::new(APPLE_ICAL, "calendar-color", caldav::escape(&list.color)),
+    ]
+}
+
+fn object_props(object: &CalendarObject) -> Vec<Prop> {
+    vec![
+        Prop::new(DAV, "resourcetype", ""),
+        Prop::new(DAV, "getetag", caldav::escape(&object.etag)),
+        Prop::new(DAV, "getcontenttype", "text/calendar; charset=utf-8; component=VTODO"),
+        Prop::new(DAV, "getlastmodified", object.task.updated_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+        Prop::new(CALDAV, "calendar-data", caldav::escape(&object.data)),
+    ]
+}
+
+// Helper: Load the user's lists
+async fn load_lists(pool: &MySqlPool, user_id: &str) -> Result<Vec<TodoList>, ApiError> {
+    sqlx::query_as(
+        r#"
+        SELECT id, user_id, name, color, created_at, updated_at, deleted_at
+        FROM todo_lists
+        WHERE user_id = ? AND deleted_at IS NULL
+        ORDER BY created_at
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))
+}
+
+// Helper: Load one of the user's lists
+async fn load_list(pool: &MySqlPool, user_id: &str, list_id: &str) -> Result<TodoList, ApiError> {
+    let list: Option<TodoList> = sqlx::query_as(
+        "SELECT id, user_id, name, color, created_at, updated_at, deleted_at FROM todo_lists WHERE id = ? AND user_id = ? AND deleted_at IS NULL"
+    )
+    .bind(list_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    list.ok_or_else(|| ApiError::not_found(Msg::ListNotFound))
+}
+
+// Helper: Live tasks of the user, of one list or all of them
+async fn load_objects(
+    pool: &MySqlPool,
+    user_id: &str,
+    list_id: Option<&str>,
+    tz: Tz,
+) -> Result<Vec<CalendarObject>, ApiError> {
+    let tasks: Vec<Task> = sqlx::query_as(
+        r#"
+        SELECT t.* FROM tasks t
+        JOIN todo_lists l ON t.list_id = l.id
+        WHERE l.user_id = ? AND (? IS NULL OR t.list_id = ?) AND t.deleted_at IS NULL AND l.deleted_at IS NULL
+        ORDER BY t.id
+        "#
+    )
+    .bind(user_id)
+    .bind(list_id)
+    .bind(list_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    Ok(tasks.into_iter().map(|task| CalendarObject::new(task, tz)).collect())
+}
+
+// Helper: One live task in a list of the user
+async fn load_object(
+    pool: &MySqlPool,
+    user_id: &str,
+    list_id: &str,
+    task_id: &str,
+    tz: Tz,
+) -> Result<Option<CalendarObject>, ApiError> {
+    let task: Option<Task> = sqlx::query_as(
+        r#"
+        SELECT t.* FROM tasks t
+        JOIN todo_lists l ON t.list_id = l.id
+        WHERE t.id = ? AND t.list_id = ? AND l.user_id = ? AND t.deleted_at IS NULL AND l.deleted_at IS NULL
+        "#
+    )
+    .bind(task_id)
+    .bind(list_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    Ok(task.map(|task| CalendarObject::new(task, tz)))
+}
+
+// /.well-known/caldav
+pub async fn well_known() -> HttpResponse {
+    HttpResponse::MovedPermanently()
+        .insert_header((header::LOCATION, "/dav/"))
+        .finish()
+}
+
+// /dav/ and /dav/principal/
+pub async fn principal(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    if req.method() == Method::OPTIONS {
+        return Ok(options());
+    }
+    let Some(auth) = authenticate(&req, &pool).await else {
+        return Ok(challenge());
+    };
+
+    match req.method().as_str() {
+        "PROPFIND" => {
+            let request = caldav::parse_propfind(&body).map_err(invalid_xml)?;
+            let mut response = Multistatus::default();
+            response.response(req.path(), principal_props(&auth), &request);
+            Ok(multistatus(response))
+        }
+        _ => Ok(not_allowed()),
+    }
+}
+
+// /dav/calendars/: one calendar collection per list
+pub async fn home(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    if req.method() == Method::OPTIONS {
+        return Ok(options());
+    }
+    let Some(auth) = authenticate(&req, &pool).await else {
+        return Ok(challenge());
+    };
+
+    match req.method().as_str() {
+        "PROPFIND" => {
+            require(&auth, &[Scope::ListsRead, Scope::TasksRead])?;
+            let request = caldav::parse_propfind(&body).map_err(invalid_xml)?;
+
+            let mut response = Multistatus::default();
+            response.response(HOME, home_props(), &request);
+
+            if !shallow(&req) {
+                let tz = load_settings(pool.get_ref(), &auth.user_id).await?.tz();
+                let lists = load_lists(pool.get_ref(), &auth.user_id).await?;
+                let objects = load_objects(pool.get_ref(), &auth.user_id, None, tz).await?;
+
+                for list in &lists {
+                    let ctag = ctag(list, objects.iter().filter(|o| o.task.list_id == list.id));
+                    response.response(&calendar_path(&list.id), calendar_props(list, ctag, &auth), &request);
+                }
+            }
+
+            Ok(multistatus(response))
+        }
+        _ => Ok(not_allowed()),
+    }
+}
+
+// /dav/calendars/{list_id}/
+pub async fn calendar(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    events: web::Data<EventHub>,
+    path: web::Path<String>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    if req.method() == Method::OPTIONS {
+        return Ok(options());
+    }
+    let Some(auth) = authenticate(&req, &pool).await else {
+        return Ok(challenge());
+    };
+    let list_id = path.into_inner();
+
+    match req.method().as_str() {
+        "PROPFIND" => {
+            require(&auth, &[Scope::ListsRead, Scope::TasksRead])?;
+            let request = caldav::parse_propfind(&body).map_err(invalid_xml)?;
+            let list = load_list(pool.get_ref(), &auth.user_id, &list_id).await?;
+            let tz = load_settings(pool.get_ref(), &auth.user_id).await?.tz();
+            let objects = load_objects(pool.get_ref(), &auth.user_id, Some(&list_id), tz).await?;
+
+            let mut response = Multistatus::default();
+            response.response(&calendar_path(&list.id), calendar_props(&list, ctag(&list, objects.iter()), &auth), &request);
+
+            if !shallow(&req) {
+                for object in &objects {
+                    response.response(&object.path(), object_props(object), &request);
+                }
+            }
+
+            Ok(multistatus(response))
+        }
+        "REPORT" => {
+            require(&auth, &[Scope::ListsRead, Scope::TasksRead])?;
+            let report = caldav::parse_report(&body).map_err(invalid_xml)?;
+            load_list(pool.get_ref(), &auth.user_id, &list_id).await?;
+            let tz = load_settings(pool.get_ref(), &auth.user_id).await?.tz();
+            let objects = load_objects(pool.get_ref(), &auth.user_id, Some(&list_id), tz).await?;
+
+            let mut response = Multistatus::default();
+            match report {
+                Report::Multiget { props, hrefs } => {
+                    for href in hrefs {
+                        let id = resource_id(&href);
+                        match objects.iter().find(|o| Some(o.task.id.as_str()) == id) {
+                            Some(object) => response.response(&object.path(), object_props(object), &props),
+                            None => response.not_found(&href),
+                        }
+                    }
+                }
+                Report::Query { props, todos } => {
+                    for object in objects.iter().filter(|_| todos) {
+                        response.response(&object.path(), object_props(object), &props);
+                    }
+                }
+                Report::Unsupported => {
+                    return Ok(HttpResponse::Forbidden()
+                        .content_type("application/xml; charset=utf-8")
+                        .body("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<d:error xmlns:d=\"DAV:\"><d:supported-report/></d:error>"));
+                }
+            }
+
+            Ok(multistatus(response))
+        }
+        "MKCALENDAR" => {
+            require(&auth, &[Scope::ListsWrite])?;
+            if !valid_id(&list_id) {
+                return Err(ApiError::bad_request(Msg::InvalidResourceName));
+            }
+            let (name, color) = caldav::parse_mkcalendar(&body).map_err(invalid_xml)?;
+
+            let existing: Option<(String,)> = sqlx::query_as("SELECT user_id FROM todo_lists WHERE id = ?")
+                .bind(&list_id)
+                .fetch_optional(pool.get_ref())
+                .await
+                .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+            if existing.is_some() {
+                return Err(ApiError::conflict(Msg::ListIdInUse));
+            }
+
+            let name: String = name.unwrap_or_else(|| DEFAULT_LIST_NAME.to_string()).chars().take(255).collect();
+            // Apple sends #RRGGBBAA
+            let color = color
+                .map(|c| c.chars().take(7).collect::<String>())
+                .filter(|c| c.len() == 7 && c.starts_with('#') && c[1..].chars().all(|h| h.is_ascii_hexdigit()))
+                .unwrap_or_else(|| DEFAULT_COLOR.to_string());
+
+            sqlx::query(
+                "INSERT INTO todo_lists (id, user_id, name, color, created_at) VALUES (?, ?, ?, ?, ?)"
+            )
+            .bind(&list_id)
+            .bind(&auth.user_id)
+            .bind(&name)
+            .bind(&color)
+            .bind(Utc::now())
+            .execute(pool.get_ref())
+            .await
+            .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+            events::log_sync(pool.get_ref(), &events, events::device_id(&req).as_deref(), &auth.user_id, "list", &list_id, "create").await;
+
+            Ok(HttpResponse::Created().finish())
+        }
+        "DELETE" => {
+            require(&auth, &[Scope::ListsWrite])?;
+
+            // Soft delete, like DELETE /lists/{id}
+            let result = sqlx::query(
+                "UPDATE todo_lists SET deleted_at = NOW() WHERE id = ? AND user_id = ? AND deleted_at IS NULL"
+            )
+            .bind(&list_id)
+            .bind(&auth.user_id)
+            .execute(pool.get_ref())
+            .await
+            .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+            if result.rows_affected() == 0 {
+                return Err(ApiError::not_found(Msg::ListNotFound));
+            }
+
+            let device_id = events::device_id(&req);
+            delete_list_tasks(pool.get_ref(), &events, device_id.as_deref(), &auth.user_id, &list_id).await?;
+
+            events::log_sync(pool.get_ref(), &events, device_id.as_deref(), &auth.user_id, "list", &list_id, "delete").await;
+
+            Ok(HttpResponse::NoContent().finish())
+        }
+        _ => Ok(not_allowed()),
+    }
+}
+
+// /dav/calendars/{list_id}/{task_id}.ics
+pub async fn calendar_object(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    events: web::Data<EventHub>,
+    path: web::Path<(String, String)>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    if req.method() == Method::OPTIONS {
+        return Ok(options());
+    }
+    let Some(auth) = authenticate(&req, &pool).await else {
+        return Ok(challenge());
+    };
+    let (list_id, resource) = path.into_inner();
+    let task_id = resource_id(&resource).ok_or_else(|| ApiError::bad_request(Msg::InvalidResourceName))?;
+    let tz = load_settings(pool.get_ref(), &auth.user_id).await?.tz();
+
+    match req.method().as_str() {
+        "GET" | "HEAD" => {
+            require(&auth, &[Scope::ListsRead, Scope::TasksRead])?;
+            let object = load_object(pool.get_ref(), &auth.user_id, &list_id, task_id, tz)
+                .await?
+                .ok_or_else(|| ApiError::not_found(Msg::TaskNotFound))?;
+
+            Ok(HttpResponse::Ok()
+                .content_type("text/calendar; charset=utf-8")
+                .insert_header((header::ETAG, object.etag))
+                .body(object.data))
+        }
+        "PROPFIND" => {
+            require(&auth, &[Scope::ListsRead, Scope::TasksRead])?;
+            let request = caldav::parse_propfind(&body).map_err(invalid_xml)?;
+            let object = load_object(pool.get_ref(), &auth.user_id, &list_id, task_id, tz)
+                .await?
+                .ok_or_else(|| ApiError::not_found(Msg::TaskNotFound))?;
+
+            let mut response = Multistatus::default();
+            response.response(&object.path(), object_props(&object), &request);
+            Ok(multistatus(response))
+        }
+        "PUT" => {
+            require(&auth, &[Scope::TasksWrite])?;
+            put_object(&req, &pool, &events, &auth, &list_id, task_id, tz, &body).await
+        }
+        "DELETE" => {
+            require(&auth, &[Scope::TasksWrite])?;
+            let object = load_object(pool.get_ref(), &auth.user_id, &list_id, task_id, tz)
+                .await?
+                .ok_or_else(|| ApiError::not_found(Msg::TaskNotFound))?;
+            check_preconditions(&req, Some(&object.etag))?;
+
+            // Soft delete, like DELETE /tasks/{id}
+            sqlx::query("UPDATE tasks SET deleted_at = NOW() WHERE id = ?")
+                .bind(task_id)
+                .execute(pool.get_ref())
+                .await
+                .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+            events::log_sync(pool.get_ref(), &events, events::device_id(&req).as_deref(), &auth.user_id, "task", task_id, "delete").await;
+
+            Ok(HttpResponse::NoContent().finish())
+        }
+        _ => Ok(not_allowed()),
+    }
+}
+
+// Helper: Create or replace a task from a VTODO. A task of the user with the same id is
+// moved here (and restored, if deleted); ids of other users' tasks are refused.
+#[allow(clippy::too_many_arguments)]
+async fn put_object(
+    req: &HttpRequest,
+    pool: &MySqlPool,
+    events: &EventHub,
+    auth: &ApiTokenAuth,
+    list_id: &str,
+    task_id: &str,
+    tz: Tz,
+    body: &[u8],
+) -> Result<HttpResponse, ApiError> {
+    load_list(pool, &auth.user_id, list_id).await?;
+
+    let current = load_object(pool, &auth.user_id, list_id, task_id, tz).await?;
+    check_preconditions(req, current.as_ref().map(|o| o.etag.as_str()))?;
+
+    let existing: Option<(String, String, bool, Option<DateTime<Utc>>)> = sqlx::query_as(
+        r#"
+        SELECT l.user_id, t.list_id, t.completed, t.deleted_at FROM tasks t
+        JOIN todo_lists l ON t.list_id = l.id
+        WHERE t.id = ?
+        "#
+    )
+    .bind(task_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    if existing.as_ref().is_some_and(|(owner, _, _, _)| *owner != auth.user_id) {
+        return Err(ApiError::conflict(Msg::TaskIdInUse));
+    }
+
+    let todo = std::str::from_utf8(body)
+        .ok()
+        .and_then(|body| ics::parse_todo(body, tz).ok())
+        .ok_or_else(|| ApiError::bad_request(Msg::InvalidCalendarData))?;
+
+    let title: String = todo
+        .summary
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| ApiError::bad_request(Msg::InvalidCalendarData))?
+        .chars()
+        .take(500)
+        .collect();
+    let description = todo.description.filter(|d| !d.trim().is_empty());
+
+    if existing.is_some() {
+        sqlx::query(
+            r#"
+            UPDATE tasks
+            SET list_id = ?, title = ?, description = ?, completed = ?, reminder = ?, due_at = ?, recurrence = ?,
+                updated_at = NOW(), deleted_at = NULL,
+                completed_at = CASE WHEN ? THEN COALESCE(completed_at, NOW()) ELSE NULL END
+            WHERE id = ?
+            "#
+        )
+        .bind(list_id)
+        .bind(&title)
+        .bind(&description)
+        .bind(todo.completed)
+        .bind(todo.reminder)
+        .bind(todo.due_at)
+        .bind(&todo.recurrence)
+        .bind(todo.completed)
+        .bind(task_id)
+        .execute(pool)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+    } else {
+        sqlx::query(
+            r#"
+            INSERT INTO tasks (id, list_id, title, description, completed, reminder, due_at, recurrence, created_at, completed_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, IF(?, NOW(), NULL))
+            "#
+        )
+        .bind(task_id)
+        .bind(list_id)
+        .bind(&title)
+        .bind(&description)
+        .bind(todo.completed)
+        .bind(todo.reminder)
+        .bind(todo.due_at)
+        .bind(&todo.recurrence)
+        .bind(Utc::now())
+        .bind(todo.completed)
+        .execute(pool)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+    }
+
+    // A restored task counts as created
+    let was_live = existing.as_ref().is_some_and(|(_, _, _, deleted_at)| deleted_at.is_none());
+    let action = if was_live { "update" } else { "create" };
+    let device_id = events::device_id(req);
+    events::log_sync(pool, events, device_id.as_deref(), &auth.user_id, "task", task_id, action).await;
+
+    // Moved from another calendar: that list changed too
+    if let Some((_, old_list_id, _, _)) = existing.as_ref().filter(|(_, old, _, _)| old != list_id) {
+        events::log_sync(pool, events, device_id.as_deref(), &auth.user_id, "list", old_list_id, "update").await;
+    }
+
+    let was_completed = was_live && existing.is_some_and(|(_, _, completed, _)| completed);
+    if todo.completed && !was_completed {
+        webhooks::notify(pool, &auth.user_id, WebhookEvent::TaskCompleted, task_id).await;
+    }
+
+    // No ETag: what is stored differs from what was sent, so the client has to fetch it
+    Ok(if current.is_some() {
+        HttpResponse::NoContent().finish()
+    } else {
+        HttpResponse::Created().finish()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::{call_service, init_service, read_body, TestRequest};
+    use actix_web::App;
+
+    use super::*;
+    use crate::config::Config;
+    use crate::test_support::{self, lazy_pool};
+
+    #[test]
+    fn reads_resource_names() {
+        assert_eq!(resource_id("task-1.ics"), Some("task-1"));
+        assert_eq!(resource_id("/dav/calendars/list/task_2.ics/"), Some("task_2"));
+        assert_eq!(resource_id("task-1"), None);
+        assert_eq!(resource_id("bad id.ics"), None);
+        assert_eq!(resource_id(&format!("{}.ics", "a".repeat(37))), None);
+    }
+
+    #[test]
+    fn checks_preconditions() {
+        let check = |header: (&str, &str), etag: Option<&str>| {
+            let req = TestRequest::default().insert_header(header).to_http_request();
+            check_preconditions(&req, etag).is_ok()
+        };
+
+        assert!(check(("If-Match", "\"a\", \"b\""), Some("\"b\"")));
+        assert!(!check(("If-Match", "\"a\""), Some("\"b\"")));
+        assert!(!check(("If-Match", "*"), None));
+        // Create only if absent
+        assert!(check(("If-None-Match", "*"), None));
+        assert!(!check(("If-None-Match", "*"), Some("\"b\"")));
+    }
+
+    // Requests without a token are challenged before any query
+    #[actix_web::test]
+    async fn challenges_requests_without_a_token() {
+        let config = Config::for_tests();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(lazy_pool(&config)))
+                .app_data(web::Data::new(EventHub::default()))
+                .route("/dav/calendars/{list_id}/{resource}", web::to(calendar_object)),
+        )
+        .await;
+
+        for authorization in [None, Some("Basic dXNlcjpwYXNzd29yZA==")] {
+            let mut req = TestRequest::get().uri("/dav/calendars/list/task.ics");
+            if let Some(authorization) = authorization {
+                req = req.insert_header(("Authorization", authorization));
+            }
+            let res = call_service(&app, req.to_request()).await;
+            assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+            assert!(res.headers().contains_key(header::WWW_AUTHENTICATE));
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn stores_what_clients_put() {
+        let (pool, _) = test_support::database().await;
+        let (user_id, email) = test_support::create_user(&pool, "caldav", "!", true).await;
+        let list_id = test_support::create_list(&pool, &user_id, "Home").await;
+        let token = test_support::create_api_token(&pool, &user_id, "lists:read tasks:read tasks:write").await;
+        let basic = format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", email, token)));
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(EventHub::default()))
+                .route("/dav/calendars/{list_id}/{resource}", web::to(calendar_object)),
+        )
+        .await;
+        let uri = format!("/dav/calendars/{}/caldav-task.ics", list_id);
+        let put = |trigger: &str| {
+            let body = format!(
+                "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:caldav-task\r\nSUMMARY:Water plants\r\n\
+                 DUE:20300305T120000Z\r\nBEGIN:VALARM\r\n{}\r\nEND:VALARM\r\nEND:VTODO\r\nEND:VCALENDAR\r\n",
+                trigger
+            );
+            TestRequest::put()
+                .uri(&uri)
+                .insert_header(("Authorization", basic.as_str()))
+                .set_payload(body)
+                .to_request()
+        };
+
+        let overflow = call_service(&app, put("TRIGGER:-P99999999999W")).await.status();
+        let created = call_service(&app, put("TRIGGER;RELATED=END:-PT15M")).await.status();
+        let get = TestRequest::get().uri(&uri).insert_header(("Authorization", basic.as_str())).to_request();
+        let body = String::from_utf8(read_body(call_service(&app, get).await).await.to_vec()).unwrap();
+        test_support::delete_user(&pool, &user_id).await;
+
+        assert_eq!(overflow, StatusCode::BAD_REQUEST);
+        assert_eq!(created, StatusCode::CREATED);
+        assert!(body.contains("SUMMARY:Water plants"));
+        assert!(body.contains("DUE:20300305T120000Z"));
+        assert!(body.contains("TRIGGER;RELATED=END:-PT15M"));
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::BTreeMap;
+
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use rand::Rng;
+use sqlx::MySqlPool;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::i18n::Msg;
+use crate::ics::{self, Feed};
+use crate::middleware::api_token::hash_token;
+use crate::middleware::jwt::get_auth_user;
+use crate::handlers::settings::load_settings;
+use crate::models::*;
+use crate::recurrence::Recurrence;
+
+const MAX_RANGE_DAYS: i64 = 400;
+// Completed tasks stay in the feed this long, so they don't vanish from calendars at once
+const FEED_COMPLETED_DAYS: i64 = 30;
+
+// Helper: Parse a range bound, either an RFC 3339 instant or a local calendar date.
+// Dates are expanded to the start of `from` and the end of `to` in the given time zone.
+fn parse_bound(value: &str, tz: &Tz, end_of_day: bool) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    let date = if end_of_day { date.succ_opt()? } else { date };
+    let start = tz.from_local_datetime(&date.and_time(NaiveTime::MIN)).earliest()?;
+    let start = start.with_timezone(&Utc);
+
+    Some(if end_of_day { start - Duration::seconds(1) } else { start })
+}
+
+// Helper: Expand a reminder or due date into the instants that fall in the range
+fn expand(
+    at: DateTime<Utc>,
+    rule: Option<&Recurrence>,
+    tz: &Tz,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<(DateTime<Utc>, bool)> {
+    match rule {
+        Some(rule) => rule
+            .occurrences(at.with_timezone(tz), from, to)
+            .into_iter()
+            .map(|occurrence| (occurrence, occurrence != at))
+            .collect(),
+        None if at >= from && at <= to => vec![(at, false)],
+        None => Vec::new(),
+    }
+}
+
+// GET /calendar
+#[utoipa::path(
+    get,
+    path = "/calendar",
+    tag = "calendar",
+    params(CalendarQuery),
+    responses(
+        (status = 200, description = "Reminders and due dates grouped by day", body = ApiResponse<CalendarResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_calendar(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    query: web::Query<CalendarQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    query.validate()?;
+
+    let tz: Tz = match query.tz.as_deref() {
+        Some(name) => name.parse().map_err(|_| ApiError::bad_request(Msg::InvalidTimeZone))?,
+        None => load_settings(pool.get_ref(), &claims.user_id).await?.tz(),
+    };
+
+    let from = parse_bound(&query.from, &tz, false)
+        .ok_or_else(|| ApiError::bad_request(Msg::InvalidFrom))?;
+    let to = parse_bound(&query.to, &tz, true)
+        .ok_or_else(|| ApiError::bad_request(Msg::InvalidTo))?;
+
+    if to < from {
+        return Err(ApiError::bad_request(Msg::RangeInverted));
+    }
+
+    if to - from > Duration::days(MAX_RANGE_DAYS) {
+        return Err(ApiError::bad_request(Msg::RangeTooLarge)
+            .with_message(Msg::RangeTooLarge.with(&[("max", &MAX_RANGE_DAYS.to_string())])));
+    }
+
+    let tasks: Vec<Task> = sqlx::query_as(
+        r#"
+        SELECT t.id, t.list_id, t.title, t.description, t.completed, t.reminder, t.due_at, t.recurrence, t.created_at, t.updated_at, t.deleted_at
+        FROM tasks t
+        JOIN todo_lists l ON t.list_id = l.id
+        WHERE l.user_id = ? AND t.deleted_at IS NULL AND l.deleted_at IS NULL
+            AND (
+                (t.reminder BETWEEN ? AND ?)
+                OR (t.due_at BETWEEN ? AND ?)
+                OR (t.recurrence IS NOT NULL AND COALESCE(t.due_at, t.reminder) <= ?)
+            )
+        "#
+    )
+    .bind(&claims.user_id)
+    .bind(from)
+    .bind(to)
+    .bind(from)
+    .bind(to)
+    .bind(to)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let mut days: BTreeMap<NaiveDate, Vec<CalendarEntry>> = BTreeMap::new();
+
+    for task in tasks {
+        let rule: Option<Recurrence> = task.recurrence.as_deref().and_then(|r| r.parse().ok());
+        let reminder = task.reminder;
+        let due_at = task.due_at;
+        let task = TaskResponse::from(task);
+
+        let dates = [(CalendarEntryKind::Reminder, reminder), (CalendarEntryKind::Due, due_at)];
+        for (kind, at) in dates {
+            let Some(at) = at else { continue };
+            for (occurrence, is_occurrence) in expand(at, rule.as_ref(), &tz, from, to) {
+                let day = occurrence.with_timezone(&tz).date_naive();
+                days.entry(day).or_default().push(CalendarEntry {
+                    kind,
+                    at: occurrence,
+                    is_occurrence,
+                    task: task.clone(),
+                });
+            }
+        }
+    }
+
+    let days = days
+        .into_iter()
+        .map(|(date, mut entries)| {
+            entries.sort_by_key(|e| e.at);
+            CalendarDay { date, entries }
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(
+        Msg::CalendarLoaded,
+        CalendarResponse {
+            from,
+            to,
+            time_zone: tz.name().to_string(),
+            days,
+        },
+    )))
+}
+
+// Helper: Feed secret, 24 random bytes as hex
+fn generate_feed_token() -> String {
+    let bytes: [u8; 24] = rand::thread_rng().gen();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// GET /calendar/feed
+#[utoipa::path(
+    get,
+    path = "/calendar/feed",
+    tag = "calendar",
+    responses(
+        (status = 200, description = "Whether an iCalendar feed link exists", body = ApiResponse<CalendarFeedResponse>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_feed(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+
+    let feed: Option<CalendarFeed> = sqlx::query_as(
+        "SELECT created_at, last_used_at FROM calendar_feeds WHERE user_id = ?"
+    )
+    .bind(&claims.user_id)
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(
+        Msg::CalendarFeedLoaded,
+        CalendarFeedResponse {
+            active: feed.is_some(),
+            created_at: feed.as_ref().map(|f| f.created_at),
+            last_used_at: feed.and_then(|f| f.last_used_at),
+        },
+    )))
+}
+
+// POST /calendar/feed
+#[utoipa::path(
+    post,
+    path = "/calendar/feed",
+    tag = "calendar",
+    responses(
+        (status = 201, description = "New feed link; any previous link stops working", body = ApiResponse<CreateCalendarFeedResponse>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_feed(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+
+    let token = generate_feed_token();
+    let now = Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO calendar_feeds (user_id, token_hash, created_at)
+        VALUES (?, ?, ?)
+        ON DUPLICATE KEY UPDATE token_hash = VALUES(token_hash), created_at = VALUES(created_at), last_used_at = NULL
+        "#
+    )
+    .bind(&claims.user_id)
+    .bind(hash_token(&token))
+    .bind(now)
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(
+        Msg::CalendarFeedCreated,
+        CreateCalendarFeedResponse {
+            url: format!("{}/calendar.ics?token={}", config.public_url.trim_end_matches('/'), token),
+            created_at: now,
+        },
+    )))
+}
+
+// DELETE /calendar/feed
+#[utoipa::path(
+    delete,
+    path = "/calendar/feed",
+    tag = "calendar",
+    responses(
+        (status = 200, description = "Feed link revoked", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "No feed link", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn revoke_feed(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+
+    let result = sqlx::query("DELETE FROM calendar_feeds WHERE user_id = ?")
+        .bind(&claims.user_id)
+        .execute(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found(Msg::CalendarFeedNotFound));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data(Msg::CalendarFeedRevoked)))
+}
+
+// GET /calendar.ics
+#[utoipa::path(
+    get,
+    path = "/calendar.ics",
+    tag = "calendar",
+    params(CalendarFeedQuery),
+    responses(
+        (status = 200, description = "iCalendar feed of tasks with a reminder or due date", body = String, content_type = "text/calendar"),
+        (status = 304, description = "Unchanged since the `ETag` in `If-None-Match`"),
+        (status = 400, description = "Invalid query", body = MessageResponse),
+        (status = 404, description = "Unknown or revoked feed link, or list not found", body = MessageResponse),
+    )
+)]
+pub async fn calendar_feed(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    query: web::Query<CalendarFeedQuery>,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+
+    // Calendar apps can't send headers, so the secret in the URL is the credential
+    let user_id: Option<(String,)> = sqlx::query_as(
+        "SELECT user_id FROM calendar_feeds WHERE token_hash = ?"
+    )
+    .bind(hash_token(query.token.trim()))
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let (user_id,) = user_id.ok_or_else(|| ApiError::not_found(Msg::CalendarFeedNotFound))?;
+
+    sqlx::query("UPDATE calendar_feeds SET last_used_at = ? WHERE user_id = ?")
+        .bind(Utc::now())
+        .bind(&user_id)
+        .execute(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let name = match &query.list_id {
+        Some(list_id) => {
+            let list: Option<(String,)> = sqlx::query_as(
+                "SELECT name FROM todo_lists WHERE id = ? AND user_id = ? AND deleted_at IS NULL"
+            )
+            .bind(list_id)
+            .bind(&user_id)
+            .fetch_optional(pool.get_ref())
+            .await
+            .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+            let (list_name,) = list.ok_or_else(|| ApiError::not_found(Msg::ListNotFound))?;
+            format!("MyTudo - {}", list_name)
+        }
+        None => "MyTudo".to_string(),
+    };
+
+    let tasks: Vec<FeedTask> = sqlx::query_as(
+        r#"
+        SELECT t.id, t.list_id, t.title, t.description, t.completed, t.reminder, t.due_at, t.recurrence, t.created_at, t.updated_at, t.deleted_at,
+            t.completed_at, l.name AS list_name
+        FROM tasks t
+        JOIN todo_lists l ON t.list_id = l.id
+        WHERE l.user_id = ? AND t.deleted_at IS NULL AND l.deleted_at IS NULL
+            AND (? IS NULL OR t.list_id = ?)
+            AND (t.reminder IS NOT NULL OR t.due_at IS NOT NULL)
+            AND (t.completed = 0 OR COALESCE(t.completed_at, t.updated_at) >= ?)
+        ORDER BY t.id
+        "#
+    )
+    .bind(&user_id)
+    .bind(&query.list_id)
+    .bind(&query.list_id)
+    .bind(Utc::now() - Duration::days(FEED_COMPLETED_DAYS))
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let feed = Feed {
+        name: &name,
+        tz: load_settings(pool.get_ref(), &user_id).await?.tz(),
+        component: query
+            .component
+            .as_deref()
+            .and_then(|c| c.parse().ok())
+            .unwrap_or(FeedComponent::Event),
+    };
+    let body = ics::render(&feed, &tasks);
+
+    // Apps poll the feed; unchanged tasks give the same body and a 304
+    let etag = format!("\"{:x}\"", md5::compute(&body));
+    let unchanged = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|h| h.split(',').any(|tag| tag.trim() == etag));
+
+    if unchanged {
+        return Ok(HttpResponse::NotModified()
+            .insert_header((header::ETAG, etag))
+            .finish());
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .insert_header((header::ETAG, etag))
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .insert_header((header::CONTENT_DISPOSITION, "inline; filename=\"mytudo.ics\""))
+        .body(body))
+}
@@ -0,0 +1,42 @@
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use crate::config::Config;
+use crate::errors::{ApiError, MessageResponse};
+use crate::events::{self, EventHub};
+use crate::middleware::jwt::get_auth_user;
+use crate::models::*;
+
+// GET /events
+#[utoipa::path(
+    get,
+    path = "/events",
+    tag = "sync",
+    params(EventsQuery),
+    responses(
+        (status = 200, description = "Server-Sent Events: `change` with `{entity_type, entity_id, action, cursor}` for changes made by your other devices, `resync` when some were missed", body = String, content_type = "text/event-stream"),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn stream_events(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    hub: web::Data<EventHub>,
+    query: web::Query<EventsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    let device_id = events::device_id(&req).or_else(|| {
+        query
+            .into_inner()
+            .device_id
+            .filter(|id| !id.trim().is_empty() && id.len() <= 100)
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        // Stops nginx from buffering the stream
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(events::stream(&hub, claims.user_id, device_id)))
+}
@@ -0,0 +1,222 @@
+use std::io::{Cursor, Write};
+
+use actix_web::http::header::{Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue};
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use sqlx::MySqlPool;
+use validator::Validate;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::config::Config;
+use crate::errors::{ApiError, MessageResponse};
+use crate::export;
+use crate::handlers::settings::load_settings;
+use crate::i18n::Msg;
+use crate::middleware::jwt::get_auth_user;
+use crate::models::*;
+
+// Helper: List name usable as a file name
+fn file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+        .take(100)
+        .collect();
+    match name.trim() {
+        "" => "lista".to_string(),
+        name => name.to_string(),
+    }
+}
+
+// Helper: UTF-8 file name, plus an ASCII fallback for clients without RFC 5987
+fn disposition(disposition: DispositionType, file_name: String) -> ContentDisposition {
+    let fallback: String = file_name.chars().map(|c| if c.is_ascii() { c } else { '_' }).collect();
+    ContentDisposition {
+        disposition,
+        parameters: vec![
+            DispositionParam::Filename(fallback),
+            DispositionParam::FilenameExt(ExtendedValue {
+                charset: Charset::Ext("UTF-8".to_string()),
+                language_tag: None,
+                value: file_name.into_bytes(),
+            }),
+        ],
+    }
+}
+
+fn exported_list(list: TodoList) -> ExportedList {
+    ExportedList {
+        id: list.id,
+        name: list.name,
+        color: list.color,
+        created_at: list.created_at,
+        updated_at: list.updated_at,
+    }
+}
+
+// GET /lists/{id}/export
+#[utoipa::path(
+    get,
+    path = "/lists/{id}/export",
+    tag = "lists",
+    params(("id" = String, Path, description = "List id"), ExportQuery),
+    responses(
+        (status = 200, description = "The list and its tasks; HTML opens as a printable page", content(
+            (String = "text/markdown"),
+            (String = "text/csv"),
+            (ListExport = "application/json"),
+            (String = "text/html"),
+        )),
+        (status = 400, description = "Invalid format", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn export_list(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    path: web::Path<String>,
+    query: web::Query<ExportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    query.validate()?;
+
+    let format: ExportFormat = query
+        .format
+        .as_deref()
+        .and_then(|f| f.parse().ok())
+        .unwrap_or(ExportFormat::Markdown);
+
+    let list: Option<TodoList> = sqlx::query_as(
+        "SELECT id, user_id, name, color, created_at, updated_at, deleted_at FROM todo_lists WHERE id = ? AND user_id = ? AND deleted_at IS NULL"
+    )
+    .bind(path.as_str())
+    .bind(&claims.user_id)
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let list = list.ok_or_else(|| ApiError::not_found(Msg::ListNotFound))?;
+
+    let tasks: Vec<ExportedTask> = sqlx::query_as(
+        r#"
+        SELECT id, list_id, title, description, completed, completed_at, due_at, reminder, recurrence, created_at, updated_at
+        FROM tasks
+        WHERE list_id = ? AND deleted_at IS NULL
+        ORDER BY created_at
+        "#
+    )
+    .bind(&list.id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let tz = load_settings(pool.get_ref(), &claims.user_id).await?.tz();
+    let name = format!("{}.{}", file_name(&list.name), format.extension());
+    let body = export::render(format, exported_list(list), tasks, tz, Utc::now())?;
+
+    // The printable page opens in the browser; the rest download
+    let kind = if format == ExportFormat::Html { DispositionType::Inline } else { DispositionType::Attachment };
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(disposition(kind, name))
+        .body(body))
+}
+
+// GET /lists/export
+#[utoipa::path(
+    get,
+    path = "/lists/export",
+    tag = "lists",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Zip with one file per list in the requested format", body = String, content_type = "application/zip"),
+        (status = 400, description = "Invalid format", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn export_lists(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    query: web::Query<ExportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    query.validate()?;
+
+    let format: ExportFormat = query
+        .format
+        .as_deref()
+        .and_then(|f| f.parse().ok())
+        .unwrap_or(ExportFormat::Markdown);
+
+    let lists: Vec<TodoList> = sqlx::query_as(
+        r#"
+        SELECT id, user_id, name, color, created_at, updated_at, deleted_at
+        FROM todo_lists
+        WHERE user_id = ? AND deleted_at IS NULL
+        ORDER BY created_at
+        "#
+    )
+    .bind(&claims.user_id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let mut tasks: Vec<ExportedTask> = sqlx::query_as(
+        r#"
+        SELECT t.id, t.list_id, t.title, t.description, t.completed, t.completed_at, t.due_at, t.reminder, t.recurrence, t.created_at, t.updated_at
+        FROM tasks t
+        JOIN todo_lists l ON t.list_id = l.id
+        WHERE l.user_id = ? AND l.deleted_at IS NULL AND t.deleted_at IS NULL
+        ORDER BY t.created_at
+        "#
+    )
+    .bind(&claims.user_id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let tz = load_settings(pool.get_ref(), &claims.user_id).await?.tz();
+    let exported_at = Utc::now();
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let mut names: Vec<String> = Vec::new();
+
+    for list in lists {
+        // Lists may share a name; later ones get a number
+        let base = file_name(&list.name);
+        let mut name = format!("{}.{}", base, format.extension());
+        let mut n = 2;
+        while names.contains(&name) {
+            name = format!("{} ({}).{}", base, n, format.extension());
+            n += 1;
+        }
+        names.push(name.clone());
+
+        let (mine, rest): (Vec<ExportedTask>, Vec<ExportedTask>) = tasks.into_iter().partition(|t| t.list_id == list.id);
+        tasks = rest;
+
+        let body = export::render(format, exported_list(list), mine, tz, exported_at)?;
+        zip.start_file(name, SimpleFileOptions::default())
+            .and_then(|_| zip.write_all(&body).map_err(Into::into))
+            .map_err(|e| ApiError::internal(format!("Export error: {}", e)))?;
+    }
+
+    let archive = zip
+        .finish()
+        .map_err(|e| ApiError::internal(format!("Export error: {}", e)))?
+        .into_inner();
+
+    let name = format!("mytudo-lists-{}.zip", exported_at.format("%Y%m%d"));
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header(disposition(DispositionType::Attachment, name))
+        .body(archive))
+}
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use actix_web::{web, HttpResponse};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, Tokio1Executor};
+use sqlx::MySqlPool;
+
+use crate::config::Config;
+use crate::errors::{ApiResponse, MessageResponse};
+use crate::i18n::Msg;
+use crate::mail::TransportKind;
+use crate::metrics::METRICS;
+use crate::models::*;
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+// Helper: Round-trip to MySQL through the pool
+async fn check_database(pool: &MySqlPool) -> CheckStatus {
+    match tokio::time::timeout(CHECK_TIMEOUT, sqlx::query("SELECT 1").execute(pool)).await {
+        Ok(Ok(_)) => CheckStatus::Ok,
+        Ok(Err(e)) => {
+            tracing::warn!(error = %e, "Readiness: database check failed");
+            CheckStatus::Error
+        }
+        Err(_) => {
+            tracing::warn!("Readiness: database check timed out");
+            CheckStatus::Error
+        }
+    }
+}
+
+// Helper: Connect and authenticate to the SMTP relay. The file and stdout transports
+// have nothing to check.
+async fn check_smtp(config: &Config) -> CheckStatus {
+    if config.mail_transport != TransportKind::Smtp {
+        return CheckStatus::Disabled;
+    }
+
+    let mailer: AsyncSmtpTransport<Tokio1Executor> = match AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host) {
+        Ok(builder) => builder
+            .port(config.smtp_port)
+            .credentials(Credentials::new(config.smtp_user.clone(), config.smtp_pass.clone()))
+            .timeout(Some(CHECK_TIMEOUT))
+            .build(),
+        Err(e) => {
+            tracing::warn!(error = %e, "Readiness: invalid SMTP relay");
+            return CheckStatus::Error;
+        }
+    };
+
+    match tokio::time::timeout(CHECK_TIMEOUT, mailer.test_connection()).await {
+        Ok(Ok(true)) => CheckStatus::Ok,
+        Ok(Ok(false)) => {
+            tracing::warn!("Readiness: SMTP relay refused the connection");
+            CheckStatus::Error
+        }
+        Ok(Err(e)) => {
+            tracing::warn!(error = %e, "Readiness: SMTP check failed");
+            CheckStatus::Error
+        }
+        Err(_) => {
+            tracing::warn!("Readiness: SMTP check timed out");
+            CheckStatus::Error
+        }
+    }
+}
+
+// GET /healthz
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses(
+        (status = 200, description = "Process is alive", body = MessageResponse)
+    )
+)]
+pub async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().json(ApiResponse::<()>::success_no_data(Msg::ServiceHealthy))
+}
+
+// GET /readyz
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "MySQL and SMTP are reachable", body = ApiResponse<ReadinessResponse>),
+        (status = 503, description = "A dependency is down", body = ApiResponse<ReadinessResponse>)
+    )
+)]
+pub async fn readyz(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+) -> HttpResponse {
+    let (database, smtp) = tokio::join!(check_database(pool.get_ref()), check_smtp(&config));
+    let checks = ReadinessResponse { database, smtp };
+
+    if checks.is_ready() {
+        HttpResponse::Ok().json(ApiResponse::success(Msg::ServiceReady, checks))
+    } else {
+        let mut response = ApiResponse::success(Msg::ServiceNotReady, checks);
+        response.success = false;
+        HttpResponse::ServiceUnavailable().json(response)
+    }
+}
+
+// GET /metrics
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses(
+        (status = 200, description = "Prometheus text exposition format", body = String, content_type = "text/plain")
+    )
+)]
+pub async fn metrics(pool: web::Data<MySqlPool>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(METRICS.render(pool.get_ref()))
+}
@@ -0,0 +1,197 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use sqlx::MySqlPool;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::events::{self, EventHub};
+use crate::handlers::settings::load_settings;
+use crate::handlers::sync::apply_push;
+use crate::i18n::Msg;
+use crate::import::{self, Import, ImportError};
+use crate::middleware::jwt::get_auth_user;
+use crate::models::*;
+
+// Keeps one import to a single reasonable transaction
+const MAX_IMPORT_TASKS: usize = 10_000;
+
+// Helper: The import as a sync push, so it is written like any other client's changes
+fn to_push(import: &Import) -> SyncPushRequest {
+    SyncPushRequest {
+        lists: import
+            .lists
+            .iter()
+            .map(|list| SyncListItem {
+                id: Some(list.id.clone()),
+                name: list.name.clone(),
+                color: Some(list.color.clone()),
+                created_at: None,
+            })
+            .collect(),
+        tasks: import
+            .lists
+            .iter()
+            .flat_map(|list| {
+                list.tasks.iter().map(|task| SyncTaskItem {
+                    id: Some(task.id.clone()),
+                    list_id: list.id.clone(),
+                    title: task.title.clone(),
+                    description: task.description.clone(),
+                    completed: Some(task.completed),
+                    reminder: task.reminder.map(|at| at.to_rfc3339()),
+                    due_at: task.due_at.map(|at| at.to_rfc3339()),
+                    recurrence: task.recurrence.clone(),
+                    created_at: task.created_at.map(|at| at.to_rfc3339()),
+                })
+            })
+            .collect(),
+        deleted_lists: Vec::new(),
+        deleted_tasks: Vec::new(),
+    }
+}
+
+// POST /import
+#[utoipa::path(
+    post,
+    path = "/import",
+    tag = "import",
+    request_body = ImportRequest,
+    responses(
+        (status = 200, description = "Preview (`dry_run`); nothing saved", body = ApiResponse<ImportResponse>),
+        (status = 201, description = "Lists and tasks imported", body = ApiResponse<ImportResponse>),
+        (status = 400, description = "Invalid request or unreadable file", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn import_data(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    events: web::Data<EventHub>,
+    body: web::Json<ImportRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+
+    let format: ImportFormat = body
+        .format
+        .parse()
+        .map_err(|_| ApiError::bad_request(Msg::InvalidImportFormat))?;
+    let settings = load_settings(pool.get_ref(), &claims.user_id).await?;
+
+    let import = import::parse(
+        format,
+        &body.data,
+        body.list_name.as_deref(),
+        body.columns.as_ref(),
+        &claims.user_id,
+        &settings,
+    )
+    .map_err(|e| match e {
+        ImportError::Invalid => ApiError::bad_request(Msg::InvalidImportFile),
+        ImportError::MissingColumn(column) => ApiError::bad_request(Msg::CsvColumnNotFound)
+            .with_message(Msg::CsvColumnNotFound.with(&[("column", &column)])),
+    })?;
+
+    if import.task_count() > MAX_IMPORT_TASKS {
+        return Err(ApiError::bad_request(Msg::ImportTooLarge)
+            .with_message(Msg::ImportTooLarge.with(&[("max", &MAX_IMPORT_TASKS.to_string())])));
+    }
+
+    if !body.dry_run {
+        let device_id = events::device_id(&req);
+        let mut tx = pool.begin().await
+            .map_err(|e| ApiError::internal(format!("Transaction error: {}", e)))?;
+
+        let changes = apply_push(&mut tx, &claims.user_id, device_id.as_deref(), &to_push(&import)).await?;
+
+        tx.commit().await
+            .map_err(|e| ApiError::internal(format!("Commit error: {}", e)))?;
+
+        for change in changes {
+            events.publish(change);
+        }
+    }
+
+    let response = ImportResponse {
+        dry_run: body.dry_run,
+        lists: import.lists,
+        issues: import.issues,
+    };
+
+    Ok(if body.dry_run {
+        HttpResponse::Ok().json(ApiResponse::success(Msg::ImportPreviewed, response))
+    } else {
+        HttpResponse::Created().json(ApiResponse::success(Msg::ImportCompleted, response))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::App;
+
+    use super::*;
+    use crate::middleware::jwt::create_token;
+    use crate::test_support;
+
+    // A preview saves nothing; the import itself saves completion state and reminders
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn previews_then_imports() {
+        let (pool, config) = test_support::database().await;
+        let (user_id, email) = test_support::create_user(&pool, "import", "!", true).await;
+        let token = create_token(&config, &user_id, &email).unwrap();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(config))
+                .app_data(web::Data::new(EventHub::default()))
+                .route("/import", web::post().to(import_data)),
+        )
+        .await;
+        let import = |dry_run: bool| {
+            TestRequest::post()
+                .uri("/import")
+                .insert_header(("Authorization", format!("Bearer {}", token)))
+                .set_json(serde_json::json!({
+                    "format": "csv",
+                    "data": "title,completed,reminder\nPay rent,yes,2030-03-05T08:30:00Z\nRead,,\n",
+                    "list_name": "Imported",
+                    "dry_run": dry_run,
+                }))
+                .to_request()
+        };
+        let count = || async {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM tasks t JOIN todo_lists l ON l.id = t.list_id WHERE l.user_id = ?")
+                .bind(&user_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        };
+
+        let preview = call_service(&app, import(true)).await.status();
+        let after_preview = count().await;
+        let created = call_service(&app, import(false)).await.status();
+        let saved: Vec<(String, bool, Option<chrono::DateTime<chrono::Utc>>)> =
+            sqlx::query_as(
+                "SELECT t.title, t.completed, t.reminder FROM tasks t JOIN todo_lists l ON l.id = t.list_id \
+                 WHERE l.user_id = ? ORDER BY t.title",
+            )
+                .bind(&user_id)
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        test_support::delete_user(&pool, &user_id).await;
+
+        assert_eq!(preview, StatusCode::OK);
+        assert_eq!(after_preview, 0);
+        assert_eq!(created, StatusCode::CREATED);
+        assert_eq!(saved.len(), 2);
+        assert_eq!((saved[0].0.as_str(), saved[0].1), ("Pay rent", true));
+        assert_eq!(saved[0].2.map(|at| at.to_rfc3339()).as_deref(), Some("2030-03-05T08:30:00+00:00"));
+        assert_eq!((saved[1].0.as_str(), saved[1].1, saved[1].2), ("Read", false, None));
+    }
+}
@@ -0,0 +1,371 @@
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use futures_util::StreamExt;
+use rand::Rng;
+use sqlx::MySqlPool;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::events::EventHub;
+use crate::i18n::Msg;
+use crate::inbound;
+use crate::middleware::api_token::hash_token;
+use crate::middleware::jwt::{extract_token, get_auth_user};
+use crate::models::*;
+
+// Helper: Generate UUID
+fn generate_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+// Helper: Local part of a new address, 16 random bytes as hex
+fn generate_address_token() -> String {
+    let bytes: [u8; inbound::TOKEN_LEN / 2] = rand::thread_rng().gen();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// GET /inbound/addresses
+#[utoipa::path(
+    get,
+    path = "/inbound/addresses",
+    tag = "inbound",
+    responses(
+        (status = 200, description = "Addresses of the user", body = ApiResponse<Vec<InboundAddressResponse>>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_addresses(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+
+    let addresses: Vec<InboundAddress> = sqlx::query_as(
+        r#"
+        SELECT id, user_id, list_id, token, created_at, last_used_at
+        FROM inbound_addresses
+        WHERE user_id = ?
+        ORDER BY created_at
+        "#
+    )
+    .bind(&claims.user_id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let addresses: Vec<InboundAddressResponse> = addresses
+        .into_iter()
+        .map(|a| InboundAddressResponse::new(a, &config.inbound_email_domain))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::InboundAddressesLoaded, addresses)))
+}
+
+// POST /inbound/addresses
+#[utoipa::path(
+    post,
+    path = "/inbound/addresses",
+    tag = "inbound",
+    request_body = CreateInboundAddressRequest,
+    responses(
+        (status = 201, description = "New address; a previous one for the same list stops working", body = ApiResponse<InboundAddressResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "List not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_address(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    body: web::Json<CreateInboundAddressRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+
+    if let Some(list_id) = &body.list_id {
+        let exists: Option<(String,)> = sqlx::query_as(
+            "SELECT id FROM todo_lists WHERE id = ? AND user_id = ? AND deleted_at IS NULL"
+        )
+        .bind(list_id)
+        .bind(&claims.user_id)
+        .fetch_optional(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+        if exists.is_none() {
+            return Err(ApiError::not_found(Msg::ListNotFound));
+        }
+    }
+
+    let address = InboundAddress {
+        id: generate_uuid(),
+        user_id: claims.user_id.clone(),
+        list_id: body.list_id.clone(),
+        token: generate_address_token(),
+        created_at: Utc::now(),
+        last_used_at: None,
+    };
+
+    let mut tx = pool.begin().await
+        .map_err(|e| ApiError::internal(format!("Transaction error: {}", e)))?;
+
+    // One address per list (and one for the account); `<=>` also matches NULL
+    sqlx::query("DELETE FROM inbound_addresses WHERE user_id = ? AND list_id <=> ?")
+        .bind(&address.user_id)
+        .bind(&address.list_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    sqlx::query(
+        "INSERT INTO inbound_addresses (id, user_id, list_id, token, created_at) VALUES (?, ?, ?, ?, ?)"
+    )
+    .bind(&address.id)
+    .bind(&address.user_id)
+    .bind(&address.list_id)
+    .bind(&address.token)
+    .bind(address.created_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    tx.commit().await
+        .map_err(|e| ApiError::internal(format!("Commit error: {}", e)))?;
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(
+        Msg::InboundAddressCreated,
+        InboundAddressResponse::new(address, &config.inbound_email_domain),
+    )))
+}
+
+// DELETE /inbound/addresses/{id}
+#[utoipa::path(
+    delete,
+    path = "/inbound/addresses/{id}",
+    tag = "inbound",
+    params(("id" = String, Path, description = "Address id")),
+    responses(
+        (status = 200, description = "Address deleted; emails to it are refused", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_address(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+
+    let result = sqlx::query("DELETE FROM inbound_addresses WHERE id = ? AND user_id = ?")
+        .bind(path.as_str())
+        .bind(&claims.user_id)
+        .execute(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found(Msg::InboundAddressNotFound));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data(Msg::InboundAddressDeleted)))
+}
+
+// POST /inbound/email
+#[utoipa::path(
+    post,
+    path = "/inbound/email",
+    tag = "inbound",
+    params(InboundEmailQuery),
+    request_body(content = String, content_type = "message/rfc822", description = "The raw email, headers included"),
+    responses(
+        (status = 201, description = "Task created from the email", body = ApiResponse<InboundEmailResponse>),
+        (status = 400, description = "Unreadable email", body = MessageResponse),
+        (status = 401, description = "Missing or wrong `INBOUND_EMAIL_SECRET`", body = MessageResponse),
+        (status = 404, description = "No address matches the recipient, or its list is gone", body = MessageResponse),
+        (status = 413, description = "Email too large", body = MessageResponse),
+        (status = 503, description = "Receiving email is turned off", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn receive_email(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    events: web::Data<EventHub>,
+    query: web::Query<InboundEmailQuery>,
+    mut payload: web::Payload,
+) -> Result<HttpResponse, ApiError> {
+    // For the MTA, not for users: authorized by a server-wide secret
+    let secret = config
+        .inbound_email_secret
+        .as_deref()
+        .ok_or_else(|| ApiError::new(Msg::InboundEmailDisabled, StatusCode::SERVICE_UNAVAILABLE))?;
+    let token = extract_token(&req).ok_or_else(|| ApiError::unauthorized(Msg::MissingToken))?;
+    // Hashes are compared so the time taken says nothing about the secret
+    if hash_token(&token) != hash_token(secret) {
+        return Err(ApiError::unauthorized(Msg::InvalidToken));
+    }
+    query.validate()?;
+
+    let max = config.inbound_email_max_bytes;
+    let mut raw = Vec::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|_| ApiError::bad_request(Msg::InvalidEmailMessage))?;
+        if raw.len() + chunk.len() > max {
+            return Err(ApiError::new(Msg::EmailTooLarge, StatusCode::PAYLOAD_TOO_LARGE)
+                .with_message(Msg::EmailTooLarge.with(&[("max", &max.to_string())])));
+        }
+        raw.extend_from_slice(&chunk);
+    }
+
+    let email = inbound::parse(&raw).ok_or_else(|| ApiError::bad_request(Msg::InvalidEmailMessage))?;
+
+    let address = match &query.recipient {
+        Some(recipient) => inbound::find_address(pool.get_ref(), &[recipient]).await,
+        None => inbound::find_address(pool.get_ref(), &email.recipients).await,
+    }
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?
+    .ok_or_else(|| ApiError::not_found(Msg::InboundAddressNotFound))?;
+
+    let delivered = inbound::deliver(pool.get_ref(), &events, &address, &email).await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(
+        Msg::InboundEmailDelivered,
+        InboundEmailResponse {
+            task_id: delivered.task_id,
+            list_id: delivered.list_id,
+            attachments: delivered.attachments,
+        },
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+    use sqlx::MySqlPool;
+
+    use super::*;
+    use crate::test_support::{self, lazy_pool, raw_email};
+
+    const SECRET: &str = "mta-secret";
+
+    async fn post_email(pool: &MySqlPool, config: &Config, uri: &str, secret: Option<&str>, raw: Vec<u8>) -> (StatusCode, serde_json::Value) {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(config.clone()))
+                .app_data(web::Data::new(EventHub::default()))
+                .route("/inbound/email", web::post().to(receive_email)),
+        )
+        .await;
+
+        let mut req = test::TestRequest::post()
+            .uri(uri)
+            .insert_header(("Content-Type", "message/rfc822"))
+            .set_payload(raw);
+        if let Some(secret) = secret {
+            req = req.insert_header(("Authorization", format!("Bearer {}", secret)));
+        }
+        let res = test::call_service(&app, req.to_request()).await;
+        let status = res.status();
+        (status, test::read_body_json(res).await)
+    }
+
+    // Rejections that come before any query, so the pool never connects
+    #[actix_web::test]
+    async fn rejects_emails_before_any_lookup() {
+        let disabled = Config::for_tests();
+        let pool = lazy_pool(&disabled);
+        let email = || raw_email("someone@example.com", "Hi", 0);
+
+        let (status, _) = post_email(&pool, &disabled, "/inbound/email", Some(SECRET), email()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        let config = Config {
+            inbound_email_secret: Some(SECRET.to_string()),
+            inbound_email_max_bytes: 1024,
+            ..Config::for_tests()
+        };
+        let (status, _) = post_email(&pool, &config, "/inbound/email", None, email()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = post_email(&pool, &config, "/inbound/email", Some("guess"), email()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let uri = format!("/inbound/email?recipient={}", "a".repeat(321));
+        let (status, _) = post_email(&pool, &config, &uri, Some(SECRET), email()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = post_email(&pool, &config, "/inbound/email", Some(SECRET), raw_email("someone@example.com", "Big", 20)).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["code"], "email_too_large");
+
+        let (status, _) = post_email(&pool, &config, "/inbound/email", Some(SECRET), Vec::new()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // No recipient names an inbound address, so there is nothing to look up
+        let (status, body) = post_email(&pool, &config, "/inbound/email", Some(SECRET), email()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "inbound_address_not_found");
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn creates_tasks_for_the_addressed_list() {
+        let (pool, mut config) = test_support::database().await;
+        config.inbound_email_secret = Some(SECRET.to_string());
+        let (user_id, _) = test_support::create_user(&pool, "inbound", "!", true).await;
+        let oldest = test_support::create_list(&pool, &user_id, "Inbox").await;
+        let other = test_support::create_list(&pool, &user_id, "Work").await;
+        sqlx::query("UPDATE todo_lists SET created_at = created_at + INTERVAL 1 MINUTE WHERE id = ?")
+            .bind(&other)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let account = test_support::create_inbound_address(&pool, &user_id, None).await;
+        let work = test_support::create_inbound_address(&pool, &user_id, Some(&other)).await;
+        let domain = config.inbound_email_domain.clone();
+
+        // Recipient from the headers; an account address goes to the oldest list
+        let to = format!("{}@{}", account, domain);
+        let (status, from_headers) = post_email(&pool, &config, "/inbound/email", Some(SECRET), raw_email(&to, "Fwd: Call Ana", 25)).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        // Envelope recipient from the MTA wins over the headers
+        let uri = format!("/inbound/email?recipient=me%2B{}%40{}", work, domain);
+        let (status, from_envelope) = post_email(&pool, &config, &uri, Some(SECRET), raw_email(&to, "Report", 0)).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let unknown = format!("/inbound/email?recipient={}%40{}", "f".repeat(32), domain);
+        let (status, _) = post_email(&pool, &config, &unknown, Some(SECRET), raw_email(&to, "Lost", 0)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let task_id = from_headers["data"]["task_id"].as_str().unwrap().to_string();
+        let (title,): (String,) = sqlx::query_as("SELECT title FROM tasks WHERE id = ?")
+            .bind(&task_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let (stored,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM task_attachments WHERE task_id = ?")
+            .bind(&task_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        test_support::delete_user(&pool, &user_id).await;
+
+        assert_eq!(from_headers["data"]["list_id"], oldest.as_str());
+        assert_eq!(title, "Call Ana");
+        // Past the limit attachments are dropped, the task is still created
+        assert_eq!(from_headers["data"]["attachments"], 20);
+        assert_eq!(stored, 20);
+        assert_eq!(from_envelope["data"]["list_id"], other.as_str());
+    }
+}
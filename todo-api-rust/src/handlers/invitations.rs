@@ -0,0 +1,177 @@
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
+use minijinja::context;
+use sqlx::MySqlPool;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::handlers::settings::user_locale;
+use crate::i18n::Msg;
+use crate::mail::{self, MailKind};
+use crate::middleware::jwt::get_auth_user;
+use crate::models::*;
+
+// Keeps the endpoint from being used to send mail to arbitrary addresses
+const MAX_INVITATIONS_PER_DAY: i64 = 20;
+// The same person isn't invited twice by the same account within this many days
+const REINVITE_AFTER_DAYS: i64 = 7;
+
+// POST /auth/invitations
+#[utoipa::path(
+    post,
+    path = "/auth/invitations",
+    tag = "auth",
+    request_body = InvitationRequest,
+    responses(
+        (status = 201, description = "Invitation queued", body = MessageResponse),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 409, description = "Email already registered, or invited in the last 7 days", body = MessageResponse),
+        (status = 429, description = "Daily invitation limit reached", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_invitation(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    body: web::Json<InvitationRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+    let email = body.email.trim().to_lowercase();
+    let now = Utc::now();
+
+    let registered: Option<String> = sqlx::query_scalar("SELECT id FROM users WHERE email = ?")
+        .bind(&email)
+        .fetch_optional(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+    if registered.is_some() {
+        return Err(ApiError::conflict(Msg::EmailAlreadyRegistered));
+    }
+
+    let invited: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM invitations WHERE user_id = ? AND email = ? AND created_at > ?"
+    )
+    .bind(&claims.user_id)
+    .bind(&email)
+    .bind(now - Duration::days(REINVITE_AFTER_DAYS))
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+    if invited > 0 {
+        return Err(ApiError::conflict(Msg::AlreadyInvited)
+            .with_message(Msg::AlreadyInvited.with(&[("days", &REINVITE_AFTER_DAYS.to_string())])));
+    }
+
+    let sent_today: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM invitations WHERE user_id = ? AND created_at > ?"
+    )
+    .bind(&claims.user_id)
+    .bind(now - Duration::days(1))
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+    if sent_today >= MAX_INVITATIONS_PER_DAY {
+        return Err(ApiError::new(Msg::TooManyInvitations, StatusCode::TOO_MANY_REQUESTS)
+            .with_message(Msg::TooManyInvitations.with(&[("max", &MAX_INVITATIONS_PER_DAY.to_string())])));
+    }
+
+    let inviter: String = sqlx::query_scalar("SELECT username FROM users WHERE id = ?")
+        .bind(&claims.user_id)
+        .fetch_one(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    sqlx::query("INSERT INTO invitations (id, user_id, email, created_at) VALUES (?, ?, ?, ?)")
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&claims.user_id)
+        .bind(&email)
+        .bind(now)
+        .execute(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    // The invitee has no settings yet, so it goes out in the inviter's language
+    let locale = user_locale(pool.get_ref(), &claims.user_id).await;
+    let context = context! {
+        inviter => inviter,
+        link => &config.invite_url,
+    };
+    mail::send(pool.get_ref(), &config, MailKind::Invitation, locale, &email, context).await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse::<()>::success_no_data(Msg::InvitationSent)))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::App;
+
+    use super::*;
+    use crate::middleware::jwt::create_token;
+    use crate::test_support::{self, lazy_pool};
+
+    fn invite(token: &str, email: &str) -> TestRequest {
+        TestRequest::post()
+            .uri("/auth/invitations")
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .set_json(serde_json::json!({ "email": email }))
+    }
+
+    // Validation runs before any query, so the pool never connects
+    #[actix_web::test]
+    async fn rejects_invalid_addresses() {
+        let config = Config::for_tests();
+        let token = create_token(&config, "user-1", "ana@example.com").unwrap();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(lazy_pool(&config)))
+                .app_data(web::Data::new(config))
+                .route("/auth/invitations", web::post().to(create_invitation)),
+        )
+        .await;
+
+        for email in ["", "not an email"] {
+            assert_eq!(call_service(&app, invite(&token, email).to_request()).await.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn sends_each_invitation_once() {
+        let (pool, config) = test_support::database().await;
+        let (user_id, email) = test_support::create_user(&pool, "inviter", "!", true).await;
+        let token = create_token(&config, &user_id, &email).unwrap();
+        let invitee = format!("invitee-{}@example.com", &user_id[..8]);
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(config))
+                .route("/auth/invitations", web::post().to(create_invitation)),
+        )
+        .await;
+
+        let first = call_service(&app, invite(&token, &invitee.to_uppercase()).to_request()).await.status();
+        let again = call_service(&app, invite(&token, &invitee).to_request()).await.status();
+        let registered = call_service(&app, invite(&token, &email).to_request()).await.status();
+        let sent: Vec<(String, String)> =
+            sqlx::query_as("SELECT subject, text_body FROM mail_outbox WHERE recipient = ? AND kind = 'invitation'")
+                .bind(&invitee)
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        sqlx::query("DELETE FROM mail_outbox WHERE recipient = ?").bind(&invitee).execute(&pool).await.unwrap();
+        test_support::delete_user(&pool, &user_id).await;
+
+        assert_eq!(first, StatusCode::CREATED);
+        assert_eq!(again, StatusCode::CONFLICT);
+        assert_eq!(registered, StatusCode::CONFLICT);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, "inviter convidou você para o MyTudo");
+        assert!(sent[0].1.contains("http://localhost:8080"));
+    }
+}
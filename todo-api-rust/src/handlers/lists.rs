@@ -1,9 +1,12 @@
 use actix_web::{web, HttpRequest, HttpResponse};
 use chrono::Utc;
 use sqlx::MySqlPool;
+use validator::Validate;
 
 use crate::config::Config;
-use crate::errors::{ApiError, ApiResponse};
+use crate::events::{self, EventHub};
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::i18n::Msg;
 use crate::middleware::jwt::get_auth_user;
 use crate::models::*;
 
@@ -12,27 +15,78 @@ fn generate_uuid() -> String {
     uuid::Uuid::new_v4().to_string()
 }
 
-// Helper: Log sync
-async fn log_sync(pool: &MySqlPool, user_id: &str, entity_type: &str, entity_id: &str, action: &str) {
-    let _ = sqlx::query(
-        "INSERT INTO sync_log (user_id, entity_type, entity_id, action) VALUES (?, ?, ?, ?)"
+// Helper: Soft delete the live tasks of a list and log each one, so clients and webhooks
+// hear about them like they would with DELETE /tasks/{id}
+pub async fn delete_list_tasks(
+    pool: &MySqlPool,
+    events: &EventHub,
+    device_id: Option<&str>,
+    user_id: &str,
+    list_id: &str,
+) -> Result<(), ApiError> {
+    let task_ids: Vec<String> = sqlx::query_scalar(
+        "SELECT id FROM tasks WHERE list_id = ? AND deleted_at IS NULL"
     )
-    .bind(user_id)
-    .bind(entity_type)
-    .bind(entity_id)
-    .bind(action)
+    .bind(list_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    sqlx::query(
+        "UPDATE tasks SET deleted_at = NOW() WHERE list_id = ? AND deleted_at IS NULL"
+    )
+    .bind(list_id)
     .execute(pool)
-    .await;
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    for task_id in &task_ids {
+        events::log_sync(pool, events, device_id, user_id, "task", task_id, "delete").await;
+    }
+
+    Ok(())
 }
 
 // GET /lists
+#[utoipa::path(
+    get,
+    path = "/lists",
+    tag = "lists",
+    params(ListsQuery),
+    responses(
+        (status = 200, description = "Lists of the user; with `with_counts=true` each item also has task counts", body = ApiResponse<Vec<ListWithCountsResponse>>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_lists(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
     config: web::Data<Config>,
+    query: web::Query<ListsQuery>,
 ) -> Result<HttpResponse, ApiError> {
     let claims = get_auth_user(&req, &config)?;
 
+    if query.with_counts.unwrap_or(false) {
+        let lists: Vec<ListWithCountsResponse> = sqlx::query_as(
+            r#"
+            SELECT id, user_id, name, color, created_at, updated_at,
+                CAST(total_tasks AS SIGNED) AS total_tasks,
+                CAST(COALESCE(completed_tasks, 0) AS SIGNED) AS completed_tasks,
+                CAST(COALESCE(pending_tasks, 0) AS SIGNED) AS pending_tasks
+            FROM vw_lists_with_counts
+            WHERE user_id = ?
+            ORDER BY created_at DESC
+            "#
+        )
+        .bind(&claims.user_id)
+        .fetch_all(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+        return Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::ListsLoaded, lists)));
+    }
+
     let lists: Vec<TodoList> = sqlx::query_as(
         r#"
         SELECT id, user_id, name, color, created_at, updated_at, deleted_at
@@ -48,10 +102,22 @@ pub async fn get_lists(
 
     let lists: Vec<ListResponse> = lists.into_iter().map(|l| l.into()).collect();
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success("Listas carregadas", lists)))
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::ListsLoaded, lists)))
 }
 
 // GET /lists/{id}
+#[utoipa::path(
+    get,
+    path = "/lists/{id}",
+    tag = "lists",
+    params(("id" = String, Path, description = "List id")),
+    responses(
+        (status = 200, description = "List", body = ApiResponse<ListResponse>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_list(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
@@ -74,29 +140,39 @@ pub async fn get_list(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let list = list.ok_or_else(|| ApiError::not_found("Lista não encontrada"))?;
+    let list = list.ok_or_else(|| ApiError::not_found(Msg::ListNotFound))?;
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success("Lista encontrada", ListResponse::from(list))))
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::ListFound, ListResponse::from(list))))
 }
 
 // POST /lists
+#[utoipa::path(
+    post,
+    path = "/lists",
+    tag = "lists",
+    request_body = CreateListRequest,
+    responses(
+        (status = 201, description = "List created", body = ApiResponse<ListResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn create_list(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
     config: web::Data<Config>,
+    events: web::Data<EventHub>,
     body: web::Json<CreateListRequest>,
 ) -> Result<HttpResponse, ApiError> {
     let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
 
     let id = body.id.clone().unwrap_or_else(generate_uuid);
     let name = body.name.trim();
     let color = body.color.clone().unwrap_or_else(|| "#3B82F6".to_string());
     let created_at = body.created_at.clone().unwrap_or_else(|| Utc::now().to_rfc3339());
 
-    if name.is_empty() {
-        return Err(ApiError::bad_request("Nome da lista é obrigatório"));
-    }
-
     // Check if exists (for sync)
     let existing: Option<(String,)> = sqlx::query_as(
         "SELECT id FROM todo_lists WHERE id = ?"
@@ -140,10 +216,10 @@ pub async fn create_list(
         .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
     }
 
-    log_sync(pool.get_ref(), &claims.user_id, "list", &id, "create").await;
+    events::log_sync(pool.get_ref(), &events, events::device_id(&req).as_deref(), &claims.user_id, "list", &id, "create").await;
 
     Ok(HttpResponse::Created().json(ApiResponse::success(
-        "Lista criada com sucesso",
+        Msg::ListCreated,
         ListResponse {
             id,
             user_id: claims.user_id,
@@ -156,14 +232,30 @@ pub async fn create_list(
 }
 
 // PUT /lists/{id}
+#[utoipa::path(
+    put,
+    path = "/lists/{id}",
+    tag = "lists",
+    params(("id" = String, Path, description = "List id")),
+    request_body = UpdateListRequest,
+    responses(
+        (status = 200, description = "List updated", body = ApiResponse<ListResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn update_list(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
     config: web::Data<Config>,
+    events: web::Data<EventHub>,
     path: web::Path<String>,
     body: web::Json<UpdateListRequest>,
 ) -> Result<HttpResponse, ApiError> {
     let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
     let list_id = path.into_inner();
 
     // Check ownership
@@ -176,7 +268,7 @@ pub async fn update_list(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let mut list = existing.ok_or_else(|| ApiError::not_found("Lista não encontrada"))?;
+    let mut list = existing.ok_or_else(|| ApiError::not_found(Msg::ListNotFound))?;
 
     // Update fields
     let mut has_updates = false;
@@ -195,7 +287,7 @@ pub async fn update_list(
     }
 
     if !has_updates {
-        return Err(ApiError::bad_request("Nenhum campo para atualizar"));
+        return Err(ApiError::bad_request(Msg::NothingToUpdate));
     }
 
     sqlx::query(
@@ -209,7 +301,7 @@ pub async fn update_list(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    log_sync(pool.get_ref(), &claims.user_id, "list", &list_id, "update").await;
+    events::log_sync(pool.get_ref(), &events, events::device_id(&req).as_deref(), &claims.user_id, "list", &list_id, "update").await;
 
     // Return updated list
     let list: TodoList = sqlx::query_as(
@@ -220,14 +312,27 @@ pub async fn update_list(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success("Lista atualizada", ListResponse::from(list))))
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::ListUpdated, ListResponse::from(list))))
 }
 
 // DELETE /lists/{id}
+#[utoipa::path(
+    delete,
+    path = "/lists/{id}",
+    tag = "lists",
+    params(("id" = String, Path, description = "List id")),
+    responses(
+        (status = 200, description = "List deleted", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn delete_list(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
     config: web::Data<Config>,
+    events: web::Data<EventHub>,
     path: web::Path<String>,
 ) -> Result<HttpResponse, ApiError> {
     let claims = get_auth_user(&req, &config)?;
@@ -244,15 +349,10 @@ pub async fn delete_list(
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
     // Soft delete tasks
-    sqlx::query(
-        "UPDATE tasks SET deleted_at = NOW() WHERE list_id = ?"
-    )
-    .bind(&list_id)
-    .execute(pool.get_ref())
-    .await
-    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+    let device_id = events::device_id(&req);
+    delete_list_tasks(pool.get_ref(), &events, device_id.as_deref(), &claims.user_id, &list_id).await?;
 
-    log_sync(pool.get_ref(), &claims.user_id, "list", &list_id, "delete").await;
+    events::log_sync(pool.get_ref(), &events, device_id.as_deref(), &claims.user_id, "list", &list_id, "delete").await;
 
-    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data("Lista deletada com sucesso")))
+    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data(Msg::ListDeleted)))
 }
@@ -1,5 +1,22 @@
 pub mod root;
 pub mod auth;
+pub mod account;
+pub mod api_tokens;
+pub mod invitations;
 pub mod lists;
+pub mod oidc;
 pub mod tasks;
+pub mod attachments;
+pub mod reminders;
 pub mod sync;
+pub mod import;
+pub mod export;
+pub mod inbound;
+pub mod stats;
+pub mod calendar;
+pub mod caldav;
+pub mod events;
+pub mod settings;
+pub mod health;
+pub mod webhooks;
+pub mod push;
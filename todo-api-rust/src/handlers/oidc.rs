@@ -0,0 +1,660 @@
+use actix_web::http::header;
+use actix_web::{web, HttpResponse};
+use chrono::{Duration, Utc};
+use openidconnect::core::CoreAuthenticationFlow;
+use openidconnect::{
+    AuthorizationCode, CsrfToken, Nonce, PkceCodeChallenge, PkceCodeVerifier, Scope as OidcScope,
+};
+use rand::Rng;
+use sqlx::MySqlPool;
+
+use crate::config::Config;
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::i18n::Msg;
+use crate::middleware::jwt::create_token;
+use crate::models::*;
+use crate::oidc::{Oidc, OidcProvider};
+use crate::password;
+
+// How long the user has to finish signing in at the provider
+const STATE_TTL_MINUTES: i64 = 10;
+
+// What the ID token says about the user
+struct Identity {
+    subject: String,
+    email: Option<String>,
+    email_verified: bool,
+    name: Option<String>,
+}
+
+// Helper: Generate UUID
+fn generate_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+// Helper: Configured provider by name
+fn find_provider<'a>(config: &'a Config, name: &str) -> Result<&'a OidcProvider, ApiError> {
+    config
+        .oidc_providers
+        .iter()
+        .find(|provider| provider.name == name)
+        .ok_or_else(|| ApiError::not_found(Msg::OidcProviderNotFound))
+}
+
+// Helper: Display name for a new account, from the ID token or the email
+fn username_for(identity: &Identity, email: &str) -> String {
+    let local_part = email.split('@').next().unwrap_or("");
+
+    let name = [identity.name.as_deref(), Some(local_part)]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|name| name.chars().count() >= 3)
+        .unwrap_or(email);
+
+    name.chars().take(100).collect()
+}
+
+// Helper: Random password nobody knows; "forgot password" sets a real one if wanted
+async fn unknown_password_hash(config: &Config) -> Result<String, ApiError> {
+    let secret: [u8; 32] = rand::thread_rng().gen();
+    let secret: String = secret.iter().map(|b| format!("{:02x}", b)).collect();
+    password::hash(&secret, config.password_hashing).await
+}
+
+// Helper: Consume the state saved by `login`; each one works once
+async fn take_state(pool: &MySqlPool, state: &str, provider: &str) -> Result<OidcLoginState, ApiError> {
+    let mut tx = pool.begin().await
+        .map_err(|e| ApiError::internal(format!("Transaction error: {}", e)))?;
+
+    let login_state: Option<OidcLoginState> = sqlx::query_as(
+        "SELECT provider, nonce, pkce_verifier, expires_at FROM oidc_login_states WHERE state = ? FOR UPDATE"
+    )
+    .bind(state)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    sqlx::query("DELETE FROM oidc_login_states WHERE state = ?")
+        .bind(state)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    tx.commit().await
+        .map_err(|e| ApiError::internal(format!("Commit error: {}", e)))?;
+
+    login_state
+        .filter(|s| s.provider == provider && s.expires_at > Utc::now())
+        .ok_or_else(|| ApiError::bad_request(Msg::InvalidOidcState))
+}
+
+// Helper: User already linked to this identity, else the account with the same verified
+// email, else a new account. Links the identity in the last two cases; an account that
+// never verified its email is reset first, since its owner never proved the address.
+async fn resolve_user(pool: &MySqlPool, config: &Config, provider: &str, identity: &Identity) -> Result<User, ApiError> {
+    let now = Utc::now();
+
+    let linked: Option<User> = sqlx::query_as(
+        r#"
+        SELECT u.*
+        FROM users u
+        JOIN user_identities i ON i.user_id = u.id
+        WHERE i.provider = ? AND i.subject = ?
+        "#
+    )
+    .bind(provider)
+    .bind(&identity.subject)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    if let Some(user) = linked {
+        sqlx::query("UPDATE user_identities SET last_login_at = ? WHERE provider = ? AND subject = ?")
+            .bind(now)
+            .bind(provider)
+            .bind(&identity.subject)
+            .execute(pool)
+            .await
+            .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+        return Ok(user);
+    }
+
+    // Linking by email is only safe when the provider vouches for the address
+    let email = match &identity.email {
+        Some(email) if identity.email_verified => email.clone(),
+        _ => return Err(ApiError::forbidden(Msg::OidcEmailNotVerified)),
+    };
+
+    let mut tx = pool.begin().await
+        .map_err(|e| ApiError::internal(format!("Transaction error: {}", e)))?;
+
+    let existing: Option<User> = sqlx::query_as(
+        "SELECT * FROM users WHERE email = ? FOR UPDATE"
+    )
+    .bind(&email)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let user_id = match existing {
+        Some(user) if !user.is_verified => {
+            // Whoever registered the address never proved it, and the provider just did.
+            // Their password, codes and tokens must not outlive the takeover.
+            sqlx::query(
+                r#"
+                UPDATE users
+                SET is_verified = 1, password_hash = ?, verification_code = NULL, code_expires_at = NULL, sessions_revoked_at = ?
+                WHERE id = ?
+                "#
+            )
+            .bind(unknown_password_hash(config).await?)
+            .bind(now)
+            .bind(&user.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+            sqlx::query("UPDATE api_tokens SET revoked_at = ? WHERE user_id = ? AND revoked_at IS NULL")
+                .bind(now)
+                .bind(&user.id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+            sqlx::query("DELETE FROM email_changes WHERE user_id = ?")
+                .bind(&user.id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+            tracing::info!(user_id = %user.id, %provider, "Unverified account claimed through OIDC");
+            user.id
+        }
+        Some(user) => user.id,
+        None => {
+            let password_hash = unknown_password_hash(config).await?;
+            let id = generate_uuid();
+
+            sqlx::query(
+                r#"
+                INSERT INTO users (id, username, email, password_hash, created_at, is_verified)
+                VALUES (?, ?, ?, ?, ?, 1)
+                "#
+            )
+            .bind(&id)
+            .bind(username_for(identity, &email))
+            .bind(&email)
+            .bind(&password_hash)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+            tracing::info!(user_id = %id, %provider, "Account created through OIDC");
+            id
+        }
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_identities (id, user_id, provider, subject, email, created_at, last_login_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(generate_uuid())
+    .bind(&user_id)
+    .bind(provider)
+    .bind(&identity.subject)
+    .bind(&email)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let user: User = sqlx::query_as("SELECT * FROM users WHERE id = ?")
+        .bind(&user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    tx.commit().await
+        .map_err(|e| ApiError::internal(format!("Commit error: {}", e)))?;
+
+    tracing::info!(%user_id, %provider, "OIDC identity linked");
+
+    Ok(user)
+}
+
+// Helper: Trade the authorization code for tokens and read the verified ID token
+async fn exchange_code(
+    oidc: &Oidc,
+    provider: &OidcProvider,
+    code: String,
+    pkce_verifier: String,
+    nonce: String,
+) -> Result<Identity, ApiError> {
+    let client = oidc.client(provider).await?;
+
+    let token_response = client
+        .exchange_code(AuthorizationCode::new(code))
+        .map_err(|e| ApiError::internal(format!("OIDC configuration error: {}", e)))?
+        .set_pkce_verifier(PkceCodeVerifier::new(pkce_verifier))
+        .request_async(&oidc.http)
+        .await
+        .map_err(|e| {
+            tracing::warn!(provider = %provider.name, error = %e, "OIDC code exchange failed");
+            ApiError::unauthorized(Msg::OidcLoginFailed)
+        })?;
+
+    let id_token = token_response
+        .extra_fields()
+        .id_token()
+        .ok_or_else(|| ApiError::unauthorized(Msg::OidcLoginFailed))?;
+
+    // Checks signature, issuer, audience, expiry and nonce
+    let claims = id_token
+        .claims(&client.id_token_verifier(), &Nonce::new(nonce))
+        .map_err(|e| {
+            tracing::warn!(provider = %provider.name, error = %e, "OIDC ID token rejected");
+            // Keys may have rotated; fetch them again next time
+            oidc.invalidate(&provider.name);
+            ApiError::unauthorized(Msg::OidcLoginFailed)
+        })?;
+
+    Ok(Identity {
+        subject: claims.subject().to_string(),
+        email: claims.email().map(|email| email.trim().to_lowercase()),
+        email_verified: claims.email_verified() == Some(true),
+        name: claims
+            .name()
+            .and_then(|name| name.get(None))
+            .map(|name| name.to_string())
+            .or_else(|| claims.preferred_username().map(|name| name.to_string())),
+    })
+}
+
+// GET /auth/oidc/providers
+#[utoipa::path(
+    get,
+    path = "/auth/oidc/providers",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Identity providers available for sign-in", body = ApiResponse<Vec<OidcProviderInfo>>),
+    )
+)]
+pub async fn list_providers(config: web::Data<Config>) -> Result<HttpResponse, ApiError> {
+    let providers: Vec<OidcProviderInfo> = config
+        .oidc_providers
+        .iter()
+        .map(|provider| OidcProviderInfo {
+            name: provider.name.clone(),
+            display_name: provider.display_name.clone(),
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::OidcProvidersLoaded, providers)))
+}
+
+// GET /auth/oidc/{provider}/login
+#[utoipa::path(
+    get,
+    path = "/auth/oidc/{provider}/login",
+    tag = "auth",
+    params(("provider" = String, Path, description = "Provider name")),
+    responses(
+        (status = 302, description = "Redirect to the provider's sign-in page (authorization code flow with PKCE)"),
+        (status = 404, description = "Unknown provider", body = MessageResponse),
+        (status = 502, description = "Provider discovery failed", body = MessageResponse),
+    )
+)]
+pub async fn login(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    oidc: web::Data<Oidc>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let provider = find_provider(&config, &path)?;
+    let client = oidc.client(provider).await?;
+
+    let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+    let request = provider.scopes.iter().fold(
+        client
+            .authorize_url(CoreAuthenticationFlow::AuthorizationCode, CsrfToken::new_random, Nonce::new_random)
+            .set_pkce_challenge(pkce_challenge),
+        |request, scope| request.add_scope(OidcScope::new(scope.clone())),
+    );
+    let (authorize_url, csrf_state, nonce) = request.url();
+
+    let now = Utc::now();
+    sqlx::query(
+        r#"
+        INSERT INTO oidc_login_states (state, provider, nonce, pkce_verifier, expires_at, created_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(csrf_state.secret())
+    .bind(&provider.name)
+    .bind(nonce.secret())
+    .bind(pkce_verifier.secret())
+    .bind(now + Duration::minutes(STATE_TTL_MINUTES))
+    .bind(now)
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    Ok(HttpResponse::Found()
+        .insert_header((header::LOCATION, authorize_url.to_string()))
+        .finish())
+}
+
+// GET /auth/oidc/{provider}/callback
+#[utoipa::path(
+    get,
+    path = "/auth/oidc/{provider}/callback",
+    tag = "auth",
+    params(("provider" = String, Path, description = "Provider name"), OidcCallbackQuery),
+    responses(
+        (status = 200, description = "Signed in; same body as /auth/login", body = ApiResponse<LoginResponse>),
+        (status = 400, description = "Unknown, used or expired state", body = MessageResponse),
+        (status = 401, description = "The provider refused or the ID token is invalid", body = MessageResponse),
+        (status = 403, description = "The provider did not confirm the email", body = MessageResponse),
+        (status = 404, description = "Unknown provider", body = MessageResponse),
+    )
+)]
+pub async fn callback(
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    oidc: web::Data<Oidc>,
+    path: web::Path<String>,
+    query: web::Query<OidcCallbackQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let provider = find_provider(&config, &path)?;
+    let query = query.into_inner();
+
+    if let Some(error) = &query.error {
+        tracing::info!(provider = %provider.name, %error, description = ?query.error_description, "OIDC provider returned an error");
+        return Err(ApiError::unauthorized(Msg::OidcLoginFailed));
+    }
+
+    let (Some(code), Some(state)) = (query.code, query.state) else {
+        return Err(ApiError::bad_request(Msg::InvalidOidcState));
+    };
+
+    let login_state = take_state(pool.get_ref(), &state, &provider.name).await?;
+    let identity = exchange_code(&oidc, provider, code, login_state.pkce_verifier, login_state.nonce).await?;
+
+    let user = resolve_user(pool.get_ref(), &config, &provider.name, &identity).await?;
+    let token = create_token(&config, &user.id, &user.email)?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(
+        Msg::LoggedIn,
+        LoginResponse {
+            user: user.into(),
+            token,
+        },
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App, HttpServer};
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use jsonwebtoken::{Algorithm, EncodingKey, Header};
+    use openssl::rsa::Rsa;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::test_support;
+
+    const CLIENT_ID: &str = "todo-test";
+    const KEY_ID: &str = "test-key";
+
+    // What the mock hands out from its token endpoint
+    struct Issued {
+        key: EncodingKey,
+        claims: Value,
+    }
+
+    // In-process identity provider: discovery, JWKS and a token endpoint that signs
+    // whatever claims the test set last, plus issuer, audience and lifetimes.
+    struct MockProvider {
+        issuer: String,
+        issued: Arc<Mutex<Issued>>,
+    }
+
+    fn rsa_key() -> (EncodingKey, Value) {
+        let rsa = Rsa::generate(2048).unwrap();
+        let jwk = json!({
+            "kty": "RSA",
+            "use": "sig",
+            "alg": "RS256",
+            "kid": KEY_ID,
+            "n": URL_SAFE_NO_PAD.encode(rsa.n().to_vec()),
+            "e": URL_SAFE_NO_PAD.encode(rsa.e().to_vec()),
+        });
+        (EncodingKey::from_rsa_pem(&rsa.private_key_to_pem().unwrap()).unwrap(), jwk)
+    }
+
+    impl MockProvider {
+        fn start() -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let issuer = format!("http://{}", listener.local_addr().unwrap());
+            let (key, jwk) = rsa_key();
+            let issued = Arc::new(Mutex::new(Issued { key, claims: json!({}) }));
+
+            let discovery = json!({
+                "issuer": issuer,
+                "authorization_endpoint": format!("{}/authorize", issuer),
+                "token_endpoint": format!("{}/token", issuer),
+                "jwks_uri": format!("{}/jwks", issuer),
+                "response_types_supported": ["code"],
+                "subject_types_supported": ["public"],
+                "id_token_signing_alg_values_supported": ["RS256"],
+            });
+            let jwks = json!({ "keys": [jwk] });
+            let state = (issuer.clone(), issued.clone());
+
+            let server = HttpServer::new(move || {
+                let (discovery, jwks, (issuer, issued)) = (discovery.clone(), jwks.clone(), state.clone());
+                App::new()
+                    .route("/.well-known/openid-configuration", web::get().to(move || {
+                        let discovery = discovery.clone();
+                        async move { HttpResponse::Ok().json(discovery) }
+                    }))
+                    .route("/jwks", web::get().to(move || {
+                        let jwks = jwks.clone();
+                        async move { HttpResponse::Ok().json(jwks) }
+                    }))
+                    .route("/token", web::post().to(move || {
+                        let (issuer, issued) = (issuer.clone(), issued.clone());
+                        async move {
+                            let issued = issued.lock().unwrap();
+                            let now = Utc::now().timestamp();
+                            let mut claims = json!({ "iss": issuer, "aud": CLIENT_ID, "iat": now, "exp": now + 300 });
+                            claims.as_object_mut().unwrap().extend(issued.claims.as_object().unwrap().clone());
+
+                            let mut header = Header::new(Algorithm::RS256);
+                            header.kid = Some(KEY_ID.to_string());
+                            let id_token = jsonwebtoken::encode(&header, &claims, &issued.key).unwrap();
+                            HttpResponse::Ok().json(json!({
+                                "access_token": "mock-access-token",
+                                "token_type": "Bearer",
+                                "expires_in": 300,
+                                "id_token": id_token,
+                            }))
+                        }
+                    }))
+            })
+            .workers(1)
+            .listen(listener)
+            .unwrap()
+            .run();
+            actix_web::rt::spawn(server);
+
+            Self { issuer, issued }
+        }
+
+        fn provider(&self) -> OidcProvider {
+            OidcProvider {
+                name: "mock".to_string(),
+                display_name: "Mock".to_string(),
+                issuer_url: self.issuer.clone(),
+                client_id: CLIENT_ID.to_string(),
+                client_secret: Some("secret".to_string()),
+                scopes: vec!["email".to_string()],
+                redirect_url: "http://localhost/auth/oidc/mock/callback".to_string(),
+            }
+        }
+
+        fn sign_in(&self, claims: Value) {
+            self.issued.lock().unwrap().claims = claims;
+        }
+
+        // Signs later tokens with a key the JWKS does not publish
+        fn rotate_to_unknown_key(&self) {
+            self.issued.lock().unwrap().key = rsa_key().0;
+        }
+    }
+
+    async fn exchange(mock: &MockProvider, nonce: &str) -> Result<Identity, ApiError> {
+        let verifier = "v".repeat(43);
+        exchange_code(&Oidc::default(), &mock.provider(), "code".to_string(), verifier, nonce.to_string()).await
+    }
+
+    #[actix_web::test]
+    async fn reads_the_verified_identity() {
+        let mock = MockProvider::start();
+        mock.sign_in(json!({ "sub": "abc", "email": " Ana@Example.com ", "email_verified": true, "name": "Ana", "nonce": "n-1" }));
+
+        let identity = exchange(&mock, "n-1").await.unwrap();
+        assert_eq!(identity.subject, "abc");
+        assert_eq!(identity.email.as_deref(), Some("ana@example.com"));
+        assert!(identity.email_verified);
+        assert_eq!(identity.name.as_deref(), Some("Ana"));
+
+        // Missing `email_verified` is not a yes
+        mock.sign_in(json!({ "sub": "abc", "email": "ana@example.com", "nonce": "n-1" }));
+        assert!(!exchange(&mock, "n-1").await.unwrap().email_verified);
+    }
+
+    #[actix_web::test]
+    async fn rejects_replayed_or_forged_id_tokens() {
+        let mock = MockProvider::start();
+        mock.sign_in(json!({ "sub": "abc", "email": "ana@example.com", "email_verified": true, "nonce": "n-1" }));
+        let err = exchange(&mock, "n-2").await.err().expect("nonce mismatch accepted");
+        assert_eq!(err.code, Msg::OidcLoginFailed);
+
+        mock.rotate_to_unknown_key();
+        let err = exchange(&mock, "n-1").await.err().expect("unknown key accepted");
+        assert_eq!(err.code, Msg::OidcLoginFailed);
+    }
+
+    // Runs `/login` and `/callback` against the mock for `email`; returns the callback status
+    async fn sign_in_through_callback(pool: &MySqlPool, config: &Config, mock: &MockProvider, email: &str) -> StatusCode {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(config.clone()))
+                .app_data(web::Data::new(Oidc::default()))
+                .route("/auth/oidc/{provider}/login", web::get().to(login))
+                .route("/auth/oidc/{provider}/callback", web::get().to(callback)),
+        )
+        .await;
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("/auth/oidc/mock/login").to_request()).await;
+        assert_eq!(res.status(), StatusCode::FOUND);
+        let location = reqwest::Url::parse(res.headers().get(header::LOCATION).unwrap().to_str().unwrap()).unwrap();
+        let param = |name: &str| location.query_pairs().find(|(key, _)| key == name).unwrap().1.into_owned();
+
+        mock.sign_in(json!({
+            "sub": uuid::Uuid::new_v4().to_string(),
+            "email": email,
+            "email_verified": true,
+            "nonce": param("nonce"),
+        }));
+        let uri = format!("/auth/oidc/mock/callback?code=abc&state={}", param("state"));
+        test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await.status()
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn claiming_an_unverified_account_drops_its_password_and_tokens() {
+        let (pool, mut config) = test_support::database().await;
+        let mock = MockProvider::start();
+        config.oidc_providers = vec![mock.provider()];
+
+        // Someone registered the victim's address and never verified it
+        let squatter_hash = bcrypt::hash("squatter password", 4).unwrap();
+        let (user_id, email) = test_support::create_user(&pool, "squatter", &squatter_hash, false).await;
+        sqlx::query(
+            r#"
+            INSERT INTO api_tokens (id, user_id, name, token_hash, token_prefix, scopes, created_at)
+            VALUES (?, ?, 'squatter', ?, 'mtd_test', 'lists:read', NOW())
+            "#
+        )
+        .bind(generate_uuid())
+        .bind(&user_id)
+        .bind(format!("{:064}", 0))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(sign_in_through_callback(&pool, &config, &mock, &email).await, StatusCode::OK);
+
+        let (verified, stored, revoked_at): (bool, String, Option<chrono::DateTime<Utc>>) =
+            sqlx::query_as("SELECT is_verified, password_hash, sessions_revoked_at FROM users WHERE id = ?")
+                .bind(&user_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let (active_tokens,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM api_tokens WHERE user_id = ? AND revoked_at IS NULL")
+                .bind(&user_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        test_support::delete_user(&pool, &user_id).await;
+
+        assert!(verified);
+        assert!(!password::verify("squatter password", &stored).await.unwrap());
+        assert!(revoked_at.is_some());
+        assert_eq!(active_tokens, 0);
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn linking_a_verified_account_keeps_its_password() {
+        let (pool, mut config) = test_support::database().await;
+        let mock = MockProvider::start();
+        config.oidc_providers = vec![mock.provider()];
+
+        let owner_hash = bcrypt::hash("owner password", 4).unwrap();
+        let (user_id, email) = test_support::create_user(&pool, "owner", &owner_hash, true).await;
+
+        assert_eq!(sign_in_through_callback(&pool, &config, &mock, &email).await, StatusCode::OK);
+
+        let (stored, revoked_at): (String, Option<chrono::DateTime<Utc>>) =
+            sqlx::query_as("SELECT password_hash, sessions_revoked_at FROM users WHERE id = ?")
+                .bind(&user_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let (identities,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM user_identities WHERE user_id = ?")
+            .bind(&user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        test_support::delete_user(&pool, &user_id).await;
+
+        assert_eq!(stored, owner_hash);
+        assert!(revoked_at.is_none());
+        assert_eq!(identities, 1);
+    }
+}
@@ -0,0 +1,339 @@
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::MySqlPool;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::i18n::Msg;
+use crate::middleware::jwt::get_auth_user;
+use crate::models::*;
+use crate::push::{self, sink, Sent, VapidKey};
+
+// Longest User-Agent kept, to tell devices apart in the list
+const MAX_USER_AGENT: usize = 255;
+
+// Helper: Generate UUID
+fn generate_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+// Helper: The server's VAPID key, or 503 when Web Push is not set up
+fn vapid_key(config: &Config) -> Result<VapidKey, ApiError> {
+    config
+        .vapid_private_key
+        .as_deref()
+        .and_then(|key| VapidKey::from_base64(key).ok())
+        .ok_or_else(|| ApiError::new(Msg::PushDisabled, StatusCode::SERVICE_UNAVAILABLE))
+}
+
+// Helper: The test push service only exists with PUSH_TEST_SINK
+fn require_sink(config: &Config) -> Result<(), ApiError> {
+    if !config.push_test_sink {
+        return Err(ApiError::not_found(Msg::PushSinkNotFound));
+    }
+    Ok(())
+}
+
+// Helper: Load one of the user's subscriptions
+async fn load_subscription(pool: &MySqlPool, id: &str, user_id: &str) -> Result<PushSubscription, ApiError> {
+    let subscription: Option<PushSubscription> = sqlx::query_as(&format!(
+        "SELECT {} FROM push_subscriptions WHERE id = ? AND user_id = ?",
+        PUSH_SUBSCRIPTION_COLUMNS
+    ))
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    subscription.ok_or_else(|| ApiError::not_found(Msg::PushSubscriptionNotFound))
+}
+
+// GET /push/vapid-key
+#[utoipa::path(
+    get,
+    path = "/push/vapid-key",
+    tag = "push",
+    responses(
+        (status = 200, description = "Public key for `pushManager.subscribe`", body = ApiResponse<VapidKeyResponse>),
+        (status = 503, description = "Web Push is not set up on this server", body = MessageResponse),
+    )
+)]
+pub async fn get_vapid_key(config: web::Data<Config>) -> Result<HttpResponse, ApiError> {
+    let vapid = vapid_key(&config)?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(
+        Msg::VapidKeyLoaded,
+        VapidKeyResponse { public_key: vapid.public_key },
+    )))
+}
+
+// GET /push/subscriptions
+#[utoipa::path(
+    get,
+    path = "/push/subscriptions",
+    tag = "push",
+    responses(
+        (status = 200, description = "Devices of the user that receive reminders", body = ApiResponse<Vec<PushSubscriptionResponse>>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_subscriptions(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+
+    let subscriptions: Vec<PushSubscription> = sqlx::query_as(&format!(
+        "SELECT {} FROM push_subscriptions WHERE user_id = ? ORDER BY created_at",
+        PUSH_SUBSCRIPTION_COLUMNS
+    ))
+    .bind(&claims.user_id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let subscriptions: Vec<PushSubscriptionResponse> = subscriptions.into_iter().map(Into::into).collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::PushSubscriptionsLoaded, subscriptions)))
+}
+
+// POST /push/subscriptions
+#[utoipa::path(
+    post,
+    path = "/push/subscriptions",
+    tag = "push",
+    request_body = CreatePushSubscriptionRequest,
+    responses(
+        (status = 201, description = "Subscription saved; an existing endpoint gets the new keys and owner", body = ApiResponse<PushSubscriptionResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_subscription(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    body: web::Json<CreatePushSubscriptionRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+
+    let endpoint = body.endpoint.trim();
+    // The endpoint is too long for a unique index, so its hash has one
+    let endpoint_hash = format!("{:x}", Sha256::digest(endpoint.as_bytes()));
+    let user_agent = req
+        .headers()
+        .get(actix_web::http::header::USER_AGENT)
+        .and_then(|ua| ua.to_str().ok())
+        .map(|ua| ua.chars().take(MAX_USER_AGENT).collect::<String>());
+
+    // A browser keeps its endpoint across logins, so whoever subscribes last owns it
+    sqlx::query(
+        r#"
+        INSERT INTO push_subscriptions (id, user_id, endpoint, endpoint_hash, p256dh, auth, user_agent, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE user_id = VALUES(user_id), p256dh = VALUES(p256dh), auth = VALUES(auth),
+            user_agent = VALUES(user_agent)
+        "#
+    )
+    .bind(generate_uuid())
+    .bind(&claims.user_id)
+    .bind(endpoint)
+    .bind(&endpoint_hash)
+    .bind(body.keys.p256dh.trim())
+    .bind(body.keys.auth.trim())
+    .bind(&user_agent)
+    .bind(Utc::now())
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let subscription: PushSubscription = sqlx::query_as(&format!(
+        "SELECT {} FROM push_subscriptions WHERE endpoint_hash = ?",
+        PUSH_SUBSCRIPTION_COLUMNS
+    ))
+    .bind(&endpoint_hash)
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(
+        Msg::PushSubscribed,
+        PushSubscriptionResponse::from(subscription),
+    )))
+}
+
+// DELETE /push/subscriptions/{id}
+#[utoipa::path(
+    delete,
+    path = "/push/subscriptions/{id}",
+    tag = "push",
+    params(("id" = String, Path, description = "Subscription id")),
+    responses(
+        (status = 200, description = "Subscription deleted", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_subscription(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+
+    let result = sqlx::query("DELETE FROM push_subscriptions WHERE id = ? AND user_id = ?")
+        .bind(path.as_str())
+        .bind(&claims.user_id)
+        .execute(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found(Msg::PushSubscriptionNotFound));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data(Msg::PushUnsubscribed)))
+}
+
+// POST /push/subscriptions/{id}/test
+#[utoipa::path(
+    post,
+    path = "/push/subscriptions/{id}/test",
+    tag = "push",
+    params(("id" = String, Path, description = "Subscription id")),
+    responses(
+        (status = 200, description = "The push service accepted a test notification", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found, or the push service no longer knows the subscription", body = MessageResponse),
+        (status = 502, description = "The push service refused the message", body = MessageResponse),
+        (status = 503, description = "Web Push is not set up on this server", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn test_subscription(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    let vapid = vapid_key(&config)?;
+    let subscription = load_subscription(pool.get_ref(), &path, &claims.user_id).await?;
+
+    let payload = json!({
+        "type": "test",
+        "title": Msg::PushTestTitle.with(&[]),
+        "body": Msg::PushTestBody.with(&[]),
+    })
+    .to_string();
+    let client = push::client(&config).map_err(|e| ApiError::internal(e.to_string()))?;
+
+    match push::send(&client, &config, &vapid, &subscription, payload.as_bytes(), 60).await {
+        Sent::Delivered => {
+            sqlx::query("UPDATE push_subscriptions SET last_used_at = ? WHERE id = ?")
+                .bind(Utc::now())
+                .bind(&subscription.id)
+                .execute(pool.get_ref())
+                .await
+                .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+            Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data(Msg::PushTestSent)))
+        }
+        Sent::Expired => {
+            sqlx::query("DELETE FROM push_subscriptions WHERE id = ?")
+                .bind(&subscription.id)
+                .execute(pool.get_ref())
+                .await
+                .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+            Err(ApiError::not_found(Msg::PushSubscriptionNotFound))
+        }
+        Sent::Failed(error) => Err(ApiError::new(Msg::PushTestFailed, StatusCode::BAD_GATEWAY)
+            .with_message(Msg::PushTestFailed.with(&[("error", &error)]))),
+    }
+}
+
+// POST /push/sink
+#[utoipa::path(
+    post,
+    path = "/push/sink",
+    tag = "push",
+    responses(
+        (status = 201, description = "A subscription on the test push service, to save with `POST /push/subscriptions`", body = ApiResponse<PushSinkSubscription>),
+        (status = 404, description = "`PUSH_TEST_SINK` is off", body = MessageResponse),
+    )
+)]
+pub async fn create_sink(config: web::Data<Config>) -> Result<HttpResponse, ApiError> {
+    require_sink(&config)?;
+    let subscription = sink::create(&config)?;
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(Msg::PushSinkCreated, subscription)))
+}
+
+// POST /push/sink/{id}
+#[utoipa::path(
+    post,
+    path = "/push/sink/{id}",
+    tag = "push",
+    params(("id" = String, Path, description = "Test subscription id")),
+    request_body(content = Vec<u8>, content_type = "application/octet-stream", description = "aes128gcm-encrypted message"),
+    responses(
+        (status = 201, description = "Message accepted, as a push service answers", body = MessageResponse),
+        (status = 400, description = "The message does not decrypt", body = MessageResponse),
+        (status = 401, description = "Missing or invalid VAPID token", body = MessageResponse),
+        (status = 404, description = "Unknown test subscription, or `PUSH_TEST_SINK` is off", body = MessageResponse),
+        (status = 413, description = "Message too large", body = MessageResponse),
+    )
+)]
+pub async fn receive_sink(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    path: web::Path<String>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    require_sink(&config)?;
+    let header = |name: &str| req.headers().get(name).and_then(|value| value.to_str().ok());
+
+    sink::receive(
+        &config,
+        &path,
+        header("Authorization"),
+        header("TTL").and_then(|ttl| ttl.trim().parse().ok()),
+        header("Urgency").map(str::to_string),
+        &body,
+    )?;
+
+    Ok(HttpResponse::Created().json(ApiResponse::<()>::success_no_data(Msg::PushSinkReceived)))
+}
+
+// GET /push/sink/{id}
+#[utoipa::path(
+    get,
+    path = "/push/sink/{id}",
+    tag = "push",
+    params(("id" = String, Path, description = "Test subscription id")),
+    responses(
+        (status = 200, description = "Messages received, decrypted, oldest first", body = ApiResponse<Vec<PushSinkMessage>>),
+        (status = 404, description = "Unknown test subscription, or `PUSH_TEST_SINK` is off", body = MessageResponse),
+    )
+)]
+pub async fn list_sink_messages(
+    config: web::Data<Config>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    require_sink(&config)?;
+    let messages = sink::messages(&path).ok_or_else(|| ApiError::not_found(Msg::PushSinkNotFound))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::PushSinkMessagesLoaded, messages)))
+}
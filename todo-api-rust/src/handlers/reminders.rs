@@ -0,0 +1,442 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::MySqlPool;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::events::{self, EventHub};
+use crate::handlers::settings::{load_settings, parse_user_datetime};
+use crate::i18n::Msg;
+use crate::middleware::jwt::get_auth_user;
+use crate::models::*;
+
+const MAX_REMINDERS: i64 = 20;
+const DEFAULT_SNOOZE_MINUTES: u32 = 10;
+
+// Helper: Generate UUID
+fn generate_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+// Helper: Whether the task exists and belongs to the user
+async fn owns_task(pool: &MySqlPool, task_id: &str, user_id: &str) -> Result<bool, ApiError> {
+    let task: Option<(String,)> = sqlx::query_as(
+        r#"
+        SELECT t.id
+        FROM tasks t
+        JOIN todo_lists l ON t.list_id = l.id
+        WHERE t.id = ? AND l.user_id = ? AND t.deleted_at IS NULL
+        "#
+    )
+    .bind(task_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    Ok(task.is_some())
+}
+
+// Helper: One reminder of the task
+async fn find_reminder(pool: &MySqlPool, task_id: &str, reminder_id: &str) -> Result<TaskReminder, ApiError> {
+    let reminder: Option<TaskReminder> = sqlx::query_as(&format!(
+        "SELECT {} FROM task_reminders r JOIN tasks t ON r.task_id = t.id WHERE r.id = ? AND r.task_id = ?",
+        REMINDER_COLUMNS
+    ))
+    .bind(reminder_id)
+    .bind(task_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    reminder.ok_or_else(|| ApiError::not_found(Msg::ReminderNotFound))
+}
+
+// Helper: Reminders travel with their task, so a change bumps the task for `/sync/pull`
+async fn touch_task(pool: &MySqlPool, task_id: &str) -> Result<(), ApiError> {
+    sqlx::query("UPDATE tasks SET updated_at = NOW() WHERE id = ?")
+        .bind(task_id)
+        .execute(pool)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+    Ok(())
+}
+
+// Helper: `remind_at` in UTC and `minutes_before_due`, exactly one of them set
+async fn reminder_time(
+    pool: &MySqlPool,
+    user_id: &str,
+    body: &ReminderRequest,
+) -> Result<(Option<DateTime<Utc>>, Option<u32>), ApiError> {
+    let remind_at = body.remind_at.as_deref().map(str::trim).filter(|at| !at.is_empty());
+    match (remind_at, body.minutes_before_due) {
+        (Some(at), None) => {
+            let settings = load_settings(pool, user_id).await?;
+            let at = parse_user_datetime(at, &settings).ok_or_else(|| ApiError::bad_request(Msg::InvalidDate))?;
+            Ok((Some(at), None))
+        }
+        (None, Some(minutes)) => Ok((None, Some(minutes))),
+        _ => Err(ApiError::bad_request(Msg::ReminderTimeRequired)),
+    }
+}
+
+/// Reminders of the given tasks, for sync responses.
+pub async fn reminders_of_tasks(pool: &MySqlPool, task_ids: &[String]) -> Result<Vec<TaskReminder>, ApiError> {
+    if task_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let placeholders = vec!["?"; task_ids.len()].join(", ");
+    let sql = format!(
+        "SELECT {} FROM task_reminders r JOIN tasks t ON r.task_id = t.id WHERE r.task_id IN ({}) ORDER BY r.created_at",
+        REMINDER_COLUMNS, placeholders
+    );
+    let mut query = sqlx::query_as(&sql);
+    for id in task_ids {
+        query = query.bind(id);
+    }
+
+    query
+        .fetch_all(pool)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))
+}
+
+// GET /tasks/{id}/reminders
+#[utoipa::path(
+    get,
+    path = "/tasks/{id}/reminders",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task id")),
+    responses(
+        (status = 200, description = "Reminders of the task", body = ApiResponse<Vec<TaskReminderResponse>>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Task not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_reminders(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+
+    if !owns_task(pool.get_ref(), &path, &claims.user_id).await? {
+        return Err(ApiError::not_found(Msg::TaskNotFound));
+    }
+
+    let reminders = reminders_of_tasks(pool.get_ref(), &[path.into_inner()]).await?;
+    let reminders: Vec<TaskReminderResponse> = reminders.into_iter().map(|r| r.into()).collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::RemindersLoaded, reminders)))
+}
+
+// POST /tasks/{id}/reminders
+#[utoipa::path(
+    post,
+    path = "/tasks/{id}/reminders",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task id")),
+    request_body = ReminderRequest,
+    responses(
+        (status = 201, description = "Reminder created", body = ApiResponse<TaskReminderResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Task not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_reminder(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    events: web::Data<EventHub>,
+    path: web::Path<String>,
+    body: web::Json<ReminderRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+    let task_id = path.into_inner();
+
+    if !owns_task(pool.get_ref(), &task_id, &claims.user_id).await? {
+        return Err(ApiError::not_found(Msg::TaskNotFound));
+    }
+    let (remind_at, minutes_before_due) = reminder_time(pool.get_ref(), &claims.user_id, &body).await?;
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM task_reminders WHERE task_id = ?")
+        .bind(&task_id)
+        .fetch_one(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    if count >= MAX_REMINDERS {
+        return Err(ApiError::bad_request(Msg::TooManyReminders)
+            .with_message(Msg::TooManyReminders.with(&[("max", &MAX_REMINDERS.to_string())])));
+    }
+
+    let id = generate_uuid();
+    let now = Utc::now();
+    sqlx::query(
+        r#"
+        INSERT INTO task_reminders (id, task_id, remind_at, minutes_before_due, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&id)
+    .bind(&task_id)
+    .bind(remind_at)
+    .bind(minutes_before_due)
+    .bind(now)
+    .bind(now)
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    touch_task(pool.get_ref(), &task_id).await?;
+    events::log_sync(pool.get_ref(), &events, events::device_id(&req).as_deref(), &claims.user_id, "task", &task_id, "update").await;
+
+    let reminder = find_reminder(pool.get_ref(), &task_id, &id).await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(Msg::ReminderCreated, TaskReminderResponse::from(reminder))))
+}
+
+// PUT /tasks/{id}/reminders/{reminder_id}
+#[utoipa::path(
+    put,
+    path = "/tasks/{id}/reminders/{reminder_id}",
+    tag = "tasks",
+    params(
+        ("id" = String, Path, description = "Task id"),
+        ("reminder_id" = String, Path, description = "Reminder id"),
+    ),
+    request_body = ReminderRequest,
+    responses(
+        (status = 200, description = "Reminder updated; snooze and dismissal are cleared", body = ApiResponse<TaskReminderResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_reminder(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    events: web::Data<EventHub>,
+    path: web::Path<(String, String)>,
+    body: web::Json<ReminderRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+    let (task_id, reminder_id) = path.into_inner();
+
+    if !owns_task(pool.get_ref(), &task_id, &claims.user_id).await? {
+        return Err(ApiError::not_found(Msg::TaskNotFound));
+    }
+    let (remind_at, minutes_before_due) = reminder_time(pool.get_ref(), &claims.user_id, &body).await?;
+
+    // A new time is a new reminder, so it rings again
+    let result = sqlx::query(
+        r#"
+        UPDATE task_reminders
+        SET remind_at = ?, minutes_before_due = ?, snoozed_until = NULL, dismissed_at = NULL, updated_at = NOW()
+        WHERE id = ? AND task_id = ?
+        "#
+    )
+    .bind(remind_at)
+    .bind(minutes_before_due)
+    .bind(&reminder_id)
+    .bind(&task_id)
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found(Msg::ReminderNotFound));
+    }
+
+    touch_task(pool.get_ref(), &task_id).await?;
+    events::log_sync(pool.get_ref(), &events, events::device_id(&req).as_deref(), &claims.user_id, "task", &task_id, "update").await;
+
+    let reminder = find_reminder(pool.get_ref(), &task_id, &reminder_id).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::ReminderUpdated, TaskReminderResponse::from(reminder))))
+}
+
+// DELETE /tasks/{id}/reminders/{reminder_id}
+#[utoipa::path(
+    delete,
+    path = "/tasks/{id}/reminders/{reminder_id}",
+    tag = "tasks",
+    params(
+        ("id" = String, Path, description = "Task id"),
+        ("reminder_id" = String, Path, description = "Reminder id"),
+    ),
+    responses(
+        (status = 200, description = "Reminder deleted", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_reminder(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    events: web::Data<EventHub>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    let (task_id, reminder_id) = path.into_inner();
+
+    if !owns_task(pool.get_ref(), &task_id, &claims.user_id).await? {
+        return Err(ApiError::not_found(Msg::TaskNotFound));
+    }
+
+    let result = sqlx::query("DELETE FROM task_reminders WHERE id = ? AND task_id = ?")
+        .bind(&reminder_id)
+        .bind(&task_id)
+        .execute(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found(Msg::ReminderNotFound));
+    }
+
+    touch_task(pool.get_ref(), &task_id).await?;
+    events::log_sync(pool.get_ref(), &events, events::device_id(&req).as_deref(), &claims.user_id, "task", &task_id, "update").await;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data(Msg::ReminderDeleted)))
+}
+
+// POST /tasks/{id}/reminders/{reminder_id}/snooze
+#[utoipa::path(
+    post,
+    path = "/tasks/{id}/reminders/{reminder_id}/snooze",
+    tag = "tasks",
+    params(
+        ("id" = String, Path, description = "Task id"),
+        ("reminder_id" = String, Path, description = "Reminder id"),
+    ),
+    request_body = SnoozeReminderRequest,
+    responses(
+        (status = 200, description = "Reminder snoozed on every device", body = ApiResponse<TaskReminderResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn snooze_reminder(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    events: web::Data<EventHub>,
+    path: web::Path<(String, String)>,
+    body: web::Json<SnoozeReminderRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+    let (task_id, reminder_id) = path.into_inner();
+
+    if !owns_task(pool.get_ref(), &task_id, &claims.user_id).await? {
+        return Err(ApiError::not_found(Msg::TaskNotFound));
+    }
+
+    let now = Utc::now();
+    let until = match body.until.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+        Some(until) => {
+            let settings = load_settings(pool.get_ref(), &claims.user_id).await?;
+            parse_user_datetime(until, &settings).ok_or_else(|| ApiError::bad_request(Msg::InvalidDate))?
+        }
+        None => now + Duration::minutes(body.minutes.unwrap_or(DEFAULT_SNOOZE_MINUTES).into()),
+    };
+    if until <= now {
+        return Err(ApiError::bad_request(Msg::SnoozeInPast));
+    }
+
+    let result = sqlx::query(
+        r#"
+        UPDATE task_reminders
+        SET snoozed_until = ?, dismissed_at = NULL, updated_at = NOW()
+        WHERE id = ? AND task_id = ?
+        "#
+    )
+    .bind(until)
+    .bind(&reminder_id)
+    .bind(&task_id)
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found(Msg::ReminderNotFound));
+    }
+
+    touch_task(pool.get_ref(), &task_id).await?;
+    events::log_sync(pool.get_ref(), &events, events::device_id(&req).as_deref(), &claims.user_id, "task", &task_id, "update").await;
+
+    let reminder = find_reminder(pool.get_ref(), &task_id, &reminder_id).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::ReminderSnoozed, TaskReminderResponse::from(reminder))))
+}
+
+// POST /tasks/{id}/reminders/{reminder_id}/dismiss
+#[utoipa::path(
+    post,
+    path = "/tasks/{id}/reminders/{reminder_id}/dismiss",
+    tag = "tasks",
+    params(
+        ("id" = String, Path, description = "Task id"),
+        ("reminder_id" = String, Path, description = "Reminder id"),
+    ),
+    responses(
+        (status = 200, description = "Reminder dismissed on every device", body = ApiResponse<TaskReminderResponse>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn dismiss_reminder(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    events: web::Data<EventHub>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    let (task_id, reminder_id) = path.into_inner();
+
+    if !owns_task(pool.get_ref(), &task_id, &claims.user_id).await? {
+        return Err(ApiError::not_found(Msg::TaskNotFound));
+    }
+
+    // Dismissing twice keeps the first time, so devices agree on it
+    let result = sqlx::query(
+        r#"
+        UPDATE task_reminders
+        SET dismissed_at = COALESCE(dismissed_at, NOW()), snoozed_until = NULL, updated_at = NOW()
+        WHERE id = ? AND task_id = ?
+        "#
+    )
+    .bind(&reminder_id)
+    .bind(&task_id)
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found(Msg::ReminderNotFound));
+    }
+
+    touch_task(pool.get_ref(), &task_id).await?;
+    events::log_sync(pool.get_ref(), &events, events::device_id(&req).as_deref(), &claims.user_id, "task", &task_id, "update").await;
+
+    let reminder = find_reminder(pool.get_ref(), &task_id, &reminder_id).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::ReminderDismissed, TaskReminderResponse::from(reminder))))
+}
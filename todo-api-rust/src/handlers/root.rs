@@ -1,36 +1,33 @@
-use actix_web::HttpResponse;
+use actix_web::{error::{JsonPayloadError, QueryPayloadError}, HttpRequest, HttpResponse};
 use serde_json::json;
 
-use crate::errors::ApiResponse;
+use crate::errors::{ApiError, ApiResponse};
+use crate::i18n::Msg;
 
 pub async fn index() -> HttpResponse {
-    let endpoints = json!({
-        "POST /auth/register": "Registrar usuário",
-        "POST /auth/verify": "Verificar email",
-        "POST /auth/resend-code": "Reenviar código",
-        "POST /auth/forgot-password": "Esqueci minha senha",
-        "POST /auth/verify-reset-code": "Verificar código de reset",
-        "POST /auth/reset-password": "Resetar senha",
-        "POST /auth/login": "Login",
-        "GET /auth/me": "Dados do usuário",
-        "PUT /auth/update-username": "Atualizar nome",
-        "PUT /auth/update-email": "Atualizar email",
-        "PUT /auth/update-password": "Atualizar senha",
-        "GET /lists": "Listar listas",
-        "POST /lists": "Criar lista",
-        "GET /lists/{id}": "Obter lista",
-        "PUT /lists/{id}": "Atualizar lista",
-        "DELETE /lists/{id}": "Deletar lista",
-        "GET /lists/{id}/tasks": "Tarefas da lista",
-        "GET /tasks": "Listar todas as tarefas",
-        "POST /tasks": "Criar tarefa",
-        "GET /tasks/{id}": "Obter tarefa",
-        "PUT /tasks/{id}": "Atualizar tarefa",
-        "DELETE /tasks/{id}": "Deletar tarefa",
-        "POST /sync/push": "Sincronizar para servidor",
-        "POST /sync/pull": "Baixar do servidor",
-        "POST /sync/full": "Sincronização completa"
+    let info = json!({
+        "name": "Todo API",
+        "version": env!("CARGO_PKG_VERSION"),
+        "docs": "/docs/",
+        "openapi": "/openapi.json"
     });
 
-    HttpResponse::Ok().json(ApiResponse::success("Todo App API v1.0 (Rust)", endpoints))
+    HttpResponse::Ok().json(ApiResponse::success(Msg::ApiIndex, info))
+}
+
+// Fallback for unknown routes, so 404s use the same error envelope as everything else
+pub async fn not_found() -> Result<HttpResponse, ApiError> {
+    Err(ApiError::not_found(Msg::RouteNotFound))
+}
+
+// Helper: Malformed JSON bodies (bad syntax, wrong types, missing fields)
+pub fn json_error(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    tracing::debug!(error = %err, "Rejected JSON body");
+    ApiError::bad_request(Msg::InvalidRequestBody).into()
+}
+
+// Helper: Query strings that don't deserialize into the handler's query struct
+pub fn query_error(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    tracing::debug!(error = %err, "Rejected query string");
+    ApiError::bad_request(Msg::InvalidQuery).into()
 }
@@ -0,0 +1,184 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use sqlx::MySqlPool;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::digest;
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::i18n::{self, Locale, Msg};
+use crate::middleware::jwt::get_auth_user;
+use crate::models::*;
+
+// Helper: Load the user's settings, falling back to defaults when none were saved yet
+pub async fn load_settings(pool: &MySqlPool, user_id: &str) -> Result<UserSettings, ApiError> {
+    let settings: Option<UserSettings> = sqlx::query_as(
+        r#"
+        SELECT time_zone, locale, week_start, default_reminder_time, reminder_email, digest_email, updated_at
+        FROM user_settings
+        WHERE user_id = ?
+        "#
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    Ok(settings.unwrap_or_else(UserSettings::defaults))
+}
+
+// Helper: Locale for messages that outlive the request (e.g. emails). The user's saved
+// choice wins over the language of the request that triggered them.
+pub async fn user_locale(pool: &MySqlPool, user_id: &str) -> Locale {
+    let saved: Option<String> = sqlx::query_scalar("SELECT locale FROM user_settings WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten();
+
+    saved.and_then(|l| Locale::from_tag(&l)).unwrap_or_else(i18n::current)
+}
+
+// Helper: Parse a client date/time in the user's time zone.
+// Accepts RFC 3339, a local "YYYY-MM-DDTHH:MM[:SS]" or a bare date, which gets the
+// user's default reminder time.
+pub fn parse_user_datetime(value: &str, settings: &UserSettings) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = value.parse::<DateTime<Utc>>() {
+        return Some(dt);
+    }
+
+    let local = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .map(|d| d.and_time(settings.default_reminder_time))
+        })?;
+
+    settings
+        .tz()
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+// GET /auth/settings
+#[utoipa::path(
+    get,
+    path = "/auth/settings",
+    tag = "settings",
+    responses(
+        (status = 200, description = "User settings", body = ApiResponse<UserSettings>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_settings(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    let settings = load_settings(pool.get_ref(), &claims.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::SettingsLoaded, settings)))
+}
+
+// PUT /auth/settings
+#[utoipa::path(
+    put,
+    path = "/auth/settings",
+    tag = "settings",
+    request_body = UpdateSettingsRequest,
+    responses(
+        (status = 200, description = "Settings updated", body = ApiResponse<UserSettings>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_settings(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    body: web::Json<UpdateSettingsRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+    let mut settings = load_settings(pool.get_ref(), &claims.user_id).await?;
+
+    if let Some(time_zone) = &body.time_zone {
+        let tz: Tz = time_zone
+            .trim()
+            .parse()
+            .map_err(|_| ApiError::bad_request(Msg::InvalidTimeZone))?;
+        settings.time_zone = tz.name().to_string();
+    }
+
+    if let Some(locale) = &body.locale {
+        let locale = Locale::from_tag(locale).ok_or_else(|| ApiError::bad_request(Msg::UnsupportedLocale))?;
+        settings.locale = locale.tag().to_string();
+    }
+
+    if let Some(week_start) = &body.week_start {
+        let day: Weekday = week_start
+            .trim()
+            .parse()
+            .map_err(|_| ApiError::bad_request(Msg::InvalidWeekStart))?;
+        settings.week_start = weekday_name(day).to_string();
+    }
+
+    if let Some(time) = &body.default_reminder_time {
+        settings.default_reminder_time = NaiveTime::parse_from_str(time.trim(), "%H:%M")
+            .or_else(|_| NaiveTime::parse_from_str(time.trim(), "%H:%M:%S"))
+            .map_err(|_| ApiError::bad_request(Msg::InvalidReminderTime))?;
+    }
+
+    if let Some(reminder_email) = body.reminder_email {
+        settings.reminder_email = reminder_email;
+    }
+
+    if let Some(digest_email) = body.digest_email {
+        settings.digest_email = digest_email;
+    }
+
+    // A new time or time zone moves the next digest too
+    let digest_next_at = settings
+        .digest_email
+        .then(|| digest::next_run(&settings, Utc::now()))
+        .flatten();
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_settings
+            (user_id, time_zone, locale, week_start, default_reminder_time, reminder_email, digest_email, digest_next_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE
+            time_zone = VALUES(time_zone),
+            locale = VALUES(locale),
+            week_start = VALUES(week_start),
+            default_reminder_time = VALUES(default_reminder_time),
+            reminder_email = VALUES(reminder_email),
+            digest_email = VALUES(digest_email),
+            digest_next_at = VALUES(digest_next_at)
+        "#
+    )
+    .bind(&claims.user_id)
+    .bind(&settings.time_zone)
+    .bind(&settings.locale)
+    .bind(&settings.week_start)
+    .bind(settings.default_reminder_time)
+    .bind(settings.reminder_email)
+    .bind(settings.digest_email)
+    .bind(digest_next_at)
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let settings = load_settings(pool.get_ref(), &claims.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::SettingsUpdated, settings)))
+}
@@ -0,0 +1,143 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use sqlx::MySqlPool;
+
+use crate::config::Config;
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::handlers::settings::load_settings;
+use crate::i18n::Msg;
+use crate::middleware::jwt::get_auth_user;
+use crate::models::*;
+
+const DAYS_WINDOW: i64 = 30;
+const WEEKS_WINDOW: i64 = 12;
+
+// Helper: First day of the week containing `date`
+fn week_start(date: NaiveDate, first_day: Weekday) -> NaiveDate {
+    let offset = (7 + date.weekday().num_days_from_monday() - first_day.num_days_from_monday()) % 7;
+    date - Duration::days(offset as i64)
+}
+
+// Helper: Current and longest run of consecutive days with at least one completion.
+// The current streak stays alive until the end of the day after the last completion.
+fn compute_streaks(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> (i64, i64) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for day in days {
+        run = match previous {
+            Some(prev) if *day - prev == Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(*day);
+    }
+
+    let mut current = 0;
+    let mut cursor = if days.contains(&today) { today } else { today - Duration::days(1) };
+    while days.contains(&cursor) {
+        current += 1;
+        cursor -= Duration::days(1);
+    }
+
+    (current, longest)
+}
+
+// GET /stats
+#[utoipa::path(
+    get,
+    path = "/stats",
+    tag = "stats",
+    responses(
+        (status = 200, description = "Productivity stats", body = ApiResponse<StatsResponse>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_stats(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    let settings = load_settings(pool.get_ref(), &claims.user_id).await?;
+    let tz = settings.tz();
+    let first_day = settings.week_start_day();
+
+    let (total_tasks, completed_tasks, pending_tasks, overdue_tasks): (i64, i64, i64, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            COUNT(t.id),
+            CAST(COALESCE(SUM(t.completed = 1), 0) AS SIGNED),
+            CAST(COALESCE(SUM(t.completed = 0), 0) AS SIGNED),
+            CAST(COALESCE(SUM(t.completed = 0 AND COALESCE(t.due_at, t.reminder) < UTC_TIMESTAMP()), 0) AS SIGNED)
+        FROM tasks t
+        JOIN todo_lists l ON t.list_id = l.id
+        WHERE l.user_id = ? AND t.deleted_at IS NULL AND l.deleted_at IS NULL
+        "#
+    )
+    .bind(&claims.user_id)
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let completions: Vec<DateTime<Utc>> = sqlx::query_scalar(
+        r#"
+        SELECT t.completed_at FROM tasks t
+        JOIN todo_lists l ON t.list_id = l.id
+        WHERE l.user_id = ? AND t.deleted_at IS NULL AND l.deleted_at IS NULL
+            AND t.completed = 1 AND t.completed_at IS NOT NULL
+        "#
+    )
+    .bind(&claims.user_id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let today = Utc::now().with_timezone(&tz).date_naive();
+
+    let mut per_day: BTreeMap<NaiveDate, i64> = (0..DAYS_WINDOW)
+        .map(|i| (today - Duration::days(i), 0))
+        .collect();
+    let this_week = week_start(today, first_day);
+    let mut per_week: BTreeMap<NaiveDate, i64> = (0..WEEKS_WINDOW)
+        .map(|i| (this_week - Duration::weeks(i), 0))
+        .collect();
+    let mut active_days = BTreeSet::new();
+
+    for completed_at in completions {
+        let day = completed_at.with_timezone(&tz).date_naive();
+        active_days.insert(day);
+        if let Some(count) = per_day.get_mut(&day) {
+            *count += 1;
+        }
+        if let Some(count) = per_week.get_mut(&week_start(day, first_day)) {
+            *count += 1;
+        }
+    }
+
+    let (current_streak, longest_streak) = compute_streaks(&active_days, today);
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(
+        Msg::StatsLoaded,
+        StatsResponse {
+            total_tasks,
+            pending_tasks,
+            completed_tasks,
+            overdue_tasks,
+            completions_per_day: per_day
+                .into_iter()
+                .map(|(date, completed)| DailyCompletions { date, completed })
+                .collect(),
+            completions_per_week: per_week
+                .into_iter()
+                .map(|(week_start, completed)| WeeklyCompletions { week_start, completed })
+                .collect(),
+            current_streak,
+            longest_streak,
+        },
+    )))
+}
@@ -1,53 +1,89 @@
 use actix_web::{web, HttpRequest, HttpResponse};
 use chrono::{DateTime, Utc};
 use sqlx::MySqlPool;
+use validator::Validate;
 
 use crate::config::Config;
-use crate::errors::{ApiError, ApiResponse};
+use crate::handlers::reminders::reminders_of_tasks;
+use crate::events::{self, Change, EventHub};
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::i18n::Msg;
+use crate::metrics::METRICS;
 use crate::middleware::jwt::get_auth_user;
 use crate::models::*;
+use crate::recurrence;
+use crate::webhooks;
 
 // Helper: Generate UUID
 fn generate_uuid() -> String {
     uuid::Uuid::new_v4().to_string()
 }
 
-// POST /sync/push
-pub async fn sync_push(
-    req: HttpRequest,
-    pool: web::Data<MySqlPool>,
-    config: web::Data<Config>,
-    body: web::Json<SyncPushRequest>,
-) -> Result<HttpResponse, ApiError> {
-    let claims = get_auth_user(&req, &config)?;
-    let user_id = &claims.user_id;
+// Helper: Log a pushed change and queue its webhooks inside the push transaction
+async fn record(
+    tx: &mut sqlx::Transaction<'_, sqlx::MySql>,
+    user_id: &str,
+    device_id: Option<&str>,
+    entity_type: &'static str,
+    entity_id: &str,
+    action: &'static str,
+) -> Result<Change, ApiError> {
+    let change = events::record(&mut **tx, user_id, device_id, entity_type, entity_id, action)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let mut synced_lists = 0;
-    let mut synced_tasks = 0;
+    if let Some(event) = WebhookEvent::from_change(entity_type, action) {
+        queue_webhooks(tx, user_id, event, entity_id).await?;
+    }
 
-    // Start transaction
-    let mut tx = pool.begin().await
-        .map_err(|e| ApiError::internal(format!("Transaction error: {}", e)))?;
+    Ok(change)
+}
+
+// Helper: Queue webhook deliveries inside the push transaction
+async fn queue_webhooks(
+    tx: &mut sqlx::Transaction<'_, sqlx::MySql>,
+    user_id: &str,
+    event: WebhookEvent,
+    entity_id: &str,
+) -> Result<(), ApiError> {
+    webhooks::enqueue(tx, user_id, event, entity_id)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))
+}
+
+// Helper: Apply pushed deletions and upsert pushed lists and tasks inside `tx`. Also the
+// write path of POST /import. Returns the changes to publish once `tx` commits.
+pub async fn apply_push(
+    tx: &mut sqlx::Transaction<'_, sqlx::MySql>,
+    user_id: &str,
+    device_id: Option<&str>,
+    body: &SyncPushRequest,
+) -> Result<Vec<Change>, ApiError> {
+    let mut changes: Vec<Change> = Vec::new();
 
     // Process deleted lists
     for list_id in &body.deleted_lists {
-        sqlx::query("UPDATE todo_lists SET deleted_at = NOW() WHERE id = ? AND user_id = ?")
+        let deleted = sqlx::query("UPDATE todo_lists SET deleted_at = NOW() WHERE id = ? AND user_id = ?")
             .bind(list_id)
             .bind(user_id)
-            .execute(&mut *tx)
+            .execute(&mut **tx)
             .await
             .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
         sqlx::query("UPDATE tasks SET deleted_at = NOW() WHERE list_id = ?")
             .bind(list_id)
-            .execute(&mut *tx)
+            .execute(&mut **tx)
             .await
             .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+        if deleted.rows_affected() > 0 {
+            changes.push(record(tx, user_id, device_id, "list", list_id, "delete").await?);
+        }
     }
 
     // Process deleted tasks
     for task_id in &body.deleted_tasks {
-        sqlx::query(
+        let deleted = sqlx::query(
             r#"
             UPDATE tasks t
             JOIN todo_lists l ON t.list_id = l.id
@@ -57,9 +93,13 @@ pub async fn sync_push(
         )
         .bind(task_id)
         .bind(user_id)
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await
         .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+        if deleted.rows_affected() > 0 {
+            changes.push(record(tx, user_id, device_id, "task", task_id, "delete").await?);
+        }
     }
 
     // Process lists
@@ -72,10 +112,11 @@ pub async fn sync_push(
         // Check if exists
         let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM todo_lists WHERE id = ?")
             .bind(&id)
-            .fetch_optional(&mut *tx)
+            .fetch_optional(&mut **tx)
             .await
             .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
+        let action = if existing.is_some() { "update" } else { "create" };
         if existing.is_some() {
             sqlx::query(
                 "UPDATE todo_lists SET name = ?, color = ?, updated_at = NOW(), deleted_at = NULL WHERE id = ? AND user_id = ?"
@@ -84,7 +125,7 @@ pub async fn sync_push(
             .bind(&color)
             .bind(&id)
             .bind(user_id)
-            .execute(&mut *tx)
+            .execute(&mut **tx)
             .await
             .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
         } else {
@@ -96,12 +137,12 @@ pub async fn sync_push(
             .bind(name)
             .bind(&color)
             .bind(&created_at)
-            .execute(&mut *tx)
+            .execute(&mut **tx)
             .await
             .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
         }
 
-        synced_lists += 1;
+        changes.push(record(tx, user_id, device_id, "list", &id, action).await?);
     }
 
     // Process tasks
@@ -112,54 +153,117 @@ pub async fn sync_push(
         let description = task.description.clone().unwrap_or_default();
         let completed = task.completed.unwrap_or(false);
         let reminder: Option<DateTime<Utc>> = task.reminder.as_ref().and_then(|r| r.parse().ok());
+        let due_at: Option<DateTime<Utc>> = task.due_at.as_ref().and_then(|d| d.parse().ok());
+        // Unknown rules from older clients are dropped instead of failing the whole push
+        let recurrence = recurrence::normalize(task.recurrence.as_deref()).unwrap_or(None);
         let created_at = task.created_at.clone().unwrap_or_else(|| Utc::now().to_rfc3339());
 
         // Check if exists
-        let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM tasks WHERE id = ?")
+        let existing: Option<(String, bool)> = sqlx::query_as("SELECT id, completed FROM tasks WHERE id = ?")
             .bind(&id)
-            .fetch_optional(&mut *tx)
+            .fetch_optional(&mut **tx)
             .await
             .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
+        let action = if existing.is_some() { "update" } else { "create" };
         if existing.is_some() {
             sqlx::query(
-                "UPDATE tasks SET title = ?, description = ?, completed = ?, reminder = ?, updated_at = NOW(), deleted_at = NULL WHERE id = ?"
+                r#"
+                UPDATE tasks SET title = ?, description = ?, completed = ?, reminder = ?, due_at = ?, recurrence = ?,
+                    updated_at = NOW(), deleted_at = NULL,
+                    completed_at = CASE WHEN ? THEN COALESCE(completed_at, NOW()) ELSE NULL END
+                WHERE id = ?
+                "#
             )
             .bind(title)
             .bind(&description)
             .bind(completed)
-            .bind(&reminder)
+            .bind(reminder)
+            .bind(due_at)
+            .bind(&recurrence)
+            .bind(completed)
             .bind(&id)
-            .execute(&mut *tx)
+            .execute(&mut **tx)
             .await
             .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
         } else {
             sqlx::query(
-                "INSERT INTO tasks (id, list_id, title, description, completed, reminder, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+                r#"
+                INSERT INTO tasks (id, list_id, title, description, completed, reminder, due_at, recurrence, created_at, completed_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, IF(?, NOW(), NULL))
+                "#
             )
             .bind(&id)
             .bind(list_id)
             .bind(title)
             .bind(&description)
             .bind(completed)
-            .bind(&reminder)
+            .bind(reminder)
+            .bind(due_at)
+            .bind(&recurrence)
             .bind(&created_at)
-            .execute(&mut *tx)
+            .bind(completed)
+            .execute(&mut **tx)
             .await
             .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
         }
 
-        synced_tasks += 1;
+        changes.push(record(tx, user_id, device_id, "task", &id, action).await?);
+        if completed && !existing.is_some_and(|(_, was_completed)| was_completed) {
+            queue_webhooks(tx, user_id, WebhookEvent::TaskCompleted, &id).await?;
+        }
     }
 
+    Ok(changes)
+}
+
+// POST /sync/push
+#[utoipa::path(
+    post,
+    path = "/sync/push",
+    tag = "sync",
+    request_body = SyncPushRequest,
+    responses(
+        (status = 200, description = "Changes applied", body = ApiResponse<SyncPushResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn sync_push(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    events: web::Data<EventHub>,
+    body: web::Json<SyncPushRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+    let user_id = &claims.user_id;
+    let device_id = events::device_id(&req);
+
+    METRICS.observe_sync("push", "lists", body.lists.len() + body.deleted_lists.len());
+    METRICS.observe_sync("push", "tasks", body.tasks.len() + body.deleted_tasks.len());
+
+    // Start transaction
+    let mut tx = pool.begin().await
+        .map_err(|e| ApiError::internal(format!("Transaction error: {}", e)))?;
+
+    // Published once the transaction commits
+    let changes = apply_push(&mut tx, user_id, device_id.as_deref(), &body).await?;
+
     tx.commit().await
         .map_err(|e| ApiError::internal(format!("Commit error: {}", e)))?;
 
+    for change in changes {
+        events.publish(change);
+    }
+
     Ok(HttpResponse::Ok().json(ApiResponse::success(
-        "Sincronização concluída",
+        Msg::SyncPushed,
         SyncPushResponse {
-            synced_lists,
-            synced_tasks,
+            synced_lists: body.lists.len(),
+            synced_tasks: body.tasks.len(),
             deleted_lists: body.deleted_lists.len(),
             deleted_tasks: body.deleted_tasks.len(),
             server_time: Utc::now().to_rfc3339(),
@@ -168,6 +272,18 @@ pub async fn sync_push(
 }
 
 // POST /sync/pull
+#[utoipa::path(
+    post,
+    path = "/sync/pull",
+    tag = "sync",
+    request_body = SyncPullRequest,
+    responses(
+        (status = 200, description = "Changes since last sync", body = ApiResponse<SyncPullResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn sync_pull(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
@@ -175,12 +291,13 @@ pub async fn sync_pull(
     body: web::Json<SyncPullRequest>,
 ) -> Result<HttpResponse, ApiError> {
     let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
     let user_id = &claims.user_id;
 
     let (lists, tasks, deleted_lists, deleted_tasks) = if let Some(last_sync) = &body.last_sync {
         // Only changes since last sync
         let last_sync_dt: DateTime<Utc> = last_sync.parse()
-            .map_err(|_| ApiError::bad_request("Invalid last_sync format"))?;
+            .map_err(|_| ApiError::bad_request(Msg::InvalidLastSync))?;
 
         let lists: Vec<TodoList> = sqlx::query_as(
             r#"
@@ -197,7 +314,7 @@ pub async fn sync_pull(
 
         let tasks: Vec<Task> = sqlx::query_as(
             r#"
-            SELECT t.id, t.list_id, t.title, t.description, t.completed, t.reminder, t.created_at, t.updated_at, t.deleted_at
+            SELECT t.id, t.list_id, t.title, t.description, t.completed, t.reminder, t.due_at, t.recurrence, t.created_at, t.updated_at, t.deleted_at
             FROM tasks t
             JOIN todo_lists l ON t.list_id = l.id
             WHERE l.user_id = ? AND t.deleted_at IS NULL AND l.deleted_at IS NULL AND t.updated_at > ?
@@ -277,11 +394,18 @@ pub async fn sync_pull(
         (lists, tasks, deleted_lists, deleted_tasks)
     };
 
+    METRICS.observe_sync("pull", "lists", lists.len() + deleted_lists.len());
+    METRICS.observe_sync("pull", "tasks", tasks.len() + deleted_tasks.len());
+
+    let task_ids: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+    let reminders = reminders_of_tasks(pool.get_ref(), &task_ids).await?;
+
     Ok(HttpResponse::Ok().json(ApiResponse::success(
-        "Dados sincronizados",
+        Msg::SyncPulled,
         SyncPullResponse {
             lists: lists.into_iter().map(|l| l.into()).collect(),
             tasks: tasks.into_iter().map(|t| t.into()).collect(),
+            reminders: reminders.into_iter().map(|r| r.into()).collect(),
             deleted_lists,
             deleted_tasks,
             server_time: Utc::now().to_rfc3339(),
@@ -290,6 +414,16 @@ pub async fn sync_pull(
 }
 
 // POST /sync/full
+#[utoipa::path(
+    post,
+    path = "/sync/full",
+    tag = "sync",
+    responses(
+        (status = 200, description = "Everything the user has", body = ApiResponse<SyncFullResponse>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn sync_full(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
@@ -319,11 +453,18 @@ pub async fn sync_full(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
+    METRICS.observe_sync("full", "lists", lists.len());
+    METRICS.observe_sync("full", "tasks", tasks.len());
+
+    let task_ids: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+    let reminders = reminders_of_tasks(pool.get_ref(), &task_ids).await?;
+
     Ok(HttpResponse::Ok().json(ApiResponse::success(
-        "Sincronização completa",
+        Msg::SyncFull,
         SyncFullResponse {
             lists: lists.into_iter().map(|l| l.into()).collect(),
             tasks: tasks.into_iter().map(|t| t.into()).collect(),
+            reminders: reminders.into_iter().map(|r| r.into()).collect(),
             server_time: Utc::now().to_rfc3339(),
         },
     )))
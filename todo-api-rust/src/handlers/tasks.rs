@@ -1,58 +1,109 @@
 use actix_web::{web, HttpRequest, HttpResponse};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
 use sqlx::MySqlPool;
+use validator::Validate;
 
 use crate::config::Config;
-use crate::errors::{ApiError, ApiResponse};
+use crate::events::{self, EventHub};
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::handlers::settings::{load_settings, parse_user_datetime};
+use crate::i18n::{Locale, Msg};
 use crate::middleware::jwt::get_auth_user;
 use crate::models::*;
+use crate::quick_add;
+use crate::recurrence;
+use crate::webhooks;
 
 // Helper: Generate UUID
 fn generate_uuid() -> String {
     uuid::Uuid::new_v4().to_string()
 }
 
-// Helper: Log sync
-async fn log_sync(pool: &MySqlPool, user_id: &str, entity_type: &str, entity_id: &str, action: &str) {
-    let _ = sqlx::query(
-        "INSERT INTO sync_log (user_id, entity_type, entity_id, action) VALUES (?, ?, ?, ?)"
-    )
-    .bind(user_id)
-    .bind(entity_type)
-    .bind(entity_id)
-    .bind(action)
-    .execute(pool)
-    .await;
-}
-
 // GET /tasks
+#[utoipa::path(
+    get,
+    path = "/tasks",
+    tag = "tasks",
+    params(TasksQuery),
+    responses(
+        (status = 200, description = "Tasks of the user", body = ApiResponse<Vec<TaskResponse>>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_all_tasks(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
     config: web::Data<Config>,
+    query: web::Query<TasksQuery>,
 ) -> Result<HttpResponse, ApiError> {
     let claims = get_auth_user(&req, &config)?;
+    query.validate()?;
+
+    // Optional "due" filter, evaluated against the user's local day
+    let (due_from, due_to, pending_only) = match query.due.as_deref() {
+        None => (None, None, false),
+        Some(filter) => {
+            let settings = load_settings(pool.get_ref(), &claims.user_id).await?;
+            let tz = settings.tz();
+            let now = Utc::now();
+            let today = now.with_timezone(&tz).date_naive();
+            let start_of = |date: NaiveDate| {
+                tz.from_local_datetime(&date.and_time(NaiveTime::MIN))
+                    .earliest()
+                    .map(|dt| dt.with_timezone(&Utc))
+            };
+
+            match filter {
+                "today" => (start_of(today), start_of(today + Duration::days(1)), false),
+                "week" => (start_of(today), start_of(today + Duration::days(7)), false),
+                "overdue" => (None, Some(now), true),
+                _ => return Err(ApiError::bad_request(Msg::InvalidDueFilter)),
+            }
+        }
+    };
 
     let tasks: Vec<Task> = sqlx::query_as(
         r#"
-        SELECT t.id, t.list_id, t.title, t.description, t.completed, t.reminder, t.created_at, t.updated_at, t.deleted_at
+        SELECT t.id, t.list_id, t.title, t.description, t.completed, t.reminder, t.due_at, t.recurrence, t.created_at, t.updated_at, t.deleted_at
         FROM tasks t
         JOIN todo_lists l ON t.list_id = l.id
         WHERE l.user_id = ? AND t.deleted_at IS NULL AND l.deleted_at IS NULL
+            AND (? IS NULL OR COALESCE(t.due_at, t.reminder) >= ?)
+            AND (? IS NULL OR COALESCE(t.due_at, t.reminder) < ?)
+            AND (? = 0 OR t.completed = 0)
         ORDER BY t.completed ASC, t.created_at DESC
         "#
     )
     .bind(&claims.user_id)
+    .bind(due_from)
+    .bind(due_from)
+    .bind(due_to)
+    .bind(due_to)
+    .bind(pending_only)
     .fetch_all(pool.get_ref())
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
     let tasks: Vec<TaskResponse> = tasks.into_iter().map(|t| t.into()).collect();
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success("Tarefas carregadas", tasks)))
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::TasksLoaded, tasks)))
 }
 
 // GET /lists/{id}/tasks
+#[utoipa::path(
+    get,
+    path = "/lists/{id}/tasks",
+    tag = "lists",
+    params(("id" = String, Path, description = "List id")),
+    responses(
+        (status = 200, description = "Tasks of the list", body = ApiResponse<Vec<TaskResponse>>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_tasks_by_list(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
@@ -73,12 +124,12 @@ pub async fn get_tasks_by_list(
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
     if list_exists.is_none() {
-        return Err(ApiError::not_found("Lista não encontrada"));
+        return Err(ApiError::not_found(Msg::ListNotFound));
     }
 
     let tasks: Vec<Task> = sqlx::query_as(
         r#"
-        SELECT id, list_id, title, description, completed, reminder, created_at, updated_at, deleted_at
+        SELECT id, list_id, title, description, completed, reminder, due_at, recurrence, created_at, updated_at, deleted_at
         FROM tasks 
         WHERE list_id = ? AND deleted_at IS NULL
         ORDER BY completed ASC, created_at DESC
@@ -91,10 +142,22 @@ pub async fn get_tasks_by_list(
 
     let tasks: Vec<TaskResponse> = tasks.into_iter().map(|t| t.into()).collect();
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success("Tarefas carregadas", tasks)))
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::TasksLoaded, tasks)))
 }
 
 // GET /tasks/{id}
+#[utoipa::path(
+    get,
+    path = "/tasks/{id}",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task id")),
+    responses(
+        (status = 200, description = "Task", body = ApiResponse<TaskResponse>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_task(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
@@ -106,7 +169,7 @@ pub async fn get_task(
 
     let task: Option<Task> = sqlx::query_as(
         r#"
-        SELECT t.id, t.list_id, t.title, t.description, t.completed, t.reminder, t.created_at, t.updated_at, t.deleted_at
+        SELECT t.id, t.list_id, t.title, t.description, t.completed, t.reminder, t.due_at, t.recurrence, t.created_at, t.updated_at, t.deleted_at
         FROM tasks t
         JOIN todo_lists l ON t.list_id = l.id
         WHERE t.id = ? AND l.user_id = ? AND t.deleted_at IS NULL
@@ -118,52 +181,173 @@ pub async fn get_task(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let task = task.ok_or_else(|| ApiError::not_found("Tarefa não encontrada"))?;
+    let task = task.ok_or_else(|| ApiError::not_found(Msg::TaskNotFound))?;
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success("Tarefa encontrada", TaskResponse::from(task))))
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::TaskFound, TaskResponse::from(task))))
 }
 
 // POST /tasks
+#[utoipa::path(
+    post,
+    path = "/tasks",
+    tag = "tasks",
+    request_body = CreateTaskRequest,
+    responses(
+        (status = 201, description = "Task created", body = ApiResponse<TaskResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn create_task(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
     config: web::Data<Config>,
+    events: web::Data<EventHub>,
     body: web::Json<CreateTaskRequest>,
 ) -> Result<HttpResponse, ApiError> {
     let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+
+    let task = insert_task(pool.get_ref(), &events, &req, &claims.user_id, &body).await?;
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(Msg::TaskCreated, task)))
+}
+
+// POST /tasks/quick
+#[utoipa::path(
+    post,
+    path = "/tasks/quick",
+    tag = "tasks",
+    request_body = QuickAddRequest,
+    responses(
+        (status = 201, description = "Task created from the text, with what was understood", body = ApiResponse<QuickAddResponse>),
+        (status = 400, description = "Invalid request, or nothing left for the title", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "List not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn quick_add_task(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    events: web::Data<EventHub>,
+    body: web::Json<QuickAddRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
 
+    let lists: Vec<(String, String)> = sqlx::query_as(
+        "SELECT id, name FROM todo_lists WHERE user_id = ? AND deleted_at IS NULL ORDER BY created_at"
+    )
+    .bind(&claims.user_id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let settings = load_settings(pool.get_ref(), &claims.user_id).await?;
+    let tz = settings.tz();
+    let now = Utc::now().with_timezone(&tz).naive_local();
+    let locale = Locale::from_tag(&settings.locale).unwrap_or_default();
+
+    let parsed = quick_add::parse(&body.text, now.date(), locale, &lists);
+    if parsed.title.is_empty() {
+        return Err(ApiError::bad_request(Msg::QuickAddTitleRequired));
+    }
+
+    let list_id = parsed
+        .list_id
+        .clone()
+        .or_else(|| body.list_id.clone())
+        .or_else(|| lists.first().map(|(id, _)| id.clone()))
+        .ok_or_else(|| ApiError::not_found(Msg::ListNotFound))?;
+
+    let due_at = parsed
+        .due(now, settings.default_reminder_time)
+        .and_then(|local| tz.from_local_datetime(&local).earliest())
+        .map(|at| at.with_timezone(&Utc));
+    // Only a time of day is worth ringing for; a bare date is just a deadline
+    let reminder = parsed.time.and(due_at);
+
+    let request = CreateTaskRequest {
+        id: None,
+        list_id,
+        title: parsed.title.clone(),
+        description: None,
+        completed: None,
+        reminder: reminder.map(|at| at.to_rfc3339()),
+        due_at: due_at.map(|at| at.to_rfc3339()),
+        recurrence: parsed.recurrence.as_ref().map(|rule| rule.to_string()),
+        created_at: None,
+    };
+    request.validate()?;
+
+    let task = insert_task(pool.get_ref(), &events, &req, &claims.user_id, &request).await?;
+
+    let parsed = QuickAddParsed {
+        title: task.title.clone(),
+        list_id: task.list_id.clone(),
+        priority: parsed.priority.map(|p| p.as_str().to_string()),
+        due_at: task.due_at,
+        reminder: task.reminder,
+        recurrence: task.recurrence.clone(),
+        phrases: parsed
+            .phrases
+            .into_iter()
+            .map(|phrase| QuickAddPhrase {
+                text: phrase.text,
+                kind: phrase.kind.as_str().to_string(),
+            })
+            .collect(),
+    };
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(Msg::TaskCreated, QuickAddResponse { task, parsed })))
+}
+
+/// Creates (or, for sync clients resending a task, overwrites) a task from a validated
+/// request. Shared by `POST /tasks` and `POST /tasks/quick`.
+pub async fn insert_task(
+    pool: &MySqlPool,
+    events: &EventHub,
+    req: &HttpRequest,
+    user_id: &str,
+    body: &CreateTaskRequest,
+) -> Result<TaskResponse, ApiError> {
     let id = body.id.clone().unwrap_or_else(generate_uuid);
     let list_id = &body.list_id;
     let title = body.title.trim();
     let description = body.description.clone().unwrap_or_default();
     let completed = body.completed.unwrap_or(false);
-    let reminder: Option<DateTime<Utc>> = body.reminder.as_ref().and_then(|r| r.parse().ok());
+    let settings = load_settings(pool, user_id).await?;
+    let reminder: Option<DateTime<Utc>> = body.reminder.as_ref().and_then(|r| parse_user_datetime(r, &settings));
+    let due_at: Option<DateTime<Utc>> = body.due_at.as_ref().and_then(|d| parse_user_datetime(d, &settings));
     let created_at = body.created_at.clone().unwrap_or_else(|| Utc::now().to_rfc3339());
 
-    if list_id.is_empty() || title.is_empty() {
-        return Err(ApiError::bad_request("Lista e título são obrigatórios"));
-    }
+    let recurrence = recurrence::normalize(body.recurrence.as_deref())
+        .map_err(|_| ApiError::bad_request(Msg::InvalidRecurrence))?;
 
     // Check list ownership
     let list_exists: Option<(String,)> = sqlx::query_as(
         "SELECT id FROM todo_lists WHERE id = ? AND user_id = ? AND deleted_at IS NULL"
     )
     .bind(list_id)
-    .bind(&claims.user_id)
-    .fetch_optional(pool.get_ref())
+    .bind(user_id)
+    .fetch_optional(pool)
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
     if list_exists.is_none() {
-        return Err(ApiError::not_found("Lista não encontrada"));
+        return Err(ApiError::not_found(Msg::ListNotFound));
     }
 
     // Check if exists (for sync)
-    let existing: Option<(String,)> = sqlx::query_as(
-        "SELECT id FROM tasks WHERE id = ?"
+    let existing: Option<(String, bool)> = sqlx::query_as(
+        "SELECT id, completed FROM tasks WHERE id = ?"
     )
     .bind(&id)
-    .fetch_optional(pool.get_ref())
+    .fetch_optional(pool)
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
@@ -172,24 +356,29 @@ pub async fn create_task(
         sqlx::query(
             r#"
             UPDATE tasks 
-            SET title = ?, description = ?, completed = ?, reminder = ?, updated_at = NOW(), deleted_at = NULL
+            SET title = ?, description = ?, completed = ?, reminder = ?, due_at = ?, recurrence = ?,
+                updated_at = NOW(), deleted_at = NULL,
+                completed_at = CASE WHEN ? THEN COALESCE(completed_at, NOW()) ELSE NULL END
             WHERE id = ?
             "#
         )
         .bind(title)
         .bind(&description)
         .bind(completed)
-        .bind(&reminder)
+        .bind(reminder)
+        .bind(due_at)
+        .bind(&recurrence)
+        .bind(completed)
         .bind(&id)
-        .execute(pool.get_ref())
+        .execute(pool)
         .await
         .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
     } else {
         // Create new
         sqlx::query(
             r#"
-            INSERT INTO tasks (id, list_id, title, description, completed, reminder, created_at) 
-            VALUES (?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO tasks (id, list_id, title, description, completed, reminder, due_at, recurrence, created_at, completed_at) 
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, IF(?, NOW(), NULL))
             "#
         )
         .bind(&id)
@@ -197,39 +386,61 @@ pub async fn create_task(
         .bind(title)
         .bind(&description)
         .bind(completed)
-        .bind(&reminder)
+        .bind(reminder)
+        .bind(due_at)
+        .bind(&recurrence)
         .bind(&created_at)
-        .execute(pool.get_ref())
+        .bind(completed)
+        .execute(pool)
         .await
         .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
     }
 
-    log_sync(pool.get_ref(), &claims.user_id, "task", &id, "create").await;
-
-    Ok(HttpResponse::Created().json(ApiResponse::success(
-        "Tarefa criada com sucesso",
-        TaskResponse {
-            id,
-            list_id: list_id.clone(),
-            title: title.to_string(),
-            description: if description.is_empty() { None } else { Some(description) },
-            completed,
-            reminder,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        },
-    )))
+    events::log_sync(pool, events, events::device_id(req).as_deref(), user_id, "task", &id, "create").await;
+
+    if completed && !existing.is_some_and(|(_, was_completed)| was_completed) {
+        webhooks::notify(pool, user_id, WebhookEvent::TaskCompleted, &id).await;
+    }
+
+    Ok(TaskResponse {
+        id,
+        list_id: list_id.clone(),
+        title: title.to_string(),
+        description: if description.is_empty() { None } else { Some(description) },
+        completed,
+        reminder,
+        due_at,
+        recurrence,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    })
 }
 
 // PUT /tasks/{id}
+#[utoipa::path(
+    put,
+    path = "/tasks/{id}",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task id")),
+    request_body = UpdateTaskRequest,
+    responses(
+        (status = 200, description = "Task updated", body = ApiResponse<TaskResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn update_task(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
     config: web::Data<Config>,
+    events: web::Data<EventHub>,
     path: web::Path<String>,
     body: web::Json<UpdateTaskRequest>,
 ) -> Result<HttpResponse, ApiError> {
     let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
     let task_id = path.into_inner();
 
     // Check ownership
@@ -246,7 +457,10 @@ pub async fn update_task(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    let mut task = existing.ok_or_else(|| ApiError::not_found("Tarefa não encontrada"))?;
+    let mut task = existing.ok_or_else(|| ApiError::not_found(Msg::TaskNotFound))?;
+    let settings = load_settings(pool.get_ref(), &claims.user_id).await?;
+    let was_completed = task.completed;
+    let previous_due_at = task.due_at;
 
     // Update fields
     if let Some(title) = &body.title {
@@ -262,26 +476,57 @@ pub async fn update_task(
     }
 
     if let Some(reminder) = &body.reminder {
-        task.reminder = if reminder.is_empty() { None } else { reminder.parse().ok() };
+        task.reminder = if reminder.is_empty() { None } else { parse_user_datetime(reminder, &settings) };
+    }
+
+    if let Some(due_at) = &body.due_at {
+        task.due_at = if due_at.is_empty() { None } else { parse_user_datetime(due_at, &settings) };
+    }
+
+    if let Some(rule) = &body.recurrence {
+        task.recurrence = recurrence::normalize(Some(rule))
+            .map_err(|_| ApiError::bad_request(Msg::InvalidRecurrence))?;
     }
 
     sqlx::query(
         r#"
         UPDATE tasks 
-        SET title = ?, description = ?, completed = ?, reminder = ?, updated_at = NOW()
+        SET title = ?, description = ?, completed = ?, reminder = ?, due_at = ?, recurrence = ?, updated_at = NOW(),
+            completed_at = CASE WHEN ? THEN COALESCE(completed_at, NOW()) ELSE NULL END
         WHERE id = ?
         "#
     )
     .bind(&task.title)
     .bind(&task.description)
     .bind(task.completed)
-    .bind(&task.reminder)
+    .bind(task.reminder)
+    .bind(task.due_at)
+    .bind(&task.recurrence)
+    .bind(task.completed)
     .bind(&task_id)
     .execute(pool.get_ref())
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    log_sync(pool.get_ref(), &claims.user_id, "task", &task_id, "update").await;
+    // Reminders relative to a new due date ring again, even if the old ones were dismissed
+    if task.due_at != previous_due_at {
+        sqlx::query(
+            r#"
+            UPDATE task_reminders SET snoozed_until = NULL, dismissed_at = NULL, updated_at = NOW()
+            WHERE task_id = ? AND minutes_before_due IS NOT NULL
+            "#
+        )
+        .bind(&task_id)
+        .execute(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+    }
+
+    events::log_sync(pool.get_ref(), &events, events::device_id(&req).as_deref(), &claims.user_id, "task", &task_id, "update").await;
+
+    if task.completed && !was_completed {
+        webhooks::notify(pool.get_ref(), &claims.user_id, WebhookEvent::TaskCompleted, &task_id).await;
+    }
 
     // Return updated task
     let task: Task = sqlx::query_as(
@@ -292,14 +537,27 @@ pub async fn update_task(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success("Tarefa atualizada", TaskResponse::from(task))))
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::TaskUpdated, TaskResponse::from(task))))
 }
 
 // DELETE /tasks/{id}
+#[utoipa::path(
+    delete,
+    path = "/tasks/{id}",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task id")),
+    responses(
+        (status = 200, description = "Task deleted", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn delete_task(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
     config: web::Data<Config>,
+    events: web::Data<EventHub>,
     path: web::Path<String>,
 ) -> Result<HttpResponse, ApiError> {
     let claims = get_auth_user(&req, &config)?;
@@ -320,7 +578,7 @@ pub async fn delete_task(
     .await
     .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
-    log_sync(pool.get_ref(), &claims.user_id, "task", &task_id, "delete").await;
+    events::log_sync(pool.get_ref(), &events, events::device_id(&req).as_deref(), &claims.user_id, "task", &task_id, "delete").await;
 
-    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data("Tarefa deletada com sucesso")))
+    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data(Msg::TaskDeleted)))
 }
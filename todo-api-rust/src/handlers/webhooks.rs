@@ -0,0 +1,288 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use rand::Rng;
+use sqlx::MySqlPool;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::errors::{ApiError, ApiResponse, MessageResponse};
+use crate::i18n::Msg;
+use crate::middleware::jwt::get_auth_user;
+use crate::models::*;
+
+const DEFAULT_DELIVERIES_LIMIT: u32 = 50;
+
+// Helper: Generate UUID
+fn generate_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+// Helper: `whsec_` followed by 24 random bytes as hex
+fn generate_secret() -> String {
+    let bytes: [u8; 24] = rand::thread_rng().gen();
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("whsec_{}", hex)
+}
+
+// Helper: Requested events in the canonical order, without repeats, as stored
+fn join_events(values: &[String]) -> String {
+    let requested: Vec<WebhookEvent> = values.iter().filter_map(|v| v.parse().ok()).collect();
+    WebhookEvent::ALL
+        .into_iter()
+        .filter(|event| requested.contains(event))
+        .map(WebhookEvent::as_str)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Helper: Load one of the user's webhooks
+async fn load_webhook(pool: &MySqlPool, id: &str, user_id: &str) -> Result<Webhook, ApiError> {
+    let webhook: Option<Webhook> = sqlx::query_as(
+        "SELECT id, user_id, url, secret, events, is_active, created_at, updated_at FROM webhooks WHERE id = ? AND user_id = ?"
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    webhook.ok_or_else(|| ApiError::not_found(Msg::WebhookNotFound))
+}
+
+// GET /webhooks
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "Webhooks of the user", body = ApiResponse<Vec<WebhookResponse>>),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_webhooks(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+
+    let webhooks: Vec<Webhook> = sqlx::query_as(
+        r#"
+        SELECT id, user_id, url, secret, events, is_active, created_at, updated_at
+        FROM webhooks
+        WHERE user_id = ?
+        ORDER BY created_at
+        "#
+    )
+    .bind(&claims.user_id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let webhooks: Vec<WebhookResponse> = webhooks.into_iter().map(Into::into).collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::WebhooksLoaded, webhooks)))
+}
+
+// POST /webhooks
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    tag = "webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook created; the secret is returned only here", body = ApiResponse<CreateWebhookResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_webhook(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    body: web::Json<CreateWebhookRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+
+    let now = Utc::now();
+    let webhook = Webhook {
+        id: generate_uuid(),
+        user_id: claims.user_id,
+        url: body.url.trim().to_string(),
+        secret: body.secret.clone().unwrap_or_else(generate_secret),
+        events: join_events(&body.events),
+        is_active: true,
+        created_at: now,
+        updated_at: now,
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO webhooks (id, user_id, url, secret, events, is_active, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, 1, ?, ?)
+        "#
+    )
+    .bind(&webhook.id)
+    .bind(&webhook.user_id)
+    .bind(&webhook.url)
+    .bind(&webhook.secret)
+    .bind(&webhook.events)
+    .bind(webhook.created_at)
+    .bind(webhook.updated_at)
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(
+        Msg::WebhookCreated,
+        CreateWebhookResponse {
+            secret: webhook.secret.clone(),
+            webhook: webhook.into(),
+        },
+    )))
+}
+
+// PUT /webhooks/{id}
+#[utoipa::path(
+    put,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook id")),
+    request_body = UpdateWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook updated", body = ApiResponse<WebhookResponse>),
+        (status = 400, description = "Invalid request", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_webhook(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    path: web::Path<String>,
+    body: web::Json<UpdateWebhookRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    body.validate()?;
+
+    let mut webhook = load_webhook(pool.get_ref(), &path, &claims.user_id).await?;
+
+    if let Some(url) = &body.url {
+        webhook.url = url.trim().to_string();
+    }
+
+    if let Some(events) = &body.events {
+        webhook.events = join_events(events);
+    }
+
+    if let Some(secret) = &body.secret {
+        webhook.secret = secret.clone();
+    }
+
+    if let Some(is_active) = body.is_active {
+        webhook.is_active = is_active;
+    }
+
+    webhook.updated_at = Utc::now();
+
+    sqlx::query(
+        "UPDATE webhooks SET url = ?, secret = ?, events = ?, is_active = ?, updated_at = ? WHERE id = ?"
+    )
+    .bind(&webhook.url)
+    .bind(&webhook.secret)
+    .bind(&webhook.events)
+    .bind(webhook.is_active)
+    .bind(webhook.updated_at)
+    .bind(&webhook.id)
+    .execute(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::WebhookUpdated, WebhookResponse::from(webhook))))
+}
+
+// DELETE /webhooks/{id}
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 200, description = "Webhook and its delivery log deleted", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_webhook(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+
+    let result = sqlx::query("DELETE FROM webhooks WHERE id = ? AND user_id = ?")
+        .bind(path.as_str())
+        .bind(&claims.user_id)
+        .execute(pool.get_ref())
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found(Msg::WebhookNotFound));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse::<()>::success_no_data(Msg::WebhookDeleted)))
+}
+
+// GET /webhooks/{id}/deliveries
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook id"), DeliveriesQuery),
+    responses(
+        (status = 200, description = "Delivery log, newest first", body = ApiResponse<Vec<WebhookDeliveryResponse>>),
+        (status = 400, description = "Invalid query", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = MessageResponse),
+        (status = 404, description = "Not found", body = MessageResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_deliveries(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    config: web::Data<Config>,
+    path: web::Path<String>,
+    query: web::Query<DeliveriesQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = get_auth_user(&req, &config)?;
+    query.validate()?;
+
+    let webhook = load_webhook(pool.get_ref(), &path, &claims.user_id).await?;
+
+    let deliveries: Vec<WebhookDelivery> = sqlx::query_as(
+        r#"
+        SELECT id, event, status, attempts, response_status, last_error, next_attempt_at, delivered_at, created_at, payload
+        FROM webhook_deliveries
+        WHERE webhook_id = ?
+        ORDER BY id DESC
+        LIMIT ?
+        "#
+    )
+    .bind(&webhook.id)
+    .bind(query.limit.unwrap_or(DEFAULT_DELIVERIES_LIMIT))
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let deliveries: Vec<WebhookDeliveryResponse> = deliveries.into_iter().map(Into::into).collect();
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(Msg::WebhookDeliveriesLoaded, deliveries)))
+}
@@ -0,0 +1,409 @@
+use std::future::Future;
+
+use serde::de::value::Error as DeError;
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const DEFAULT_LOCALE: Locale = Locale::PtBr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    PtBr,
+    En,
+}
+
+impl Locale {
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag.trim().to_lowercase();
+        let primary = tag.split(['-', '_']).next().unwrap_or("");
+        match primary {
+            "pt" => Some(Locale::PtBr),
+            "en" => Some(Locale::En),
+            _ => None,
+        }
+    }
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::PtBr => "pt-BR",
+            Locale::En => "en",
+        }
+    }
+
+    /// Picks the best supported locale from an `Accept-Language` header, honouring q-values.
+    pub fn negotiate(accept_language: &str) -> Option<Self> {
+        let mut candidates: Vec<(f32, Locale)> = accept_language
+            .split(',')
+            .filter_map(|part| {
+                let mut pieces = part.split(';');
+                let locale = Locale::from_tag(pieces.next()?)?;
+                let quality = pieces
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse().ok())
+                    .unwrap_or(1.0);
+                Some((quality, locale))
+            })
+            .filter(|(quality, _)| *quality > 0.0)
+            .collect();
+
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        candidates.first().map(|(_, locale)| *locale)
+    }
+}
+
+tokio::task_local! {
+    static LOCALE: Locale;
+}
+
+/// Locale of the request being handled, or the default outside of a request.
+pub fn current() -> Locale {
+    LOCALE.try_with(|locale| *locale).unwrap_or(DEFAULT_LOCALE)
+}
+
+/// Runs `f` with `locale` as the current locale.
+pub async fn scope<F: Future>(locale: Locale, f: F) -> F::Output {
+    LOCALE.scope(locale, f).await
+}
+
+// Declares `Msg` from one table, so a key's code, its serde name and its texts can't
+// drift apart: `Variant = "code" => ("pt-BR", "en"),`. The code is a `tt` rather than a
+// `literal` so that utoipa's derive sees a plain string in `#[serde(rename)]`.
+macro_rules! messages {
+    ($($variant:ident = $code:tt => ($pt:literal, $en:literal),)*) => {
+        /// Stable keys for every message the API sends to clients. Error keys double as the
+        /// machine-readable `code` of error responses.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+        pub enum Msg {
+            $(#[serde(rename = $code)] $variant,)*
+        }
+
+        impl Msg {
+            /// Every key, in declaration order.
+            #[cfg(test)]
+            pub const ALL: &'static [Msg] = &[$(Msg::$variant,)*];
+
+            /// Stable snake_case code, as serialized in responses.
+            pub fn code(self) -> &'static str {
+                match self {
+                    $(Msg::$variant => $code,)*
+                }
+            }
+
+            pub fn text(self, locale: Locale) -> &'static str {
+                let (pt, en) = match self {
+                    $(Msg::$variant => ($pt, $en),)*
+                };
+
+                match locale {
+                    Locale::PtBr => pt,
+                    Locale::En => en,
+                }
+            }
+        }
+    };
+}
+
+messages! {
+    MissingToken = "missing_token" => ("Token não fornecido", "Token not provided"),
+    InvalidToken = "invalid_token" => ("Token inválido ou expirado", "Invalid or expired token"),
+    SessionRevoked = "session_revoked" => ("Sessão encerrada. Entre novamente", "Session ended. Please sign in again"),
+    FillAllFields = "fill_all_fields" => ("Preencha todos os campos", "Please fill in all fields"),
+    FillEmailAndPassword = "fill_email_and_password" => ("Preencha email e senha", "Please enter email and password"),
+    InvalidEmail = "invalid_email" => ("Email inválido", "Invalid email"),
+    EmailRequired = "email_required" => ("Email é obrigatório", "Email is required"),
+    EmailAndCodeRequired = "email_and_code_required" => ("Email e código são obrigatórios", "Email and code are required"),
+    PasswordTooShort = "password_too_short" => ("Senha deve ter no mínimo 8 caracteres", "Password must be at least 8 characters long"),
+    PasswordTooLong = "password_too_long" => ("Senha deve ter no máximo 128 caracteres", "Password must be at most 128 characters long"),
+    PasswordTooCommon = "password_too_common" => ("Senha muito comum ou já vazada. Escolha outra.", "This password is too common or has appeared in a breach. Choose another one."),
+    PasswordTooSimple = "password_too_simple" => ("Senha muito simples: evite sequências e caracteres repetidos", "Password is too simple: avoid sequences and repeated characters"),
+    PasswordContainsPersonalInfo = "password_contains_personal_info" => ("Senha não pode conter seu nome ou email", "Password must not contain your name or email"),
+    PasswordsDoNotMatch = "passwords_do_not_match" => ("As senhas não coincidem", "Passwords do not match"),
+    CurrentPasswordRequired = "current_password_required" => ("Senha atual é obrigatória", "Current password is required"),
+    EmailAlreadyRegistered = "email_already_registered" => ("Email já cadastrado", "Email already registered"),
+    EmailInUse = "email_in_use" => ("Email já está em uso", "Email is already in use"),
+    EmailNotFound = "email_not_found" => ("Email não encontrado", "Email not found"),
+    EmailAlreadyVerified = "email_already_verified" => ("Email já verificado", "Email already verified"),
+    InvalidCode = "invalid_code" => ("Código inválido", "Invalid code"),
+    CodeExpired = "code_expired" => ("Código expirado. Solicite um novo.", "Code expired. Please request a new one."),
+    InvalidResetToken = "invalid_reset_token" => ("Token inválido", "Invalid token"),
+    NoPendingEmailChange = "no_pending_email_change" => ("Nenhuma troca de email pendente", "No pending email change"),
+    InvalidRevertLink = "invalid_revert_link" => ("Link inválido ou expirado", "Invalid or expired link"),
+    InvalidCredentials = "invalid_credentials" => ("Email ou senha incorretos", "Incorrect email or password"),
+    WrongPassword = "wrong_password" => ("Senha incorreta", "Incorrect password"),
+    WrongCurrentPassword = "wrong_current_password" => ("Senha atual incorreta", "Current password is incorrect"),
+    AccountNotVerified = "account_not_verified" => ("Conta não verificada. Código enviado para o email.", "Account not verified. A code was sent to your email."),
+    UserNotFound = "user_not_found" => ("Usuário não encontrado", "User not found"),
+    UsernameRequired = "username_required" => ("Nome de usuário é obrigatório", "Username is required"),
+    UsernameTooShort = "username_too_short" => ("Nome deve ter no mínimo 3 caracteres", "Name must be at least 3 characters long"),
+    OidcProviderNotFound = "oidc_provider_not_found" => ("Provedor de login não encontrado", "Sign-in provider not found"),
+    OidcUnavailable = "oidc_unavailable" => ("Provedor de login indisponível no momento", "The sign-in provider is unavailable right now"),
+    InvalidOidcState = "invalid_oidc_state" => ("Sessão de login inválida ou expirada. Tente novamente.", "Invalid or expired sign-in session. Please try again."),
+    OidcLoginFailed = "oidc_login_failed" => ("Não foi possível entrar com o provedor", "Could not sign in with the provider"),
+    OidcEmailNotVerified = "oidc_email_not_verified" => ("O provedor não confirmou seu email", "The provider did not confirm your email"),
+    InsufficientScope = "insufficient_scope" => ("Token sem permissão para esta operação", "This token is not allowed to perform this operation"),
+    ApiTokenNotFound = "api_token_not_found" => ("Token de API não encontrado", "API token not found"),
+    InvalidScope = "invalid_scope" => ("Escopo inválido", "Invalid scope"),
+    ScopesRequired = "scopes_required" => ("Informe ao menos um escopo", "At least one scope is required"),
+    InvalidTokenExpiry = "invalid_token_expiry" => ("Validade deve ser de 1 a 3650 dias", "Expiry must be between 1 and 3650 days"),
+    WebhookNotFound = "webhook_not_found" => ("Webhook não encontrado", "Webhook not found"),
+    InvalidWebhookUrl = "invalid_webhook_url" => ("URL inválida (use http:// ou https://)", "Invalid URL (use http:// or https://)"),
+    InvalidWebhookEvent = "invalid_webhook_event" => ("Evento de webhook inválido", "Invalid webhook event"),
+    WebhookEventsRequired = "webhook_events_required" => ("Informe ao menos um evento", "At least one event is required"),
+    InvalidWebhookSecret = "invalid_webhook_secret" => ("O segredo deve ter de 16 a 100 caracteres", "The secret must be 16 to 100 characters long"),
+    InternalError = "internal_error" => ("Erro interno do servidor", "Internal server error"),
+    ValidationFailed = "validation_failed" => ("Dados inválidos", "Invalid data"),
+    InvalidRequestBody = "invalid_request_body" => ("Corpo da requisição inválido", "Invalid request body"),
+    InvalidQuery = "invalid_query" => ("Parâmetros de consulta inválidos", "Invalid query parameters"),
+    RouteNotFound = "route_not_found" => ("Rota não encontrada", "Route not found"),
+    FieldRequired = "field_required" => ("Campo obrigatório", "This field is required"),
+    FieldTooLong = "field_too_long" => ("Texto muito longo", "Text is too long"),
+    InvalidColor = "invalid_color" => ("Cor inválida (use #RRGGBB)", "Invalid color (use #RRGGBB)"),
+    InvalidDate = "invalid_date" => ("Data inválida", "Invalid date"),
+    ListNotFound = "list_not_found" => ("Lista não encontrada", "List not found"),
+    ListNameRequired = "list_name_required" => ("Nome da lista é obrigatório", "List name is required"),
+    NothingToUpdate = "nothing_to_update" => ("Nenhum campo para atualizar", "No fields to update"),
+    TaskNotFound = "task_not_found" => ("Tarefa não encontrada", "Task not found"),
+    ListAndTitleRequired = "list_and_title_required" => ("Lista e título são obrigatórios", "List and title are required"),
+    InvalidRecurrence = "invalid_recurrence" => ("Recorrência inválida", "Invalid recurrence"),
+    InvalidDueFilter = "invalid_due_filter" => ("Filtro 'due' inválido", "Invalid 'due' filter"),
+    InvalidLastSync = "invalid_last_sync" => ("Formato de last_sync inválido", "Invalid last_sync format"),
+    InvalidCalendarData = "invalid_calendar_data" => ("Dados iCalendar inválidos: envie um VCALENDAR com um VTODO e um SUMMARY", "Invalid iCalendar data: send a VCALENDAR with one VTODO and a SUMMARY"),
+    InvalidResourceName = "invalid_resource_name" => ("Nome de recurso inválido (até 36 letras, números, '-', '_' ou '.', mais .ics)", "Invalid resource name (up to 36 letters, digits, '-', '_' or '.', plus .ics)"),
+    TaskIdInUse = "task_id_in_use" => ("Já existe uma tarefa com este id", "A task with this id already exists"),
+    ListIdInUse = "list_id_in_use" => ("Já existe uma lista com este id", "A list with this id already exists"),
+    PreconditionFailed = "precondition_failed" => ("O item mudou no servidor. Sincronize e tente novamente.", "The item changed on the server. Sync and try again."),
+    InvalidTimeZone = "invalid_time_zone" => ("Fuso horário inválido", "Invalid time zone"),
+    InvalidFrom = "invalid_from" => ("Parâmetro 'from' inválido", "Invalid 'from' parameter"),
+    InvalidTo = "invalid_to" => ("Parâmetro 'to' inválido", "Invalid 'to' parameter"),
+    RangeInverted = "range_inverted" => ("'to' deve ser posterior a 'from'", "'to' must be after 'from'"),
+    RangeTooLarge = "range_too_large" => ("Intervalo máximo de {max} dias", "Maximum range is {max} days"),
+    UnsupportedLocale = "unsupported_locale" => ("Idioma não suportado", "Unsupported language"),
+    InvalidWeekStart = "invalid_week_start" => ("Início da semana inválido", "Invalid week start"),
+    InvalidReminderTime = "invalid_reminder_time" => ("Horário padrão de lembrete inválido", "Invalid default reminder time"),
+    CalendarFeedNotFound = "calendar_feed_not_found" => ("Link do calendário não encontrado", "Calendar link not found"),
+    InvalidFeedComponent = "invalid_feed_component" => ("Formato inválido (use 'event' ou 'todo')", "Invalid format (use 'event' or 'todo')"),
+    InvalidExportFormat = "invalid_export_format" => ("Formato inválido (use 'markdown', 'csv', 'json' ou 'html')", "Invalid format (use 'markdown', 'csv', 'json' or 'html')"),
+    InvalidImportFormat = "invalid_import_format" => ("Formato inválido (use 'todoist', 'todoist_csv', 'microsoft_todo' ou 'csv')", "Invalid format (use 'todoist', 'todoist_csv', 'microsoft_todo' or 'csv')"),
+    InvalidImportFile = "invalid_import_file" => ("Não foi possível ler o arquivo no formato escolhido", "Could not read the file in the chosen format"),
+    CsvColumnNotFound = "csv_column_not_found" => ("Coluna '{column}' não encontrada no CSV", "Column '{column}' not found in the CSV"),
+    ImportTooLarge = "import_too_large" => ("O arquivo tem mais de {max} tarefas. Divida-o e importe por partes.", "The file has more than {max} tasks. Split it and import it in parts."),
+    InboundAddressNotFound = "inbound_address_not_found" => ("Endereço de email não encontrado", "Email address not found"),
+    InboundEmailDisabled = "inbound_email_disabled" => ("Recebimento de emails desativado neste servidor", "Receiving email is turned off on this server"),
+    InvalidEmailMessage = "invalid_email_message" => ("Não foi possível ler o email (envie a mensagem RFC 5322 completa)", "Could not read the email (send the full RFC 5322 message)"),
+    EmailTooLarge = "email_too_large" => ("O email passa de {max} bytes", "The email is larger than {max} bytes"),
+    AttachmentNotFound = "attachment_not_found" => ("Anexo não encontrado", "Attachment not found"),
+    ReminderNotFound = "reminder_not_found" => ("Lembrete não encontrado", "Reminder not found"),
+    ReminderTimeRequired = "reminder_time_required" => ("Informe remind_at ou minutes_before_due, apenas um deles", "Give either remind_at or minutes_before_due, not both"),
+    TooManyInvitations = "too_many_invitations" => ("Limite de {max} convites por dia atingido. Tente novamente amanhã.", "You can send up to {max} invitations a day. Try again tomorrow."),
+    AlreadyInvited = "already_invited" => ("Você já convidou este email nos últimos {days} dias", "You already invited this email in the last {days} days"),
+    TooManyReminders = "too_many_reminders" => ("Uma tarefa pode ter até {max} lembretes", "A task can have at most {max} reminders"),
+    InvalidReminderOffset = "invalid_reminder_offset" => ("O lembrete pode ser de até 1 ano antes do prazo", "The reminder can be at most 1 year before the due date"),
+    InvalidSnooze = "invalid_snooze" => ("Adie por 1 minuto a 7 dias", "Snooze for 1 minute to 7 days"),
+    SnoozeInPast = "snooze_in_past" => ("Adie para um horário no futuro", "Snooze until a time in the future"),
+    InvalidPushEndpoint = "invalid_push_endpoint" => ("Endpoint de push inválido (use uma URL http ou https)", "Invalid push endpoint (use an http or https URL)"),
+    InvalidPushKeys = "invalid_push_keys" => ("Chaves de push inválidas (p256dh e auth em base64url)", "Invalid push keys (p256dh and auth in base64url)"),
+    PushDisabled = "push_disabled" => ("Web Push desativado neste servidor (configure VAPID_PRIVATE_KEY)", "Web Push is turned off on this server (set VAPID_PRIVATE_KEY)"),
+    PushSubscriptionNotFound = "push_subscription_not_found" => ("Inscrição de push não encontrada", "Push subscription not found"),
+    PushSinkNotFound = "push_sink_not_found" => ("Inscrição de teste não encontrada", "Test subscription not found"),
+    InvalidVapidToken = "invalid_vapid_token" => ("Token VAPID inválido", "Invalid VAPID token"),
+    InvalidPushMessage = "invalid_push_message" => ("Mensagem de push inválida", "Invalid push message"),
+    PushTestFailed = "push_test_failed" => ("O serviço de push recusou a mensagem: {error}", "The push service refused the message: {error}"),
+    QuickAddTitleRequired = "quick_add_title_required" => ("Escreva o que fazer, além da data e das etiquetas", "Write what to do, besides the date and tags"),
+    ApiIndex = "api_index" => ("Todo App API v1.0 (Rust)", "Todo App API v1.0 (Rust)"),
+    CodeResent = "code_resent" => ("Código reenviado para o email", "Code resent to your email"),
+    VerificationCodeSent = "verification_code_sent" => ("Código de verificação enviado para o email", "Verification code sent to your email"),
+    AccountActivated = "account_activated" => ("Conta ativada com sucesso!", "Account activated successfully!"),
+    NewCodeSent = "new_code_sent" => ("Novo código enviado para o email", "A new code was sent to your email"),
+    ResetCodeSent = "reset_code_sent" => ("Código de recuperação enviado para o email", "Recovery code sent to your email"),
+    CodeVerified = "code_verified" => ("Código verificado", "Code verified"),
+    PasswordReset = "password_reset" => ("Senha alterada com sucesso!", "Password changed successfully!"),
+    OidcProvidersLoaded = "oidc_providers_loaded" => ("Provedores de login carregados", "Sign-in providers loaded"),
+    LoggedIn = "logged_in" => ("Login realizado com sucesso", "Logged in successfully"),
+    UserData = "user_data" => ("Dados do usuário", "User data"),
+    UsernameUpdated = "username_updated" => ("Nome atualizado com sucesso", "Name updated successfully"),
+    EmailUpdated = "email_updated" => ("Email atualizado com sucesso", "Email updated successfully"),
+    EmailChangeRequested = "email_change_requested" => ("Código de confirmação enviado para o novo email", "Confirmation code sent to the new email"),
+    PasswordUpdated = "password_updated" => ("Senha atualizada com sucesso", "Password updated successfully"),
+    AccountDeletionScheduled = "account_deletion_scheduled" => ("Exclusão da conta agendada", "Account deletion scheduled"),
+    AccountDeletionCancelled = "account_deletion_cancelled" => ("Exclusão da conta cancelada", "Account deletion cancelled"),
+    ApiTokensLoaded = "api_tokens_loaded" => ("Tokens de API carregados", "API tokens loaded"),
+    ApiTokenCreated = "api_token_created" => ("Token de API criado. Copie agora: ele não será mostrado novamente.", "API token created. Copy it now: it will not be shown again."),
+    ApiTokenRevoked = "api_token_revoked" => ("Token de API revogado", "API token revoked"),
+    InvitationSent = "invitation_sent" => ("Convite enviado", "Invitation sent"),
+    WebhooksLoaded = "webhooks_loaded" => ("Webhooks carregados", "Webhooks loaded"),
+    WebhookCreated = "webhook_created" => ("Webhook criado. Guarde o segredo para validar as assinaturas.", "Webhook created. Keep the secret to verify signatures."),
+    WebhookUpdated = "webhook_updated" => ("Webhook atualizado", "Webhook updated"),
+    WebhookDeleted = "webhook_deleted" => ("Webhook removido", "Webhook deleted"),
+    WebhookDeliveriesLoaded = "webhook_deliveries_loaded" => ("Entregas carregadas", "Deliveries loaded"),
+    SettingsLoaded = "settings_loaded" => ("Configurações carregadas", "Settings loaded"),
+    SettingsUpdated = "settings_updated" => ("Configurações atualizadas", "Settings updated"),
+    ListsLoaded = "lists_loaded" => ("Listas carregadas", "Lists loaded"),
+    ListFound = "list_found" => ("Lista encontrada", "List found"),
+    ListCreated = "list_created" => ("Lista criada com sucesso", "List created successfully"),
+    ListUpdated = "list_updated" => ("Lista atualizada", "List updated"),
+    ListDeleted = "list_deleted" => ("Lista deletada com sucesso", "List deleted successfully"),
+    TasksLoaded = "tasks_loaded" => ("Tarefas carregadas", "Tasks loaded"),
+    TaskFound = "task_found" => ("Tarefa encontrada", "Task found"),
+    TaskCreated = "task_created" => ("Tarefa criada com sucesso", "Task created successfully"),
+    TaskUpdated = "task_updated" => ("Tarefa atualizada", "Task updated"),
+    TaskDeleted = "task_deleted" => ("Tarefa deletada com sucesso", "Task deleted successfully"),
+    StatsLoaded = "stats_loaded" => ("Estatísticas carregadas", "Statistics loaded"),
+    CalendarLoaded = "calendar_loaded" => ("Calendário carregado", "Calendar loaded"),
+    CalendarFeedLoaded = "calendar_feed_loaded" => ("Link do calendário carregado", "Calendar link loaded"),
+    CalendarFeedCreated = "calendar_feed_created" => ("Novo link do calendário gerado. O anterior deixou de funcionar.", "New calendar link generated. The previous one no longer works."),
+    CalendarFeedRevoked = "calendar_feed_revoked" => ("Link do calendário revogado", "Calendar link revoked"),
+    ExportProgress = "export_progress" => ("{done} de {total} concluídas", "{done} of {total} completed"),
+    ExportFooter = "export_footer" => ("Exportado do MyTudo em {date}", "Exported from MyTudo on {date}"),
+    ImportPreviewed = "import_previewed" => ("Prévia da importação. Nada foi salvo.", "Import preview. Nothing was saved."),
+    ImportCompleted = "import_completed" => ("Importação concluída", "Import completed"),
+    InboundAddressesLoaded = "inbound_addresses_loaded" => ("Endereços de email carregados", "Email addresses loaded"),
+    InboundAddressCreated = "inbound_address_created" => ("Novo endereço de email gerado. O anterior deixou de funcionar.", "New email address generated. The previous one no longer works."),
+    InboundAddressDeleted = "inbound_address_deleted" => ("Endereço de email removido", "Email address deleted"),
+    InboundEmailDelivered = "inbound_email_delivered" => ("Email recebido e tarefa criada", "Email received and task created"),
+    InboundNoSubject = "inbound_no_subject" => ("(sem assunto)", "(no subject)"),
+    AttachmentsLoaded = "attachments_loaded" => ("Anexos carregados", "Attachments loaded"),
+    AttachmentDeleted = "attachment_deleted" => ("Anexo removido", "Attachment deleted"),
+    PushSubscriptionsLoaded = "push_subscriptions_loaded" => ("Inscrições de push carregadas", "Push subscriptions loaded"),
+    PushSubscribed = "push_subscribed" => ("Notificações ativadas neste dispositivo", "Notifications turned on for this device"),
+    PushUnsubscribed = "push_unsubscribed" => ("Inscrição de push removida", "Push subscription deleted"),
+    PushTestSent = "push_test_sent" => ("Notificação de teste enviada", "Test notification sent"),
+    VapidKeyLoaded = "vapid_key_loaded" => ("Chave VAPID do servidor", "Server VAPID key"),
+    PushSinkCreated = "push_sink_created" => ("Inscrição de teste criada", "Test subscription created"),
+    PushSinkReceived = "push_sink_received" => ("Mensagem recebida", "Message received"),
+    PushSinkMessagesLoaded = "push_sink_messages_loaded" => ("Mensagens recebidas", "Messages received"),
+    PushTestTitle = "push_test_title" => ("MyTudo", "MyTudo"),
+    PushTestBody = "push_test_body" => ("As notificações estão funcionando.", "Notifications are working."),
+    RemindersLoaded = "reminders_loaded" => ("Lembretes carregados", "Reminders loaded"),
+    ReminderCreated = "reminder_created" => ("Lembrete criado", "Reminder created"),
+    ReminderUpdated = "reminder_updated" => ("Lembrete atualizado", "Reminder updated"),
+    ReminderDeleted = "reminder_deleted" => ("Lembrete removido", "Reminder deleted"),
+    ReminderSnoozed = "reminder_snoozed" => ("Lembrete adiado", "Reminder snoozed"),
+    ReminderDismissed = "reminder_dismissed" => ("Lembrete dispensado", "Reminder dismissed"),
+    SyncPushed = "sync_pushed" => ("Sincronização concluída", "Sync completed"),
+    SyncPulled = "sync_pulled" => ("Dados sincronizados", "Data synchronized"),
+    SyncFull = "sync_full" => ("Sincronização completa", "Full sync"),
+    ServiceHealthy = "service_healthy" => ("Serviço no ar", "Service is up"),
+    ServiceReady = "service_ready" => ("Serviço pronto", "Service is ready"),
+    ServiceNotReady = "service_not_ready" => ("Serviço indisponível", "Service is not ready"),
+    EmailVerificationSubject = "email_verification_subject" => ("MyTudo - Código de Verificação: {code}", "MyTudo - Verification Code: {code}"),
+    EmailPasswordResetSubject = "email_password_reset_subject" => ("MyTudo - Recuperar Senha: {code}", "MyTudo - Reset Password: {code}"),
+    EmailGreeting = "email_greeting" => ("Olá <strong>{name}</strong>!", "Hi <strong>{name}</strong>!"),
+    EmailVerificationInstructions = "email_verification_instructions" => ("Use o código abaixo para ativar sua conta:", "Use the code below to activate your account:"),
+    EmailPasswordResetInstructions = "email_password_reset_instructions" => ("Use o código abaixo para recuperar sua senha:", "Use the code below to reset your password:"),
+    EmailCodeExpiry = "email_code_expiry" => ("Este código expira em <strong>15 minutos</strong>.", "This code expires in <strong>15 minutes</strong>."),
+    EmailVerificationFooter = "email_verification_footer" => ("Se você não solicitou este código, ignore este email.", "If you did not request this code, please ignore this email."),
+    EmailPasswordResetFooter = "email_password_reset_footer" => ("Se você não solicitou a recuperação de senha, ignore este email.", "If you did not request a password reset, please ignore this email."),
+    EmailChangeSubject = "email_change_subject" => ("MyTudo - Confirme seu novo email: {code}", "MyTudo - Confirm your new email: {code}"),
+    EmailChangeInstructions = "email_change_instructions" => ("Use o código abaixo para confirmar <strong>{email}</strong> como o novo email da sua conta:", "Use the code below to confirm <strong>{email}</strong> as your account's new email:"),
+    EmailChangeFooter = "email_change_footer" => ("Se você não pediu para trocar de email, ignore esta mensagem. Seu email atual continua valendo.", "If you did not ask to change your email, ignore this message. Your current email stays in place."),
+    EmailChangedSubject = "email_changed_subject" => ("MyTudo - Seu email foi alterado", "MyTudo - Your email was changed"),
+    EmailChangedText = "email_changed_text" => ("O email da sua conta foi alterado para <strong>{email}</strong>.", "Your account's email was changed to <strong>{email}</strong>."),
+    EmailChangedRevert = "email_changed_revert" => ("Se não foi você, desfaça a alteração pelo link abaixo. Ele vale por 7 dias.", "If this wasn't you, undo the change with the link below. It is valid for 7 days."),
+    EmailChangedButton = "email_changed_button" => ("Desfazer alteração", "Undo change"),
+    EmailChangedFooter = "email_changed_footer" => ("Se foi você, nada mais precisa ser feito.", "If it was you, there is nothing else to do."),
+    RevertEmailConfirm = "revert_email_confirm" => ("O email da sua conta voltará a ser <strong>{email}</strong>. Por segurança, todas as sessões e tokens de acesso serão encerrados e você precisará criar uma nova senha.", "Your account's email will go back to <strong>{email}</strong>. For your safety, every session and access token will be signed out and you will need to choose a new password."),
+    RevertEmailDone = "revert_email_done" => ("Pronto! O email da sua conta voltou a ser <strong>{email}</strong>. Enviamos para ele um código para criar uma nova senha.", "Done! Your account's email is <strong>{email}</strong> again. We sent it a code to choose a new password."),
+    EmailInvitationSubject = "email_invitation_subject" => ("{inviter} convidou você para o MyTudo", "{inviter} invited you to MyTudo"),
+    EmailInvitationText = "email_invitation_text" => ("<strong>{inviter}</strong> usa o MyTudo para organizar listas e tarefas e convidou você para experimentar.", "<strong>{inviter}</strong> uses MyTudo to organize lists and tasks and invited you to try it."),
+    EmailInvitationButton = "email_invitation_button" => ("Conhecer o MyTudo", "Get MyTudo"),
+    EmailInvitationFooter = "email_invitation_footer" => ("Se você não conhece quem enviou, ignore este email.", "If you don't know the sender, please ignore this email."),
+    EmailDigestSubject = "email_digest_subject" => ("MyTudo - Seu resumo: {count} tarefas pendentes", "MyTudo - Your summary: {count} pending tasks"),
+    EmailDigestIntro = "email_digest_intro" => ("Este é o resumo das suas tarefas:", "Here is the summary of your tasks:"),
+    EmailDigestOverdue = "email_digest_overdue" => ("Atrasadas", "Overdue"),
+    EmailDigestDueToday = "email_digest_due_today" => ("Para hoje", "Due today"),
+    EmailDigestFooter = "email_digest_footer" => ("Você recebe este resumo porque ativou o resumo diário nas configurações.", "You get this summary because the daily digest is turned on in your settings."),
+    EmailReminderSubject = "email_reminder_subject" => ("Lembrete: {title}", "Reminder: {title}"),
+    EmailReminderText = "email_reminder_text" => ("Está na hora de: <strong>{title}</strong>", "It's time for: <strong>{title}</strong>"),
+    EmailReminderList = "email_reminder_list" => ("Lista: {list}", "List: {list}"),
+    EmailReminderDue = "email_reminder_due" => ("Prazo: {due}", "Due: {due}"),
+    EmailReminderFooter = "email_reminder_footer" => ("Você recebe este email porque ativou os lembretes por email nas configurações.", "You get this email because email reminders are turned on in your settings."),
+}
+
+impl Msg {
+    /// Looks a key up by its snake_case code, e.g. `"list_not_found"`.
+    pub fn from_code(code: &str) -> Option<Self> {
+        Msg::deserialize(IntoDeserializer::<DeError>::into_deserializer(code)).ok()
+    }
+
+    /// Translates into the current locale, replacing `{name}` placeholders.
+    pub fn with(self, args: &[(&str, &str)]) -> String {
+        self.with_locale(current(), args)
+    }
+
+    pub fn with_locale(self, locale: Locale, args: &[(&str, &str)]) -> String {
+        args.iter().fold(self.text(locale).to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+    }
+}
+
+impl From<Msg> for String {
+    fn from(msg: Msg) -> Self {
+        msg.text(current()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashSet};
+
+    use super::*;
+
+    const LOCALES: [Locale; 2] = [Locale::PtBr, Locale::En];
+
+    // `{name}` placeholders of a text
+    fn placeholders(text: &str) -> BTreeSet<&str> {
+        text.split('{').skip(1).filter_map(|rest| rest.split_once('}')).map(|(name, _)| name).collect()
+    }
+
+    #[test]
+    fn every_message_is_translated_in_every_locale() {
+        for msg in Msg::ALL {
+            for locale in LOCALES {
+                assert!(!msg.text(locale).trim().is_empty(), "{:?} has no {} text", msg, locale.tag());
+            }
+            assert_eq!(
+                placeholders(msg.text(Locale::PtBr)),
+                placeholders(msg.text(Locale::En)),
+                "{:?} has different placeholders per locale",
+                msg
+            );
+        }
+    }
+
+    #[test]
+    fn codes_are_the_serialized_names() {
+        let mut seen = HashSet::new();
+        for &msg in Msg::ALL {
+            let code = msg.code();
+            assert!(seen.insert(code), "duplicate code {}", code);
+            assert!(code.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_'), "{}", code);
+            assert_eq!(serde_json::to_value(msg).unwrap(), code);
+            assert_eq!(Msg::from_code(code), Some(msg));
+        }
+        assert_eq!(Msg::from_code("no_such_message"), None);
+    }
+
+    #[test]
+    fn openapi_lists_the_codes() {
+        let schema = serde_json::to_value(<Msg as utoipa::PartialSchema>::schema()).unwrap();
+        let codes: Vec<&str> = Msg::ALL.iter().map(|msg| msg.code()).collect();
+        assert_eq!(schema["enum"], serde_json::json!(codes));
+    }
+
+    #[test]
+    fn replaces_placeholders() {
+        let text = Msg::EmailReminderSubject.with_locale(Locale::En, &[("title", "Pay rent")]);
+        assert_eq!(text, "Reminder: Pay rent");
+    }
+
+    #[test]
+    fn negotiates_accept_language() {
+        assert_eq!(Locale::negotiate("en-US,pt;q=0.5"), Some(Locale::En));
+        assert_eq!(Locale::negotiate("fr, pt-BR;q=0.8, en;q=0.3"), Some(Locale::PtBr));
+        assert_eq!(Locale::negotiate("en;q=0, fr"), None);
+        assert_eq!(Locale::from_tag("PT_pt"), Some(Locale::PtBr));
+    }
+}
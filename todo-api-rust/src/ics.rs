@@ -0,0 +1,632 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::models::{FeedComponent, FeedTask, Task};
+use crate::recurrence;
+
+// RFC 5545 limit per content line, in octets, before folding
+const MAX_LINE_OCTETS: usize = 75;
+// Hint for calendar apps on how often to poll the feed
+const REFRESH_INTERVAL: &str = "PT15M";
+
+/// One `/calendar.ics` document.
+pub struct Feed<'a> {
+    pub name: &'a str,
+    pub tz: Tz,
+    pub component: FeedComponent,
+}
+
+// Content lines joined with CRLF and folded at 75 octets
+struct Writer {
+    out: String,
+}
+
+impl Writer {
+    fn line(&mut self, name: &str, value: &str) {
+        let line = format!("{}:{}", name, value);
+        let mut octets = 0;
+        for c in line.chars() {
+            if octets + c.len_utf8() > MAX_LINE_OCTETS {
+                self.out.push_str("\r\n ");
+                // The leading space of the continuation counts too
+                octets = 1;
+            }
+            self.out.push(c);
+            octets += c.len_utf8();
+        }
+        self.out.push_str("\r\n");
+    }
+
+    fn text(&mut self, name: &str, value: &str) {
+        self.line(name, &escape(value));
+    }
+}
+
+// Helper: TEXT value escaping
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn utc(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// Helper: Signed DURATION in whole minutes, e.g. `-PT15M` or `-P1DT2H`
+fn duration(offset: Duration) -> String {
+    let sign = if offset < Duration::zero() { "-" } else { "" };
+    let minutes = offset.num_minutes().abs();
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+
+    let mut out = format!("{}P", sign);
+    if days > 0 {
+        out.push_str(&format!("{}D", days));
+    }
+    if hours > 0 || minutes > 0 {
+        out.push('T');
+        if hours > 0 {
+            out.push_str(&format!("{}H", hours));
+        }
+        if minutes > 0 {
+            out.push_str(&format!("{}M", minutes));
+        }
+    }
+    if days == 0 && hours == 0 && minutes == 0 {
+        out.push_str("T0S");
+    }
+    out
+}
+
+/// Renders tasks with a reminder or due date. The due date (or the reminder, when there
+/// is none) is the entry's time and the reminder becomes a VALARM relative to it.
+/// Recurring tasks are written in the user's time zone so the RRULE expands in local
+/// time, as `/calendar` does. Output only depends on the tasks, so it can be hashed into
+/// an ETag.
+pub fn render(feed: &Feed, tasks: &[FeedTask]) -> String {
+    let mut w = Writer { out: String::new() };
+
+    w.line("BEGIN", "VCALENDAR");
+    w.line("VERSION", "2.0");
+    w.line("PRODID", "-//MyTudo//Tasks//EN");
+    w.line("CALSCALE", "GREGORIAN");
+    w.line("METHOD", "PUBLISH");
+    w.text("X-WR-CALNAME", feed.name);
+    w.line("X-WR-TIMEZONE", feed.tz.name());
+    w.line("REFRESH-INTERVAL;VALUE=DURATION", REFRESH_INTERVAL);
+    w.line("X-PUBLISHED-TTL", REFRESH_INTERVAL);
+
+    for entry in tasks {
+        if entry.task.due_at.is_some() || entry.task.reminder.is_some() {
+            let uid = format!("{}@mytudo", entry.task.id);
+            write_task(&mut w, feed.tz, feed.component, &entry.task, &uid, Some(&entry.list_name));
+        }
+    }
+
+    w.line("END", "VCALENDAR");
+    w.out
+}
+
+/// A task as a CalDAV calendar object: one VTODO whose UID is the task id. Tasks without
+/// dates are included too.
+pub fn render_todo(task: &Task, tz: Tz) -> String {
+    let mut w = Writer { out: String::new() };
+
+    w.line("BEGIN", "VCALENDAR");
+    w.line("VERSION", "2.0");
+    w.line("PRODID", "-//MyTudo//Tasks//EN");
+    write_task(&mut w, tz, FeedComponent::Todo, task, &task.id, None);
+    w.line("END", "VCALENDAR");
+    w.out
+}
+
+fn write_task(
+    w: &mut Writer,
+    tz: Tz,
+    component: FeedComponent,
+    task: &Task,
+    uid: &str,
+    category: Option<&str>,
+) {
+    let component_name = match component {
+        FeedComponent::Event => "VEVENT",
+        FeedComponent::Todo => "VTODO",
+    };
+
+    // Recurring tasks keep their wall-clock time across DST changes
+    let time = |name: &str, at: DateTime<Utc>| match &task.recurrence {
+        Some(_) => (
+            format!("{};TZID={}", name, tz.name()),
+            at.with_timezone(&tz).format("%Y%m%dT%H%M%S").to_string(),
+        ),
+        None => (name.to_string(), utc(at)),
+    };
+
+    w.line("BEGIN", component_name);
+    w.text("UID", uid);
+    w.line("DTSTAMP", &utc(task.updated_at));
+    w.line("CREATED", &utc(task.created_at));
+    w.line("LAST-MODIFIED", &utc(task.updated_at));
+    w.text("SUMMARY", &task.title);
+    if let Some(description) = task.description.as_deref().filter(|d| !d.is_empty()) {
+        w.text("DESCRIPTION", description);
+    }
+    if let Some(category) = category {
+        w.text("CATEGORIES", category);
+    }
+
+    // Events start at the due date or reminder. To-dos are due at the due date; one with
+    // only a reminder starts then instead, so a client saving it back keeps `due_at` empty.
+    let (at, time_property, related) = match (component, task.due_at, task.reminder) {
+        (FeedComponent::Event, due, reminder) => (due.or(reminder), "DTSTART", ""),
+        (FeedComponent::Todo, Some(due), _) => (Some(due), "DUE", ";RELATED=END"),
+        (FeedComponent::Todo, None, reminder) => (reminder, "DTSTART", ""),
+    };
+
+    if let Some(at) = at {
+        let (name, value) = time(time_property, at);
+        w.line(&name, &value);
+
+        if let Some(rule) = &task.recurrence {
+            // RRULE needs a DTSTART; task apps write it equal to DUE
+            if time_property == "DUE" {
+                let (name, value) = time("DTSTART", at);
+                w.line(&name, &value);
+            }
+            w.line("RRULE", rule);
+        }
+    }
+
+    match component {
+        FeedComponent::Event => w.line("TRANSP", "TRANSPARENT"),
+        FeedComponent::Todo if task.completed => {
+            w.line("STATUS", "COMPLETED");
+            // Rows completed before `completed_at` existed only have the last change
+            w.line("COMPLETED", &utc(task.completed_at.unwrap_or(task.updated_at)));
+            w.line("PERCENT-COMPLETE", "100");
+        }
+        FeedComponent::Todo => w.line("STATUS", "NEEDS-ACTION"),
+    }
+
+    // Calendars would still ring for a completed task; task apps know better
+    let alarm = task.reminder.filter(|_| component == FeedComponent::Todo || !task.completed);
+    if let (Some(reminder), Some(at)) = (alarm, at) {
+        w.line("BEGIN", "VALARM");
+        w.line("ACTION", "DISPLAY");
+        w.text("DESCRIPTION", &task.title);
+        w.line(&format!("TRIGGER{}", related), &duration(reminder - at));
+        w.line("END", "VALARM");
+    }
+
+    w.line("END", component_name);
+}
+
+/// What a CalDAV client sent in a VTODO, reduced to the task's columns. Everything else
+/// (categories, priority, client extensions) is dropped.
+#[derive(Debug)]
+pub struct Todo {
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub completed: bool,
+    pub due_at: Option<DateTime<Utc>>,
+    pub reminder: Option<DateTime<Utc>>,
+    pub recurrence: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct InvalidCalendar;
+
+// One unfolded content line
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    fn text(&self) -> String {
+        unescape(&self.value)
+    }
+}
+
+// Helper: Join folded lines back together
+fn unfold(body: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in body.split('\n').map(|l| l.trim_end_matches('\r')) {
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ if line.is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+// Helper: `NAME;PARAM=value;PARAM="quoted:value":VALUE`
+fn parse_line(line: &str) -> Option<Property> {
+    let mut in_quotes = false;
+    let mut parts: Vec<&str> = Vec::new();
+    let mut start = 0;
+    let mut colon = None;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => {
+                parts.push(&line[start..i]);
+                start = i + 1;
+            }
+            ':' if !in_quotes => {
+                parts.push(&line[start..i]);
+                colon = Some(i);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let colon = colon?;
+    let (name, params) = parts.split_first()?;
+    let params = params
+        .iter()
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.trim().to_uppercase(), v.trim().trim_matches('"').to_string()))
+        .collect();
+
+    Some(Property {
+        name: name.trim().to_uppercase(),
+        params,
+        value: line[colon + 1..].to_string(),
+    })
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+// Helper: DATE-TIME in UTC, with a TZID, or floating (taken in the user's zone). A bare
+// DATE means the end of that day.
+fn parse_time(prop: &Property, tz: Tz) -> Option<DateTime<Utc>> {
+    let value = prop.value.trim();
+
+    if prop.param("VALUE") == Some("DATE") || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        let end_of_day = NaiveTime::from_hms_opt(23, 59, 59)?;
+        return tz.from_local_datetime(&date.and_time(end_of_day)).earliest().map(|t| t.with_timezone(&Utc));
+    }
+
+    if let Some(value) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&naive));
+    }
+
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    // Some clients prefix the IANA name, e.g. `/mozilla.org/20050126_1/Europe/Berlin`
+    let zone = prop.param("TZID").and_then(|id| {
+        id.parse::<Tz>().ok().or_else(|| {
+            let segments: Vec<&str> = id.rsplitn(3, '/').collect();
+            match segments.as_slice() {
+                [city, region, ..] => format!("{}/{}", region, city).parse().ok(),
+                _ => None,
+            }
+        })
+    });
+    zone.unwrap_or(tz)
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+// Helper: DURATION such as `-PT15M`, `P1D` or `-P1W`. `None` when malformed or too
+// large to add to a date.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (negative, value) = match value.as_bytes().first()? {
+        b'-' => (true, &value[1..]),
+        b'+' => (false, &value[1..]),
+        _ => (false, value),
+    };
+    let value = value.strip_prefix('P')?;
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in value.chars() {
+        match c {
+            'T' => in_time = true,
+            '0'..='9' => number.push(c),
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                let part = match (unit, in_time) {
+                    ('W', false) => TimeDelta::try_weeks(n),
+                    ('D', false) => TimeDelta::try_days(n),
+                    ('H', true) => TimeDelta::try_hours(n),
+                    ('M', true) => TimeDelta::try_minutes(n),
+                    ('S', true) => TimeDelta::try_seconds(n),
+                    _ => return None,
+                };
+                total = total.checked_add(&part?)?;
+            }
+        }
+    }
+
+    if !number.is_empty() {
+        return None;
+    }
+    Some(if negative { -total } else { total })
+}
+
+/// Reads the first VTODO of a calendar object. Times without a zone are taken in `tz`,
+/// the first alarm becomes the reminder and unsupported RRULEs are dropped, as
+/// `/sync/push` does.
+pub fn parse_todo(body: &str, tz: Tz) -> Result<Todo, InvalidCalendar> {
+    let mut todo = Todo {
+        summary: None,
+        description: None,
+        completed: false,
+        due_at: None,
+        reminder: None,
+        recurrence: None,
+    };
+    let mut start = None;
+    let mut trigger: Option<Property> = None;
+    let mut components: Vec<String> = Vec::new();
+    let mut todos = 0;
+
+    for line in unfold(body) {
+        let prop = parse_line(&line).ok_or(InvalidCalendar)?;
+
+        match prop.name.as_str() {
+            "BEGIN" => {
+                let name = prop.value.trim().to_uppercase();
+                if name == "VTODO" {
+                    todos += 1;
+                }
+                components.push(name);
+                continue;
+            }
+            "END" => {
+                components.pop();
+                continue;
+            }
+            _ => {}
+        }
+
+        // Later VTODOs are overridden occurrences of a recurring task
+        if todos != 1 {
+            continue;
+        }
+
+        match (components.last().map(String::as_str), prop.name.as_str()) {
+            (Some("VTODO"), "SUMMARY") => todo.summary = Some(prop.text()),
+            (Some("VTODO"), "DESCRIPTION") => todo.description = Some(prop.text()),
+            (Some("VTODO"), "STATUS") => todo.completed |= prop.value.trim().eq_ignore_ascii_case("COMPLETED"),
+            (Some("VTODO"), "COMPLETED") => todo.completed = true,
+            (Some("VTODO"), "DUE") => todo.due_at = parse_time(&prop, tz),
+            (Some("VTODO"), "DTSTART") => start = parse_time(&prop, tz),
+            (Some("VTODO"), "RRULE") => todo.recurrence = recurrence::normalize(Some(&prop.value)).ok().flatten(),
+            (Some("VALARM"), "TRIGGER") if trigger.is_none() => trigger = Some(prop),
+            _ => {}
+        }
+    }
+
+    if todos == 0 {
+        return Err(InvalidCalendar);
+    }
+
+    todo.reminder = match trigger {
+        Some(trigger) if trigger.param("VALUE") == Some("DATE-TIME") => parse_time(&trigger, tz),
+        Some(trigger) => {
+            let offset = parse_duration(&trigger.value).ok_or(InvalidCalendar)?;
+            let anchor = match trigger.param("RELATED") {
+                Some("END") => todo.due_at.or(start),
+                _ => start.or(todo.due_at),
+            };
+            match anchor {
+                Some(anchor) => Some(anchor.checked_add_signed(offset).ok_or(InvalidCalendar)?),
+                None => None,
+            }
+        }
+        None => None,
+    };
+
+    Ok(todo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    fn task() -> Task {
+        Task {
+            id: "task-1".to_string(),
+            list_id: "list-1".to_string(),
+            title: "Pay rent; call Ana, then relax".to_string(),
+            description: Some("Line one\nLine two with a long tail that goes past the fold of seventy-five octets".to_string()),
+            completed: false,
+            reminder: Some(at("2026-03-05T11:45:00Z")),
+            due_at: Some(at("2026-03-05T12:00:00Z")),
+            recurrence: None,
+            created_at: at("2026-03-01T10:00:00Z"),
+            updated_at: at("2026-03-02T10:00:00Z"),
+            deleted_at: None,
+            completed_at: None,
+        }
+    }
+
+    fn parse(lines: &[&str]) -> Result<Todo, InvalidCalendar> {
+        let body = [&["BEGIN:VCALENDAR", "BEGIN:VTODO"], lines, &["END:VTODO", "END:VCALENDAR"]].concat().join("\r\n");
+        parse_todo(&body, Tz::America__Sao_Paulo)
+    }
+
+    #[test]
+    fn parses_durations() {
+        for (value, minutes) in [
+            ("-PT15M", -15),
+            ("PT1H30M", 90),
+            ("+P1D", 1440),
+            ("-P1W", -10080),
+            ("P1DT2H", 1560),
+            ("PT0S", 0),
+            ("PT90S", 1),
+        ] {
+            assert_eq!(parse_duration(value).map(|d| d.num_minutes()), Some(minutes), "{}", value);
+        }
+
+        for value in ["", "15M", "P1H", "PT1D", "P1X", "P1", "PT-5M", "-P99999999999W", "P99999999999999D", "PT9223372036854775807S"] {
+            assert_eq!(parse_duration(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn reads_a_vtodo() {
+        let todo = parse(&[
+            "UID:abc",
+            "SUMMARY:Pay rent\\; call Ana\\, then",
+            "  relax",
+            "DESCRIPTION:Line one\\nLine two",
+            "DUE;TZID=/mozilla.org/20050126_1/America/Sao_Paulo:20260305T090000",
+            "STATUS:COMPLETED",
+            "RRULE:FREQ=MONTHLY;BYMONTHDAY=5",
+            "BEGIN:VALARM",
+            "TRIGGER;RELATED=END:-PT15M",
+            "END:VALARM",
+        ])
+        .unwrap();
+
+        assert_eq!(todo.summary.as_deref(), Some("Pay rent; call Ana, then relax"));
+        assert_eq!(todo.description.as_deref(), Some("Line one\nLine two"));
+        assert_eq!(todo.due_at, Some(at("2026-03-05T12:00:00Z")));
+        assert_eq!(todo.reminder, Some(at("2026-03-05T11:45:00Z")));
+        assert_eq!(todo.recurrence.as_deref(), Some("FREQ=MONTHLY;BYMONTHDAY=5"));
+        assert!(todo.completed);
+    }
+
+    #[test]
+    fn reads_dates_and_absolute_triggers() {
+        // A bare date is the end of that day in the user's zone; floating times are in it too
+        let todo = parse(&[
+            "DUE;VALUE=DATE:20260305",
+            "DTSTART:20260304T080000",
+            "RRULE:FREQ=HOURLY",
+            "BEGIN:VALARM",
+            "TRIGGER;VALUE=DATE-TIME:20260304T100000Z",
+            "END:VALARM",
+        ])
+        .unwrap();
+
+        assert_eq!(todo.due_at, Some(at("2026-03-06T02:59:59Z")));
+        assert_eq!(todo.reminder, Some(at("2026-03-04T10:00:00Z")));
+        // Unsupported rules are dropped, as `/sync/push` does
+        assert_eq!(todo.recurrence, None);
+
+        // Relative to DTSTART by default; nothing to anchor to means no reminder
+        let todo = parse(&["DTSTART:20260304T120000Z", "BEGIN:VALARM", "TRIGGER:-P1D", "END:VALARM"]).unwrap();
+        assert_eq!(todo.reminder, Some(at("2026-03-03T12:00:00Z")));
+        let todo = parse(&["SUMMARY:No dates", "BEGIN:VALARM", "TRIGGER:-PT5M", "END:VALARM"]).unwrap();
+        assert_eq!(todo.reminder, None);
+    }
+
+    #[test]
+    fn rejects_invalid_calendars() {
+        assert!(parse_todo("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nEND:VEVENT\r\nEND:VCALENDAR", Tz::UTC).is_err());
+        assert!(parse_todo("not a calendar", Tz::UTC).is_err());
+
+        // Triggers that overflow a duration or a date are refused, not panicked on
+        for trigger in ["TRIGGER:-P99999999999W", "TRIGGER:P999999999D", "TRIGGER;RELATED=END:-P99999999D", "TRIGGER:P1Q"] {
+            let result = parse(&["DUE:20260305T120000Z", "BEGIN:VALARM", trigger, "END:VALARM"]);
+            assert!(result.is_err(), "{}", trigger);
+        }
+    }
+
+    #[test]
+    fn round_trips_a_task() {
+        let mut recurring = task();
+        recurring.recurrence = Some("FREQ=WEEKLY;BYDAY=MO,TH".to_string());
+        let mut completed = task();
+        completed.completed = true;
+        completed.completed_at = Some(at("2026-03-04T18:30:00Z"));
+        let mut reminder_only = task();
+        reminder_only.due_at = None;
+
+        for task in [task(), recurring, completed, reminder_only] {
+            let rendered = render_todo(&task, Tz::America__Sao_Paulo);
+            assert!(rendered.lines().all(|line| line.len() <= MAX_LINE_OCTETS + 1), "{}", rendered);
+
+            let todo = parse_todo(&rendered, Tz::UTC).unwrap();
+            assert_eq!(todo.summary.as_deref(), Some(task.title.as_str()));
+            assert_eq!(todo.description, task.description);
+            assert_eq!(todo.completed, task.completed);
+            assert_eq!(todo.due_at, task.due_at);
+            assert_eq!(todo.reminder, task.reminder);
+            assert_eq!(todo.recurrence, task.recurrence);
+        }
+    }
+
+    #[test]
+    fn writes_the_completion_time() {
+        let mut task = task();
+        task.completed = true;
+        task.completed_at = Some(at("2026-03-04T18:30:00Z"));
+        // Edited after it was completed
+        task.updated_at = at("2026-03-09T08:00:00Z");
+
+        assert!(render_todo(&task, Tz::UTC).contains("COMPLETED:20260304T183000Z\r\n"));
+    }
+
+    #[test]
+    fn renders_a_feed_of_dated_tasks() {
+        let mut undated = task();
+        undated.id = "task-2".to_string();
+        undated.due_at = None;
+        undated.reminder = None;
+        let mut done = task();
+        done.id = "task-3".to_string();
+        done.completed = true;
+        let tasks: Vec<FeedTask> = [task(), undated, done]
+            .into_iter()
+            .map(|task| FeedTask { task, list_name: "Home".to_string() })
+            .collect();
+        let feed = Feed { name: "MyTudo", tz: Tz::UTC, component: FeedComponent::Event };
+
+        let ics = render(&feed, &tasks);
+
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(!ics.contains("task-2@mytudo"));
+        assert!(ics.contains("CATEGORIES:Home"));
+        // Calendars would ring for the completed one, so only the open task has an alarm
+        assert_eq!(ics.matches("BEGIN:VALARM").count(), 1);
+        assert!(ics.contains("TRIGGER:-PT15M"));
+    }
+}
@@ -0,0 +1,900 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+use crate::handlers::settings::parse_user_datetime;
+use crate::i18n::Msg;
+use crate::models::{CsvColumns, ImportFormat, ImportIssue, ImportedList, ImportedTask, UserSettings};
+use crate::recurrence;
+
+const DEFAULT_COLOR: &str = "#3B82F6";
+const DEFAULT_LIST_NAME: &str = "Importadas";
+const MAX_NAME_CHARS: usize = 255;
+const MAX_TITLE_CHARS: usize = 500;
+
+/// Why a file could not be read at all. Problems with single tasks become `ImportIssue`s.
+#[derive(Debug)]
+pub enum ImportError {
+    Invalid,
+    MissingColumn(String),
+}
+
+/// Lists and tasks read from an export, for the preview or for `sync::apply_push`.
+pub struct Import {
+    pub lists: Vec<ImportedList>,
+    pub issues: Vec<ImportIssue>,
+}
+
+impl Import {
+    pub fn task_count(&self) -> usize {
+        self.lists.iter().map(|list| list.tasks.len()).sum()
+    }
+}
+
+pub fn parse(
+    format: ImportFormat,
+    data: &str,
+    list_name: Option<&str>,
+    columns: Option<&CsvColumns>,
+    user_id: &str,
+    settings: &UserSettings,
+) -> Result<Import, ImportError> {
+    let mut builder = Builder {
+        format,
+        user_id,
+        settings,
+        default_list: list_name
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(DEFAULT_LIST_NAME)
+            .to_string(),
+        lists: Vec::new(),
+        issues: Vec::new(),
+    };
+    let data = data.trim_start_matches('\u{feff}');
+
+    match format {
+        ImportFormat::Todoist => todoist(&mut builder, data)?,
+        ImportFormat::TodoistCsv => todoist_csv(&mut builder, data)?,
+        ImportFormat::MicrosoftTodo => microsoft_todo(&mut builder, data)?,
+        ImportFormat::Csv => generic_csv(&mut builder, data, columns.unwrap_or(&CsvColumns::default()))?,
+    }
+
+    Ok(Import {
+        lists: builder.lists,
+        issues: builder.issues,
+    })
+}
+
+struct Builder<'a> {
+    format: ImportFormat,
+    user_id: &'a str,
+    settings: &'a UserSettings,
+    default_list: String,
+    lists: Vec<ImportedList>,
+    issues: Vec<ImportIssue>,
+}
+
+impl Builder<'_> {
+    // Same user, format and source id give the same id, so importing again updates in place
+    fn id(&self, kind: &str, source_id: Option<&str>) -> String {
+        match source_id.filter(|s| !s.is_empty()) {
+            Some(source) => {
+                let key = format!("{}:{}:{}:{}", self.user_id, self.format.as_str(), kind, source);
+                uuid::Uuid::from_bytes(md5::compute(key).0).to_string()
+            }
+            None => uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    // Index of the list with this source id, added on first use
+    fn list(&mut self, source_id: &str, name: &str, color: Option<&str>) -> usize {
+        let id = self.id("list", Some(source_id));
+        if let Some(index) = self.lists.iter().position(|list| list.id == id) {
+            return index;
+        }
+
+        let name = match truncate(name.trim(), MAX_NAME_CHARS) {
+            name if name.is_empty() => self.default_list.clone(),
+            name => name,
+        };
+        self.lists.push(ImportedList {
+            id,
+            name,
+            color: color.unwrap_or(DEFAULT_COLOR).to_string(),
+            tasks: Vec::new(),
+        });
+        self.lists.len() - 1
+    }
+
+    fn default_list(&mut self) -> usize {
+        let name = self.default_list.clone();
+        self.list(&name, &name, None)
+    }
+
+    fn issue(&mut self, row: usize, field: &str, code: Msg, skipped: bool) {
+        self.issues.push(ImportIssue {
+            row,
+            field: field.to_string(),
+            code,
+            skipped,
+        });
+    }
+
+    // Dates that can't be read are dropped from the task and reported
+    fn date(&mut self, row: usize, field: &str, value: &str) -> Option<DateTime<Utc>> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        let date = parse_date(value, self.settings);
+        if date.is_none() {
+            self.issue(row, field, Msg::InvalidDate, false);
+        }
+        date
+    }
+
+    fn recurrence(&mut self, row: usize, rule: Option<String>) -> Option<String> {
+        if rule.is_none() {
+            self.issue(row, "recurrence", Msg::InvalidRecurrence, false);
+        }
+        rule
+    }
+
+    // Adds a task, or reports it as skipped when it has no title
+    fn push(&mut self, list: usize, row: usize, mut task: ImportedTask) {
+        task.title = truncate(task.title.trim(), MAX_TITLE_CHARS);
+        if task.title.is_empty() {
+            self.issue(row, "title", Msg::FieldRequired, true);
+            return;
+        }
+        task.description = task.description.map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+        self.lists[list].tasks.push(task);
+    }
+}
+
+fn truncate(value: &str, max: usize) -> String {
+    value.chars().take(max).collect()
+}
+
+// Helper: RFC 3339, or a local date/time in the user's time zone. Spreadsheets often put a
+// space between date and time.
+fn parse_date(value: &str, settings: &UserSettings) -> Option<DateTime<Utc>> {
+    parse_user_datetime(value, settings).or_else(|| parse_user_datetime(&value.replacen(' ', "T", 1), settings))
+}
+
+// Helper: Reader for a CSV file. Spreadsheets set to Portuguese save with `;`.
+fn csv_reader(data: &str) -> csv::Reader<&[u8]> {
+    let header = data.lines().next().unwrap_or_default();
+    let delimiter = if header.matches(';').count() > header.matches(',').count() { b';' } else { b',' };
+    csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(data.as_bytes())
+}
+
+fn column(headers: &csv::StringRecord, name: &str) -> Option<usize> {
+    headers.iter().position(|h| h.trim().eq_ignore_ascii_case(name))
+}
+
+// Helper: Todoist ids are strings today and were numbers in older exports
+fn id_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(s) => s,
+        other => other.to_string(),
+    })
+}
+
+// Helper: Todoist flags are booleans today and were 0/1 in older exports
+fn flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Bool(b) => b,
+        Value::Number(n) => n.as_i64() != Some(0),
+        _ => false,
+    })
+}
+
+#[derive(Deserialize)]
+struct TodoistExport {
+    #[serde(default)]
+    projects: Vec<TodoistProject>,
+    #[serde(default)]
+    items: Vec<TodoistItem>,
+    #[serde(default)]
+    reminders: Vec<TodoistReminder>,
+}
+
+#[derive(Deserialize)]
+struct TodoistProject {
+    #[serde(deserialize_with = "id_string")]
+    id: String,
+    name: String,
+    color: Option<String>,
+    #[serde(default, deserialize_with = "flag")]
+    is_deleted: bool,
+}
+
+#[derive(Deserialize)]
+struct TodoistItem {
+    #[serde(deserialize_with = "id_string")]
+    id: String,
+    #[serde(deserialize_with = "id_string")]
+    project_id: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default, deserialize_with = "flag")]
+    checked: bool,
+    #[serde(default, deserialize_with = "flag")]
+    is_deleted: bool,
+    added_at: Option<String>,
+    due: Option<TodoistDue>,
+}
+
+#[derive(Deserialize)]
+struct TodoistDue {
+    date: String,
+    #[serde(default)]
+    is_recurring: bool,
+    #[serde(default)]
+    string: String,
+}
+
+#[derive(Deserialize)]
+struct TodoistReminder {
+    #[serde(deserialize_with = "id_string")]
+    item_id: String,
+    due: Option<TodoistDue>,
+    minute_offset: Option<i64>,
+    #[serde(default, deserialize_with = "flag")]
+    is_deleted: bool,
+}
+
+// Helper: Hex for Todoist's named colors
+fn todoist_color(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "berry_red" => "#B8256F",
+        "red" => "#DB4035",
+        "orange" => "#FF9933",
+        "yellow" => "#FAD000",
+        "olive_green" => "#AFB83B",
+        "lime_green" => "#7ECC49",
+        "green" => "#299438",
+        "mint_green" => "#6ACCBC",
+        "teal" => "#158FAD",
+        "sky_blue" => "#14AAF5",
+        "light_blue" => "#96C3EB",
+        "blue" => "#4073FF",
+        "grape" => "#884DFF",
+        "violet" => "#AF38EB",
+        "lavender" => "#EB96EB",
+        "magenta" => "#E05194",
+        "salmon" => "#FF8D85",
+        "charcoal" => "#808080",
+        "grey" => "#B8B8B8",
+        "taupe" => "#CCAC93",
+        _ => return None,
+    })
+}
+
+// Helper: RRULE for Todoist's simpler recurring dates ("every day", "every 2 weeks",
+// "every mon, fri", "toda semana"). A time ("at 9am") is left to the due date.
+fn todoist_rrule(value: &str) -> Option<String> {
+    let value = value.trim().to_lowercase().replace("every!", "every");
+    let value = value.split(" at ").next()?.split(" às ").next()?.trim();
+
+    let rule = match value {
+        "daily" | "every day" | "todo dia" | "todos os dias" | "diariamente" => "FREQ=DAILY".to_string(),
+        "weekly" | "every week" | "toda semana" | "todas as semanas" | "semanalmente" => "FREQ=WEEKLY".to_string(),
+        "monthly" | "every month" | "todo mês" | "todos os meses" | "mensalmente" => "FREQ=MONTHLY".to_string(),
+        "yearly" | "annually" | "every year" | "todo ano" | "todos os anos" | "anualmente" => "FREQ=YEARLY".to_string(),
+        "every weekday" | "every workday" | "todo dia útil" | "todos os dias úteis" => {
+            "FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR".to_string()
+        }
+        _ => {
+            let rest = value.strip_prefix("every ")?;
+            let words: Vec<&str> = rest
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|w| !w.is_empty() && *w != "and")
+                .collect();
+
+            if let [count, unit] = words.as_slice() {
+                if let Ok(interval) = count.parse::<u32>() {
+                    let freq = match unit.trim_end_matches('s') {
+                        "day" => "DAILY",
+                        "week" => "WEEKLY",
+                        "month" => "MONTHLY",
+                        "year" => "YEARLY",
+                        _ => return None,
+                    };
+                    return recurrence::normalize(Some(&format!("FREQ={};INTERVAL={}", freq, interval))).ok().flatten();
+                }
+            }
+
+            let days = words
+                .iter()
+                .map(|w| match w.get(..3)? {
+                    "mon" => Some("MO"),
+                    "tue" => Some("TU"),
+                    "wed" => Some("WE"),
+                    "thu" => Some("TH"),
+                    "fri" => Some("FR"),
+                    "sat" => Some("SA"),
+                    "sun" => Some("SU"),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            format!("FREQ=WEEKLY;BYDAY={}", days.join(","))
+        }
+    };
+
+    recurrence::normalize(Some(&rule)).ok().flatten()
+}
+
+fn todoist(b: &mut Builder, data: &str) -> Result<(), ImportError> {
+    let export: TodoistExport = serde_json::from_str(data).map_err(|_| ImportError::Invalid)?;
+    if export.projects.is_empty() && export.items.is_empty() {
+        return Err(ImportError::Invalid);
+    }
+
+    let mut lists: HashMap<&str, usize> = HashMap::new();
+    for project in export.projects.iter().filter(|p| !p.is_deleted) {
+        let color = project.color.as_deref().and_then(todoist_color);
+        lists.insert(&project.id, b.list(&project.id, &project.name, color));
+    }
+
+    for (index, item) in export.items.iter().enumerate().filter(|(_, item)| !item.is_deleted) {
+        let row = index + 1;
+        let list = match lists.get(item.project_id.as_str()) {
+            Some(&list) => list,
+            None => b.default_list(),
+        };
+
+        let due_at = item.due.as_ref().and_then(|due| b.date(row, "due_at", &due.date));
+        let recurrence = match item.due.as_ref().filter(|due| due.is_recurring) {
+            Some(due) => b.recurrence(row, todoist_rrule(&due.string)),
+            None => None,
+        };
+        // First absolute reminder, or a relative one counted back from the due date. An
+        // offset too large for a date is reported like an unreadable one.
+        let mut out_of_range = false;
+        let reminder = export
+            .reminders
+            .iter()
+            .filter(|r| r.item_id == item.id && !r.is_deleted)
+            .find_map(|r| match (&r.due, r.minute_offset) {
+                (Some(due), _) => parse_date(&due.date, b.settings),
+                (None, Some(offset)) => {
+                    let at = due_at.map(|at| TimeDelta::try_minutes(offset).and_then(|d| at.checked_sub_signed(d)));
+                    out_of_range |= matches!(at, Some(None));
+                    at.flatten()
+                }
+                (None, None) => None,
+            });
+        if out_of_range && reminder.is_none() {
+            b.issue(row, "reminder", Msg::InvalidDate, false);
+        }
+
+        let task = ImportedTask {
+            id: b.id("task", Some(&item.id)),
+            title: item.content.clone(),
+            description: Some(item.description.clone()),
+            completed: item.checked,
+            due_at,
+            reminder,
+            recurrence,
+            created_at: item.added_at.as_deref().and_then(|at| at.parse().ok()),
+        };
+        b.push(list, row, task);
+    }
+
+    Ok(())
+}
+
+fn todoist_csv(b: &mut Builder, data: &str) -> Result<(), ImportError> {
+    let mut reader = csv_reader(data);
+    let headers = reader.headers().map_err(|_| ImportError::Invalid)?.clone();
+    let kind = column(&headers, "TYPE").ok_or_else(|| ImportError::MissingColumn("TYPE".to_string()))?;
+    let content = column(&headers, "CONTENT").ok_or_else(|| ImportError::MissingColumn("CONTENT".to_string()))?;
+    let description = column(&headers, "DESCRIPTION");
+    let date = column(&headers, "DATE");
+    let list = b.default_list();
+
+    for record in reader.records() {
+        let record = record.map_err(|_| ImportError::Invalid)?;
+        let row = record.position().map_or(0, |p| p.line() as usize);
+        let field = |column: Option<usize>| column.and_then(|c| record.get(c)).unwrap_or_default().trim();
+
+        // Sections and notes are not tasks
+        if !field(Some(kind)).eq_ignore_ascii_case("task") {
+            continue;
+        }
+
+        // DATE is whatever was typed in Todoist: a date, or a recurrence like "every monday"
+        let date = field(date);
+        let (due_at, recurrence) = match parse_date(date, b.settings) {
+            Some(at) => (Some(at), None),
+            None if date.is_empty() => (None, None),
+            None => match todoist_rrule(date) {
+                Some(rule) => (None, Some(rule)),
+                None => {
+                    b.issue(row, "due_at", Msg::InvalidDate, false);
+                    (None, None)
+                }
+            },
+        };
+
+        let task = ImportedTask {
+            id: b.id("task", None),
+            title: field(Some(content)).to_string(),
+            description: Some(field(description).to_string()),
+            completed: false,
+            due_at,
+            reminder: None,
+            recurrence,
+            created_at: None,
+        };
+        b.push(list, row, task);
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MicrosoftExport {
+    Lists(Vec<MicrosoftList>),
+    Wrapped {
+        #[serde(alias = "value")]
+        lists: Vec<MicrosoftList>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MicrosoftList {
+    id: Option<String>,
+    display_name: String,
+    #[serde(default)]
+    tasks: Vec<MicrosoftTask>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MicrosoftTask {
+    id: Option<String>,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    status: String,
+    body: Option<MicrosoftBody>,
+    due_date_time: Option<MicrosoftDateTime>,
+    reminder_date_time: Option<MicrosoftDateTime>,
+    #[serde(default)]
+    is_reminder_on: bool,
+    created_date_time: Option<String>,
+    recurrence: Option<MicrosoftRecurrence>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MicrosoftBody {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    content_type: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MicrosoftDateTime {
+    date_time: String,
+    #[serde(default)]
+    time_zone: String,
+}
+
+#[derive(Deserialize)]
+struct MicrosoftRecurrence {
+    pattern: MicrosoftPattern,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MicrosoftPattern {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    interval: u32,
+    #[serde(default)]
+    days_of_week: Vec<String>,
+    #[serde(default)]
+    day_of_month: u32,
+}
+
+impl MicrosoftDateTime {
+    // Graph sends local time with seven fractional digits, plus the zone it is in
+    fn local(&self) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(&self.date_time, "%Y-%m-%dT%H:%M:%S%.f").ok()
+    }
+
+    fn instant(&self, fallback: Tz) -> Option<DateTime<Utc>> {
+        let tz: Tz = self.time_zone.parse().unwrap_or(fallback);
+        let at = tz.from_local_datetime(&self.local()?).earliest()?;
+        Some(at.with_timezone(&Utc))
+    }
+}
+
+impl MicrosoftPattern {
+    fn rrule(&self) -> Option<String> {
+        let freq = match self.kind.as_str() {
+            "daily" => "DAILY",
+            "weekly" => "WEEKLY",
+            "absoluteMonthly" | "relativeMonthly" => "MONTHLY",
+            "absoluteYearly" | "relativeYearly" => "YEARLY",
+            _ => return None,
+        };
+
+        let mut rule = format!("FREQ={}", freq);
+        if self.interval > 1 {
+            rule.push_str(&format!(";INTERVAL={}", self.interval));
+        }
+        if freq == "WEEKLY" && !self.days_of_week.is_empty() {
+            let days: Vec<String> = self
+                .days_of_week
+                .iter()
+                .map(|d| d.chars().take(2).collect::<String>().to_uppercase())
+                .collect();
+            rule.push_str(&format!(";BYDAY={}", days.join(",")));
+        }
+        if self.kind == "absoluteMonthly" && self.day_of_month > 0 {
+            rule.push_str(&format!(";BYMONTHDAY={}", self.day_of_month));
+        }
+
+        recurrence::normalize(Some(&rule)).ok().flatten()
+    }
+}
+
+// Helper: Text of an HTML note
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&nbsp;", " ").replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&")
+}
+
+fn microsoft_todo(b: &mut Builder, data: &str) -> Result<(), ImportError> {
+    let lists = match serde_json::from_str(data).map_err(|_| ImportError::Invalid)? {
+        MicrosoftExport::Lists(lists) | MicrosoftExport::Wrapped { lists } => lists,
+    };
+    if lists.is_empty() {
+        return Err(ImportError::Invalid);
+    }
+
+    let mut row = 0;
+    for source in &lists {
+        let list = b.list(source.id.as_deref().unwrap_or(&source.display_name), &source.display_name, None);
+
+        for item in &source.tasks {
+            row += 1;
+
+            // Due dates are whole days; they get the user's default time like dates sent to /tasks
+            let due_at = match &item.due_date_time {
+                Some(due) => {
+                    let at = due.local().and_then(|at| {
+                        parse_user_datetime(&at.date().format("%Y-%m-%d").to_string(), b.settings)
+                    });
+                    if at.is_none() {
+                        b.issue(row, "due_at", Msg::InvalidDate, false);
+                    }
+                    at
+                }
+                None => None,
+            };
+            let reminder = match item.reminder_date_time.as_ref().filter(|_| item.is_reminder_on) {
+                Some(reminder) => {
+                    let at = reminder.instant(b.settings.tz());
+                    if at.is_none() {
+                        b.issue(row, "reminder", Msg::InvalidDate, false);
+                    }
+                    at
+                }
+                None => None,
+            };
+            let recurrence = match &item.recurrence {
+                Some(recurrence) => b.recurrence(row, recurrence.pattern.rrule()),
+                None => None,
+            };
+            let description = item.body.as_ref().map(|body| {
+                if body.content_type.eq_ignore_ascii_case("html") {
+                    strip_tags(&body.content)
+                } else {
+                    body.content.clone()
+                }
+            });
+
+            let task = ImportedTask {
+                id: b.id("task", item.id.as_deref()),
+                title: item.title.clone(),
+                description,
+                completed: item.status == "completed",
+                due_at,
+                reminder,
+                recurrence,
+                created_at: item.created_date_time.as_deref().and_then(|at| at.parse().ok()),
+            };
+            b.push(list, row, task);
+        }
+    }
+
+    Ok(())
+}
+
+fn generic_csv(b: &mut Builder, data: &str, columns: &CsvColumns) -> Result<(), ImportError> {
+    let mut reader = csv_reader(data);
+    let headers = reader.headers().map_err(|_| ImportError::Invalid)?.clone();
+
+    // A column named in `columns` must exist; the defaults are optional, except the title
+    let find = |configured: &Option<String>, default: &str, required: bool| {
+        let name = configured.as_deref().map(str::trim).unwrap_or(default);
+        match column(&headers, name) {
+            Some(index) => Ok(Some(index)),
+            None if configured.is_some() || required => Err(ImportError::MissingColumn(name.to_string())),
+            None => Ok(None),
+        }
+    };
+    let title = find(&columns.title, "title", true)?;
+    let description = find(&columns.description, "description", false)?;
+    let completed = find(&columns.completed, "completed", false)?;
+    let due_at = find(&columns.due_at, "due_at", false)?;
+    let reminder = find(&columns.reminder, "reminder", false)?;
+    let recurrence = find(&columns.recurrence, "recurrence", false)?;
+    let list_name = find(&columns.list, "list", false)?;
+    let id = find(&columns.id, "id", false)?;
+
+    for record in reader.records() {
+        let record = record.map_err(|_| ImportError::Invalid)?;
+        let row = record.position().map_or(0, |p| p.line() as usize);
+        let field = |column: Option<usize>| column.and_then(|c| record.get(c)).unwrap_or_default().trim();
+
+        let list = match field(list_name) {
+            "" => b.default_list(),
+            name => b.list(name, name, None),
+        };
+        let done = matches!(
+            field(completed).to_lowercase().as_str(),
+            "1" | "true" | "yes" | "y" | "x" | "sim" | "s"
+        );
+        let rule = match field(recurrence) {
+            "" => None,
+            rule => b.recurrence(row, recurrence::normalize(Some(rule)).ok().flatten()),
+        };
+
+        let task = ImportedTask {
+            id: b.id("task", Some(field(id))),
+            title: field(title).to_string(),
+            description: Some(field(description).to_string()),
+            completed: done,
+            due_at: b.date(row, "due_at", field(due_at)),
+            reminder: b.date(row, "reminder", field(reminder)),
+            recurrence: rule,
+            created_at: None,
+        };
+        b.push(list, row, task);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(format: ImportFormat, data: &str, columns: Option<&CsvColumns>) -> Result<Import, ImportError> {
+        parse(format, data, None, columns, "user-1", &UserSettings::defaults())
+    }
+
+    // List, title, completed, due date, reminder and recurrence of a task
+    type Row<'a> = (&'a str, &'a str, bool, Option<String>, Option<String>, Option<&'a str>);
+
+    fn tasks(import: &Import) -> Vec<Row<'_>> {
+        let minute = |at: Option<DateTime<Utc>>| at.map(|at| at.format("%Y-%m-%d %H:%M").to_string());
+        import
+            .lists
+            .iter()
+            .flat_map(|list| {
+                list.tasks.iter().map(move |task| {
+                    (
+                        list.name.as_str(),
+                        task.title.as_str(),
+                        task.completed,
+                        minute(task.due_at),
+                        minute(task.reminder),
+                        task.recurrence.as_deref(),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn issues(import: &Import) -> Vec<(usize, &str, Msg, bool)> {
+        import.issues.iter().map(|i| (i.row, i.field.as_str(), i.code, i.skipped)).collect()
+    }
+
+    fn at(value: &str) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    #[test]
+    fn reads_todoist_exports() {
+        let data = r#"{
+            "projects": [
+                {"id": "p1", "name": "Work", "color": "red"},
+                {"id": 2, "name": "Old", "is_deleted": 1}
+            ],
+            "items": [
+                {"id": "1", "project_id": "p1", "content": "Pay rent", "checked": true,
+                 "due": {"date": "2030-03-05"}},
+                {"id": "2", "project_id": "p1", "content": "Standup", "checked": 0,
+                 "due": {"date": "2030-03-06T10:00:00", "is_recurring": true, "string": "every weekday at 10am"}},
+                {"id": "3", "project_id": "p1", "content": "Far off", "due": {"date": "2030-03-07"}},
+                {"id": "4", "project_id": "p1", "content": "Gone", "is_deleted": true},
+                {"id": "5", "project_id": 2, "content": "Orphan", "due": {"date": "someday"}},
+                {"id": "6", "project_id": "p1", "content": "  "}
+            ],
+            "reminders": [
+                {"item_id": "1", "minute_offset": 30},
+                {"item_id": "2", "due": {"date": "2030-03-06T08:00:00"}},
+                {"item_id": "3", "minute_offset": 9223372036854775807},
+                {"item_id": "5", "minute_offset": 10}
+            ]
+        }"#;
+        let import = read(ImportFormat::Todoist, data, None).unwrap();
+
+        assert_eq!(
+            tasks(&import),
+            [
+                ("Work", "Pay rent", true, at("2030-03-05 09:00"), at("2030-03-05 08:30"), None),
+                ("Work", "Standup", false, at("2030-03-06 10:00"), at("2030-03-06 08:00"), Some("FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR")),
+                ("Work", "Far off", false, at("2030-03-07 09:00"), None, None),
+                ("Importadas", "Orphan", false, None, None, None),
+            ]
+        );
+        assert_eq!(
+            issues(&import),
+            [
+                (3, "reminder", Msg::InvalidDate, false),
+                (5, "due_at", Msg::InvalidDate, false),
+                (6, "title", Msg::FieldRequired, true),
+            ]
+        );
+        assert_eq!(import.lists[0].color, "#DB4035");
+        assert_eq!(import.task_count(), 4);
+        // Importing the same file again gives the same ids
+        let again = read(ImportFormat::Todoist, data, None).unwrap();
+        assert_eq!(again.lists[0].tasks[0].id, import.lists[0].tasks[0].id);
+    }
+
+    #[test]
+    fn reads_todoist_csv_exports() {
+        let data = "TYPE,CONTENT,DESCRIPTION,PRIORITY,DATE\n\
+                    section,Morning,,,\n\
+                    task,Buy milk,Two liters,4,2030-03-05\n\
+                    task,Stretch,,1,every day\n\
+                    task,Call mom,,1,someday\n\
+                    note,Remember the list,,,\n";
+        let import = read(ImportFormat::TodoistCsv, data, None).unwrap();
+
+        assert_eq!(
+            tasks(&import),
+            [
+                ("Importadas", "Buy milk", false, at("2030-03-05 09:00"), None, None),
+                ("Importadas", "Stretch", false, None, None, Some("FREQ=DAILY")),
+                ("Importadas", "Call mom", false, None, None, None),
+            ]
+        );
+        assert_eq!(issues(&import), [(5, "due_at", Msg::InvalidDate, false)]);
+        assert_eq!(import.lists[0].tasks[0].description.as_deref(), Some("Two liters"));
+
+        assert!(matches!(
+            read(ImportFormat::TodoistCsv, "TYPE,TITLE\ntask,Buy milk\n", None),
+            Err(ImportError::MissingColumn(column)) if column == "CONTENT"
+        ));
+    }
+
+    #[test]
+    fn reads_microsoft_todo_exports() {
+        let data = r#"{"value": [{
+            "id": "l1",
+            "displayName": "Groceries",
+            "tasks": [
+                {"id": "t1", "title": "Milk", "status": "completed",
+                 "body": {"content": "<p>Two&nbsp;liters</p>", "contentType": "html"},
+                 "dueDateTime": {"dateTime": "2030-03-05T00:00:00.0000000", "timeZone": "UTC"},
+                 "reminderDateTime": {"dateTime": "2030-03-05T07:30:00.0000000", "timeZone": "America/Sao_Paulo"},
+                 "isReminderOn": true},
+                {"id": "t2", "title": "Bread", "status": "notStarted",
+                 "reminderDateTime": {"dateTime": "2030-03-05T07:30:00.0000000", "timeZone": "UTC"},
+                 "isReminderOn": false,
+                 "recurrence": {"pattern": {"type": "weekly", "interval": 2, "daysOfWeek": ["monday", "thursday"]}}},
+                {"id": "t3", "title": "Eggs",
+                 "reminderDateTime": {"dateTime": "tomorrow", "timeZone": "UTC"},
+                 "isReminderOn": true,
+                 "recurrence": {"pattern": {"type": "hourly"}}}
+            ]
+        }]}"#;
+        let import = read(ImportFormat::MicrosoftTodo, data, None).unwrap();
+
+        assert_eq!(
+            tasks(&import),
+            [
+                ("Groceries", "Milk", true, at("2030-03-05 09:00"), at("2030-03-05 10:30"), None),
+                ("Groceries", "Bread", false, None, None, Some("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH")),
+                ("Groceries", "Eggs", false, None, None, None),
+            ]
+        );
+        assert_eq!(
+            issues(&import),
+            [(3, "reminder", Msg::InvalidDate, false), (3, "recurrence", Msg::InvalidRecurrence, false)]
+        );
+        assert_eq!(import.lists[0].tasks[0].description.as_deref(), Some("Two liters"));
+
+        assert!(matches!(read(ImportFormat::MicrosoftTodo, "[]", None), Err(ImportError::Invalid)));
+    }
+
+    #[test]
+    fn maps_csv_columns() {
+        let columns = CsvColumns {
+            title: Some("Tarefa".to_string()),
+            completed: Some("Feita".to_string()),
+            due_at: Some("Prazo".to_string()),
+            reminder: Some("Lembrete".to_string()),
+            list: Some("Lista".to_string()),
+            ..CsvColumns::default()
+        };
+        let data = "Tarefa;Feita;Prazo;Lembrete;Lista\n\
+                    Pagar conta;sim;2030-03-05 18:00;2030-03-05 17:00;Casa\n\
+                    Ler;;amanhã;;\n\
+                    ;x;;;Casa\n";
+        let import = read(ImportFormat::Csv, data, Some(&columns)).unwrap();
+
+        assert_eq!(
+            tasks(&import),
+            [
+                ("Casa", "Pagar conta", true, at("2030-03-05 18:00"), at("2030-03-05 17:00"), None),
+                ("Importadas", "Ler", false, None, None, None),
+            ]
+        );
+        assert_eq!(
+            issues(&import),
+            [(3, "due_at", Msg::InvalidDate, false), (4, "title", Msg::FieldRequired, true)]
+        );
+
+        // Without a mapping each column is named after its field
+        let import = read(ImportFormat::Csv, "title,completed,recurrence\nRun,true,FREQ=DAILY\n", None).unwrap();
+        assert_eq!(tasks(&import), [("Importadas", "Run", true, None, None, Some("FREQ=DAILY"))]);
+
+        let missing = CsvColumns {
+            due_at: Some("Quando".to_string()),
+            ..CsvColumns::default()
+        };
+        assert!(matches!(
+            read(ImportFormat::Csv, "title\nRun\n", Some(&missing)),
+            Err(ImportError::MissingColumn(column)) if column == "Quando"
+        ));
+        assert!(matches!(
+            read(ImportFormat::Csv, "name\nRun\n", None),
+            Err(ImportError::MissingColumn(column)) if column == "title"
+        ));
+    }
+}
@@ -0,0 +1,283 @@
+pub mod smtp;
+
+use chrono::Utc;
+use mail_parser::{MessageParser, MimeHeaders};
+use sqlx::MySqlPool;
+
+use crate::errors::ApiError;
+use crate::events::{self, EventHub};
+use crate::handlers::settings::load_settings;
+use crate::i18n::{Locale, Msg};
+use crate::models::InboundAddress;
+
+/// Length of the random part of an address, in hex characters.
+pub const TOKEN_LEN: usize = 32;
+
+// Column limits of `tasks` and `task_attachments`
+const MAX_TITLE: usize = 500;
+const MAX_DESCRIPTION: usize = 16_000;
+const MAX_FILENAME: usize = 255;
+// Anything past this is dropped, not refused: the task itself still matters
+const MAX_ATTACHMENTS: usize = 20;
+
+// Prefixes mail clients put on forwarded messages
+const FORWARD_PREFIXES: [&str; 4] = ["fwd:", "fw:", "enc:", "tr:"];
+
+/// A file attached to an inbound email.
+pub struct Attachment {
+    pub filename: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// The parts of an RFC 5322 message that make up a task.
+pub struct Email {
+    pub subject: Option<String>,
+    pub body: Option<String>,
+    pub attachments: Vec<Attachment>,
+    /// `To`, `Cc` and `Delivered-To`, for uploads that do not name the recipient
+    pub recipients: Vec<String>,
+}
+
+/// What a delivered email became.
+pub struct Delivered {
+    pub task_id: String,
+    pub list_id: String,
+    pub attachments: usize,
+}
+
+pub fn parse(raw: &[u8]) -> Option<Email> {
+    let message = MessageParser::default().parse(raw)?;
+
+    let mut recipients: Vec<String> = message
+        .to()
+        .into_iter()
+        .chain(message.cc())
+        .flat_map(|address| address.iter())
+        .filter_map(|addr| addr.address())
+        .map(str::to_string)
+        .collect();
+    if let Some(delivered_to) = message.header_raw("Delivered-To") {
+        recipients.push(delivered_to.trim().to_string());
+    }
+
+    let attachments = message
+        .attachments()
+        .take(MAX_ATTACHMENTS)
+        .enumerate()
+        .map(|(i, part)| {
+            let content_type = part
+                .content_type()
+                .map(|ct| match ct.subtype() {
+                    Some(subtype) => format!("{}/{}", ct.ctype(), subtype),
+                    None => ct.ctype().to_string(),
+                })
+                .unwrap_or_else(|| "application/octet-stream".to_string());
+            let filename = match part.attachment_name() {
+                Some(name) if !name.trim().is_empty() => name.trim().chars().take(MAX_FILENAME).collect(),
+                _ if part.is_message() => format!("message-{}.eml", i + 1),
+                _ => format!("attachment-{}", i + 1),
+            };
+            Attachment {
+                filename,
+                content_type: content_type.to_lowercase(),
+                data: part.contents().to_vec(),
+            }
+        })
+        .collect();
+
+    Some(Email {
+        subject: message.subject().map(str::to_string),
+        body: message.body_text(0).map(|b| b.into_owned()),
+        attachments,
+        recipients,
+    })
+}
+
+/// The token of `recipient`: the whole local part, or what follows a `+`, so both
+/// `<token>@domain` and `me+<token>@domain` work. The domain is not checked.
+pub fn token_of(recipient: &str) -> Option<String> {
+    let address = recipient.trim().trim_start_matches('<').trim_end_matches('>');
+    let (local, _) = address.rsplit_once('@')?;
+    let token = local.rsplit('+').next()?.to_lowercase();
+
+    (token.len() == TOKEN_LEN && token.bytes().all(|b| b.is_ascii_hexdigit())).then_some(token)
+}
+
+/// The address behind the first recipient that names one.
+pub async fn find_address<S: AsRef<str>>(
+    pool: &MySqlPool,
+    recipients: &[S],
+) -> Result<Option<InboundAddress>, sqlx::Error> {
+    for token in recipients.iter().filter_map(|r| token_of(r.as_ref())) {
+        let address: Option<InboundAddress> = sqlx::query_as(
+            "SELECT id, user_id, list_id, token, created_at, last_used_at FROM inbound_addresses WHERE token = ?"
+        )
+        .bind(&token)
+        .fetch_optional(pool)
+        .await?;
+
+        if address.is_some() {
+            return Ok(address);
+        }
+    }
+
+    Ok(None)
+}
+
+// Helper: Subject without the "Fwd:" a forward adds, possibly several times
+fn title_of(subject: &str) -> String {
+    let mut title = subject.trim();
+    while let Some(prefix) = FORWARD_PREFIXES
+        .iter()
+        .find(|p| title.get(..p.len()).is_some_and(|start| start.eq_ignore_ascii_case(p)))
+    {
+        title = title[prefix.len()..].trim_start();
+    }
+    title.chars().take(MAX_TITLE).collect()
+}
+
+/// Creates the task for `email` in the list of `address`, with its attachments.
+pub async fn deliver(
+    pool: &MySqlPool,
+    events: &EventHub,
+    address: &InboundAddress,
+    email: &Email,
+) -> Result<Delivered, ApiError> {
+    // Account addresses go to the oldest list, which is usually the default one
+    let list_id: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM todo_lists
+        WHERE user_id = ? AND deleted_at IS NULL AND (? IS NULL OR id = ?)
+        ORDER BY created_at
+        LIMIT 1
+        "#
+    )
+    .bind(&address.user_id)
+    .bind(&address.list_id)
+    .bind(&address.list_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    let list_id = list_id.ok_or_else(|| ApiError::not_found(Msg::ListNotFound))?;
+
+    let body = email.body.as_deref().map(str::trim).filter(|b| !b.is_empty());
+    let title = match email.subject.as_deref().map(title_of).filter(|t| !t.is_empty()) {
+        Some(title) => title,
+        None => match body.and_then(|b| b.lines().next()) {
+            Some(line) => line.chars().take(MAX_TITLE).collect(),
+            None => {
+                let settings = load_settings(pool, &address.user_id).await?;
+                Msg::InboundNoSubject.text(Locale::from_tag(&settings.locale).unwrap_or_default()).to_string()
+            }
+        },
+    };
+    let description: Option<String> = body.map(|b| b.chars().take(MAX_DESCRIPTION).collect());
+
+    let task_id = uuid::Uuid::new_v4().to_string();
+    let now = Utc::now();
+
+    let mut tx = pool.begin().await
+        .map_err(|e| ApiError::internal(format!("Transaction error: {}", e)))?;
+
+    sqlx::query(
+        "INSERT INTO tasks (id, list_id, title, description, completed, created_at) VALUES (?, ?, ?, ?, 0, ?)"
+    )
+    .bind(&task_id)
+    .bind(&list_id)
+    .bind(&title)
+    .bind(&description)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    for attachment in &email.attachments {
+        sqlx::query(
+            r#"
+            INSERT INTO task_attachments (id, task_id, filename, content_type, size, data, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&task_id)
+        .bind(&attachment.filename)
+        .bind(&attachment.content_type)
+        .bind(attachment.data.len() as u32)
+        .bind(&attachment.data)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+    }
+
+    sqlx::query("UPDATE inbound_addresses SET last_used_at = ? WHERE id = ?")
+        .bind(now)
+        .bind(&address.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    tx.commit().await
+        .map_err(|e| ApiError::internal(format!("Commit error: {}", e)))?;
+
+    events::log_sync(pool, events, None, &address.user_id, "task", &task_id, "create").await;
+
+    Ok(Delivered {
+        task_id,
+        list_id,
+        attachments: email.attachments.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::raw_email;
+
+    const TOKEN: &str = "0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn finds_the_token_in_the_local_part() {
+        assert_eq!(token_of(&format!("{}@in.example.com", TOKEN)).as_deref(), Some(TOKEN));
+        assert_eq!(token_of(&format!("<me+{}@in.example.com>", TOKEN.to_uppercase())).as_deref(), Some(TOKEN));
+        assert_eq!(token_of("someone@example.com"), None);
+        assert_eq!(token_of(&format!("{}@in.example.com", &TOKEN[1..])), None);
+        assert_eq!(token_of(TOKEN), None);
+    }
+
+    #[test]
+    fn strips_forward_prefixes_from_the_title() {
+        assert_eq!(title_of("Fwd: FW: Pay rent"), "Pay rent");
+        assert_eq!(title_of("  Tr:Enc: Pagar aluguel "), "Pagar aluguel");
+        assert_eq!(title_of("Forward planning"), "Forward planning");
+        assert_eq!(title_of(&"x".repeat(MAX_TITLE + 10)).len(), MAX_TITLE);
+    }
+
+    #[test]
+    fn reads_recipients_from_to_cc_and_delivered_to() {
+        let raw = b"Delivered-To: c@example.com\r\nFrom: s@example.com\r\nTo: A <a@example.com>\r\nCc: b@example.com\r\nSubject: Hi\r\n\r\nBody\r\n";
+        let email = parse(raw).unwrap();
+
+        assert_eq!(email.recipients, ["a@example.com", "b@example.com", "c@example.com"]);
+        assert_eq!(email.subject.as_deref(), Some("Hi"));
+        assert!(email.attachments.is_empty());
+    }
+
+    #[test]
+    fn keeps_at_most_the_attachment_limit() {
+        let email = parse(&raw_email("a@example.com", "Files", MAX_ATTACHMENTS + 5)).unwrap();
+
+        assert_eq!(email.attachments.len(), MAX_ATTACHMENTS);
+        assert_eq!(email.attachments[0].filename, "note-1.txt");
+        assert_eq!(email.attachments[0].content_type, "text/plain");
+        assert_eq!(email.attachments[0].data, b"note 1");
+        assert_eq!(email.body.as_deref().map(str::trim), Some("First line\r\nSecond line"));
+    }
+
+    #[test]
+    fn refuses_what_is_not_an_email() {
+        assert!(parse(b"").is_none());
+    }
+}
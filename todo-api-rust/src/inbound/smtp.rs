@@ -0,0 +1,415 @@
+use std::time::Duration;
+
+use sqlx::MySqlPool;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::{deliver, find_address, parse};
+use crate::config::Config;
+use crate::events::EventHub;
+use crate::models::InboundAddress;
+
+// RFC 5321 allows 512 for commands and 1000 for text lines; some clients go past that
+const MAX_LINE: u64 = 4096;
+const MAX_RECIPIENTS: usize = 50;
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Minimal SMTP server that turns messages for known addresses into tasks. It never
+/// relays, so it only accepts recipients that are inbound addresses. No TLS or AUTH:
+/// meant to sit behind the MTA that receives the domain's mail, or to test locally.
+pub async fn run(pool: MySqlPool, config: Config, events: EventHub) {
+    let Some(listen) = config.inbound_smtp_listen.clone() else {
+        return;
+    };
+
+    let listener = match TcpListener::bind(&listen).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!(error = %e, %listen, "Failed to start the inbound SMTP listener");
+            return;
+        }
+    };
+    tracing::info!(%listen, "Inbound SMTP listener started");
+
+    accept(listener, pool, config, events).await;
+}
+
+// Helper: One session per connection, until the process stops
+async fn accept(listener: TcpListener, pool: MySqlPool, config: Config, events: EventHub) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                let (pool, config, events) = (pool.clone(), config.clone(), events.clone());
+                actix_web::rt::spawn(async move {
+                    if let Err(e) = Session::new(&pool, &config, &events).serve(stream).await {
+                        tracing::debug!(error = %e, %peer, "Inbound SMTP session ended");
+                    }
+                });
+            }
+            Err(e) => tracing::warn!(error = %e, "Inbound SMTP accept failed"),
+        }
+    }
+}
+
+struct Session<'a> {
+    pool: &'a MySqlPool,
+    config: &'a Config,
+    events: &'a EventHub,
+    mail_from: bool,
+    recipients: Vec<InboundAddress>,
+}
+
+// What the DATA section turned out to be
+enum Data {
+    Message(Vec<u8>),
+    TooLarge,
+}
+
+impl<'a> Session<'a> {
+    fn new(pool: &'a MySqlPool, config: &'a Config, events: &'a EventHub) -> Self {
+        Self {
+            pool,
+            config,
+            events,
+            mail_from: false,
+            recipients: Vec::new(),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.mail_from = false;
+        self.recipients.clear();
+    }
+
+    async fn serve(mut self, stream: TcpStream) -> std::io::Result<()> {
+        let (read, mut write) = stream.into_split();
+        let mut reader = BufReader::new(read);
+        let domain = self.config.inbound_email_domain.clone();
+
+        write.write_all(format!("220 {} ESMTP MyTudo\r\n", domain).as_bytes()).await?;
+
+        loop {
+            let Some(line) = read_line(&mut reader).await? else {
+                return Ok(());
+            };
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end();
+            let (verb, arg) = line.split_once(' ').unwrap_or((line, ""));
+
+            let reply = match verb.to_ascii_uppercase().as_str() {
+                "EHLO" => {
+                    self.reset();
+                    format!("250-{}\r\n250-SIZE {}\r\n250-8BITMIME\r\n250 SMTPUTF8", domain, self.config.inbound_email_max_bytes)
+                }
+                "HELO" => {
+                    self.reset();
+                    format!("250 {}", domain)
+                }
+                "MAIL" => self.mail(arg),
+                "RCPT" => self.rcpt(arg).await,
+                "DATA" if !self.mail_from || self.recipients.is_empty() => "503 5.5.1 Need MAIL and RCPT first".to_string(),
+                "DATA" => {
+                    write.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n").await?;
+                    let data = read_data(&mut reader, self.config.inbound_email_max_bytes).await?;
+                    let reply = self.data(data).await;
+                    self.reset();
+                    reply
+                }
+                "RSET" => {
+                    self.reset();
+                    "250 2.0.0 OK".to_string()
+                }
+                "NOOP" => "250 2.0.0 OK".to_string(),
+                "VRFY" => "252 2.1.5 Send some mail, I'll try my best".to_string(),
+                "QUIT" => {
+                    write.write_all(b"221 2.0.0 Bye\r\n").await?;
+                    return Ok(());
+                }
+                _ => "502 5.5.2 Command not recognized".to_string(),
+            };
+
+            write.write_all(reply.as_bytes()).await?;
+            write.write_all(b"\r\n").await?;
+        }
+    }
+
+    fn mail(&mut self, arg: &str) -> String {
+        if !arg.to_ascii_uppercase().starts_with("FROM:") {
+            return "501 5.5.4 Syntax: MAIL FROM:<address>".to_string();
+        }
+
+        // SIZE=n lets clients learn about the limit before sending everything
+        let size = arg
+            .split_whitespace()
+            .filter_map(|param| param.to_ascii_uppercase().strip_prefix("SIZE=")?.parse::<usize>().ok())
+            .next();
+        if size.is_some_and(|size| size > self.config.inbound_email_max_bytes) {
+            return "552 5.3.4 Message too large".to_string();
+        }
+
+        self.reset();
+        self.mail_from = true;
+        "250 2.1.0 OK".to_string()
+    }
+
+    async fn rcpt(&mut self, arg: &str) -> String {
+        if !self.mail_from {
+            return "503 5.5.1 Need MAIL first".to_string();
+        }
+        let Some(recipient) = arg.get(..3).filter(|to| to.eq_ignore_ascii_case("TO:")).map(|_| arg[3..].trim()) else {
+            return "501 5.5.4 Syntax: RCPT TO:<address>".to_string();
+        };
+        let recipient = recipient.split_whitespace().next().unwrap_or_default();
+
+        if self.recipients.len() >= MAX_RECIPIENTS {
+            return "452 4.5.3 Too many recipients".to_string();
+        }
+
+        match find_address(self.pool, &[recipient]).await {
+            Ok(Some(address)) => {
+                if !self.recipients.iter().any(|a| a.id == address.id) {
+                    self.recipients.push(address);
+                }
+                "250 2.1.5 OK".to_string()
+            }
+            Ok(None) => "550 5.1.1 No such mailbox".to_string(),
+            Err(e) => {
+                tracing::error!(error = %e, "Inbound address lookup failed");
+                "451 4.3.0 Temporary failure, try again later".to_string()
+            }
+        }
+    }
+
+    async fn data(&self, data: Data) -> String {
+        let raw = match data {
+            Data::Message(raw) => raw,
+            Data::TooLarge => return "552 5.3.4 Message too large".to_string(),
+        };
+        let Some(email) = parse(&raw) else {
+            return "554 5.6.0 Unreadable message".to_string();
+        };
+
+        let mut created = Vec::new();
+        for address in &self.recipients {
+            match deliver(self.pool, self.events, address, &email).await {
+                Ok(delivered) => created.push(delivered.task_id),
+                // The sender retries, so tasks made for earlier recipients may be made twice
+                Err(e) if e.status.is_server_error() => {
+                    tracing::error!(error = %e.message, "Inbound email delivery failed");
+                    return "451 4.3.0 Temporary failure, try again later".to_string();
+                }
+                Err(e) => return format!("554 5.1.1 {}", e.message),
+            }
+        }
+
+        tracing::info!(tasks = created.len(), "Inbound email delivered");
+        format!("250 2.0.0 OK {}", created.join(" "))
+    }
+}
+
+// Helper: One CRLF-terminated line, or `None` when the client hung up or went quiet
+async fn read_line<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> std::io::Result<Option<Vec<u8>>> {
+    let mut line = Vec::new();
+    match tokio::time::timeout(IDLE_TIMEOUT, (&mut *reader).take(MAX_LINE).read_until(b'\n', &mut line)).await {
+        Ok(Ok(0)) | Err(_) => Ok(None),
+        Ok(Ok(_)) => Ok(Some(line)),
+        Ok(Err(e)) => Err(e),
+    }
+}
+
+// Helper: The message up to the lone ".", with dot-stuffing removed. Past `max` bytes
+// the rest is read and dropped so the session can go on.
+async fn read_data<R: AsyncBufReadExt + Unpin>(reader: &mut R, max: usize) -> std::io::Result<Data> {
+    let mut message = Vec::new();
+    let mut too_large = false;
+    // Over-long lines come in pieces; only the first piece can hold the dot
+    let mut line_start = true;
+
+    loop {
+        let Some(chunk) = read_line(reader).await? else {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        };
+        if line_start && (chunk == b".\r\n" || chunk == b".\n") {
+            break;
+        }
+
+        let line = if line_start { chunk.strip_prefix(b".").unwrap_or(&chunk) } else { &chunk };
+        line_start = chunk.ends_with(b"\n");
+        if message.len() + line.len() > max {
+            too_large = true;
+        } else if !too_large {
+            message.extend_from_slice(line);
+        }
+    }
+
+    Ok(if too_large { Data::TooLarge } else { Data::Message(message) })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+
+    use super::*;
+    use crate::test_support::{self, lazy_pool, raw_email};
+
+    // Serves sessions on a free local port
+    async fn start(pool: MySqlPool, config: Config) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        actix_web::rt::spawn(accept(listener, pool, config, EventHub::default()));
+        addr
+    }
+
+    // Stands in for the MTA that hands mail over
+    struct Client {
+        reader: BufReader<OwnedReadHalf>,
+        writer: OwnedWriteHalf,
+    }
+
+    impl Client {
+        async fn connect(addr: SocketAddr) -> (Self, String) {
+            let (read, writer) = TcpStream::connect(addr).await.unwrap().into_split();
+            let mut client = Self { reader: BufReader::new(read), writer };
+            let greeting = client.reply().await;
+            (client, greeting)
+        }
+
+        // Last line of the reply; the others end their code with '-'
+        async fn reply(&mut self) -> String {
+            loop {
+                let mut line = String::new();
+                self.reader.read_line(&mut line).await.unwrap();
+                if line.as_bytes().get(3) != Some(&b'-') {
+                    return line.trim_end().to_string();
+                }
+            }
+        }
+
+        async fn send(&mut self, command: &str) -> String {
+            self.writer.write_all(format!("{}\r\n", command).as_bytes()).await.unwrap();
+            self.reply().await
+        }
+
+        async fn send_data(&mut self, raw: &[u8]) -> String {
+            assert!(self.send("DATA").await.starts_with("354"));
+            self.writer.write_all(raw).await.unwrap();
+            self.writer.write_all(b".\r\n").await.unwrap();
+            self.reply().await
+        }
+    }
+
+    #[actix_web::test]
+    async fn reads_dot_stuffed_data() {
+        let mut input: &[u8] = b"Subject: Hi\r\n\r\n..leading dot\r\n.\r\nNOOP\r\n";
+        let Data::Message(message) = read_data(&mut input, 1024).await.unwrap() else {
+            panic!("message refused");
+        };
+
+        assert_eq!(message, b"Subject: Hi\r\n\r\n.leading dot\r\n");
+        assert_eq!(input, b"NOOP\r\n");
+    }
+
+    #[actix_web::test]
+    async fn drops_data_past_the_limit() {
+        let mut input: &[u8] = b"Subject: Hi\r\n\r\nlong enough\r\n.\r\nNOOP\r\n";
+
+        assert!(matches!(read_data(&mut input, 16).await.unwrap(), Data::TooLarge));
+        // The whole message was consumed, so the session can go on
+        assert_eq!(input, b"NOOP\r\n");
+    }
+
+    // Rejections that come before any query, so the pool never connects
+    #[actix_web::test]
+    async fn rejects_commands_out_of_order() {
+        let config = Config {
+            inbound_email_max_bytes: 1024,
+            ..Config::for_tests()
+        };
+        let addr = start(lazy_pool(&config), config).await;
+        let (mut client, greeting) = Client::connect(addr).await;
+
+        assert!(greeting.starts_with("220 "));
+        assert_eq!(client.send("EHLO mta.example.com").await, "250 SMTPUTF8");
+        assert!(client.send("RCPT TO:<someone@example.com>").await.starts_with("503"));
+        assert!(client.send("DATA").await.starts_with("503"));
+        assert!(client.send("MAIL <sender@example.com>").await.starts_with("501"));
+        assert!(client.send("MAIL FROM:<sender@example.com> SIZE=2048").await.starts_with("552"));
+        assert!(client.send("MAIL FROM:<sender@example.com> SIZE=512").await.starts_with("250"));
+        assert!(client.send("RCPT <someone@example.com>").await.starts_with("501"));
+        // Not an inbound address: refused without a lookup, the server never relays
+        assert!(client.send("RCPT TO:<someone@example.com>").await.starts_with("550"));
+        assert!(client.send("DATA").await.starts_with("503"));
+        assert!(client.send("STARTTLS").await.starts_with("502"));
+        assert!(client.send("QUIT").await.starts_with("221"));
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn delivers_to_known_addresses() {
+        let (pool, config) = test_support::database().await;
+        let (user_id, _) = test_support::create_user(&pool, "smtp", "!", true).await;
+        let list_id = test_support::create_list(&pool, &user_id, "Inbox").await;
+        let token = test_support::create_inbound_address(&pool, &user_id, Some(&list_id)).await;
+        let to = format!("me+{}@{}", token, config.inbound_email_domain);
+
+        let addr = start(pool.clone(), config.clone()).await;
+        let (mut client, _) = Client::connect(addr).await;
+        client.send("HELO mta.example.com").await;
+        client.send("MAIL FROM:<sender@example.com>").await;
+        let unknown = client.send(&format!("RCPT TO:<{}@{}>", "f".repeat(32), config.inbound_email_domain)).await;
+        let known = client.send(&format!("RCPT TO:<{}>", to)).await;
+        let delivered = client.send_data(&raw_email(&to, "Fwd: Pay rent", 2)).await;
+
+        let tasks: Vec<(String, String)> = sqlx::query_as("SELECT id, title FROM tasks WHERE list_id = ?")
+            .bind(&list_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        let (attachments,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM task_attachments a JOIN tasks t ON a.task_id = t.id WHERE t.list_id = ?"
+        )
+        .bind(&list_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        test_support::delete_user(&pool, &user_id).await;
+
+        assert!(unknown.starts_with("550"));
+        assert!(known.starts_with("250"));
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(delivered, format!("250 2.0.0 OK {}", tasks[0].0));
+        assert_eq!(tasks[0].1, "Pay rent");
+        assert_eq!(attachments, 2);
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn refuses_messages_over_the_limit() {
+        let (pool, mut config) = test_support::database().await;
+        config.inbound_email_max_bytes = 512;
+        let (user_id, _) = test_support::create_user(&pool, "smtp-limit", "!", true).await;
+        let list_id = test_support::create_list(&pool, &user_id, "Inbox").await;
+        let token = test_support::create_inbound_address(&pool, &user_id, None).await;
+        let to = format!("{}@{}", token, config.inbound_email_domain);
+
+        let addr = start(pool.clone(), config).await;
+        let (mut client, _) = Client::connect(addr).await;
+        client.send("MAIL FROM:<sender@example.com>").await;
+        client.send(&format!("RCPT TO:<{}>", to)).await;
+        let refused = client.send_data(&raw_email(&to, "Too big", 20)).await;
+        let after = client.send("NOOP").await;
+
+        let (tasks,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM tasks WHERE list_id = ?")
+            .bind(&list_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        test_support::delete_user(&pool, &user_id).await;
+
+        assert!(refused.starts_with("552"));
+        assert!(after.starts_with("250"));
+        assert_eq!(tasks, 0);
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::LazyLock;
+
+use chrono::Utc;
+use sqlx::MySqlPool;
+use minijinja::Value;
+use tokio::sync::Notify;
+
+use crate::config::Config;
+use crate::errors::ApiError;
+use crate::i18n::Locale;
+
+pub mod templates;
+pub mod transport;
+pub mod worker;
+
+pub use templates::RenderedEmail;
+pub use transport::TransportKind;
+
+// Wakes the worker so new mail doesn't wait for the next poll
+static WAKE: LazyLock<Notify> = LazyLock::new(Notify::new);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MailKind {
+    Verification,
+    PasswordReset,
+    EmailChange,
+    EmailChanged,
+    Reminder,
+    Invitation,
+    Digest,
+}
+
+impl MailKind {
+    #[cfg(test)]
+    pub const ALL: [MailKind; 7] = [
+        MailKind::Verification,
+        MailKind::PasswordReset,
+        MailKind::EmailChange,
+        MailKind::EmailChanged,
+        MailKind::Reminder,
+        MailKind::Invitation,
+        MailKind::Digest,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MailKind::Verification => "verification",
+            MailKind::PasswordReset => "password_reset",
+            MailKind::EmailChange => "email_change",
+            MailKind::EmailChanged => "email_changed",
+            MailKind::Reminder => "reminder",
+            MailKind::Invitation => "invitation",
+            MailKind::Digest => "digest",
+        }
+    }
+}
+
+/// Queues a message in `mail_outbox`; the worker delivers it in the background.
+pub async fn enqueue(
+    pool: &MySqlPool,
+    kind: MailKind,
+    recipient: &str,
+    email: &RenderedEmail,
+) -> Result<(), ApiError> {
+    let now = Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO mail_outbox (kind, recipient, subject, html_body, text_body, status, next_attempt_at, created_at)
+        VALUES (?, ?, ?, ?, ?, 'pending', ?, ?)
+        "#
+    )
+    .bind(kind.as_str())
+    .bind(recipient)
+    .bind(&email.subject)
+    .bind(&email.html)
+    .bind(&email.text)
+    .bind(now)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
+
+    WAKE.notify_one();
+    Ok(())
+}
+
+/// Renders the templates for `kind` and queues the result.
+pub async fn send(
+    pool: &MySqlPool,
+    config: &Config,
+    kind: MailKind,
+    locale: Locale,
+    recipient: &str,
+    context: Value,
+) -> Result<(), ApiError> {
+    let email = templates::render(config, kind, locale, context)?;
+    enqueue(pool, kind, recipient, &email).await
+}
@@ -0,0 +1,244 @@
+use std::path::{Path, PathBuf};
+
+use minijinja::value::Kwargs;
+use minijinja::{AutoEscape, Environment, Error, ErrorKind, HtmlEscape, State, Value};
+
+use super::MailKind;
+use crate::config::Config;
+use crate::errors::ApiError;
+use crate::i18n::{Locale, Msg};
+
+// Built-in copies of templates/email, so the binary works without the directory on disk
+const EMBEDDED: &[(&str, &str)] = &[
+    ("layout.html", include_str!("../../templates/email/layout.html")),
+    ("layout.txt", include_str!("../../templates/email/layout.txt")),
+    ("verification.subject.txt", include_str!("../../templates/email/verification.subject.txt")),
+    ("verification.html", include_str!("../../templates/email/verification.html")),
+    ("verification.txt", include_str!("../../templates/email/verification.txt")),
+    ("password_reset.subject.txt", include_str!("../../templates/email/password_reset.subject.txt")),
+    ("password_reset.html", include_str!("../../templates/email/password_reset.html")),
+    ("password_reset.txt", include_str!("../../templates/email/password_reset.txt")),
+    ("email_change.subject.txt", include_str!("../../templates/email/email_change.subject.txt")),
+    ("email_change.html", include_str!("../../templates/email/email_change.html")),
+    ("email_change.txt", include_str!("../../templates/email/email_change.txt")),
+    ("email_changed.subject.txt", include_str!("../../templates/email/email_changed.subject.txt")),
+    ("email_changed.html", include_str!("../../templates/email/email_changed.html")),
+    ("email_changed.txt", include_str!("../../templates/email/email_changed.txt")),
+    ("invitation.subject.txt", include_str!("../../templates/email/invitation.subject.txt")),
+    ("invitation.html", include_str!("../../templates/email/invitation.html")),
+    ("invitation.txt", include_str!("../../templates/email/invitation.txt")),
+    ("digest.subject.txt", include_str!("../../templates/email/digest.subject.txt")),
+    ("digest.html", include_str!("../../templates/email/digest.html")),
+    ("digest.txt", include_str!("../../templates/email/digest.txt")),
+    ("reminder.subject.txt", include_str!("../../templates/email/reminder.subject.txt")),
+    ("reminder.html", include_str!("../../templates/email/reminder.html")),
+    ("reminder.txt", include_str!("../../templates/email/reminder.txt")),
+    ("pages/revert_email_change.html", include_str!("../../templates/pages/revert_email_change.html")),
+];
+
+/// Subject and both bodies of one message, ready for the outbox.
+pub struct RenderedEmail {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+// Helper: MAIL_TEMPLATES_DIR wins over the embedded copy, file by file
+fn load(dir: Option<&Path>, name: &str) -> Result<Option<String>, Error> {
+    if let Some(dir) = dir {
+        if !name.contains("..") && !name.starts_with('/') {
+            let path = dir.join(name);
+            if path.is_file() {
+                return std::fs::read_to_string(&path).map(Some).map_err(|e| {
+                    Error::new(ErrorKind::InvalidOperation, format!("cannot read {}", path.display()))
+                        .with_source(e)
+                });
+            }
+        }
+    }
+
+    Ok(EMBEDDED
+        .iter()
+        .find(|(embedded, _)| *embedded == name)
+        .map(|(_, source)| source.to_string()))
+}
+
+// Helper: Catalog texts carry inline markup such as `<strong>`, which text parts drop
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+// `t("msg_code", name=...)` looks a message up in the catalog for the template's `locale`.
+// Arguments are escaped in HTML templates, since catalog texts themselves are trusted.
+fn translate(state: &State, code: &str, kwargs: Kwargs) -> Result<Value, Error> {
+    let msg = Msg::from_code(code).ok_or_else(|| {
+        Error::new(ErrorKind::InvalidOperation, format!("unknown message code '{}'", code))
+    })?;
+    let locale = state
+        .lookup("locale")
+        .and_then(|tag| tag.as_str().and_then(Locale::from_tag))
+        .unwrap_or_default();
+    let html = !matches!(state.auto_escape(), AutoEscape::None);
+
+    let mut text = if html { msg.text(locale).to_string() } else { strip_tags(msg.text(locale)) };
+    for name in kwargs.args() {
+        let value = kwargs.get::<Value>(name)?.to_string();
+        let value = if html { HtmlEscape(&value).to_string() } else { value };
+        text = text.replace(&format!("{{{}}}", name), &value);
+    }
+
+    Ok(if html { Value::from_safe_string(text) } else { Value::from(text) })
+}
+
+// Helper: `verification.en.html`, then `verification.html`
+fn render_part(env: &Environment, kind: MailKind, locale: Locale, ext: &str, context: &Value) -> Result<String, Error> {
+    let localized = format!("{}.{}.{}", kind.as_str(), locale.tag(), ext);
+    let template = match env.get_template(&localized) {
+        Ok(template) => template,
+        Err(e) if e.kind() == ErrorKind::TemplateNotFound => {
+            env.get_template(&format!("{}.{}", kind.as_str(), ext))?
+        }
+        Err(e) => return Err(e),
+    };
+    template.render(context)
+}
+
+// Helper: Environment over MAIL_TEMPLATES_DIR and the embedded copies, with `t()`
+fn environment(config: &Config) -> Environment<'static> {
+    let dir: Option<PathBuf> = config.mail_templates_dir.as_ref().map(PathBuf::from);
+
+    let mut env = Environment::new();
+    env.set_loader(move |name| load(dir.as_deref(), name));
+    env.add_function("t", translate);
+    env
+}
+
+/// Renders `{kind}.subject.txt`, `{kind}.html` and `{kind}.txt`, preferring a
+/// `{kind}.{locale}.*` variant when one exists. `locale` is added to the context.
+pub fn render(config: &Config, kind: MailKind, locale: Locale, context: Value) -> Result<RenderedEmail, ApiError> {
+    let env = environment(config);
+    let context = minijinja::context! { locale => locale.tag(), ..context };
+    let part = |ext: &str| {
+        render_part(&env, kind, locale, ext, &context)
+            .map_err(|e| ApiError::internal(format!("Template error: {:#}", e)))
+    };
+
+    Ok(RenderedEmail {
+        subject: part("subject.txt")?.trim().to_string(),
+        html: part("html")?,
+        text: part("txt")?,
+    })
+}
+
+/// Renders `pages/{name}.html`, a page opened from a link in an email. It shares the
+/// email layout, so MAIL_TEMPLATES_DIR can restyle both.
+pub fn render_page(config: &Config, name: &str, locale: Locale, context: Value) -> Result<String, ApiError> {
+    let context = minijinja::context! { locale => locale.tag(), ..context };
+    environment(config)
+        .get_template(&format!("pages/{}.html", name))
+        .and_then(|template| template.render(context))
+        .map_err(|e| ApiError::internal(format!("Template error: {:#}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCALES: [Locale; 2] = [Locale::PtBr, Locale::En];
+
+    // Every variable any template reads, with markup that must not survive unescaped
+    fn context() -> Value {
+        minijinja::context! {
+            name => "Ana <b>",
+            inviter => "Ana <b>",
+            code => "123456",
+            email => "ana@example.com",
+            link => "https://example.com/confirm?token=abc",
+            revert_link => "https://example.com/revert?token=abc",
+            title => "Pay <rent>",
+            list => "Home",
+            due => "2026-10-19 09:00",
+            overdue => vec![minijinja::context! { title => "Pay <rent>", list => "Home" }],
+            due_today => vec![minijinja::context! { title => "Call <mom>", list => "" }],
+        }
+    }
+
+    // Helper: A catalog placeholder such as `{name}` that was never filled in
+    fn leftover_placeholder(part: &str) -> Option<&str> {
+        part.match_indices('{').find_map(|(start, _)| {
+            let rest = &part[start + 1..];
+            let end = rest.find('}')?;
+            let name = &rest[..end];
+            (!name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c == '_')).then_some(name)
+        })
+    }
+
+    #[test]
+    fn renders_every_kind_in_every_locale() {
+        let config = Config::for_tests();
+        for kind in MailKind::ALL {
+            for locale in LOCALES {
+                let email = render(&config, kind, locale, context())
+                    .unwrap_or_else(|e| panic!("{} in {}: {}", kind.as_str(), locale.tag(), e.message));
+                let label = format!("{} in {}", kind.as_str(), locale.tag());
+
+                assert!(!email.subject.is_empty(), "{}: empty subject", label);
+                for part in [&email.subject, &email.html, &email.text] {
+                    assert_eq!(leftover_placeholder(part), None, "{}: unfilled placeholder", label);
+                    assert!(!part.contains("{{") && !part.contains("{%"), "{}: unrendered template syntax", label);
+                }
+                assert!(email.text.contains("Ana <b>"), "{}: text lost the name", label);
+                assert!(email.html.contains("Ana &lt;b&gt;"), "{}: name not escaped in HTML", label);
+                assert!(!email.html.contains("<b>"), "{}: raw markup in HTML", label);
+            }
+        }
+    }
+
+    #[test]
+    fn texts_follow_the_locale() {
+        let config = Config::for_tests();
+        let pt = render(&config, MailKind::Reminder, Locale::PtBr, context()).unwrap();
+        let en = render(&config, MailKind::Reminder, Locale::En, context()).unwrap();
+
+        assert_eq!(pt.subject, "Lembrete: Pay <rent>");
+        assert_eq!(en.subject, "Reminder: Pay <rent>");
+        assert!(en.html.contains("Pay &lt;rent&gt;"));
+        assert!(!en.text.contains("<strong>"));
+    }
+
+    #[test]
+    fn renders_the_revert_page_in_every_state() {
+        let config = Config::for_tests();
+        for locale in LOCALES {
+            let confirm = minijinja::context! {
+                email => "ana@example.com",
+                token => "abc\"><script>",
+                action => "https://api.example.com/auth/revert-email-change",
+            };
+            let page = render_page(&config, "revert_email_change", locale, confirm).unwrap();
+            // minijinja also escapes `/` in HTML, which browsers read back as is
+            assert!(page.contains(r#"<form method="post" action="https:&#x2f;&#x2f;api.example.com&#x2f;auth&#x2f;revert-email-change">"#));
+            assert!(page.contains("abc&quot;&gt;&lt;script&gt;"), "token not escaped");
+            assert!(page.contains(Msg::EmailChangedButton.text(locale)));
+            assert_eq!(leftover_placeholder(&page), None);
+
+            let done = render_page(&config, "revert_email_change", locale, minijinja::context! { done => true, email => "ana@example.com" }).unwrap();
+            assert!(!done.contains("<form"));
+            assert!(done.contains("<strong>ana@example.com</strong>"));
+
+            let error = render_page(&config, "revert_email_change", locale, minijinja::context! { error => Msg::InvalidRevertLink.text(locale) }).unwrap();
+            assert!(!error.contains("<form"));
+            assert!(error.contains(Msg::InvalidRevertLink.text(locale)));
+        }
+    }
+}
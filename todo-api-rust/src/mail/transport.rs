@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::config::Config;
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Where outgoing mail goes. `file` and `stdout` are for development.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportKind {
+    Smtp,
+    File,
+    Stdout,
+}
+
+impl FromStr for TransportKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "smtp" => Ok(Self::Smtp),
+            "file" => Ok(Self::File),
+            "stdout" => Ok(Self::Stdout),
+            _ => Err(()),
+        }
+    }
+}
+
+pub enum Transport {
+    Smtp(AsyncSmtpTransport<Tokio1Executor>),
+    File(AsyncFileTransport<Tokio1Executor>),
+    Stdout,
+}
+
+impl Transport {
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        match config.mail_transport {
+            TransportKind::Smtp => {
+                let credentials = Credentials::new(config.smtp_user.clone(), config.smtp_pass.clone());
+                let mailer = AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host)
+                    .map_err(|e| e.to_string())?
+                    .port(config.smtp_port)
+                    .credentials(credentials)
+                    .timeout(Some(SMTP_TIMEOUT))
+                    .build();
+                Ok(Self::Smtp(mailer))
+            }
+            TransportKind::File => {
+                let dir = PathBuf::from(&config.mail_dir);
+                std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+                Ok(Self::File(AsyncFileTransport::new(dir)))
+            }
+            TransportKind::Stdout => Ok(Self::Stdout),
+        }
+    }
+
+    pub async fn send(&self, message: &Message) -> Result<(), String> {
+        match self {
+            Self::Smtp(mailer) => mailer.send(message.clone()).await.map(|_| ()).map_err(|e| e.to_string()),
+            Self::File(mailer) => mailer.send(message.clone()).await.map(|_| ()).map_err(|e| e.to_string()),
+            Self::Stdout => {
+                println!("{}", String::from_utf8_lossy(&message.formatted()));
+                Ok(())
+            }
+        }
+    }
+}
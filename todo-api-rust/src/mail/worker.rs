@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+use lettre::message::{header::ContentType, Mailbox, MultiPart};
+use lettre::Message;
+use sqlx::{FromRow, MySqlPool};
+
+use super::transport::Transport;
+use super::WAKE;
+use crate::config::Config;
+use crate::logging::mask_email;
+use crate::metrics::METRICS;
+use crate::outbox::{self, backoff, Queue};
+
+const MAX_ATTEMPTS: u32 = 6;
+
+#[derive(Debug, FromRow)]
+struct OutboxMessage {
+    id: u64,
+    kind: String,
+    recipient: String,
+    subject: String,
+    html_body: String,
+    text_body: Option<String>,
+    attempts: u32,
+}
+
+enum Outcome {
+    Sent,
+    Retry { at: DateTime<Utc>, error: String },
+    Failed { error: String },
+}
+
+/// Delivers `mail_outbox` until the process exits. Runs alongside the HTTP server.
+pub async fn run(pool: MySqlPool, config: Config) {
+    let transport = match Transport::from_config(&config) {
+        Ok(transport) => transport,
+        Err(e) => {
+            tracing::error!(error = %e, "Mail transport unavailable, outbox will not be delivered");
+            return;
+        }
+    };
+    let from: Mailbox = config
+        .smtp_from
+        .parse()
+        .unwrap_or_else(|_| "noreply@localhost".parse().unwrap());
+
+    tracing::info!(transport = ?config.mail_transport, "Mail worker started");
+
+    outbox::drain(Queue::Mail, &WAKE, || deliver_batch(&pool, &transport, &from)).await
+}
+
+async fn deliver_batch(pool: &MySqlPool, transport: &Transport, from: &Mailbox) -> Result<usize, sqlx::Error> {
+    let token = outbox::claim(pool, Queue::Mail, Utc::now()).await?;
+
+    let messages: Vec<OutboxMessage> = sqlx::query_as(
+        r#"
+        SELECT id, kind, recipient, subject, html_body, text_body, attempts
+        FROM mail_outbox
+        WHERE claim_token = ?
+        ORDER BY id
+        "#
+    )
+    .bind(&token)
+    .fetch_all(pool)
+    .await?;
+
+    for message in &messages {
+        let result = deliver(transport, from, message).await;
+        record(pool, message, result).await?;
+    }
+
+    Ok(messages.len())
+}
+
+async fn deliver(transport: &Transport, from: &Mailbox, message: &OutboxMessage) -> Result<(), (String, bool)> {
+    let to: Mailbox = message
+        .recipient
+        .parse()
+        .map_err(|e: lettre::address::AddressError| (e.to_string(), false))?;
+
+    let builder = Message::builder()
+        .from(from.clone())
+        .to(to)
+        .subject(&message.subject);
+
+    // Rows queued before text bodies existed are sent as HTML only
+    let email = match message.text_body.as_deref().filter(|text| !text.is_empty()) {
+        Some(text) => builder.multipart(MultiPart::alternative_plain_html(
+            text.to_string(),
+            message.html_body.clone(),
+        )),
+        None => builder
+            .header(ContentType::TEXT_HTML)
+            .body(message.html_body.clone()),
+    }
+    .map_err(|e| (e.to_string(), false))?;
+
+    transport.send(&email).await.map_err(|e| (e, true))
+}
+
+// Helper: Persist the result of one attempt. Errors flagged as not retryable (bad address,
+// unbuildable message) fail immediately.
+async fn record(
+    pool: &MySqlPool,
+    message: &OutboxMessage,
+    result: Result<(), (String, bool)>,
+) -> Result<(), sqlx::Error> {
+    let attempts = message.attempts + 1;
+    let now = Utc::now();
+
+    let outcome = match result {
+        Ok(()) => Outcome::Sent,
+        Err((error, retryable)) if retryable && attempts < MAX_ATTEMPTS => Outcome::Retry {
+            at: now + backoff(attempts),
+            error,
+        },
+        Err((error, _)) => Outcome::Failed { error },
+    };
+
+    match outcome {
+        Outcome::Sent => {
+            METRICS.email(&message.kind, "sent");
+            tracing::info!(id = message.id, kind = %message.kind, to = %mask_email(&message.recipient), "Email sent");
+
+            // The body may hold one-time codes, so it is not kept once delivered
+            sqlx::query(
+                r#"
+                UPDATE mail_outbox
+                SET status = 'sent', attempts = ?, sent_at = ?, html_body = '', text_body = NULL, last_error = NULL, claim_token = NULL
+                WHERE id = ?
+                "#
+            )
+            .bind(attempts)
+            .bind(now)
+            .bind(message.id)
+            .execute(pool)
+            .await?;
+        }
+        Outcome::Retry { at, error } => {
+            METRICS.email(&message.kind, "retry");
+            tracing::warn!(id = message.id, kind = %message.kind, attempts, error = %error, "Email failed, will retry");
+
+            sqlx::query(
+                r#"
+                UPDATE mail_outbox
+                SET status = 'pending', attempts = ?, next_attempt_at = ?, last_error = ?, claim_token = NULL
+                WHERE id = ?
+                "#
+            )
+            .bind(attempts)
+            .bind(at)
+            .bind(&error)
+            .bind(message.id)
+            .execute(pool)
+            .await?;
+        }
+        Outcome::Failed { error } => {
+            METRICS.email(&message.kind, "failed");
+            tracing::error!(id = message.id, kind = %message.kind, attempts, error = %error, "Email failed permanently");
+
+            sqlx::query(
+                r#"
+                UPDATE mail_outbox
+                SET status = 'failed', attempts = ?, last_error = ?, claim_token = NULL
+                WHERE id = ?
+                "#
+            )
+            .bind(attempts)
+            .bind(&error)
+            .bind(message.id)
+            .execute(pool)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
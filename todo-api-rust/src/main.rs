@@ -1,13 +1,38 @@
 use actix_cors::Cors;
-use actix_web::{web, App, HttpServer, middleware::Logger};
+use actix_web::{web, App, HttpServer, middleware::from_fn};
 use sqlx::mysql::MySqlPoolOptions;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
+mod caldav;
 mod config;
-mod db;
+mod digest;
 mod errors;
+mod events;
+mod export;
 mod handlers;
+mod i18n;
+mod ics;
+mod import;
+mod inbound;
+mod logging;
+mod mail;
+mod metrics;
 mod middleware;
 mod models;
+mod oidc;
+mod openapi;
+mod outbox;
+mod password;
+mod purge;
+mod push;
+mod quick_add;
+mod recurrence;
+mod reminders;
+mod webhooks;
+
+#[cfg(test)]
+mod test_support;
 
 use config::Config;
 
@@ -16,11 +41,12 @@ async fn main() -> std::io::Result<()> {
     // Load .env file
     dotenvy::dotenv().ok();
     
-    // Initialize logger
-    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info"));
-    
     // Load configuration
     let config = Config::from_env();
+
+    // Initialize logger
+    logging::init(config.log_format);
+    password::init(&config);
     
     // Create database pool
     let pool = MySqlPoolOptions::new()
@@ -29,12 +55,33 @@ async fn main() -> std::io::Result<()> {
         .await
         .expect("Failed to create database pool");
     
-    log::info!("✅ Connected to database");
+    tracing::info!("Connected to database");
     
     let host = config.host.clone();
     let port = config.port;
     
-    log::info!("🚀 Starting server at http://{}:{}", host, port);
+    tracing::info!(%host, port, "Starting server");
+
+    // Deliver queued emails in the background
+    actix_web::rt::spawn(mail::worker::run(pool.clone(), config.clone()));
+    // Deliver queued webhooks in the background
+    actix_web::rt::spawn(webhooks::worker::run(pool.clone(), config.clone()));
+    // Send due reminders by push, email and webhook (REMINDER_CHANNELS)
+    actix_web::rt::spawn(reminders::run(pool.clone(), config.clone()));
+    // Send the daily digest to users who turned it on
+    actix_web::rt::spawn(digest::run(pool.clone(), config.clone()));
+    // Remove accounts once their deletion grace period ends
+    actix_web::rt::spawn(purge::run(pool.clone()));
+
+    // Shared by all workers so discovery documents are cached once
+    let oidc = web::Data::new(oidc::Oidc::default());
+    // One hub for all workers, so every open /events stream sees every change
+    let event_hub = web::Data::new(events::EventHub::default());
+
+    // Turn emails sent to inbound addresses into tasks (only with INBOUND_SMTP_LISTEN)
+    actix_web::rt::spawn(inbound::smtp::run(pool.clone(), config.clone(), event_hub.get_ref().clone()));
+
+    let api_doc = openapi::ApiDoc::openapi();
     
     // Start HTTP server
     HttpServer::new(move || {
@@ -42,17 +89,35 @@ async fn main() -> std::io::Result<()> {
             .allow_any_origin()
             .allow_any_method()
             .allow_any_header()
+            .expose_headers([middleware::request_id::REQUEST_ID_HEADER])
             .max_age(3600);
         
         App::new()
-            .wrap(Logger::default())
+            .wrap(from_fn(middleware::locale::resolve_locale))
+            .wrap(from_fn(middleware::api_token::resolve_api_token))
+            .wrap(from_fn(middleware::jwt::check_session))
+            .wrap(from_fn(middleware::metrics::track_requests))
+            .wrap(from_fn(middleware::request_id::trace_requests))
             .wrap(cors)
             .app_data(web::Data::new(pool.clone()))
             .app_data(web::Data::new(config.clone()))
-            .app_data(web::JsonConfig::default().limit(4096 * 1024))
+            .app_data(oidc.clone())
+            .app_data(event_hub.clone())
+            .app_data(
+                web::JsonConfig::default()
+                    .limit(4096 * 1024)
+                    .error_handler(handlers::root::json_error)
+            )
+            .app_data(web::QueryConfig::default().error_handler(handlers::root::query_error))
             // Root
             .route("/", web::get().to(handlers::root::index))
             .route("/api", web::get().to(handlers::root::index))
+            // Health and monitoring
+            .route("/healthz", web::get().to(handlers::health::healthz))
+            .route("/readyz", web::get().to(handlers::health::readyz))
+            .route("/metrics", web::get().to(handlers::health::metrics))
+            // Docs: /openapi.json and Swagger UI at /docs/
+            .service(SwaggerUi::new("/docs/{_:.*}").url("/openapi.json", api_doc.clone()))
             // Auth routes
             .service(
                 web::scope("/auth")
@@ -63,30 +128,65 @@ async fn main() -> std::io::Result<()> {
                     .route("/verify-reset-code", web::post().to(handlers::auth::verify_reset_code))
                     .route("/reset-password", web::post().to(handlers::auth::reset_password))
                     .route("/login", web::post().to(handlers::auth::login))
+                    .route("/oidc/providers", web::get().to(handlers::oidc::list_providers))
+                    .route("/oidc/{provider}/login", web::get().to(handlers::oidc::login))
+                    .route("/oidc/{provider}/callback", web::get().to(handlers::oidc::callback))
                     .route("/me", web::get().to(handlers::auth::me))
+                    .route("/me", web::delete().to(handlers::account::delete_account))
+                    .route("/me/restore", web::post().to(handlers::account::restore_account))
+                    .route("/export", web::get().to(handlers::account::export_data))
                     .route("/update-username", web::put().to(handlers::auth::update_username))
                     .route("/update-email", web::put().to(handlers::auth::update_email))
+                    .route("/confirm-email-change", web::post().to(handlers::auth::confirm_email_change))
+                    .route("/revert-email-change", web::get().to(handlers::auth::revert_email_change_page))
+                    .route("/revert-email-change", web::post().to(handlers::auth::revert_email_change))
                     .route("/update-password", web::put().to(handlers::auth::update_password))
+                    .route("/settings", web::get().to(handlers::settings::get_settings))
+                    .route("/settings", web::put().to(handlers::settings::update_settings))
+                    .route("/tokens", web::get().to(handlers::api_tokens::list_tokens))
+                    .route("/tokens", web::post().to(handlers::api_tokens::create_token))
+                    .route("/tokens/{id}", web::delete().to(handlers::api_tokens::revoke_token))
+                    .route("/invitations", web::post().to(handlers::invitations::create_invitation))
             )
             // Lists routes
             .service(
                 web::scope("/lists")
                     .route("", web::get().to(handlers::lists::get_lists))
                     .route("", web::post().to(handlers::lists::create_list))
+                    .route("/export", web::get().to(handlers::export::export_lists))
                     .route("/{id}", web::get().to(handlers::lists::get_list))
                     .route("/{id}", web::put().to(handlers::lists::update_list))
                     .route("/{id}", web::delete().to(handlers::lists::delete_list))
                     .route("/{id}/tasks", web::get().to(handlers::tasks::get_tasks_by_list))
+                    .route("/{id}/export", web::get().to(handlers::export::export_list))
             )
             // Tasks routes
             .service(
                 web::scope("/tasks")
                     .route("", web::get().to(handlers::tasks::get_all_tasks))
                     .route("", web::post().to(handlers::tasks::create_task))
+                    .route("/quick", web::post().to(handlers::tasks::quick_add_task))
                     .route("/{id}", web::get().to(handlers::tasks::get_task))
                     .route("/{id}", web::put().to(handlers::tasks::update_task))
                     .route("/{id}", web::delete().to(handlers::tasks::delete_task))
+                    .route("/{id}/attachments", web::get().to(handlers::attachments::list_attachments))
+                    .route("/{id}/attachments/{attachment_id}", web::get().to(handlers::attachments::download_attachment))
+                    .route("/{id}/attachments/{attachment_id}", web::delete().to(handlers::attachments::delete_attachment))
+                    .route("/{id}/reminders", web::get().to(handlers::reminders::list_reminders))
+                    .route("/{id}/reminders", web::post().to(handlers::reminders::create_reminder))
+                    .route("/{id}/reminders/{reminder_id}", web::put().to(handlers::reminders::update_reminder))
+                    .route("/{id}/reminders/{reminder_id}", web::delete().to(handlers::reminders::delete_reminder))
+                    .route("/{id}/reminders/{reminder_id}/snooze", web::post().to(handlers::reminders::snooze_reminder))
+                    .route("/{id}/reminders/{reminder_id}/dismiss", web::post().to(handlers::reminders::dismiss_reminder))
             )
+            // Stats routes
+            .route("/stats", web::get().to(handlers::stats::get_stats))
+            // Calendar routes
+            .route("/calendar", web::get().to(handlers::calendar::get_calendar))
+            .route("/calendar/feed", web::get().to(handlers::calendar::get_feed))
+            .route("/calendar/feed", web::post().to(handlers::calendar::create_feed))
+            .route("/calendar/feed", web::delete().to(handlers::calendar::revoke_feed))
+            .route("/calendar.ics", web::get().to(handlers::calendar::calendar_feed))
             // Sync routes
             .service(
                 web::scope("/sync")
@@ -94,6 +194,52 @@ async fn main() -> std::io::Result<()> {
                     .route("/pull", web::post().to(handlers::sync::sync_pull))
                     .route("/full", web::post().to(handlers::sync::sync_full))
             )
+            // Import (Todoist, Microsoft To Do, CSV)
+            .route("/import", web::post().to(handlers::import::import_data))
+            // Email-to-task: addresses, and the upload endpoint for MTA pipes
+            .service(
+                web::scope("/inbound")
+                    .route("/addresses", web::get().to(handlers::inbound::list_addresses))
+                    .route("/addresses", web::post().to(handlers::inbound::create_address))
+                    .route("/addresses/{id}", web::delete().to(handlers::inbound::delete_address))
+                    .route("/email", web::post().to(handlers::inbound::receive_email))
+            )
+            // Webhooks routes
+            .service(
+                web::scope("/webhooks")
+                    .route("", web::get().to(handlers::webhooks::list_webhooks))
+                    .route("", web::post().to(handlers::webhooks::create_webhook))
+                    .route("/{id}", web::put().to(handlers::webhooks::update_webhook))
+                    .route("/{id}", web::delete().to(handlers::webhooks::delete_webhook))
+                    .route("/{id}/deliveries", web::get().to(handlers::webhooks::list_deliveries))
+            )
+            // Web Push: subscriptions for server-sent reminders, and the test push service
+            .service(
+                web::scope("/push")
+                    .route("/vapid-key", web::get().to(handlers::push::get_vapid_key))
+                    .route("/subscriptions", web::get().to(handlers::push::list_subscriptions))
+                    .route("/subscriptions", web::post().to(handlers::push::create_subscription))
+                    .route("/subscriptions/{id}", web::delete().to(handlers::push::delete_subscription))
+                    .route("/subscriptions/{id}/test", web::post().to(handlers::push::test_subscription))
+                    .route("/sink", web::post().to(handlers::push::create_sink))
+                    .route("/sink/{id}", web::post().to(handlers::push::receive_sink))
+                    .route("/sink/{id}", web::get().to(handlers::push::list_sink_messages))
+            )
+            // CalDAV (Basic auth with a personal access token as the password)
+            .route("/.well-known/caldav", web::to(handlers::caldav::well_known))
+            .service(
+                web::scope("/dav")
+                    .route("", web::to(handlers::caldav::principal))
+                    .route("/", web::to(handlers::caldav::principal))
+                    .route("/principal/", web::to(handlers::caldav::principal))
+                    .route("/calendars/", web::to(handlers::caldav::home))
+                    .route("/calendars/{list_id}", web::to(handlers::caldav::calendar))
+                    .route("/calendars/{list_id}/", web::to(handlers::caldav::calendar))
+                    .route("/calendars/{list_id}/{resource}", web::to(handlers::caldav::calendar_object))
+            )
+            // Change notifications (Server-Sent Events)
+            .route("/events", web::get().to(handlers::events::stream_events))
+            .default_service(web::to(handlers::root::not_found))
     })
     .bind(format!("{}:{}", host, port))?
     .run()
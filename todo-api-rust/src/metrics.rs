@@ -0,0 +1,150 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use sqlx::MySqlPool;
+
+const SYNC_BUCKETS: &[f64] = &[0.0, 1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 5000.0];
+
+/// Process-wide Prometheus collectors, exposed at `/metrics`.
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    db_pool: IntGaugeVec,
+    sync_items: HistogramVec,
+    emails: IntCounterVec,
+    webhooks: IntCounterVec,
+    reminders: IntCounterVec,
+    event_streams: IntGauge,
+}
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by route"),
+            &["method", "route"],
+        )
+        .unwrap();
+        let db_pool = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "MySQL pool connections by state"),
+            &["state"],
+        )
+        .unwrap();
+        let sync_items = HistogramVec::new(
+            HistogramOpts::new("sync_items", "Lists and tasks per sync request")
+                .buckets(SYNC_BUCKETS.to_vec()),
+            &["direction", "entity"],
+        )
+        .unwrap();
+        let emails = IntCounterVec::new(
+            Opts::new("emails_total", "Email delivery attempts by kind and outcome"),
+            &["kind", "outcome"],
+        )
+        .unwrap();
+
+        let webhooks = IntCounterVec::new(
+            Opts::new("webhook_deliveries_total", "Webhook delivery attempts by outcome"),
+            &["outcome"],
+        )
+        .unwrap();
+        let reminders = IntCounterVec::new(
+            Opts::new("reminder_deliveries_total", "Reminders sent by the server, by channel and outcome"),
+            &["channel", "outcome"],
+        )
+        .unwrap();
+        let event_streams =
+            IntGauge::new("event_streams", "Open /events connections").unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry.register(Box::new(http_duration.clone())).unwrap();
+        registry.register(Box::new(db_pool.clone())).unwrap();
+        registry.register(Box::new(sync_items.clone())).unwrap();
+        registry.register(Box::new(emails.clone())).unwrap();
+        registry.register(Box::new(webhooks.clone())).unwrap();
+        registry.register(Box::new(reminders.clone())).unwrap();
+        registry.register(Box::new(event_streams.clone())).unwrap();
+
+        Self {
+            registry,
+            http_requests,
+            http_duration,
+            db_pool,
+            sync_items,
+            emails,
+            webhooks,
+            reminders,
+            event_streams,
+        }
+    }
+
+    /// `route` is the matched pattern (e.g. `/tasks/{id}`), never the raw path, to keep
+    /// label cardinality bounded.
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_duration
+            .with_label_values(&[method, route])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// `direction` is `push`, `pull` or `full`; `entity` is `lists` or `tasks`. Deletions count.
+    pub fn observe_sync(&self, direction: &str, entity: &str, items: usize) {
+        self.sync_items
+            .with_label_values(&[direction, entity])
+            .observe(items as f64);
+    }
+
+    /// `outcome` is `sent`, `retry` (attempt failed, will retry) or `failed` (gave up).
+    pub fn email(&self, kind: &str, outcome: &str) {
+        self.emails.with_label_values(&[kind, outcome]).inc();
+    }
+
+    /// `outcome` is `delivered`, `retry` or `failed`, as for emails.
+    pub fn webhook(&self, outcome: &str) {
+        self.webhooks.with_label_values(&[outcome]).inc();
+    }
+
+    /// `channel` is `push`, `email` or `webhook`; `outcome` is `sent`, `failed` or `expired`
+    /// (a push subscription the push service no longer knows).
+    pub fn reminder(&self, channel: &str, outcome: &str) {
+        self.reminders.with_label_values(&[channel, outcome]).inc();
+    }
+
+    pub fn event_stream_opened(&self) {
+        self.event_streams.inc();
+    }
+
+    pub fn event_stream_closed(&self) {
+        self.event_streams.dec();
+    }
+
+    /// Text exposition format. Pool gauges are sampled here, at scrape time.
+    pub fn render(&self, pool: &MySqlPool) -> String {
+        let size = pool.size() as i64;
+        let idle = pool.num_idle() as i64;
+        self.db_pool.with_label_values(&["max"]).set(pool.options().get_max_connections() as i64);
+        self.db_pool.with_label_values(&["open"]).set(size);
+        self.db_pool.with_label_values(&["idle"]).set(idle);
+        self.db_pool.with_label_values(&["in_use"]).set(size - idle);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+}
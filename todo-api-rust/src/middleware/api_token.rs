@@ -0,0 +1,163 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::MySqlPool;
+
+use crate::middleware::jwt::extract_token;
+use crate::models::Scope;
+
+/// Personal access tokens start with this, which is how they are told apart from JWTs.
+pub const TOKEN_PREFIX: &str = "mtd_";
+
+/// The personal access token behind a request, put in the request extensions by
+/// `resolve_api_token` and turned into `Claims` by `get_auth_user`.
+#[derive(Debug, Clone)]
+pub struct ApiTokenAuth {
+    pub user_id: String,
+    pub email: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// Scope a token needs for `method path`. `None` means tokens are refused: account
+/// management, sync and anything not listed here need a login session.
+pub fn required_scope(method: &Method, path: &str) -> Option<Scope> {
+    let read = method == Method::GET || method == Method::HEAD;
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match segments.as_slice() {
+        ["lists", _, "tasks"] | ["lists", "export"] | ["lists", _, "export"] if read => Some(Scope::TasksRead),
+        ["lists", ..] => Some(if read { Scope::ListsRead } else { Scope::ListsWrite }),
+        ["tasks", ..] => Some(if read { Scope::TasksRead } else { Scope::TasksWrite }),
+        ["stats"] | ["calendar"] if read => Some(Scope::TasksRead),
+        ["auth", "me"] | ["auth", "settings"] if read => Some(Scope::AccountRead),
+        _ => None,
+    }
+}
+
+// Looks up `mtd_` bearer tokens so `get_auth_user` can stay synchronous. Unknown, revoked
+// or expired tokens are left unresolved and rejected there as invalid.
+pub async fn resolve_api_token(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    if let Some(auth) = lookup(&req).await {
+        req.extensions_mut().insert(auth);
+    }
+
+    next.call(req).await
+}
+
+// Row read when resolving a token
+#[derive(sqlx::FromRow)]
+struct TokenRow {
+    id: String,
+    user_id: String,
+    email: String,
+    scopes: String,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+async fn lookup(req: &ServiceRequest) -> Option<ApiTokenAuth> {
+    let token = extract_token(req.request()).filter(|t| t.starts_with(TOKEN_PREFIX))?;
+    let pool = req.app_data::<web::Data<MySqlPool>>()?;
+    find_token(pool.get_ref(), &token).await
+}
+
+/// Resolves a usable token and records its use. Also takes tokens sent as a Basic auth
+/// password by CalDAV clients.
+pub async fn find_token(pool: &MySqlPool, token: &str) -> Option<ApiTokenAuth> {
+    let now = Utc::now();
+
+    let row: Option<TokenRow> = sqlx::query_as(
+        r#"
+        SELECT t.id, t.user_id, u.email, t.scopes, t.expires_at
+        FROM api_tokens t
+        JOIN users u ON u.id = t.user_id
+        WHERE t.token_hash = ? AND t.revoked_at IS NULL AND (t.expires_at IS NULL OR t.expires_at > ?)
+        "#
+    )
+    .bind(hash_token(token))
+    .bind(now)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| tracing::warn!(error = %e, "API token lookup failed"))
+    .ok()
+    .flatten();
+
+    let row = row?;
+
+    if let Err(e) = sqlx::query("UPDATE api_tokens SET last_used_at = ? WHERE id = ?")
+        .bind(now)
+        .bind(&row.id)
+        .execute(pool)
+        .await
+    {
+        tracing::warn!(error = %e, "Failed to record API token use");
+    }
+
+    Some(ApiTokenAuth {
+        user_id: row.user_id,
+        email: row.email,
+        scopes: row.scopes.split_whitespace().filter_map(|s| s.parse().ok()).collect(),
+        expires_at: row.expires_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_routes_to_scopes() {
+        let cases = [
+            // Reads
+            (Method::GET, "/lists", Some(Scope::ListsRead)),
+            (Method::GET, "/lists/abc", Some(Scope::ListsRead)),
+            (Method::HEAD, "/lists/abc", Some(Scope::ListsRead)),
+            (Method::GET, "/lists/abc/tasks", Some(Scope::TasksRead)),
+            (Method::GET, "/lists/export", Some(Scope::TasksRead)),
+            (Method::GET, "/lists/abc/export", Some(Scope::TasksRead)),
+            (Method::GET, "/tasks", Some(Scope::TasksRead)),
+            (Method::GET, "/tasks/abc/attachments/def", Some(Scope::TasksRead)),
+            (Method::GET, "/stats", Some(Scope::TasksRead)),
+            (Method::GET, "/calendar", Some(Scope::TasksRead)),
+            (Method::GET, "/auth/me", Some(Scope::AccountRead)),
+            (Method::GET, "/auth/settings/", Some(Scope::AccountRead)),
+            // Writes
+            (Method::POST, "/lists", Some(Scope::ListsWrite)),
+            (Method::PUT, "/lists/abc", Some(Scope::ListsWrite)),
+            (Method::DELETE, "/lists/abc", Some(Scope::ListsWrite)),
+            (Method::POST, "/tasks/quick", Some(Scope::TasksWrite)),
+            (Method::PUT, "/tasks/abc", Some(Scope::TasksWrite)),
+            (Method::POST, "/tasks/abc/reminders/def/snooze", Some(Scope::TasksWrite)),
+            // Refused: account management, sync, webhooks, import and the rest
+            (Method::PUT, "/auth/settings", None),
+            (Method::DELETE, "/auth/me", None),
+            (Method::GET, "/auth/export", None),
+            (Method::GET, "/auth/tokens", None),
+            (Method::POST, "/auth/tokens", None),
+            (Method::POST, "/auth/invitations", None),
+            (Method::POST, "/sync/push", None),
+            (Method::POST, "/sync/full", None),
+            (Method::GET, "/webhooks", None),
+            (Method::POST, "/webhooks", None),
+            (Method::POST, "/import", None),
+            (Method::GET, "/calendar/feed", None),
+            (Method::POST, "/stats", None),
+            (Method::GET, "/inbound/addresses", None),
+        ];
+
+        for (method, path, expected) in cases {
+            assert_eq!(required_scope(&method, path), expected, "{} {}", method, path);
+        }
+    }
+}
@@ -1,8 +1,15 @@
-use actix_web::HttpRequest;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpRequest};
+use chrono::{DateTime, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use sqlx::MySqlPool;
 
 use crate::config::Config;
 use crate::errors::ApiError;
+use crate::i18n::Msg;
+use crate::middleware::api_token::{required_scope, ApiTokenAuth, TOKEN_PREFIX};
 use crate::models::Claims;
 
 pub fn create_token(config: &Config, user_id: &str, email: &str) -> Result<String, ApiError> {
@@ -27,23 +34,137 @@ pub fn verify_token(config: &Config, token: &str) -> Result<Claims, ApiError> {
         &Validation::default(),
     )
     .map(|data| data.claims)
-    .map_err(|_| ApiError::unauthorized("Token inválido ou expirado"))
+    .map_err(|_| ApiError::unauthorized(Msg::InvalidToken))
 }
 
 pub fn extract_token(req: &HttpRequest) -> Option<String> {
     req.headers()
         .get("Authorization")
         .and_then(|h| h.to_str().ok())
-        .and_then(|h| {
-            if h.starts_with("Bearer ") {
-                Some(h[7..].to_string())
-            } else {
-                None
-            }
-        })
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(|t| t.to_string())
 }
 
+/// Put in the request extensions by `check_session` when a login JWT predates the
+/// account's `sessions_revoked_at`, or when that could not be checked.
+#[derive(Debug, Clone, Copy)]
+pub struct RevokedSession;
+
+/// True when a token issued at `iat` (seconds) was cut off by a revocation at `revoked_at`.
+pub fn issued_before(iat: i64, revoked_at: Option<DateTime<Utc>>) -> bool {
+    revoked_at.is_some_and(|at| iat < at.timestamp())
+}
+
+// Looks up when the account last revoked its sessions, so `get_auth_user` can stay
+// synchronous. Tokens that fail to verify are left for `get_auth_user` to reject.
+pub async fn check_session(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    if is_revoked(&req).await {
+        req.extensions_mut().insert(RevokedSession);
+    }
+
+    next.call(req).await
+}
+
+async fn is_revoked(req: &ServiceRequest) -> bool {
+    let Some(token) = extract_token(req.request()).filter(|t| !t.starts_with(TOKEN_PREFIX)) else {
+        return false;
+    };
+    let (Some(pool), Some(config)) = (req.app_data::<web::Data<MySqlPool>>(), req.app_data::<web::Data<Config>>()) else {
+        return false;
+    };
+    let Ok(claims) = verify_token(config, &token) else {
+        return false;
+    };
+
+    let revoked_at: Result<Option<(Option<DateTime<Utc>>,)>, _> =
+        sqlx::query_as("SELECT sessions_revoked_at FROM users WHERE id = ?")
+            .bind(&claims.user_id)
+            .fetch_optional(pool.get_ref())
+            .await;
+
+    match revoked_at {
+        Ok(row) => issued_before(claims.iat, row.and_then(|(at,)| at)),
+        // Refuse rather than let a revoked session through
+        Err(e) => {
+            tracing::warn!(error = %e, "Session check failed");
+            true
+        }
+    }
+}
+
+/// Accepts a login JWT, or a personal access token whose scopes cover the request.
 pub fn get_auth_user(req: &HttpRequest, config: &Config) -> Result<Claims, ApiError> {
-    let token = extract_token(req).ok_or_else(|| ApiError::unauthorized("Token não fornecido"))?;
-    verify_token(config, &token)
+    let token = extract_token(req).ok_or_else(|| ApiError::unauthorized(Msg::MissingToken))?;
+    let claims = if token.starts_with(TOKEN_PREFIX) {
+        api_token_claims(req)?
+    } else {
+        if req.extensions().get::<RevokedSession>().is_some() {
+            return Err(ApiError::unauthorized(Msg::SessionRevoked));
+        }
+        verify_token(config, &token)?
+    };
+
+    tracing::Span::current().record("user_id", claims.user_id.as_str());
+    Ok(claims)
+}
+
+// Helper: Claims for a token already resolved by `resolve_api_token`
+fn api_token_claims(req: &HttpRequest) -> Result<Claims, ApiError> {
+    let auth = req
+        .extensions()
+        .get::<ApiTokenAuth>()
+        .cloned()
+        .ok_or_else(|| ApiError::unauthorized(Msg::InvalidToken))?;
+
+    let allowed = required_scope(req.method(), req.path())
+        .is_some_and(|scope| auth.scopes.contains(&scope));
+    if !allowed {
+        return Err(ApiError::forbidden(Msg::InsufficientScope));
+    }
+
+    Ok(Claims {
+        user_id: auth.user_id,
+        email: auth.email,
+        exp: auth.expires_at.map_or(i64::MAX, |at| at.timestamp()),
+        iat: chrono::Utc::now().timestamp(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn revocation_cuts_off_older_tokens() {
+        let revoked_at = Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap();
+        let at = revoked_at.timestamp();
+
+        assert!(issued_before(at - 1, Some(revoked_at)));
+        // A login right after the revocation is a new session
+        assert!(!issued_before(at, Some(revoked_at)));
+        assert!(!issued_before(at + 60, Some(revoked_at)));
+        assert!(!issued_before(at - 1, None));
+    }
+
+    #[test]
+    fn refuses_sessions_marked_revoked() {
+        let config = Config::for_tests();
+        let token = create_token(&config, "user-1", "ana@example.com").unwrap();
+        let bearer = ("Authorization", format!("Bearer {}", token));
+
+        let req = TestRequest::default().insert_header(bearer.clone()).to_http_request();
+        assert_eq!(get_auth_user(&req, &config).unwrap().user_id, "user-1");
+
+        let req = TestRequest::default().insert_header(bearer).to_http_request();
+        req.extensions_mut().insert(RevokedSession);
+        let err = get_auth_user(&req, &config).unwrap_err();
+        assert_eq!(err.code, Msg::SessionRevoked);
+        assert_eq!(err.status, actix_web::http::StatusCode::UNAUTHORIZED);
+    }
 }
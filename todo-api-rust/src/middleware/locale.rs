@@ -0,0 +1,43 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderValue, ACCEPT_LANGUAGE, CONTENT_LANGUAGE};
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+use sqlx::MySqlPool;
+
+use crate::config::Config;
+use crate::handlers::settings::user_locale;
+use crate::i18n::{self, Locale};
+use crate::middleware::jwt::get_auth_user;
+
+// Resolves the language for the request: a supported Accept-Language first, then the
+// authenticated user's saved locale, then the default. Handlers read it via `i18n::current()`.
+pub async fn resolve_locale(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let from_header = req
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|h| h.to_str().ok())
+        .and_then(Locale::negotiate);
+
+    let locale = match from_header {
+        Some(locale) => locale,
+        None => saved_locale(&req).await.unwrap_or(i18n::DEFAULT_LOCALE),
+    };
+
+    let mut res = i18n::scope(locale, next.call(req)).await?;
+    res.headers_mut()
+        .insert(CONTENT_LANGUAGE, HeaderValue::from_static(locale.tag()));
+
+    Ok(res)
+}
+
+async fn saved_locale(req: &ServiceRequest) -> Option<Locale> {
+    let config = req.app_data::<web::Data<Config>>()?;
+    let pool = req.app_data::<web::Data<MySqlPool>>()?;
+    let claims = get_auth_user(req.request(), config).ok()?;
+
+    Some(user_locale(pool.get_ref(), &claims.user_id).await)
+}
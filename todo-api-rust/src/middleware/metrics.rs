@@ -0,0 +1,57 @@
+use std::time::Instant;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::Error;
+
+use crate::metrics::METRICS;
+
+// Methods that get their own label: HTTP's and those of WebDAV/CalDAV. CalDAV routes
+// accept any method token, so anything else is `other` to keep the series bounded.
+const KNOWN_METHODS: [&str; 18] = [
+    "GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS", "TRACE", "CONNECT",
+    "PROPFIND", "PROPPATCH", "MKCOL", "MKCALENDAR", "REPORT", "COPY", "MOVE", "LOCK", "UNLOCK",
+];
+
+fn method_label(method: &Method) -> &'static str {
+    KNOWN_METHODS
+        .into_iter()
+        .find(|known| *known == method.as_str())
+        .unwrap_or("other")
+}
+
+// Records count and latency per matched route. Unknown paths share one `unmatched` label.
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let method = method_label(req.method());
+    let start = Instant::now();
+
+    let res = next.call(req).await?;
+
+    let route = res
+        .request()
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+    METRICS.observe_request(method, &route, res.status().as_u16(), start.elapsed());
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_only_known_methods() {
+        assert_eq!(method_label(&Method::GET), "GET");
+        assert_eq!(method_label(&Method::from_bytes(b"PROPFIND").unwrap()), "PROPFIND");
+        assert_eq!(method_label(&Method::from_bytes(b"REPORT").unwrap()), "REPORT");
+        assert_eq!(method_label(&Method::from_bytes(b"FOO123").unwrap()), "other");
+        // Method tokens are case-sensitive
+        assert_eq!(method_label(&Method::from_bytes(b"get").unwrap()), "other");
+    }
+}
@@ -1 +1,5 @@
+pub mod api_token;
 pub mod jwt;
+pub mod locale;
+pub mod metrics;
+pub mod request_id;
@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use super::{UserPublic, UserSettings};
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct DeleteAccountRequest {
+    #[validate(length(min = 1, code = "current_password_required"))]
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccountDeletionResponse {
+    /// The account and all its data are removed after this instant
+    pub deletion_scheduled_at: DateTime<Utc>,
+}
+
+// Row of `sync_log`, as written to `activity.json`
+#[derive(Debug, Serialize, FromRow)]
+pub struct ActivityEntry {
+    pub entity_type: String,
+    pub entity_id: String,
+    pub action: String,
+    pub synced_at: DateTime<Utc>,
+    pub device_id: Option<String>,
+}
+
+// `profile.json` in the export archive
+#[derive(Debug, Serialize)]
+pub struct ExportProfile {
+    pub user: UserPublic,
+    pub settings: UserSettings,
+    pub exported_at: DateTime<Utc>,
+}
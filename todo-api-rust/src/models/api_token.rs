@@ -0,0 +1,119 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use super::validation;
+
+/// What a personal access token may do. Login JWTs are not scoped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    ListsRead,
+    ListsWrite,
+    TasksRead,
+    TasksWrite,
+    AccountRead,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 5] = [
+        Scope::ListsRead,
+        Scope::ListsWrite,
+        Scope::TasksRead,
+        Scope::TasksWrite,
+        Scope::AccountRead,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::ListsRead => "lists:read",
+            Scope::ListsWrite => "lists:write",
+            Scope::TasksRead => "tasks:read",
+            Scope::TasksWrite => "tasks:write",
+            Scope::AccountRead => "account:read",
+        }
+    }
+}
+
+impl FromStr for Scope {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Scope::ALL.into_iter().find(|scope| scope.as_str() == s).ok_or(())
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Row of `api_tokens`. Only the SHA-256 of the secret is stored.
+#[derive(Debug, Clone, FromRow)]
+pub struct ApiToken {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub token_prefix: String,
+    /// Space separated, as stored
+    pub scopes: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiToken {
+    pub fn scopes(&self) -> Vec<Scope> {
+        self.scopes.split_whitespace().filter_map(|s| s.parse().ok()).collect()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiTokenResponse {
+    pub id: String,
+    pub name: String,
+    /// First characters of the token, to tell tokens apart
+    pub token_prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ApiToken> for ApiTokenResponse {
+    fn from(token: ApiToken) -> Self {
+        Self {
+            scopes: token.scopes().into_iter().map(|s| s.to_string()).collect(),
+            id: token.id,
+            name: token.name,
+            token_prefix: token.token_prefix,
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+            created_at: token.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateApiTokenRequest {
+    #[validate(custom = "validation::not_blank", length(max = 100, code = "field_too_long"))]
+    pub name: String,
+    /// Any of `lists:read`, `lists:write`, `tasks:read`, `tasks:write`, `account:read`
+    #[validate(custom = "validation::scopes")]
+    pub scopes: Vec<String>,
+    /// Days until the token stops working. Omit for a token that never expires.
+    #[validate(range(min = 1, max = 3650, code = "invalid_token_expiry"))]
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateApiTokenResponse {
+    /// The secret. Shown only in this response.
+    pub token: String,
+    pub api_token: ApiTokenResponse,
+}
@@ -0,0 +1,118 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use super::validation;
+
+use super::{Task, TaskResponse};
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CalendarQuery {
+    /// Start of the range: RFC 3339 or a local `YYYY-MM-DD`.
+    #[validate(custom = "validation::datetime", custom = "validation::not_blank")]
+    pub from: String,
+    /// End of the range, inclusive. Same formats as `from`.
+    #[validate(custom = "validation::datetime", custom = "validation::not_blank")]
+    pub to: String,
+    /// IANA time zone; defaults to the user's setting.
+    #[validate(custom = "validation::time_zone")]
+    pub tz: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CalendarEntryKind {
+    Reminder,
+    Due,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CalendarEntry {
+    pub kind: CalendarEntryKind,
+    pub at: DateTime<Utc>,
+    pub is_occurrence: bool,
+    pub task: TaskResponse,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CalendarDay {
+    pub date: NaiveDate,
+    pub entries: Vec<CalendarEntry>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CalendarResponse {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub time_zone: String,
+    pub days: Vec<CalendarDay>,
+}
+
+/// How tasks appear in `/calendar.ics`. Most calendar apps only show events; to-dos land
+/// in task apps such as Apple Reminders or Thunderbird.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedComponent {
+    Event,
+    Todo,
+}
+
+impl FromStr for FeedComponent {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "event" => Ok(FeedComponent::Event),
+            "todo" => Ok(FeedComponent::Todo),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CalendarFeedQuery {
+    /// Secret from `POST /calendar/feed`
+    #[validate(custom = "validation::not_blank")]
+    pub token: String,
+    /// Only tasks of this list
+    pub list_id: Option<String>,
+    /// `event` (default, VEVENT) or `todo` (VTODO)
+    #[validate(custom = "validation::feed_component")]
+    pub component: Option<String>,
+}
+
+// Row of `calendar_feeds`
+#[derive(Debug, FromRow)]
+pub struct CalendarFeed {
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CalendarFeedResponse {
+    /// Whether a feed link exists. The link itself is only shown when generated.
+    pub active: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    /// Last time a calendar app fetched the feed
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateCalendarFeedResponse {
+    /// Subscription URL; add `&list_id=` or `&component=todo` to narrow it
+    pub url: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// Task in the feed, with the name of its list
+#[derive(Debug, FromRow)]
+pub struct FeedTask {
+    #[sqlx(flatten)]
+    pub task: Task,
+    pub list_name: String,
+}
@@ -0,0 +1,120 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use super::validation;
+use crate::i18n::Msg;
+
+/// Where an imported file comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// JSON from the Todoist Sync API (`projects`, `items`, `reminders`)
+    Todoist,
+    /// CSV of one Todoist project ("Export as a template")
+    TodoistCsv,
+    /// JSON of Microsoft Graph To Do lists, each with its `tasks`
+    MicrosoftTodo,
+    /// Any CSV, with `columns` naming what each column holds
+    Csv,
+}
+
+impl ImportFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ImportFormat::Todoist => "todoist",
+            ImportFormat::TodoistCsv => "todoist_csv",
+            ImportFormat::MicrosoftTodo => "microsoft_todo",
+            ImportFormat::Csv => "csv",
+        }
+    }
+}
+
+impl FromStr for ImportFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "todoist" => Ok(ImportFormat::Todoist),
+            "todoist_csv" => Ok(ImportFormat::TodoistCsv),
+            "microsoft_todo" => Ok(ImportFormat::MicrosoftTodo),
+            "csv" => Ok(ImportFormat::Csv),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ImportRequest {
+    /// `todoist`, `todoist_csv`, `microsoft_todo` or `csv`
+    #[validate(custom = "validation::import_format")]
+    pub format: String,
+    /// Contents of the exported file
+    #[validate(custom = "validation::not_blank")]
+    pub data: String,
+    /// List for tasks whose file doesn't name one (Todoist CSV, CSV without a `list` column)
+    #[validate(length(max = 255, code = "field_too_long"))]
+    pub list_name: Option<String>,
+    /// Column headers for `csv`; each defaults to the field's own name
+    pub columns: Option<CsvColumns>,
+    /// Only return the preview; nothing is saved
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Header of the CSV column holding each field. Matched ignoring case.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct CsvColumns {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// `1`, `true`, `yes`, `sim` or `x` mean completed
+    pub completed: Option<String>,
+    pub due_at: Option<String>,
+    pub reminder: Option<String>,
+    /// RRULE such as `FREQ=WEEKLY;BYDAY=MO`
+    pub recurrence: Option<String>,
+    /// List name; rows without one go to `list_name`
+    pub list: Option<String>,
+    /// Stable id from the source app, so importing again updates instead of duplicating
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportedTask {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub completed: bool,
+    pub due_at: Option<DateTime<Utc>>,
+    pub reminder: Option<DateTime<Utc>>,
+    pub recurrence: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportedList {
+    pub id: String,
+    pub name: String,
+    pub color: String,
+    pub tasks: Vec<ImportedTask>,
+}
+
+/// Something in the file that was left out. With `skipped` the whole task was dropped;
+/// otherwise only `field` was.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportIssue {
+    /// CSV line, or position of the task in the JSON (from 1)
+    pub row: usize,
+    pub field: String,
+    pub code: Msg,
+    pub skipped: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportResponse {
+    pub dry_run: bool,
+    pub lists: Vec<ImportedList>,
+    pub issues: Vec<ImportIssue>,
+}
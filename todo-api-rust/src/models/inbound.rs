@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+// Row of `inbound_addresses`
+#[derive(Debug, Clone, FromRow)]
+pub struct InboundAddress {
+    pub id: String,
+    pub user_id: String,
+    pub list_id: Option<String>,
+    pub token: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InboundAddressResponse {
+    pub id: String,
+    /// Where forwarded emails go; `null` for the user's oldest list
+    pub list_id: Option<String>,
+    /// Forward or send emails here
+    pub address: String,
+    pub created_at: DateTime<Utc>,
+    /// Last time an email arrived
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl InboundAddressResponse {
+    pub fn new(address: InboundAddress, domain: &str) -> Self {
+        Self {
+            address: format!("{}@{}", address.token, domain),
+            id: address.id,
+            list_id: address.list_id,
+            created_at: address.created_at,
+            last_used_at: address.last_used_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateInboundAddressRequest {
+    /// List that receives the tasks. Omit for an address of the whole account.
+    #[validate(length(max = 36, code = "field_too_long"))]
+    pub list_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct InboundEmailQuery {
+    /// Envelope recipient from the MTA. Read from `To`, `Cc` and `Delivered-To` when omitted.
+    #[validate(length(max = 320, code = "field_too_long"))]
+    pub recipient: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InboundEmailResponse {
+    pub task_id: String,
+    pub list_id: String,
+    pub attachments: usize,
+}
+
+// Row of `task_attachments`, without the file itself
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct TaskAttachment {
+    pub id: String,
+    pub task_id: String,
+    pub filename: String,
+    pub content_type: String,
+    /// Bytes
+    pub size: u32,
+    pub created_at: DateTime<Utc>,
+}
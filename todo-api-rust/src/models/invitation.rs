@@ -0,0 +1,12 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use super::validation;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct InvitationRequest {
+    /// Address of the person being invited
+    #[validate(custom = "validation::email")]
+    pub email: String,
+}
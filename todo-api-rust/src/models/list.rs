@@ -1,6 +1,10 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use super::validation;
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct TodoList {
@@ -14,7 +18,7 @@ pub struct TodoList {
     pub deleted_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ListResponse {
     pub id: String,
     pub user_id: String,
@@ -37,16 +41,42 @@ impl From<TodoList> for ListResponse {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct CreateListRequest {
     pub id: Option<String>,
+    #[validate(custom = "validation::not_blank", length(max = 255, code = "field_too_long"))]
     pub name: String,
+    /// `#RRGGBB` or `#RRGGBBAA`.
+    #[validate(custom = "validation::hex_color")]
     pub color: Option<String>,
     pub created_at: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpdateListRequest {
+    #[validate(length(max = 255, code = "field_too_long"))]
     pub name: Option<String>,
+    /// `#RRGGBB` or `#RRGGBBAA`.
+    #[validate(custom = "validation::hex_color")]
     pub color: Option<String>,
 }
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListsQuery {
+    /// Include total, completed and pending task counts.
+    pub with_counts: Option<bool>,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct ListWithCountsResponse {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub color: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub total_tasks: i64,
+    pub completed_tasks: i64,
+    pub pending_tasks: i64,
+}
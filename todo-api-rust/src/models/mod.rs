@@ -1,11 +1,40 @@
 pub mod user;
 pub mod list;
 pub mod task;
+pub mod reminder;
 pub mod sync;
 pub mod jwt;
+pub mod stats;
+pub mod calendar;
+pub mod settings;
+pub mod health;
+pub mod account;
+pub mod api_token;
+pub mod invitation;
+pub mod oidc;
+pub mod webhook;
+pub mod import;
+pub mod export;
+pub mod inbound;
+pub mod push;
+pub mod validation;
 
 pub use user::*;
 pub use list::*;
 pub use task::*;
+pub use reminder::*;
 pub use sync::*;
 pub use jwt::*;
+pub use stats::*;
+pub use calendar::*;
+pub use settings::*;
+pub use health::*;
+pub use account::*;
+pub use api_token::*;
+pub use invitation::*;
+pub use oidc::*;
+pub use webhook::*;
+pub use import::*;
+pub use export::*;
+pub use inbound::*;
+pub use push::*;
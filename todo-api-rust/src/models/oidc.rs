@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OidcProviderInfo {
+    /// Goes in `/auth/oidc/{name}/login`
+    pub name: String,
+    pub display_name: String,
+}
+
+/// What the provider appends to the redirect URL.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    /// Set instead of `code` when the user cancels or the provider refuses
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+// Row of `oidc_login_states`, kept between the redirect to the provider and the callback
+#[derive(Debug, FromRow)]
+pub struct OidcLoginState {
+    pub provider: String,
+    pub nonce: String,
+    pub pkce_verifier: String,
+    pub expires_at: DateTime<Utc>,
+}
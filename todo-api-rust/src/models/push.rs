@@ -0,0 +1,90 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use super::validation;
+
+pub const PUSH_SUBSCRIPTION_COLUMNS: &str =
+    "id, endpoint, p256dh, auth, user_agent, created_at, last_used_at";
+
+// Row of `push_subscriptions`
+#[derive(Debug, Clone, FromRow)]
+pub struct PushSubscription {
+    pub id: String,
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PushSubscriptionResponse {
+    pub id: String,
+    pub endpoint: String,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Last message the push service accepted
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<PushSubscription> for PushSubscriptionResponse {
+    fn from(subscription: PushSubscription) -> Self {
+        Self {
+            id: subscription.id,
+            endpoint: subscription.endpoint,
+            user_agent: subscription.user_agent,
+            created_at: subscription.created_at,
+            last_used_at: subscription.last_used_at,
+        }
+    }
+}
+
+/// Keys of a subscription, base64url as in `PushSubscription.toJSON()`.
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct PushKeys {
+    /// Uncompressed P-256 public key of the browser
+    #[validate(custom = "validation::push_public_key")]
+    pub p256dh: String,
+    /// 16-byte authentication secret
+    #[validate(custom = "validation::push_auth_secret")]
+    pub auth: String,
+}
+
+/// The JSON of `PushSubscription.toJSON()`; `expirationTime` is ignored.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreatePushSubscriptionRequest {
+    #[validate(custom = "validation::push_endpoint")]
+    pub endpoint: String,
+    #[validate]
+    pub keys: PushKeys,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VapidKeyResponse {
+    /// `applicationServerKey` for `pushManager.subscribe`, base64url
+    pub public_key: String,
+}
+
+/// A subscription made by the test push service, ready for `POST /push/subscriptions`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PushSinkSubscription {
+    pub endpoint: String,
+    pub keys: PushKeys,
+}
+
+/// A message the test push service received, already decrypted.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PushSinkMessage {
+    pub received_at: DateTime<Utc>,
+    pub ttl: Option<u32>,
+    pub urgency: Option<String>,
+    /// `sub` of the VAPID token
+    pub subject: Option<String>,
+    /// The JSON the server sent
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+}
@@ -0,0 +1,93 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use super::validation;
+
+/// Columns of a reminder joined with the due date of its task, which relative
+/// reminders are computed from.
+pub const REMINDER_COLUMNS: &str =
+    "r.id, r.task_id, r.remind_at, r.minutes_before_due, r.snoozed_until, r.dismissed_at, r.created_at, r.updated_at, t.due_at";
+
+// Row of `task_reminders` plus `tasks.due_at`
+#[derive(Debug, Clone, FromRow)]
+pub struct TaskReminder {
+    pub id: String,
+    pub task_id: String,
+    pub remind_at: Option<DateTime<Utc>>,
+    pub minutes_before_due: Option<u32>,
+    pub snoozed_until: Option<DateTime<Utc>>,
+    pub dismissed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+impl TaskReminder {
+    /// When the reminder rings next: the snooze if any, else its own time. `None` once
+    /// dismissed, or for a relative reminder on a task without a due date.
+    pub fn fires_at(&self) -> Option<DateTime<Utc>> {
+        if self.dismissed_at.is_some() {
+            return None;
+        }
+        self.snoozed_until.or(self.remind_at).or_else(|| {
+            let minutes = self.minutes_before_due?;
+            Some(self.due_at? - Duration::minutes(minutes.into()))
+        })
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskReminderResponse {
+    pub id: String,
+    pub task_id: String,
+    /// Fixed time; `null` for reminders relative to the due date
+    pub remind_at: Option<DateTime<Utc>>,
+    /// e.g. 1440 for "1 day before"; `null` for fixed times
+    pub minutes_before_due: Option<u32>,
+    pub snoozed_until: Option<DateTime<Utc>>,
+    pub dismissed_at: Option<DateTime<Utc>>,
+    /// When devices should ring, already accounting for snooze and dismissal
+    pub fires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<TaskReminder> for TaskReminderResponse {
+    fn from(reminder: TaskReminder) -> Self {
+        Self {
+            fires_at: reminder.fires_at(),
+            id: reminder.id,
+            task_id: reminder.task_id,
+            remind_at: reminder.remind_at,
+            minutes_before_due: reminder.minutes_before_due,
+            snoozed_until: reminder.snoozed_until,
+            dismissed_at: reminder.dismissed_at,
+            created_at: reminder.created_at,
+            updated_at: reminder.updated_at,
+        }
+    }
+}
+
+/// Exactly one of `remind_at` and `minutes_before_due`.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ReminderRequest {
+    /// RFC 3339, or a local date/time in the user's time zone
+    #[validate(custom = "validation::datetime")]
+    pub remind_at: Option<String>,
+    /// Up to a year before the due date
+    #[validate(range(max = 525_600, code = "invalid_reminder_offset"))]
+    pub minutes_before_due: Option<u32>,
+}
+
+/// `until` wins over `minutes`; with neither the reminder rings again in 10 minutes.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct SnoozeReminderRequest {
+    #[validate(range(min = 1, max = 10_080, code = "invalid_snooze"))]
+    pub minutes: Option<u32>,
+    /// RFC 3339, or a local date/time in the user's time zone
+    #[validate(custom = "validation::datetime")]
+    pub until: Option<String>,
+}
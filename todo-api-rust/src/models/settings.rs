@@ -0,0 +1,74 @@
+use chrono::{DateTime, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use super::validation;
+
+use crate::i18n;
+
+pub const DEFAULT_TIME_ZONE: &str = "UTC";
+pub const DEFAULT_WEEK_START: &str = "monday";
+
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct UserSettings {
+    pub time_zone: String,
+    pub locale: String,
+    pub week_start: String,
+    pub default_reminder_time: NaiveTime,
+    /// Reminders sent by the server also go out by email
+    pub reminder_email: bool,
+    /// A daily email with overdue tasks and tasks due today, sent at `default_reminder_time`
+    pub digest_email: bool,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl UserSettings {
+    pub fn defaults() -> Self {
+        Self {
+            time_zone: DEFAULT_TIME_ZONE.to_string(),
+            locale: i18n::DEFAULT_LOCALE.tag().to_string(),
+            week_start: DEFAULT_WEEK_START.to_string(),
+            default_reminder_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            reminder_email: false,
+            digest_email: false,
+            updated_at: None,
+        }
+    }
+
+    pub fn tz(&self) -> Tz {
+        self.time_zone.parse().unwrap_or(Tz::UTC)
+    }
+
+    pub fn week_start_day(&self) -> Weekday {
+        self.week_start.parse().unwrap_or(Weekday::Mon)
+    }
+}
+
+pub fn weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateSettingsRequest {
+    #[validate(custom = "validation::time_zone")]
+    pub time_zone: Option<String>,
+    #[validate(custom = "validation::locale")]
+    pub locale: Option<String>,
+    #[validate(custom = "validation::week_start")]
+    pub week_start: Option<String>,
+    #[validate(custom = "validation::time_of_day")]
+    pub default_reminder_time: Option<String>,
+    pub reminder_email: Option<bool>,
+    pub digest_email: Option<bool>,
+}
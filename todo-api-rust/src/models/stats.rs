@@ -0,0 +1,27 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DailyCompletions {
+    pub date: NaiveDate,
+    pub completed: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WeeklyCompletions {
+    pub week_start: NaiveDate,
+    pub completed: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StatsResponse {
+    pub total_tasks: i64,
+    pub pending_tasks: i64,
+    pub completed_tasks: i64,
+    pub overdue_tasks: i64,
+    pub completions_per_day: Vec<DailyCompletions>,
+    pub completions_per_week: Vec<WeeklyCompletions>,
+    pub current_streak: i64,
+    pub longest_streak: i64,
+}
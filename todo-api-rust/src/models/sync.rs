@@ -1,11 +1,17 @@
 use serde::{Deserialize, Serialize};
-use super::{ListResponse, TaskResponse};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
 
-#[derive(Debug, Deserialize)]
+use super::validation;
+use super::{ListResponse, TaskReminderResponse, TaskResponse};
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct SyncPushRequest {
     #[serde(default)]
+    #[validate]
     pub lists: Vec<SyncListItem>,
     #[serde(default)]
+    #[validate]
     pub tasks: Vec<SyncTaskItem>,
     #[serde(default)]
     pub deleted_lists: Vec<String>,
@@ -13,28 +19,31 @@ pub struct SyncPushRequest {
     pub deleted_tasks: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct SyncListItem {
     pub id: Option<String>,
+    #[validate(custom = "validation::not_blank", length(max = 255, code = "field_too_long"))]
     pub name: String,
     pub color: Option<String>,
     pub created_at: Option<String>,
-    pub updated_at: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct SyncTaskItem {
     pub id: Option<String>,
+    #[validate(custom = "validation::not_blank")]
     pub list_id: String,
+    #[validate(custom = "validation::not_blank", length(max = 500, code = "field_too_long"))]
     pub title: String,
     pub description: Option<String>,
     pub completed: Option<bool>,
     pub reminder: Option<String>,
+    pub due_at: Option<String>,
+    pub recurrence: Option<String>,
     pub created_at: Option<String>,
-    pub updated_at: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SyncPushResponse {
     pub synced_lists: usize,
     pub synced_tasks: usize,
@@ -43,23 +52,36 @@ pub struct SyncPushResponse {
     pub server_time: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct SyncPullRequest {
+    /// `server_time` of the previous sync; omit for everything.
+    #[validate(custom = "validation::datetime")]
     pub last_sync: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SyncPullResponse {
     pub lists: Vec<ListResponse>,
     pub tasks: Vec<TaskResponse>,
+    /// Every reminder of the tasks above; a task's reminders replace what the device has
+    pub reminders: Vec<TaskReminderResponse>,
     pub deleted_lists: Vec<String>,
     pub deleted_tasks: Vec<String>,
     pub server_time: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SyncFullResponse {
     pub lists: Vec<ListResponse>,
     pub tasks: Vec<TaskResponse>,
+    pub reminders: Vec<TaskReminderResponse>,
     pub server_time: String,
 }
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventsQuery {
+    /// Same as the `X-Device-Id` header, for clients that can't set headers. Changes made
+    /// with this device ID are not sent back.
+    pub device_id: Option<String>,
+}
@@ -1,6 +1,10 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use super::validation;
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Task {
@@ -10,13 +14,19 @@ pub struct Task {
     pub description: Option<String>,
     pub completed: bool,
     pub reminder: Option<DateTime<Utc>>,
+    #[sqlx(default)]
+    pub due_at: Option<DateTime<Utc>>,
+    #[sqlx(default)]
+    pub recurrence: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     #[sqlx(default)]
     pub deleted_at: Option<DateTime<Utc>>,
+    #[sqlx(default)]
+    pub completed_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct TaskResponse {
     pub id: String,
     pub list_id: String,
@@ -24,6 +34,8 @@ pub struct TaskResponse {
     pub description: Option<String>,
     pub completed: bool,
     pub reminder: Option<DateTime<Utc>>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub recurrence: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -37,27 +49,93 @@ impl From<Task> for TaskResponse {
             description: task.description,
             completed: task.completed,
             reminder: task.reminder,
+            due_at: task.due_at,
+            recurrence: task.recurrence,
             created_at: task.created_at,
             updated_at: task.updated_at,
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct CreateTaskRequest {
     pub id: Option<String>,
+    #[validate(custom = "validation::not_blank")]
     pub list_id: String,
+    #[validate(custom = "validation::not_blank", length(max = 500, code = "field_too_long"))]
     pub title: String,
     pub description: Option<String>,
     pub completed: Option<bool>,
+    /// RFC 3339, or a local date/time in the user's time zone. Empty clears it.
+    #[validate(custom = "validation::datetime")]
     pub reminder: Option<String>,
+    /// Same formats as `reminder`.
+    #[validate(custom = "validation::datetime")]
+    pub due_at: Option<String>,
+    /// RRULE subset, e.g. `FREQ=WEEKLY;BYDAY=MO,TH`. Empty clears it.
+    #[validate(custom = "validation::recurrence")]
+    pub recurrence: Option<String>,
     pub created_at: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpdateTaskRequest {
+    #[validate(custom = "validation::not_blank", length(max = 500, code = "field_too_long"))]
     pub title: Option<String>,
     pub description: Option<String>,
     pub completed: Option<bool>,
+    /// RFC 3339, or a local date/time in the user's time zone. Empty clears it.
+    #[validate(custom = "validation::datetime")]
     pub reminder: Option<String>,
+    /// Same formats as `reminder`.
+    #[validate(custom = "validation::datetime")]
+    pub due_at: Option<String>,
+    /// RRULE subset, e.g. `FREQ=WEEKLY;BYDAY=MO,TH`. Empty clears it.
+    #[validate(custom = "validation::recurrence")]
+    pub recurrence: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TasksQuery {
+    /// `today`, `week` or `overdue`, in the user's time zone.
+    #[validate(custom = "validation::due_filter")]
+    pub due: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct QuickAddRequest {
+    /// e.g. `Pay rent every month on the 5th at 9am #home !high`
+    #[validate(custom = "validation::not_blank", length(max = 1000, code = "field_too_long"))]
+    pub text: String,
+    /// List used when the text names none with `#`. Defaults to the oldest list.
+    pub list_id: Option<String>,
+}
+
+/// Part of the text that was read as a field, so apps can highlight it.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QuickAddPhrase {
+    pub text: String,
+    /// `list`, `priority`, `date`, `time` or `recurrence`
+    pub kind: String,
+}
+
+/// What the quick-add text was understood as.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QuickAddParsed {
+    pub title: String,
+    pub list_id: String,
+    /// `high`, `medium` or `low`. Tasks have no priority yet, so it is only reported.
+    pub priority: Option<String>,
+    pub due_at: Option<DateTime<Utc>>,
+    /// Set when the text has a time of day
+    pub reminder: Option<DateTime<Utc>>,
+    pub recurrence: Option<String>,
+    pub phrases: Vec<QuickAddPhrase>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QuickAddResponse {
+    pub task: TaskResponse,
+    pub parsed: QuickAddParsed,
 }
@@ -1,6 +1,10 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use super::validation;
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct User {
@@ -14,15 +18,19 @@ pub struct User {
     pub verification_code: Option<String>,
     #[serde(skip_serializing)]
     pub code_expires_at: Option<DateTime<Utc>>,
+    #[sqlx(default)]
+    pub deletion_scheduled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UserPublic {
     pub id: String,
     pub username: String,
     pub email: String,
+    /// Set while the account is waiting to be deleted; `POST /auth/me/restore` cancels it
+    pub deletion_scheduled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -32,107 +40,172 @@ impl From<User> for UserPublic {
             id: user.id,
             username: user.username,
             email: user.email,
+            deletion_scheduled_at: user.deletion_scheduled_at,
             created_at: user.created_at,
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct RegisterRequest {
+    #[validate(custom = "validation::username", length(max = 100, code = "field_too_long"))]
     pub username: String,
+    #[validate(custom = "validation::email")]
     pub email: String,
+    #[validate(custom = "validation::password")]
     pub password: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct VerifyEmailRequest {
+    #[validate(custom = "validation::email")]
     pub email: String,
+    #[validate(custom = "validation::not_blank")]
     pub code: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct ResendCodeRequest {
+    #[validate(custom = "validation::email")]
     pub email: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct ForgotPasswordRequest {
+    #[validate(custom = "validation::email")]
     pub email: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct VerifyResetCodeRequest {
+    #[validate(custom = "validation::email")]
     pub email: String,
+    #[validate(custom = "validation::not_blank")]
     pub code: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct ResetPasswordRequest {
+    #[validate(custom = "validation::email")]
     pub email: String,
+    #[validate(custom = "validation::not_blank")]
     pub reset_token: String,
+    #[validate(custom = "validation::password")]
     pub new_password: String,
+    #[validate(must_match(other = "new_password", code = "passwords_do_not_match"))]
     pub confirm_password: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct LoginRequest {
+    #[validate(custom = "validation::not_blank")]
     pub email: String,
+    #[validate(custom = "validation::not_blank")]
     pub password: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct LoginResponse {
     pub user: UserPublic,
     pub token: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct RegisterResponse {
     pub email: String,
     pub requires_verification: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct VerifyResponse {
     pub user: UserPublic,
     pub token: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ForgotPasswordResponse {
     pub email: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct VerifyResetCodeResponse {
     pub reset_token: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpdateUsernameRequest {
+    #[validate(custom = "validation::username", length(max = 100, code = "field_too_long"))]
     pub username: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UpdateUsernameResponse {
     pub username: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpdateEmailRequest {
+    #[validate(custom = "validation::email")]
     pub email: String,
+    #[validate(length(min = 1, code = "current_password_required"))]
     pub password: String,
 }
 
-#[derive(Debug, Serialize)]
+/// Returned by `update-email`: the new address only takes effect after `confirm-email-change`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EmailChangeResponse {
+    pub pending_email: String,
+    pub code_expires_at: DateTime<Utc>,
+}
+
+// Confirmed row of `email_changes` that a revert link still undoes
+#[derive(Debug, FromRow)]
+pub struct RevertableEmailChange {
+    pub id: String,
+    pub user_id: String,
+    pub old_email: String,
+    pub confirmed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ConfirmEmailChangeRequest {
+    /// Code sent to the new address
+    #[validate(custom = "validation::not_blank")]
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UpdateEmailResponse {
     pub email: String,
     pub token: String,
 }
 
-#[derive(Debug, Deserialize)]
+/// Query of the revert link, and the form its confirmation page posts.
+#[derive(Debug, Deserialize, Validate, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct RevertEmailChangeRequest {
+    /// Token from the link sent to the previous address
+    #[validate(custom = "validation::not_blank")]
+    pub token: String,
+}
+
+// Row of `email_changes`
+#[derive(Debug, FromRow)]
+pub struct EmailChange {
+    pub id: String,
+    pub user_id: String,
+    pub old_email: String,
+    pub new_email: String,
+    pub code: String,
+    pub code_expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpdatePasswordRequest {
+    #[validate(length(min = 1, code = "current_password_required"))]
     pub current_password: String,
+    #[validate(custom = "validation::password")]
     pub new_password: String,
+    #[validate(must_match(other = "new_password", code = "passwords_do_not_match"))]
     pub confirm_password: String,
 }
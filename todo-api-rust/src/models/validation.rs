@@ -0,0 +1,192 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use validator::ValidationError;
+
+use super::{ExportFormat, FeedComponent, ImportFormat, Scope, WebhookEvent};
+use crate::i18n::{Locale, Msg};
+use crate::password;
+use crate::push;
+use crate::recurrence::Recurrence;
+
+// Custom validators for `#[validate(custom = "...")]`. Error codes are `Msg` keys so
+// `ApiError` can translate them.
+
+fn error(code: Msg) -> ValidationError {
+    ValidationError::new(code.code())
+}
+
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(error(Msg::FieldRequired));
+    }
+    Ok(())
+}
+
+pub fn email(value: &str) -> Result<(), ValidationError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(error(Msg::EmailRequired));
+    }
+    if !validator::validate_email(value) {
+        return Err(error(Msg::InvalidEmail));
+    }
+    Ok(())
+}
+
+pub fn password(value: &str) -> Result<(), ValidationError> {
+    password::check_policy(value).map_err(error)
+}
+
+pub fn username(value: &str) -> Result<(), ValidationError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(error(Msg::UsernameRequired));
+    }
+    if value.chars().count() < 3 {
+        return Err(error(Msg::UsernameTooShort));
+    }
+    Ok(())
+}
+
+pub fn hex_color(value: &str) -> Result<(), ValidationError> {
+    let hex = value.strip_prefix('#').unwrap_or("");
+    if !matches!(hex.len(), 6 | 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(error(Msg::InvalidColor));
+    }
+    Ok(())
+}
+
+/// RFC 3339, local "YYYY-MM-DDTHH:MM[:SS]" or a bare date. Empty clears the field.
+pub fn datetime(value: &str) -> Result<(), ValidationError> {
+    let value = value.trim();
+    let valid = value.is_empty()
+        || value.parse::<DateTime<Utc>>().is_ok()
+        || NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").is_ok()
+        || NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M").is_ok()
+        || NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok();
+    if !valid {
+        return Err(error(Msg::InvalidDate));
+    }
+    Ok(())
+}
+
+pub fn recurrence(value: &str) -> Result<(), ValidationError> {
+    if !value.trim().is_empty() && value.parse::<Recurrence>().is_err() {
+        return Err(error(Msg::InvalidRecurrence));
+    }
+    Ok(())
+}
+
+pub fn time_zone(value: &str) -> Result<(), ValidationError> {
+    if value.trim().parse::<Tz>().is_err() {
+        return Err(error(Msg::InvalidTimeZone));
+    }
+    Ok(())
+}
+
+pub fn feed_component(value: &str) -> Result<(), ValidationError> {
+    if value.parse::<FeedComponent>().is_err() {
+        return Err(error(Msg::InvalidFeedComponent));
+    }
+    Ok(())
+}
+
+pub fn import_format(value: &str) -> Result<(), ValidationError> {
+    if value.parse::<ImportFormat>().is_err() {
+        return Err(error(Msg::InvalidImportFormat));
+    }
+    Ok(())
+}
+
+pub fn export_format(value: &str) -> Result<(), ValidationError> {
+    if value.parse::<ExportFormat>().is_err() {
+        return Err(error(Msg::InvalidExportFormat));
+    }
+    Ok(())
+}
+
+pub fn locale(value: &str) -> Result<(), ValidationError> {
+    if Locale::from_tag(value).is_none() {
+        return Err(error(Msg::UnsupportedLocale));
+    }
+    Ok(())
+}
+
+pub fn week_start(value: &str) -> Result<(), ValidationError> {
+    if value.trim().parse::<Weekday>().is_err() {
+        return Err(error(Msg::InvalidWeekStart));
+    }
+    Ok(())
+}
+
+pub fn time_of_day(value: &str) -> Result<(), ValidationError> {
+    let value = value.trim();
+    if NaiveTime::parse_from_str(value, "%H:%M").is_err()
+        && NaiveTime::parse_from_str(value, "%H:%M:%S").is_err()
+    {
+        return Err(error(Msg::InvalidReminderTime));
+    }
+    Ok(())
+}
+
+pub fn scopes(values: &[String]) -> Result<(), ValidationError> {
+    if values.is_empty() {
+        return Err(error(Msg::ScopesRequired));
+    }
+    if values.iter().any(|value| value.parse::<Scope>().is_err()) {
+        return Err(error(Msg::InvalidScope));
+    }
+    Ok(())
+}
+
+pub fn webhook_url(value: &str) -> Result<(), ValidationError> {
+    let value = value.trim();
+    let valid = (value.starts_with("https://") || value.starts_with("http://"))
+        && validator::validate_url(value);
+    if !valid {
+        return Err(error(Msg::InvalidWebhookUrl));
+    }
+    Ok(())
+}
+
+pub fn webhook_events(values: &[String]) -> Result<(), ValidationError> {
+    if values.is_empty() {
+        return Err(error(Msg::WebhookEventsRequired));
+    }
+    if values.iter().any(|value| value.parse::<WebhookEvent>().is_err()) {
+        return Err(error(Msg::InvalidWebhookEvent));
+    }
+    Ok(())
+}
+
+pub fn push_endpoint(value: &str) -> Result<(), ValidationError> {
+    let value = value.trim();
+    let valid = (value.starts_with("https://") || value.starts_with("http://"))
+        && value.len() <= 2048
+        && validator::validate_url(value);
+    if !valid {
+        return Err(error(Msg::InvalidPushEndpoint));
+    }
+    Ok(())
+}
+
+pub fn push_public_key(value: &str) -> Result<(), ValidationError> {
+    match push::decode_base64url(value) {
+        Some(key) if push::is_public_key(&key) => Ok(()),
+        _ => Err(error(Msg::InvalidPushKeys)),
+    }
+}
+
+pub fn push_auth_secret(value: &str) -> Result<(), ValidationError> {
+    match push::decode_base64url(value) {
+        Some(secret) if secret.len() == push::AUTH_SECRET_LEN => Ok(()),
+        _ => Err(error(Msg::InvalidPushKeys)),
+    }
+}
+
+pub fn due_filter(value: &str) -> Result<(), ValidationError> {
+    if !matches!(value, "today" | "week" | "overdue") {
+        return Err(error(Msg::InvalidDueFilter));
+    }
+    Ok(())
+}
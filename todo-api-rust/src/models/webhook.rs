@@ -0,0 +1,214 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use super::validation;
+
+/// What a webhook can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEvent {
+    TaskCreated,
+    TaskUpdated,
+    TaskCompleted,
+    TaskDeleted,
+    ListCreated,
+    ListUpdated,
+    ListDeleted,
+    /// Sent by the reminder dispatcher when a task's reminder is due
+    ReminderDue,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 8] = [
+        WebhookEvent::TaskCreated,
+        WebhookEvent::TaskUpdated,
+        WebhookEvent::TaskCompleted,
+        WebhookEvent::TaskDeleted,
+        WebhookEvent::ListCreated,
+        WebhookEvent::ListUpdated,
+        WebhookEvent::ListDeleted,
+        WebhookEvent::ReminderDue,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::TaskCreated => "task.created",
+            WebhookEvent::TaskUpdated => "task.updated",
+            WebhookEvent::TaskCompleted => "task.completed",
+            WebhookEvent::TaskDeleted => "task.deleted",
+            WebhookEvent::ListCreated => "list.created",
+            WebhookEvent::ListUpdated => "list.updated",
+            WebhookEvent::ListDeleted => "list.deleted",
+            WebhookEvent::ReminderDue => "reminder.due",
+        }
+    }
+
+    /// Event for a `sync_log` entry. Completion has no action of its own there, so callers
+    /// add `TaskCompleted` themselves.
+    pub fn from_change(entity_type: &str, action: &str) -> Option<Self> {
+        match (entity_type, action) {
+            ("task", "create") => Some(WebhookEvent::TaskCreated),
+            ("task", "update") => Some(WebhookEvent::TaskUpdated),
+            ("task", "delete") => Some(WebhookEvent::TaskDeleted),
+            ("list", "create") => Some(WebhookEvent::ListCreated),
+            ("list", "update") => Some(WebhookEvent::ListUpdated),
+            ("list", "delete") => Some(WebhookEvent::ListDeleted),
+            _ => None,
+        }
+    }
+
+    pub fn entity_type(self) -> &'static str {
+        self.as_str().split('.').next().unwrap_or_default()
+    }
+}
+
+impl FromStr for WebhookEvent {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WebhookEvent::ALL.into_iter().find(|event| event.as_str() == s).ok_or(())
+    }
+}
+
+impl fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Row of `webhooks`
+#[derive(Debug, Clone, FromRow)]
+pub struct Webhook {
+    pub id: String,
+    pub user_id: String,
+    pub url: String,
+    pub secret: String,
+    /// Space separated, as stored
+    pub events: String,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    pub fn events(&self) -> Vec<WebhookEvent> {
+        self.events.split_whitespace().filter_map(|s| s.parse().ok()).collect()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookResponse {
+    pub id: String,
+    pub url: String,
+    pub events: Vec<String>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Webhook> for WebhookResponse {
+    fn from(webhook: Webhook) -> Self {
+        Self {
+            events: webhook.events().into_iter().map(|e| e.to_string()).collect(),
+            id: webhook.id,
+            url: webhook.url,
+            is_active: webhook.is_active,
+            created_at: webhook.created_at,
+            updated_at: webhook.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateWebhookRequest {
+    #[validate(custom = "validation::webhook_url", length(max = 2048, code = "field_too_long"))]
+    pub url: String,
+    /// Any of `task.created`, `task.updated`, `task.completed`, `task.deleted`,
+    /// `list.created`, `list.updated`, `list.deleted`, `reminder.due`
+    #[validate(custom = "validation::webhook_events")]
+    pub events: Vec<String>,
+    /// Key for the `X-Webhook-Signature` HMAC. Generated when omitted.
+    #[validate(length(min = 16, max = 100, code = "invalid_webhook_secret"))]
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateWebhookRequest {
+    #[validate(custom = "validation::webhook_url", length(max = 2048, code = "field_too_long"))]
+    pub url: Option<String>,
+    #[validate(custom = "validation::webhook_events")]
+    pub events: Option<Vec<String>>,
+    #[validate(length(min = 16, max = 100, code = "invalid_webhook_secret"))]
+    pub secret: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateWebhookResponse {
+    /// Shown only here and when set through `PUT`
+    pub secret: String,
+    pub webhook: WebhookResponse,
+}
+
+// Row of `webhook_deliveries`, without the worker's bookkeeping columns
+#[derive(Debug, FromRow)]
+pub struct WebhookDelivery {
+    pub id: u64,
+    pub event: String,
+    pub status: String,
+    pub attempts: u32,
+    pub response_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub payload: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookDeliveryResponse {
+    pub id: u64,
+    pub event: String,
+    /// `pending`, `sending`, `delivered` or `failed`
+    pub status: String,
+    pub attempts: u32,
+    /// HTTP status of the last attempt, if the endpoint answered
+    pub response_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    /// The JSON body that is (or was) sent
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+}
+
+impl From<WebhookDelivery> for WebhookDeliveryResponse {
+    fn from(delivery: WebhookDelivery) -> Self {
+        Self {
+            payload: serde_json::from_str(&delivery.payload).unwrap_or_default(),
+            id: delivery.id,
+            event: delivery.event,
+            status: delivery.status,
+            attempts: delivery.attempts,
+            response_status: delivery.response_status,
+            last_error: delivery.last_error,
+            next_attempt_at: delivery.next_attempt_at,
+            delivered_at: delivery.delivered_at,
+            created_at: delivery.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeliveriesQuery {
+    /// Newest first; 50 by default, at most 200
+    #[validate(range(min = 1, max = 200, code = "invalid_query"))]
+    pub limit: Option<u32>,
+}
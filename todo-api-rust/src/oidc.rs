@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use actix_web::http::StatusCode;
+use openidconnect::core::{CoreClient, CoreProviderMetadata};
+use openidconnect::{
+    reqwest, ClientId, ClientSecret, EndpointMaybeSet, EndpointNotSet, EndpointSet, IssuerUrl,
+    RedirectUrl,
+};
+
+use crate::errors::ApiError;
+use crate::i18n::Msg;
+
+// Discovery documents and signing keys are fetched again after this
+const METADATA_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Client for one provider, as built from its discovery document.
+pub type ProviderClient = CoreClient<
+    EndpointSet,
+    EndpointNotSet,
+    EndpointNotSet,
+    EndpointNotSet,
+    EndpointMaybeSet,
+    EndpointMaybeSet,
+>;
+
+/// An identity provider from `OIDC_PROVIDERS`.
+#[derive(Debug, Clone)]
+pub struct OidcProvider {
+    /// Used in URLs (`/auth/oidc/{name}/...`) and to link identities
+    pub name: String,
+    pub display_name: String,
+    pub issuer_url: String,
+    pub client_id: String,
+    /// Unset for public clients, which rely on PKCE alone
+    pub client_secret: Option<String>,
+    /// Requested on top of `openid`
+    pub scopes: Vec<String>,
+    pub redirect_url: String,
+}
+
+/// Reads `OIDC_PROVIDERS` (comma separated names) and, for each name, the
+/// `OIDC_<NAME>_*` variables through `lookup`. Issuer and client ID are required.
+pub fn providers_from_vars(lookup: impl Fn(&str) -> Option<String>, public_url: &str) -> Vec<OidcProvider> {
+    let names = lookup("OIDC_PROVIDERS").unwrap_or_default();
+
+    names
+        .split(',')
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let prefix = format!("OIDC_{}_", name.to_uppercase().replace('-', "_"));
+            let var = |key: &str| lookup(&format!("{}{}", prefix, key)).filter(|v| !v.is_empty());
+
+            OidcProvider {
+                issuer_url: var("ISSUER").unwrap_or_else(|| panic!("{}ISSUER must be set", prefix)),
+                client_id: var("CLIENT_ID").unwrap_or_else(|| panic!("{}CLIENT_ID must be set", prefix)),
+                client_secret: var("CLIENT_SECRET"),
+                display_name: var("DISPLAY_NAME").unwrap_or_else(|| name.clone()),
+                scopes: var("SCOPES")
+                    .unwrap_or_else(|| "email profile".to_string())
+                    .split_whitespace()
+                    .filter(|scope| *scope != "openid")
+                    .map(str::to_string)
+                    .collect(),
+                redirect_url: var("REDIRECT_URL")
+                    .unwrap_or_else(|| format!("{}/auth/oidc/{}/callback", public_url, name)),
+                name,
+            }
+        })
+        .collect()
+}
+
+/// Shared HTTP client plus a cache of each provider's discovery document.
+pub struct Oidc {
+    pub http: reqwest::Client,
+    metadata: RwLock<HashMap<String, (Instant, CoreProviderMetadata)>>,
+}
+
+impl Default for Oidc {
+    fn default() -> Self {
+        // Following redirects would let a provider point us at arbitrary URLs
+        let http = reqwest::ClientBuilder::new()
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to build OIDC HTTP client");
+
+        Self {
+            http,
+            metadata: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl Oidc {
+    /// Client for `provider`, running discovery when the cached document is missing or old.
+    pub async fn client(&self, provider: &OidcProvider) -> Result<ProviderClient, ApiError> {
+        let metadata = match self.cached(&provider.name) {
+            Some(metadata) => metadata,
+            None => {
+                let issuer = IssuerUrl::new(provider.issuer_url.clone())
+                    .map_err(|e| ApiError::internal(format!("Invalid OIDC issuer: {}", e)))?;
+                let metadata = CoreProviderMetadata::discover_async(issuer, &self.http)
+                    .await
+                    .map_err(|e| {
+                        tracing::warn!(provider = %provider.name, error = %e, "OIDC discovery failed");
+                        ApiError::new(Msg::OidcUnavailable, StatusCode::BAD_GATEWAY)
+                    })?;
+
+                if let Ok(mut cache) = self.metadata.write() {
+                    cache.insert(provider.name.clone(), (Instant::now(), metadata.clone()));
+                }
+                metadata
+            }
+        };
+
+        let redirect_url = RedirectUrl::new(provider.redirect_url.clone())
+            .map_err(|e| ApiError::internal(format!("Invalid OIDC redirect URL: {}", e)))?;
+
+        Ok(CoreClient::from_provider_metadata(
+            metadata,
+            ClientId::new(provider.client_id.clone()),
+            provider.client_secret.clone().map(ClientSecret::new),
+        )
+        .set_redirect_uri(redirect_url))
+    }
+
+    /// Forgets the cached document, e.g. after an ID token signed with an unknown key.
+    pub fn invalidate(&self, name: &str) {
+        if let Ok(mut cache) = self.metadata.write() {
+            cache.remove(name);
+        }
+    }
+
+    fn cached(&self, name: &str) -> Option<CoreProviderMetadata> {
+        let cache = self.metadata.read().ok()?;
+        cache
+            .get(name)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < METADATA_TTL)
+            .map(|(_, metadata)| metadata.clone())
+    }
+}
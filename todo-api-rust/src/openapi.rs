@@ -0,0 +1,136 @@
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::errors::{FieldError, NoData};
+use crate::handlers;
+use crate::i18n::Msg;
+
+/// OpenAPI document built from the handlers' `#[utoipa::path]` annotations. Served at
+/// `/openapi.json`, with Swagger UI at `/docs/`.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Todo API",
+        description = "REST API do Todo App. Respostas usam o envelope `ApiResponse`; erros trazem `code` e, em falhas de validação, `details` por campo."
+    ),
+    paths(
+        handlers::auth::register,
+        handlers::auth::verify_email,
+        handlers::auth::resend_code,
+        handlers::auth::forgot_password,
+        handlers::auth::verify_reset_code,
+        handlers::auth::reset_password,
+        handlers::auth::login,
+        handlers::oidc::list_providers,
+        handlers::oidc::login,
+        handlers::oidc::callback,
+        handlers::auth::me,
+        handlers::auth::update_username,
+        handlers::auth::update_email,
+        handlers::auth::confirm_email_change,
+        handlers::auth::revert_email_change_page,
+        handlers::auth::revert_email_change,
+        handlers::auth::update_password,
+        handlers::account::delete_account,
+        handlers::account::restore_account,
+        handlers::account::export_data,
+        handlers::settings::get_settings,
+        handlers::settings::update_settings,
+        handlers::api_tokens::list_tokens,
+        handlers::api_tokens::create_token,
+        handlers::api_tokens::revoke_token,
+        handlers::invitations::create_invitation,
+        handlers::lists::get_lists,
+        handlers::lists::get_list,
+        handlers::lists::create_list,
+        handlers::lists::update_list,
+        handlers::lists::delete_list,
+        handlers::export::export_list,
+        handlers::export::export_lists,
+        handlers::tasks::get_tasks_by_list,
+        handlers::tasks::get_all_tasks,
+        handlers::tasks::get_task,
+        handlers::tasks::create_task,
+        handlers::tasks::quick_add_task,
+        handlers::tasks::update_task,
+        handlers::tasks::delete_task,
+        handlers::attachments::list_attachments,
+        handlers::attachments::download_attachment,
+        handlers::attachments::delete_attachment,
+        handlers::reminders::list_reminders,
+        handlers::reminders::create_reminder,
+        handlers::reminders::update_reminder,
+        handlers::reminders::delete_reminder,
+        handlers::reminders::snooze_reminder,
+        handlers::reminders::dismiss_reminder,
+        handlers::stats::get_stats,
+        handlers::calendar::get_calendar,
+        handlers::calendar::get_feed,
+        handlers::calendar::create_feed,
+        handlers::calendar::revoke_feed,
+        handlers::calendar::calendar_feed,
+        handlers::sync::sync_push,
+        handlers::sync::sync_pull,
+        handlers::sync::sync_full,
+        handlers::import::import_data,
+        handlers::inbound::list_addresses,
+        handlers::inbound::create_address,
+        handlers::inbound::delete_address,
+        handlers::inbound::receive_email,
+        handlers::events::stream_events,
+        handlers::webhooks::list_webhooks,
+        handlers::webhooks::create_webhook,
+        handlers::webhooks::update_webhook,
+        handlers::webhooks::delete_webhook,
+        handlers::webhooks::list_deliveries,
+        handlers::push::get_vapid_key,
+        handlers::push::list_subscriptions,
+        handlers::push::create_subscription,
+        handlers::push::delete_subscription,
+        handlers::push::test_subscription,
+        handlers::push::create_sink,
+        handlers::push::receive_sink,
+        handlers::push::list_sink_messages,
+        handlers::health::healthz,
+        handlers::health::readyz,
+        handlers::health::metrics,
+    ),
+    components(schemas(Msg, FieldError, NoData)),
+    modifiers(&Extras),
+    tags(
+        (name = "auth", description = "Cadastro, login e conta"),
+        (name = "settings", description = "Preferências do usuário"),
+        (name = "tokens", description = "Tokens de acesso pessoal para scripts e integrações"),
+        (name = "lists", description = "Listas de tarefas"),
+        (name = "tasks", description = "Tarefas"),
+        (name = "stats", description = "Estatísticas de produtividade"),
+        (name = "calendar", description = "Calendário e assinatura iCalendar (.ics)"),
+        (name = "sync", description = "Sincronização offline"),
+        (name = "import", description = "Importação do Todoist, Microsoft To Do e CSV"),
+        (name = "inbound", description = "Email para tarefa: endereços de encaminhamento e recebimento"),
+        (name = "webhooks", description = "Webhooks assinados com HMAC para eventos de listas e tarefas"),
+        (name = "push", description = "Web Push e lembretes enviados pelo servidor"),
+        (name = "health", description = "Saúde do serviço e métricas"),
+    )
+)]
+pub struct ApiDoc;
+
+// Adds the JWT bearer scheme and drops the empty license taken from Cargo.toml
+struct Extras;
+
+impl Modify for Extras {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi.info.license = None;
+
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::future::Future;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::MySqlPool;
+use tokio::sync::Notify;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+pub const BATCH_SIZE: i64 = 20;
+const BASE_BACKOFF_SECS: i64 = 30;
+// Claims older than this belong to a worker that died mid-batch
+const STALE_CLAIM_MINUTES: i64 = 10;
+
+/// A table drained by a background worker. Rows go `pending` → `sending` → a final
+/// status, and carry `claim_token`, `claimed_at` and `next_attempt_at`.
+#[derive(Debug, Clone, Copy)]
+pub enum Queue {
+    Mail,
+    Webhooks,
+}
+
+impl Queue {
+    fn table(&self) -> &'static str {
+        match self {
+            Queue::Mail => "mail_outbox",
+            Queue::Webhooks => "webhook_deliveries",
+        }
+    }
+}
+
+/// Wait before the next attempt: 30s, 1m, 2m, 4m, ... after each failed one.
+pub fn backoff(attempts: u32) -> chrono::Duration {
+    chrono::Duration::seconds(BASE_BACKOFF_SECS << attempts.saturating_sub(1).min(10))
+}
+
+/// Claims up to `BATCH_SIZE` due rows and returns the token they were marked with. The
+/// claim is an UPDATE so several API instances never send the same row; rows left in
+/// `sending` by a worker that died go back to `pending` first.
+pub async fn claim(pool: &MySqlPool, queue: Queue, now: DateTime<Utc>) -> Result<String, sqlx::Error> {
+    sqlx::query(&format!(
+        "UPDATE {} SET status = 'pending', claim_token = NULL WHERE status = 'sending' AND claimed_at < ?",
+        queue.table()
+    ))
+    .bind(now - chrono::Duration::minutes(STALE_CLAIM_MINUTES))
+    .execute(pool)
+    .await?;
+
+    let token = uuid::Uuid::new_v4().to_string();
+    sqlx::query(&format!(
+        r#"
+        UPDATE {}
+        SET status = 'sending', claim_token = ?, claimed_at = ?
+        WHERE status = 'pending' AND next_attempt_at <= ?
+        ORDER BY next_attempt_at, id
+        LIMIT ?
+        "#,
+        queue.table()
+    ))
+    .bind(&token)
+    .bind(now)
+    .bind(now)
+    .bind(BATCH_SIZE)
+    .execute(pool)
+    .await?;
+
+    Ok(token)
+}
+
+/// Runs `batch` until the process exits. Between batches it waits for `wake` or the poll
+/// interval, unless the last batch was full.
+pub async fn drain<F, Fut>(queue: Queue, wake: &Notify, mut batch: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<usize, sqlx::Error>>,
+{
+    loop {
+        let delivered = match batch().await {
+            Ok(count) => count,
+            Err(e) => {
+                tracing::error!(error = %e, queue = queue.table(), "Outbox batch failed");
+                0
+            }
+        };
+
+        // A full batch likely means more is waiting
+        if delivered < BATCH_SIZE as usize {
+            tokio::select! {
+                _ = wake.notified() => {}
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_exponentially() {
+        assert_eq!(backoff(1).num_seconds(), 30);
+        assert_eq!(backoff(2).num_seconds(), 60);
+        assert_eq!(backoff(4).num_seconds(), 240);
+        // Capped so the shift can't overflow
+        assert_eq!(backoff(40), backoff(11));
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use actix_web::web;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use validator::{ValidationError, ValidationErrors};
+
+use crate::config::Config;
+use crate::errors::ApiError;
+use crate::i18n::Msg;
+
+pub const MIN_LENGTH: usize = 8;
+pub const MAX_LENGTH: usize = 128;
+
+const EMBEDDED_BLOCKLIST: &str = include_str!("../data/common-passwords.txt");
+
+static BLOCKLIST: OnceLock<HashSet<String>> = OnceLock::new();
+
+/// Argon2id cost. Defaults follow the OWASP recommendation (19 MiB, 2 passes, 1 lane).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for HashParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl HashParams {
+    fn argon2(&self) -> Result<Argon2<'static>, ApiError> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| ApiError::internal(format!("Invalid Argon2 parameters: {}", e)))?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+/// Argon2id in PHC format (`$argon2id$v=19$m=...`). Runs on the blocking thread pool,
+/// since a hash takes tens of milliseconds that would otherwise stall an actix worker.
+pub async fn hash(password: &str, params: HashParams) -> Result<String, ApiError> {
+    let password = password.to_string();
+    web::block(move || hash_now(&password, params))
+        .await
+        .map_err(|_| ApiError::internal("Failed to hash password"))?
+}
+
+/// Checks a password against an Argon2 hash or a legacy bcrypt one (`$2a$`, `$2b$`, and
+/// the `$2y$` written by the PHP API). Runs on the blocking thread pool, like `hash`.
+pub async fn verify(password: &str, stored: &str) -> Result<bool, ApiError> {
+    let (password, stored) = (password.to_string(), stored.to_string());
+    web::block(move || verify_now(&password, &stored))
+        .await
+        .map_err(|_| ApiError::internal("Password verification failed"))?
+}
+
+fn hash_now(password: &str, params: HashParams) -> Result<String, ApiError> {
+    let salt = SaltString::generate(&mut OsRng);
+    params
+        .argon2()?
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|_| ApiError::internal("Failed to hash password"))
+}
+
+fn verify_now(password: &str, stored: &str) -> Result<bool, ApiError> {
+    if stored.starts_with("$2") {
+        return bcrypt::verify(password, stored)
+            .map_err(|_| ApiError::internal("Password verification failed"));
+    }
+
+    let parsed = PasswordHash::new(stored)
+        .map_err(|_| ApiError::internal("Password verification failed"))?;
+    // Parameters come from the hash itself, so older costs still verify
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .is_ok())
+}
+
+/// True for bcrypt hashes and Argon2 hashes made with other parameters than `params`.
+pub fn needs_rehash(stored: &str, params: HashParams) -> bool {
+    let Ok(parsed) = PasswordHash::new(stored) else {
+        return true;
+    };
+    if parsed.algorithm != Algorithm::Argon2id.ident() || parsed.version != Some(Version::V0x13.into()) {
+        return true;
+    }
+
+    match Params::try_from(&parsed) {
+        Ok(current) => {
+            current.m_cost() != params.memory_kib
+                || current.t_cost() != params.iterations
+                || current.p_cost() != params.parallelism
+        }
+        Err(_) => true,
+    }
+}
+
+// Helper: One lowercase password per line; blank lines and `#` comments are skipped
+fn parse_list(source: &str, into: &mut HashSet<String>) {
+    into.extend(
+        source
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_lowercase),
+    );
+}
+
+fn blocklist() -> &'static HashSet<String> {
+    BLOCKLIST.get_or_init(|| {
+        let mut set = HashSet::new();
+        parse_list(EMBEDDED_BLOCKLIST, &mut set);
+        set
+    })
+}
+
+/// Loads the embedded list of common passwords plus `PASSWORD_BLOCKLIST`, if set.
+/// Called once at startup; a missing file is logged and the embedded list is kept.
+pub fn init(config: &Config) {
+    let mut set = HashSet::new();
+    parse_list(EMBEDDED_BLOCKLIST, &mut set);
+
+    if let Some(path) = &config.password_blocklist {
+        match std::fs::read_to_string(path) {
+            Ok(source) => parse_list(&source, &mut set),
+            Err(e) => tracing::warn!(%path, error = %e, "Could not read password blocklist"),
+        }
+    }
+
+    tracing::info!(entries = set.len(), "Password blocklist loaded");
+    let _ = BLOCKLIST.set(set);
+}
+
+// Helper: "aaaaaaaa", "12345678", "abcdefgh", "87654321"
+fn is_trivial(password: &str) -> bool {
+    let chars: Vec<char> = password.to_lowercase().chars().collect();
+    if chars.windows(2).all(|w| w[0] == w[1]) {
+        return true;
+    }
+
+    let steps: Vec<i64> = chars.windows(2).map(|w| w[1] as i64 - w[0] as i64).collect();
+    steps.iter().all(|&step| step == 1) || steps.iter().all(|&step| step == -1)
+}
+
+/// Length, blocklist and pattern checks, in that order. Used by `validation::password`.
+pub fn check_policy(password: &str) -> Result<(), Msg> {
+    let length = password.chars().count();
+    if length < MIN_LENGTH {
+        return Err(Msg::PasswordTooShort);
+    }
+    if length > MAX_LENGTH {
+        return Err(Msg::PasswordTooLong);
+    }
+    if blocklist().contains(&password.to_lowercase()) {
+        return Err(Msg::PasswordTooCommon);
+    }
+    if is_trivial(password) {
+        return Err(Msg::PasswordTooSimple);
+    }
+    Ok(())
+}
+
+/// Rejects passwords containing the username or the local part of the email. Needs the
+/// account, so handlers call it after `validate()`; the error names `field` like one would.
+pub fn check_personal_info(field: &'static str, password: &str, username: &str, email: &str) -> Result<(), ApiError> {
+    let password = password.to_lowercase();
+    let local_part = email.split('@').next().unwrap_or("");
+
+    let contains = |value: &str| {
+        let value = value.trim().to_lowercase();
+        value.chars().count() >= 3 && password.contains(&value)
+    };
+
+    if contains(username) || contains(local_part) {
+        let mut errors = ValidationErrors::new();
+        errors.add(field, ValidationError::new(Msg::PasswordContainsPersonalInfo.code()));
+        return Err(errors.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cheap enough for tests; production cost comes from `HashParams::default()`
+    const FAST: HashParams = HashParams { memory_kib: 1024, iterations: 1, parallelism: 1 };
+
+    #[actix_web::test]
+    async fn hashes_and_verifies_off_the_worker() {
+        let stored = hash("correct horse battery", FAST).await.unwrap();
+
+        assert!(stored.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+        assert!(verify("correct horse battery", &stored).await.unwrap());
+        assert!(!verify("wrong horse battery", &stored).await.unwrap());
+        assert!(!needs_rehash(&stored, FAST));
+        assert!(needs_rehash(&stored, HashParams::default()));
+    }
+
+    #[actix_web::test]
+    async fn verifies_legacy_bcrypt_hashes() {
+        let stored = bcrypt::hash("correct horse battery", 4).unwrap();
+
+        assert!(verify("correct horse battery", &stored).await.unwrap());
+        assert!(!verify("wrong horse battery", &stored).await.unwrap());
+        assert!(needs_rehash(&stored, FAST));
+    }
+}
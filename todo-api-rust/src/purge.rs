@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use sqlx::MySqlPool;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// How long finished webhook deliveries stay in the delivery log
+const DELIVERY_RETENTION_DAYS: i64 = 30;
+
+/// Deletes accounts whose grace period is over. Lists, tasks, `sync_log`, settings and
+/// email changes go with them through `ON DELETE CASCADE`. Also drops abandoned OIDC
+/// logins and old webhook deliveries.
+pub async fn run(pool: MySqlPool) {
+    loop {
+        if let Err(e) = sqlx::query("DELETE FROM oidc_login_states WHERE expires_at <= ?")
+            .bind(Utc::now())
+            .execute(&pool)
+            .await
+        {
+            tracing::error!(error = %e, "OIDC state cleanup failed");
+        }
+
+        if let Err(e) = sqlx::query(
+            "DELETE FROM webhook_deliveries WHERE status IN ('delivered', 'failed') AND created_at <= ?"
+        )
+        .bind(Utc::now() - chrono::Duration::days(DELIVERY_RETENTION_DAYS))
+        .execute(&pool)
+        .await
+        {
+            tracing::error!(error = %e, "Webhook delivery cleanup failed");
+        }
+
+        let result = sqlx::query("DELETE FROM users WHERE deletion_scheduled_at <= ?")
+            .bind(Utc::now())
+            .execute(&pool)
+            .await;
+
+        match result {
+            Ok(done) if done.rows_affected() > 0 => {
+                tracing::info!(accounts = done.rows_affected(), "Deleted accounts past their grace period");
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!(error = %e, "Account purge failed"),
+        }
+
+        tokio::time::sleep(PURGE_INTERVAL).await;
+    }
+}
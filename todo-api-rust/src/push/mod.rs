@@ -0,0 +1,284 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use hkdf::Hkdf;
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::derive::Deriver;
+use openssl::ec::{EcGroup, EcKey, EcPoint, PointConversionForm};
+use openssl::ecdsa::EcdsaSig;
+use openssl::nid::Nid;
+use openssl::pkey::{HasPublic, PKey, Private, Public};
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use rand::Rng;
+use reqwest::header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::models::PushSubscription;
+use crate::webhooks::target::{self, InternalTarget};
+
+pub mod sink;
+
+/// Length of an uncompressed P-256 public key (`0x04 || x || y`).
+pub const PUBLIC_KEY_LEN: usize = 65;
+/// Length of the `auth` secret of a subscription.
+pub const AUTH_SECRET_LEN: usize = 16;
+/// Largest payload every push service has to accept (RFC 8291, section 4).
+pub const MAX_PAYLOAD: usize = 3993;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Push services reject VAPID tokens valid for more than 24 hours
+const TOKEN_LIFETIME_HOURS: i64 = 12;
+// One record holds the whole message, so this only has to be larger than it
+const RECORD_SIZE: u32 = 4096;
+const SALT_LEN: usize = 16;
+const TAG_LEN: usize = 16;
+// salt, record size, key id length and the sender's public key
+const HEADER_LEN: usize = SALT_LEN + 4 + 1 + PUBLIC_KEY_LEN;
+
+// Helper: Errors here are reported as text, like other delivery failures
+fn failure(e: impl Display) -> String {
+    e.to_string()
+}
+
+fn curve() -> EcGroup {
+    EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).expect("P-256 is built into OpenSSL")
+}
+
+/// Base64url with or without padding, as browsers send keys either way.
+pub fn decode_base64url(value: &str) -> Option<Vec<u8>> {
+    URL_SAFE_NO_PAD.decode(value.trim().trim_end_matches('=')).ok()
+}
+
+// Helper: `0x04 || x || y`
+fn public_bytes<T: HasPublic>(key: &EcKey<T>) -> Result<Vec<u8>, String> {
+    let mut ctx = BigNumContext::new().map_err(failure)?;
+    key.public_key()
+        .to_bytes(key.group(), PointConversionForm::UNCOMPRESSED, &mut ctx)
+        .map_err(failure)
+}
+
+fn public_key(bytes: &[u8]) -> Result<EcKey<Public>, String> {
+    let group = curve();
+    let mut ctx = BigNumContext::new().map_err(failure)?;
+    let point = EcPoint::from_bytes(&group, bytes, &mut ctx).map_err(failure)?;
+    EcKey::from_public_key(&group, &point).map_err(failure)
+}
+
+/// Whether `bytes` is a point on P-256, as a subscription's `p256dh` has to be.
+pub fn is_public_key(bytes: &[u8]) -> bool {
+    bytes.len() == PUBLIC_KEY_LEN && public_key(bytes).is_ok()
+}
+
+// Helper: ECDH shared secret, the x coordinate of the shared point
+fn shared_secret<T: HasPublic>(own: &EcKey<Private>, peer: &EcKey<T>) -> Result<Vec<u8>, String> {
+    let own = PKey::from_ec_key(own.clone()).map_err(failure)?;
+    let peer = PKey::from_ec_key(peer.clone()).map_err(failure)?;
+    let mut deriver = Deriver::new(&own).map_err(failure)?;
+    deriver.set_peer(&peer).map_err(failure)?;
+    deriver.derive_to_vec().map_err(failure)
+}
+
+// Helper: Content encryption key and nonce (RFC 8291, section 3.4)
+fn content_keys(
+    secret: &[u8],
+    auth: &[u8],
+    receiver_public: &[u8],
+    sender_public: &[u8],
+    salt: &[u8],
+) -> ([u8; 16], [u8; 12]) {
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(receiver_public);
+    key_info.extend_from_slice(sender_public);
+    let mut ikm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(auth), secret)
+        .expand(&key_info, &mut ikm)
+        .expect("32 bytes is a valid HKDF-SHA256 length");
+
+    let prk = Hkdf::<Sha256>::new(Some(salt), &ikm);
+    let mut key = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    prk.expand(b"Content-Encoding: aes128gcm\0", &mut key)
+        .expect("16 bytes is a valid HKDF-SHA256 length");
+    prk.expand(b"Content-Encoding: nonce\0", &mut nonce)
+        .expect("12 bytes is a valid HKDF-SHA256 length");
+    (key, nonce)
+}
+
+/// Encrypts `payload` for a subscription's `p256dh` and `auth` keys: the body of a push
+/// request with `Content-Encoding: aes128gcm` (RFC 8188 and RFC 8291).
+pub fn encrypt(p256dh: &[u8], auth: &[u8], payload: &[u8]) -> Result<Vec<u8>, String> {
+    if payload.len() > MAX_PAYLOAD {
+        return Err(format!("Payload of {} bytes is larger than {}", payload.len(), MAX_PAYLOAD));
+    }
+
+    let receiver = public_key(p256dh)?;
+    // A new key pair for every message
+    let sender = EcKey::generate(&curve()).map_err(failure)?;
+    let sender_public = public_bytes(&sender)?;
+    let secret = shared_secret(&sender, &receiver)?;
+    let salt: [u8; SALT_LEN] = rand::thread_rng().gen();
+    let (key, nonce) = content_keys(&secret, auth, p256dh, &sender_public, &salt);
+
+    // 0x02 marks the last (and only) record; no extra padding
+    let mut plaintext = payload.to_vec();
+    plaintext.push(2);
+    let mut tag = [0u8; TAG_LEN];
+    let ciphertext = encrypt_aead(Cipher::aes_128_gcm(), &key, Some(&nonce), &[], &plaintext, &mut tag)
+        .map_err(failure)?;
+
+    let mut body = Vec::with_capacity(HEADER_LEN + ciphertext.len() + TAG_LEN);
+    body.extend_from_slice(&salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(PUBLIC_KEY_LEN as u8);
+    body.extend_from_slice(&sender_public);
+    body.extend_from_slice(&ciphertext);
+    body.extend_from_slice(&tag);
+    Ok(body)
+}
+
+/// Reverse of `encrypt`, for the receiving side: what a browser does with the message.
+pub fn decrypt(receiver: &EcKey<Private>, auth: &[u8], body: &[u8]) -> Result<Vec<u8>, String> {
+    if body.len() < HEADER_LEN + TAG_LEN || body[SALT_LEN + 4] as usize != PUBLIC_KEY_LEN {
+        return Err("Malformed aes128gcm header".to_string());
+    }
+    let (header, rest) = body.split_at(HEADER_LEN);
+    let (salt, sender_public) = (&header[..SALT_LEN], &header[SALT_LEN + 5..]);
+    let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+
+    let secret = shared_secret(receiver, &public_key(sender_public)?)?;
+    let (key, nonce) = content_keys(&secret, auth, &public_bytes(receiver)?, sender_public, salt);
+    let mut plaintext = decrypt_aead(Cipher::aes_128_gcm(), &key, Some(&nonce), &[], ciphertext, tag)
+        .map_err(|_| "Message does not decrypt with this subscription's keys".to_string())?;
+
+    // Padding is zeros after the delimiter
+    while plaintext.last() == Some(&0) {
+        plaintext.pop();
+    }
+    match plaintext.pop() {
+        Some(2) => Ok(plaintext),
+        _ => Err("Missing last-record delimiter".to_string()),
+    }
+}
+
+/// The server's VAPID key pair (RFC 8292), which push services use to tell who sends.
+#[derive(Clone)]
+pub struct VapidKey {
+    key: EcKey<Private>,
+    /// Uncompressed public key, base64url: `applicationServerKey` for `pushManager.subscribe`
+    pub public_key: String,
+}
+
+impl VapidKey {
+    /// From the base64url private key (the 32-byte scalar), as printed by
+    /// `web-push generate-vapid-keys`.
+    pub fn from_base64(private_key: &str) -> Result<Self, String> {
+        let scalar = decode_base64url(private_key)
+            .filter(|d| d.len() == 32)
+            .ok_or_else(|| "VAPID_PRIVATE_KEY must be 32 bytes in base64url".to_string())?;
+
+        let group = curve();
+        let mut ctx = BigNumContext::new().map_err(failure)?;
+        let d = BigNum::from_slice(&scalar).map_err(failure)?;
+        let mut point = EcPoint::new(&group).map_err(failure)?;
+        point.mul_generator2(&group, &d, &mut ctx).map_err(failure)?;
+        let key = EcKey::from_private_components(&group, &d, &point).map_err(failure)?;
+        key.check_key().map_err(failure)?;
+
+        let public_key = URL_SAFE_NO_PAD.encode(public_bytes(&key)?);
+        Ok(Self { key, public_key })
+    }
+
+    /// `Authorization` header for a message to `endpoint`.
+    pub fn authorization(&self, endpoint: &reqwest::Url, subject: &str) -> Result<String, String> {
+        let header = URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = json!({
+            "aud": endpoint.origin().ascii_serialization(),
+            "exp": (Utc::now() + chrono::Duration::hours(TOKEN_LIFETIME_HOURS)).timestamp(),
+            "sub": subject,
+        });
+        let signing_input = format!("{}.{}", header, URL_SAFE_NO_PAD.encode(claims.to_string()));
+
+        // JWS wants r || s, not the DER that OpenSSL produces
+        let signature = EcdsaSig::sign(&Sha256::digest(signing_input.as_bytes()), &self.key).map_err(failure)?;
+        let mut raw = signature.r().to_vec_padded(32).map_err(failure)?;
+        raw.extend(signature.s().to_vec_padded(32).map_err(failure)?);
+
+        Ok(format!("vapid t={}.{}, k={}", signing_input, URL_SAFE_NO_PAD.encode(raw), self.public_key))
+    }
+}
+
+/// Client for push services, guarded like the webhook one. With PUSH_TEST_SINK the
+/// stand-in's host stays reachable, since it is usually localhost.
+pub fn client(config: &Config) -> Result<reqwest::Client, reqwest::Error> {
+    let sink_host = config
+        .push_test_sink
+        .then(|| reqwest::Url::parse(&config.public_url).ok()?.host_str().map(str::to_string))
+        .flatten();
+    target::client(config, sink_host, REQUEST_TIMEOUT)
+}
+
+/// What the push service made of a message.
+pub enum Sent {
+    Delivered,
+    /// 404 or 410: the subscription is gone for good and should be deleted
+    Expired,
+    Failed(String),
+}
+
+/// Encrypts `payload` for `subscription` and posts it to its push service. `ttl` is how
+/// long, in seconds, the service keeps the message for a device that is offline.
+pub async fn send(
+    client: &reqwest::Client,
+    config: &Config,
+    vapid: &VapidKey,
+    subscription: &PushSubscription,
+    payload: &[u8],
+    ttl: u32,
+) -> Sent {
+    let url = match reqwest::Url::parse(&subscription.endpoint) {
+        Ok(url) => url,
+        Err(e) => return Sent::Failed(format!("Invalid endpoint: {}", e)),
+    };
+
+    if !sink::is_sink(&url, config) {
+        if let Err(error) = target::check_url(&url, config) {
+            return Sent::Failed(error);
+        }
+    }
+
+    let (Some(p256dh), Some(auth)) = (decode_base64url(&subscription.p256dh), decode_base64url(&subscription.auth)) else {
+        return Sent::Failed("Invalid subscription keys".to_string());
+    };
+    let body = match encrypt(&p256dh, &auth, payload) {
+        Ok(body) => body,
+        Err(e) => return Sent::Failed(e),
+    };
+    let authorization = match vapid.authorization(&url, &config.vapid_subject) {
+        Ok(authorization) => authorization,
+        Err(e) => return Sent::Failed(e),
+    };
+
+    let response = client
+        .post(url)
+        .header(AUTHORIZATION, authorization)
+        .header(CONTENT_ENCODING, "aes128gcm")
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .header("TTL", ttl.to_string())
+        .header("Urgency", "high")
+        .body(body)
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => Sent::Delivered,
+        Ok(response) if matches!(response.status().as_u16(), 404 | 410) => Sent::Expired,
+        Ok(response) => Sent::Failed(format!("HTTP {}", response.status().as_u16())),
+        Err(e) if target::is_blocked(&e) => Sent::Failed(InternalTarget.to_string()),
+        Err(e) => Sent::Failed(e.to_string()),
+    }
+}
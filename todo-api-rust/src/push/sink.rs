@@ -0,0 +1,151 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+
+use actix_web::http::StatusCode;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use openssl::bn::BigNum;
+use openssl::ec::EcKey;
+use openssl::ecdsa::EcdsaSig;
+use openssl::pkey::Private;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use super::{curve, decode_base64url, decrypt, public_bytes, public_key, AUTH_SECRET_LEN};
+use crate::config::Config;
+use crate::errors::ApiError;
+use crate::i18n::Msg;
+use crate::models::{PushKeys, PushSinkMessage, PushSinkSubscription};
+
+// In memory only, so keep it small
+const MAX_INBOXES: usize = 1000;
+const MAX_MESSAGES: usize = 100;
+const MAX_BODY: usize = 4096;
+
+// One subscription of the stand-in: the browser's side of the keys and what arrived
+struct Inbox {
+    key: EcKey<Private>,
+    auth: [u8; AUTH_SECRET_LEN],
+    messages: VecDeque<PushSinkMessage>,
+}
+
+static INBOXES: LazyLock<Mutex<HashMap<String, Inbox>>> = LazyLock::new(Default::default);
+
+fn endpoint(config: &Config, id: &str) -> String {
+    format!("{}/push/sink/{}", config.public_url, id)
+}
+
+/// Whether `url` points at this server's stand-in, which is on localhost in development.
+pub fn is_sink(url: &reqwest::Url, config: &Config) -> bool {
+    config.push_test_sink && url.as_str().starts_with(&endpoint(config, ""))
+}
+
+/// A subscription as a browser would make it. The oldest is dropped past the limit.
+pub fn create(config: &Config) -> Result<PushSinkSubscription, ApiError> {
+    let key = EcKey::generate(&curve()).map_err(|e| ApiError::internal(e.to_string()))?;
+    let p256dh = public_bytes(&key).map_err(ApiError::internal)?;
+    let auth: [u8; AUTH_SECRET_LEN] = rand::thread_rng().gen();
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let mut inboxes = INBOXES.lock().unwrap();
+    if inboxes.len() >= MAX_INBOXES {
+        // Ids are random, so any one will do
+        if let Some(old) = inboxes.keys().next().cloned() {
+            inboxes.remove(&old);
+        }
+    }
+    inboxes.insert(id.clone(), Inbox { key, auth, messages: VecDeque::new() });
+
+    Ok(PushSinkSubscription {
+        endpoint: endpoint(config, &id),
+        keys: PushKeys {
+            p256dh: URL_SAFE_NO_PAD.encode(p256dh),
+            auth: URL_SAFE_NO_PAD.encode(auth),
+        },
+    })
+}
+
+// Helper: Checks `vapid t=<jwt>, k=<key>` the way push services do, returning `sub`
+fn verify_vapid(authorization: &str, audience: &str) -> Result<Option<String>, String> {
+    let params = authorization
+        .strip_prefix("vapid ")
+        .ok_or("Authorization is not a VAPID token")?;
+    let param = |name: &str| {
+        params
+            .split(',')
+            .filter_map(|p| p.trim().split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.to_string())
+    };
+    let (token, key) = param("t").zip(param("k")).ok_or("VAPID token needs t and k")?;
+
+    let (signing_input, signature) = token.rsplit_once('.').ok_or("Malformed JWT")?;
+    let claims = signing_input.split_once('.').map(|(_, claims)| claims).ok_or("Malformed JWT")?;
+    let signature = decode_base64url(signature).filter(|s| s.len() == 64).ok_or("Malformed signature")?;
+    let r = BigNum::from_slice(&signature[..32]).map_err(|e| e.to_string())?;
+    let s = BigNum::from_slice(&signature[32..]).map_err(|e| e.to_string())?;
+    let signature = EcdsaSig::from_private_components(r, s).map_err(|e| e.to_string())?;
+    let key = public_key(&decode_base64url(&key).ok_or("Malformed key")?)?;
+    if !signature.verify(&Sha256::digest(signing_input.as_bytes()), &key).unwrap_or(false) {
+        return Err("Bad VAPID signature".to_string());
+    }
+
+    let claims: serde_json::Value = decode_base64url(claims)
+        .and_then(|c| serde_json::from_slice(&c).ok())
+        .ok_or("Malformed claims")?;
+    if claims["aud"].as_str() != Some(audience) {
+        return Err(format!("aud must be {}", audience));
+    }
+    let exp = claims["exp"].as_i64().ok_or("Missing exp")?;
+    let now = Utc::now().timestamp();
+    if exp <= now || exp > now + 24 * 60 * 60 {
+        return Err("exp must be within the next 24 hours".to_string());
+    }
+
+    Ok(claims["sub"].as_str().map(str::to_string))
+}
+
+/// Accepts a push message like a push service would, keeping it decrypted.
+pub fn receive(
+    config: &Config,
+    id: &str,
+    authorization: Option<&str>,
+    ttl: Option<u32>,
+    urgency: Option<String>,
+    body: &[u8],
+) -> Result<(), ApiError> {
+    let audience = reqwest::Url::parse(&config.public_url)
+        .map(|url| url.origin().ascii_serialization())
+        .unwrap_or_default();
+    let subject = verify_vapid(authorization.unwrap_or_default(), &audience)
+        .map_err(|e| ApiError::unauthorized(Msg::InvalidVapidToken).with_message(e))?;
+    if body.len() > MAX_BODY {
+        return Err(ApiError::new(Msg::InvalidPushMessage, StatusCode::PAYLOAD_TOO_LARGE));
+    }
+
+    let mut inboxes = INBOXES.lock().unwrap();
+    let inbox = inboxes.get_mut(id).ok_or_else(|| ApiError::not_found(Msg::PushSinkNotFound))?;
+    let plaintext = decrypt(&inbox.key, &inbox.auth, body)
+        .map_err(|e| ApiError::bad_request(Msg::InvalidPushMessage).with_message(e))?;
+    let payload = serde_json::from_slice(&plaintext)
+        .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(&plaintext).into_owned()));
+
+    if inbox.messages.len() >= MAX_MESSAGES {
+        inbox.messages.pop_front();
+    }
+    inbox.messages.push_back(PushSinkMessage {
+        received_at: Utc::now(),
+        ttl,
+        urgency,
+        subject,
+        payload,
+    });
+    Ok(())
+}
+
+/// Messages received for `id`, oldest first.
+pub fn messages(id: &str) -> Option<Vec<PushSinkMessage>> {
+    let inboxes = INBOXES.lock().unwrap();
+    inboxes.get(id).map(|inbox| inbox.messages.iter().cloned().collect())
+}
@@ -0,0 +1,829 @@
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+
+use crate::i18n::Locale;
+use crate::recurrence::{Frequency, Recurrence};
+
+// How far ahead the first occurrence of a recurrence is looked for
+const LOOKAHEAD_DAYS: i64 = 400;
+
+/// `!high`, `!medium` or `!low` (also `!alta`, `!média`, `!baixa`, `!1`..`!3`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    High,
+    Medium,
+    Low,
+}
+
+impl Priority {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        }
+    }
+}
+
+/// What a recognized phrase was read as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhraseKind {
+    List,
+    Priority,
+    Date,
+    Time,
+    Recurrence,
+}
+
+impl PhraseKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PhraseKind::List => "list",
+            PhraseKind::Priority => "priority",
+            PhraseKind::Date => "date",
+            PhraseKind::Time => "time",
+            PhraseKind::Recurrence => "recurrence",
+        }
+    }
+}
+
+/// Part of the text taken out of the title, as typed.
+#[derive(Debug, Clone)]
+pub struct Phrase {
+    pub text: String,
+    pub kind: PhraseKind,
+}
+
+/// A quick-add line split into a title and the task's fields. Dates and times are in
+/// the user's local time.
+#[derive(Debug, Default)]
+pub struct QuickAdd {
+    pub title: String,
+    pub list_id: Option<String>,
+    pub priority: Option<Priority>,
+    pub date: Option<NaiveDate>,
+    /// "on the 5th" / "dia 5": the next such day, or the day of a monthly recurrence
+    pub month_day: Option<u32>,
+    pub time: Option<NaiveTime>,
+    pub recurrence: Option<Recurrence>,
+    pub phrases: Vec<Phrase>,
+}
+
+impl QuickAdd {
+    /// Due date and time. Without a date, a time means today (tomorrow once it has
+    /// passed) and a recurrence starts at its next occurrence. A date without a time
+    /// gets `default_time`.
+    pub fn due(&self, now: NaiveDateTime, default_time: NaiveTime) -> Option<NaiveDateTime> {
+        let time = self.time.unwrap_or(default_time);
+
+        if let Some(rule) = &self.recurrence {
+            let start = self.date.unwrap_or(now.date()).and_time(time);
+            if self.date.is_some() {
+                return Some(start);
+            }
+            // Without a time, today's occurrence still counts once its hour has passed
+            let from = if self.time.is_some() { now } else { now.date().and_time(NaiveTime::MIN) };
+            // Local wall time throughout, so no time zone is needed here
+            let first = rule
+                .occurrences(
+                    Utc.from_utc_datetime(&start),
+                    from.and_utc(),
+                    (now + Duration::days(LOOKAHEAD_DAYS)).and_utc(),
+                )
+                .into_iter()
+                .next();
+            return Some(first.map(|at| at.naive_utc()).unwrap_or(start));
+        }
+
+        if let Some(date) = self.date {
+            return Some(date.and_time(time));
+        }
+
+        if let Some(day) = self.month_day {
+            return next_month_day(now.date(), day).map(|date| date.and_time(time));
+        }
+
+        self.time.map(|time| {
+            let today = now.date().and_time(time);
+            if today > now { today } else { today + Duration::days(1) }
+        })
+    }
+}
+
+enum Effect {
+    List(String),
+    Priority(Priority),
+    Date(NaiveDate),
+    MonthDay(u32),
+    Time(NaiveTime),
+    Recurrence(Recurrence),
+}
+
+impl Effect {
+    fn kind(&self) -> PhraseKind {
+        match self {
+            Effect::List(_) => PhraseKind::List,
+            Effect::Priority(_) => PhraseKind::Priority,
+            Effect::Date(_) | Effect::MonthDay(_) => PhraseKind::Date,
+            Effect::Time(_) => PhraseKind::Time,
+            Effect::Recurrence(_) => PhraseKind::Recurrence,
+        }
+    }
+}
+
+/// Reads `text` in English or Portuguese. `lists` are the user's lists as (id, name);
+/// a `#tag` that names none of them stays in the title. `locale` decides whether
+/// `03/04` is March 4 (`en`) or April 3 (`pt-BR`).
+pub fn parse(text: &str, today: NaiveDate, locale: Locale, lists: &[(String, String)]) -> QuickAdd {
+    let original: Vec<&str> = text.split_whitespace().collect();
+    let words: Vec<String> = original.iter().map(|w| normalize(w)).collect();
+    let parser = Parser { today, day_first: locale != Locale::En, lists };
+
+    let mut result = QuickAdd::default();
+    let mut title: Vec<&str> = Vec::new();
+    let mut i = 0;
+
+    while i < words.len() {
+        let found = parser
+            .phrase(&words[i..])
+            .filter(|(_, effect)| !result.phrases.iter().any(|p| p.kind == effect.kind()));
+
+        let Some((len, effect)) = found else {
+            title.push(original[i]);
+            i += 1;
+            continue;
+        };
+
+        result.phrases.push(Phrase {
+            text: original[i..i + len].join(" "),
+            kind: effect.kind(),
+        });
+        match effect {
+            Effect::List(id) => result.list_id = Some(id),
+            Effect::Priority(priority) => result.priority = Some(priority),
+            Effect::Date(date) => result.date = Some(date),
+            Effect::MonthDay(day) => result.month_day = Some(day),
+            Effect::Time(time) => result.time = Some(time),
+            Effect::Recurrence(rule) => result.recurrence = Some(rule),
+        }
+        i += len;
+    }
+
+    // "every month on the 5th" / "todo mês no dia 5"
+    if let (Some(rule), Some(day)) = (result.recurrence.as_mut(), result.month_day) {
+        if rule.freq == Frequency::Monthly && rule.by_month_day.is_none() {
+            rule.by_month_day = Some(day);
+            result.month_day = None;
+        }
+    }
+
+    result.title = title.join(" ").trim_matches(|c: char| c == ',' || c == '-' || c.is_whitespace()).to_string();
+    result
+}
+
+struct Parser<'a> {
+    today: NaiveDate,
+    day_first: bool,
+    lists: &'a [(String, String)],
+}
+
+impl Parser<'_> {
+    // Longest phrase starting at `w[0]`, as (words used, effect)
+    fn phrase(&self, w: &[String]) -> Option<(usize, Effect)> {
+        self.tag(w)
+            .or_else(|| self.recurrence(w))
+            .or_else(|| self.with_prefix(w, DATE_PREFIXES, |w, prefixed| self.date(w, prefixed)))
+            .or_else(|| self.with_prefix(w, TIME_PREFIXES, |w, prefixed| time(w, prefixed).map(|(n, t)| (n, Effect::Time(t)))))
+    }
+
+    // Tries `f` on the words as they are, then after one of `prefixes`
+    fn with_prefix(
+        &self,
+        w: &[String],
+        prefixes: &[&str],
+        f: impl Fn(&[String], bool) -> Option<(usize, Effect)>,
+    ) -> Option<(usize, Effect)> {
+        if let Some(found) = f(w, false) {
+            return Some(found);
+        }
+        let skip = prefix_len(w, prefixes)?;
+        f(&w[skip..], true).map(|(n, effect)| (n + skip, effect))
+    }
+
+    fn tag(&self, w: &[String]) -> Option<(usize, Effect)> {
+        let word = w.first()?;
+
+        if let Some(name) = word.strip_prefix('#').filter(|n| !n.is_empty()) {
+            let name = compact(name);
+            let (id, _) = self.lists.iter().find(|(_, list)| compact(&normalize(list)) == name)?;
+            return Some((1, Effect::List(id.clone())));
+        }
+
+        let priority = match word.as_str() {
+            "!high" | "!alta" | "!1" | "!!!" | "!p1" => Priority::High,
+            "!medium" | "!media" | "!2" | "!!" | "!p2" => Priority::Medium,
+            "!low" | "!baixa" | "!3" | "!p3" => Priority::Low,
+            _ => return None,
+        };
+        Some((1, Effect::Priority(priority)))
+    }
+
+    fn recurrence(&self, w: &[String]) -> Option<(usize, Effect)> {
+        let first = w.first()?.as_str();
+
+        let single = match first {
+            "daily" | "diariamente" => Some(Frequency::Daily),
+            "weekly" | "semanalmente" => Some(Frequency::Weekly),
+            "monthly" | "mensalmente" => Some(Frequency::Monthly),
+            "yearly" | "annually" | "anualmente" => Some(Frequency::Yearly),
+            _ => None,
+        };
+        if let Some(freq) = single {
+            return Some((1, Effect::Recurrence(Recurrence::new(freq))));
+        }
+
+        // "on mondays", "às segundas": plural weekdays repeat weekly
+        if let Some(skip) = prefix_len(w, &["on", "as", "nas", "aos"]) {
+            if let Some((n, days)) = weekday_list(&w[skip..], true) {
+                return Some((skip + n, Effect::Recurrence(weekly(days))));
+            }
+        }
+
+        let skip = prefix_len(w, EVERY)?;
+        let rest = &w[skip..];
+
+        if let Some(n) = prefix_len(rest, &["weekday", "weekdays", "dia util", "dias uteis"]) {
+            let days = vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+            return Some((skip + n, Effect::Recurrence(weekly(days))));
+        }
+
+        if let Some((n, days)) = weekday_list(rest, false) {
+            return Some((skip + n, Effect::Recurrence(weekly(days))));
+        }
+
+        let (count_len, interval) = match rest.first().map(String::as_str) {
+            Some("other") => (1, 2),
+            Some(word) => match amount(word) {
+                Some(n) if n > 0 && rest.len() > 1 && unit(&rest[1]).is_some() => (1, n),
+                _ => (0, 1),
+            },
+            None => return None,
+        };
+        let unit_word = rest.get(count_len)?;
+        let freq = unit(unit_word)?;
+        let mut used = skip + count_len + 1;
+        let mut rule = Recurrence::new(freq);
+        rule.interval = interval;
+
+        // "todo dia 5": the day of the month, not every day
+        if freq == Frequency::Daily && interval == 1 {
+            if let Some(day) = w.get(used).and_then(|d| month_day(d)) {
+                rule.freq = Frequency::Monthly;
+                rule.by_month_day = Some(day);
+                used += 1;
+            }
+        }
+
+        Some((used, Effect::Recurrence(rule)))
+    }
+
+    fn date(&self, w: &[String], prefixed: bool) -> Option<(usize, Effect)> {
+        let today = self.today;
+        let first = w.first()?.as_str();
+
+        if let Some(n) = prefix_len(w, &["day after tomorrow", "depois de amanha"]) {
+            return Some((n, Effect::Date(today + Duration::days(2))));
+        }
+        match first {
+            "today" | "hoje" => return Some((1, Effect::Date(today))),
+            "tomorrow" | "amanha" => return Some((1, Effect::Date(today + Duration::days(1)))),
+            _ => {}
+        }
+
+        // "in 3 days", "em 2 semanas", "daqui a um mês"
+        if let Some(skip) = prefix_len(w, &["in", "em", "daqui a", "dentro de"]) {
+            if let (Some(n), Some(unit)) = (w.get(skip).and_then(|n| amount(n)), w.get(skip + 1).and_then(|u| unit(u))) {
+                return Some((skip + 2, Effect::Date(add(today, unit, n)?)));
+            }
+        }
+
+        // "next week", "próximo mês", "semana que vem"
+        if let Some(skip) = prefix_len(w, NEXT) {
+            if let Some(unit) = w.get(skip).and_then(|u| unit(u)) {
+                return Some((skip + 1, Effect::Date(add(today, unit, 1)?)));
+            }
+        }
+        if let Some(unit) = unit(first) {
+            if prefix_len(&w[1..], &["que vem"]).is_some() {
+                return Some((3, Effect::Date(add(today, unit, 1)?)));
+            }
+        }
+
+        // Weekdays are always the next one, never today
+        let next = prefix_len(w, NEXT).unwrap_or(0);
+        if let Some((n, day)) = weekday(&w[next..], prefixed || next > 0) {
+            return Some((next + n, Effect::Date(next_weekday(today, day))));
+        }
+
+        // "the 5th", "dia 5", "5th"
+        let the = prefix_len(w, &["the", "dia"]).unwrap_or(0);
+        if let Some(day) = w.get(the).and_then(|d| ordinal(d).or_else(|| (the > 0).then(|| month_day(d)).flatten())) {
+            if the > 0 || prefixed || ordinal(first).is_some() {
+                return Some((the + 1, Effect::MonthDay(day)));
+            }
+        }
+
+        self.absolute(w).map(|(n, date)| (n, Effect::Date(date)))
+    }
+
+    // "2026-10-25", "25/10", "10/25/2026", "25 de outubro", "oct 25th, 2026"
+    fn absolute(&self, w: &[String]) -> Option<(usize, NaiveDate)> {
+        let first = w.first()?.as_str();
+
+        if let Ok(date) = NaiveDate::parse_from_str(first, "%Y-%m-%d") {
+            return Some((1, date));
+        }
+
+        let parts: Vec<&str> = first.split('/').collect();
+        if (2..=3).contains(&parts.len()) && parts.iter().all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit())) {
+            let a: u32 = parts[0].parse().ok()?;
+            let b: u32 = parts[1].parse().ok()?;
+            let (day, month) = if self.day_first { (a, b) } else { (b, a) };
+            let year = parts.get(2).and_then(|y| y.parse::<i32>().ok()).map(|y| if y < 100 { 2000 + y } else { y });
+            return Some((1, self.resolve(day, month, year)?));
+        }
+
+        // Day before the month, with an optional "de"
+        if let Some(day) = month_day(first).or_else(|| ordinal(first)) {
+            let of = prefix_len(&w[1..], &["de", "of"]).unwrap_or(0);
+            if let Some(month) = w.get(1 + of).and_then(|m| month(m)) {
+                let used = 2 + of;
+                let (n, year) = self.year(&w[used..]);
+                return Some((used + n, self.resolve(day, month, year)?));
+            }
+        }
+
+        // Month before the day
+        let month = month(first)?;
+        let day = w.get(1).and_then(|d| month_day(d).or_else(|| ordinal(d)))?;
+        let (n, year) = self.year(&w[2..]);
+        Some((2 + n, self.resolve(day, month, year)?))
+    }
+
+    // Optional year after a date, as "2026" or "de 2026"
+    fn year(&self, w: &[String]) -> (usize, Option<i32>) {
+        let of = prefix_len(w, &["de"]).unwrap_or(0);
+        match w.get(of).and_then(|y| y.parse::<i32>().ok()).filter(|y| (1970..=2200).contains(y)) {
+            Some(year) => (of + 1, Some(year)),
+            None => (0, None),
+        }
+    }
+
+    // A date without a year is the next one to come; for Feb 29 that can be years away
+    fn resolve(&self, day: u32, month: u32, year: Option<i32>) -> Option<NaiveDate> {
+        match year {
+            Some(year) => NaiveDate::from_ymd_opt(year, month, day),
+            None => (0..=8)
+                .filter_map(|ahead| NaiveDate::from_ymd_opt(self.today.year() + ahead, month, day))
+                .find(|date| *date >= self.today),
+        }
+    }
+}
+
+const EVERY: &[&str] = &["every", "each", "a cada", "todos os", "todas as", "todo", "toda", "cada"];
+const NEXT: &[&str] = &["next", "proxima", "proximo"];
+const DATE_PREFIXES: &[&str] = &["on", "this", "em", "no", "na", "nesta", "neste", "esta", "este", "para", "pra"];
+const TIME_PREFIXES: &[&str] = &["at", "@", "as"];
+// Portuguese parts of the day after an hour, as in "3 da tarde"
+const AFTER_NOON: &[&str] = &["da tarde", "da noite", "de tarde", "de noite"];
+const BEFORE_NOON: &[&str] = &["da manha", "de manha"];
+
+// Lowercase, without accents or trailing punctuation, so "Amanhã," reads as "amanha"
+fn normalize(word: &str) -> String {
+    word.trim_end_matches([',', ';', '.'])
+        .chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' => 'a',
+            'é' | 'ê' => 'e',
+            'í' => 'i',
+            'ó' | 'ô' | 'õ' => 'o',
+            'ú' | 'ü' => 'u',
+            'ç' => 'c',
+            'º' | 'ª' => 'o',
+            c => c,
+        })
+        .collect()
+}
+
+// Helper: List name for comparing with a `#tag`: no spaces, dashes or underscores
+fn compact(name: &str) -> String {
+    name.chars().filter(|c| !c.is_whitespace() && !"-_".contains(*c)).collect()
+}
+
+// Helper: Words used by the first of `phrases` that `w` starts with
+fn prefix_len(w: &[String], phrases: &[&str]) -> Option<usize> {
+    phrases.iter().find_map(|phrase| {
+        let parts: Vec<&str> = phrase.split(' ').collect();
+        (w.len() > parts.len() - 1 && parts.iter().zip(w).all(|(p, word)| p == word)).then_some(parts.len())
+    })
+}
+
+// Helper: 1..=99, in digits or as a small number word
+fn amount(word: &str) -> Option<u32> {
+    match word {
+        "a" | "an" | "one" | "um" | "uma" => Some(1),
+        "two" | "dois" | "duas" => Some(2),
+        "three" | "tres" => Some(3),
+        "four" | "quatro" => Some(4),
+        "five" | "cinco" => Some(5),
+        "six" | "seis" => Some(6),
+        _ => word.parse().ok().filter(|n| (1..100).contains(n)),
+    }
+}
+
+fn unit(word: &str) -> Option<Frequency> {
+    match word {
+        "day" | "days" | "dia" | "dias" => Some(Frequency::Daily),
+        "week" | "weeks" | "semana" | "semanas" => Some(Frequency::Weekly),
+        "month" | "months" | "mes" | "meses" => Some(Frequency::Monthly),
+        "year" | "years" | "ano" | "anos" => Some(Frequency::Yearly),
+        _ => None,
+    }
+}
+
+fn add(date: NaiveDate, unit: Frequency, n: u32) -> Option<NaiveDate> {
+    match unit {
+        Frequency::Daily => date.checked_add_signed(Duration::days(n as i64)),
+        Frequency::Weekly => date.checked_add_signed(Duration::weeks(n as i64)),
+        Frequency::Monthly => date.checked_add_months(Months::new(n)),
+        Frequency::Yearly => date.checked_add_months(Months::new(n * 12)),
+    }
+}
+
+fn weekly(days: Vec<Weekday>) -> Recurrence {
+    let mut rule = Recurrence::new(Frequency::Weekly);
+    rule.by_day = days;
+    rule
+}
+
+// Helper: Day of the month in digits
+fn month_day(word: &str) -> Option<u32> {
+    word.parse().ok().filter(|d| (1..=31).contains(d))
+}
+
+// Helper: "5th", "1st", "5o" (from "5º")
+fn ordinal(word: &str) -> Option<u32> {
+    ["st", "nd", "rd", "th", "o"]
+        .iter()
+        .find_map(|suffix| word.strip_suffix(suffix))
+        .and_then(month_day)
+}
+
+fn month(word: &str) -> Option<u32> {
+    const MONTHS: [(&str, &str, &str); 12] = [
+        ("january", "janeiro", "jan"),
+        ("february", "fevereiro", "fev"),
+        ("march", "marco", "mar"),
+        ("april", "abril", "abr"),
+        ("may", "maio", "mai"),
+        ("june", "junho", "jun"),
+        ("july", "julho", "jul"),
+        ("august", "agosto", "ago"),
+        ("september", "setembro", "set"),
+        ("october", "outubro", "out"),
+        ("november", "novembro", "nov"),
+        ("december", "dezembro", "dez"),
+    ];
+    MONTHS
+        .iter()
+        .position(|(en, pt, abbr)| word == *en || word == *pt || word == *abbr || (word.len() == 3 && en.starts_with(word)))
+        .map(|i| i as u32 + 1)
+}
+
+// Helper: A weekday, including "segunda-feira" and "segunda feira". Abbreviations
+// ("fri", "sex") only count after a prefix, since they are also ordinary words.
+fn weekday(w: &[String], abbreviations: bool) -> Option<(usize, Weekday)> {
+    let word = w.first()?;
+    let (name, mut used) = match word.strip_suffix("-feira") {
+        Some(name) => (name, 1),
+        None => (word.as_str(), 1),
+    };
+    if w.get(1).is_some_and(|f| f == "feira") {
+        used = 2;
+    }
+
+    let day = match name {
+        "monday" | "segunda" => Weekday::Mon,
+        "tuesday" | "terca" => Weekday::Tue,
+        "wednesday" | "quarta" => Weekday::Wed,
+        "thursday" | "quinta" => Weekday::Thu,
+        "friday" | "sexta" => Weekday::Fri,
+        "saturday" | "sabado" => Weekday::Sat,
+        "sunday" | "domingo" => Weekday::Sun,
+        _ if abbreviations => match name {
+            "mon" | "seg" => Weekday::Mon,
+            "tue" | "tues" | "ter" => Weekday::Tue,
+            "wed" | "qua" => Weekday::Wed,
+            "thu" | "thur" | "thurs" | "qui" => Weekday::Thu,
+            "fri" | "sex" => Weekday::Fri,
+            "sat" | "sab" => Weekday::Sat,
+            "sun" | "dom" => Weekday::Sun,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some((used, day))
+}
+
+// Helper: "monday and thursday", "segundas, quartas e sextas". With `plural`, only
+// plural names count ("on mondays"); otherwise both do.
+fn weekday_list(w: &[String], plural: bool) -> Option<(usize, Vec<Weekday>)> {
+    let mut days = Vec::new();
+    let mut used = 0;
+
+    while let Some((n, day)) = weekday_form(&w[used..], plural) {
+        days.push(day);
+        used += n;
+
+        // Commas are gone by now, so "segundas, quartas" is two weekdays in a row
+        let connector = matches!(w.get(used).map(String::as_str), Some("and" | "e" | "&")) as usize;
+        if weekday_form(&w[(used + connector).min(w.len())..], plural).is_none() {
+            break;
+        }
+        used += connector;
+    }
+
+    (!days.is_empty()).then_some((used, days))
+}
+
+// Helper: One weekday of a list, singular or plural ("mondays", "segundas-feiras")
+fn weekday_form(w: &[String], plural: bool) -> Option<(usize, Weekday)> {
+    let word = w.first()?;
+    let singular = match word.strip_suffix("s-feiras") {
+        Some(stem) => Some(format!("{}-feira", stem)),
+        None => word.strip_suffix('s').map(str::to_string),
+    };
+
+    if let Some(day) = singular.and_then(|s| weekday(&[s], !plural)) {
+        return Some((1, day.1));
+    }
+    if plural {
+        return None;
+    }
+    weekday(w, true)
+}
+
+fn next_weekday(today: NaiveDate, day: Weekday) -> NaiveDate {
+    let ahead = (day.num_days_from_monday() as i64 - today.weekday().num_days_from_monday() as i64 + 6) % 7 + 1;
+    today + Duration::days(ahead)
+}
+
+fn next_month_day(today: NaiveDate, day: u32) -> Option<NaiveDate> {
+    (0..12).find_map(|months| {
+        let month = today.with_day(1)?.checked_add_months(Months::new(months))?;
+        NaiveDate::from_ymd_opt(month.year(), month.month(), day).filter(|date| *date >= today)
+    })
+}
+
+// "9am", "9:30 pm", "14:00", "14h", "14h30", "noon", "meio-dia", "de manhã", and with a
+// prefix or before a part of the day a bare hour ("at 9", "às 9", "3 da tarde"). Portuguese
+// "da tarde" / "da noite" move it past noon.
+fn time(w: &[String], prefixed: bool) -> Option<(usize, NaiveTime)> {
+    let first = w.first()?.as_str();
+
+    // Parts of the day, at a typical hour
+    let periods: [(&[&str], u32); 3] = [
+        (&["in the morning", "de manha", "pela manha"], 9),
+        (&["in the afternoon", "a tarde", "de tarde"], 15),
+        (&["in the evening", "a noite", "de noite"], 19),
+    ];
+    for (phrases, hour) in periods {
+        if let Some(n) = prefix_len(w, phrases) {
+            return Some((n, NaiveTime::from_hms_opt(hour, 0, 0)?));
+        }
+    }
+
+    match first {
+        "noon" | "meio-dia" => return Some((1, NaiveTime::from_hms_opt(12, 0, 0)?)),
+        "midnight" | "meia-noite" => return Some((1, NaiveTime::from_hms_opt(0, 0, 0)?)),
+        "meio" if w.get(1).is_some_and(|d| d == "dia") => return Some((2, NaiveTime::from_hms_opt(12, 0, 0)?)),
+        _ => {}
+    }
+
+    let (clock, mut meridiem) = match ["am", "pm", "a.m", "p.m"].iter().find(|m| first.ends_with(*m)) {
+        Some(m) => (&first[..first.len() - m.len()], Some(m.starts_with('p'))),
+        None => (first, None),
+    };
+    let mut used = 1;
+    if meridiem.is_none() {
+        if let Some(m) = w.get(1).map(String::as_str).filter(|m| ["am", "pm", "a.m", "p.m"].contains(m)) {
+            meridiem = Some(m.starts_with('p'));
+            used = 2;
+        }
+    }
+
+    let (hour, minute, explicit) = if let Some((h, m)) = clock.split_once(':') {
+        (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?, true)
+    } else if let Some((h, m)) = clock.split_once('h') {
+        (h.parse::<u32>().ok()?, if m.is_empty() { 0 } else { m.parse::<u32>().ok()? }, true)
+    } else {
+        let hour = clock.parse::<u32>().ok()?;
+        // "3 da tarde" is a time even without "às"
+        let period = prefix_len(&w[used..], AFTER_NOON).or_else(|| prefix_len(&w[used..], BEFORE_NOON));
+        (hour, 0, meridiem.is_some() || period.is_some())
+    };
+    if !(explicit || prefixed) || clock.is_empty() {
+        return None;
+    }
+
+    let mut hour = match meridiem {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some(true) => hour % 12 + 12,
+        Some(false) => hour % 12,
+        None => hour,
+    };
+
+    if meridiem.is_none() && hour < 12 {
+        if let Some(skip) = prefix_len(&w[used..], AFTER_NOON) {
+            hour += 12;
+            used += skip;
+        } else if let Some(skip) = prefix_len(&w[used..], BEFORE_NOON) {
+            used += skip;
+        }
+    }
+
+    Some((used, NaiveTime::from_hms_opt(hour, minute, 0)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    fn lists() -> Vec<(String, String)> {
+        [("l-casa", "Casa"), ("l-home", "Home"), ("l-work", "Work Stuff")]
+            .into_iter()
+            .map(|(id, name)| (id.to_string(), name.to_string()))
+            .collect()
+    }
+
+    // Title and fields on one line, so a table row reads like what the user typed
+    fn read(text: &str, today: &str, locale: Locale) -> String {
+        let parsed = parse(text, date(today), locale, &lists());
+        let mut fields = vec![parsed.title.clone()];
+        fields.extend(parsed.list_id.map(|id| format!("#{}", id)));
+        fields.extend(parsed.priority.map(|p| format!("!{}", p.as_str())));
+        fields.extend(parsed.date.map(|d| format!("date {}", d)));
+        fields.extend(parsed.month_day.map(|d| format!("day {}", d)));
+        fields.extend(parsed.time.map(|t| format!("at {}", t.format("%H:%M"))));
+        fields.extend(parsed.recurrence.map(|r| r.to_string()));
+        fields.join(" | ")
+    }
+
+    fn check(today: &str, locale: Locale, cases: &[(&str, &str)]) {
+        for (text, expected) in cases {
+            assert_eq!(read(text, today, locale), *expected, "{:?} on {} in {:?}", text, today, locale);
+        }
+    }
+
+    // Wednesday
+    const TODAY: &str = "2026-10-21";
+
+    #[test]
+    fn reads_the_documented_portuguese_phrases() {
+        check(TODAY, Locale::PtBr, &[
+            ("Pagar aluguel todo mês no dia 5 às 9 #casa !alta", "Pagar aluguel | #l-casa | !high | at 09:00 | FREQ=MONTHLY;BYMONTHDAY=5"),
+            ("Comprar pão hoje", "Comprar pão | date 2026-10-21"),
+            ("Ligar pro João amanhã às 14h30", "Ligar pro João | date 2026-10-22 | at 14:30"),
+            ("Amanhã, ligar", "ligar | date 2026-10-22"),
+            ("Exame depois de amanhã", "Exame | date 2026-10-23"),
+            ("Reunião sexta 3 da tarde", "Reunião | date 2026-10-23 | at 15:00"),
+            ("Reunião na sexta às 3 da tarde", "Reunião | date 2026-10-23 | at 15:00"),
+            ("Jantar às 8 da noite", "Jantar | at 20:00"),
+            ("Entregar relatório segunda-feira", "Entregar relatório | date 2026-10-26"),
+            ("Entregar relatório segunda feira", "Entregar relatório | date 2026-10-26"),
+            ("Correr de manhã", "Correr | at 09:00"),
+            ("Almoço meio-dia", "Almoço | at 12:00"),
+            ("Backup meia-noite", "Backup | at 00:00"),
+            ("Dentista dia 10", "Dentista | day 10"),
+            ("Dentista no dia 10", "Dentista | day 10"),
+            ("Consulta 25/10", "Consulta | date 2026-10-25"),
+            ("Consulta 25/10/27", "Consulta | date 2027-10-25"),
+            ("Viagem 25 de outubro de 2027", "Viagem | date 2027-10-25"),
+            ("Revisar contrato semana que vem", "Revisar contrato | date 2026-10-28"),
+            ("Prova próximo mês", "Prova | date 2026-11-21"),
+            ("Correr em 2 semanas", "Correr | date 2026-11-04"),
+            ("Renovar passaporte daqui a 2 meses", "Renovar passaporte | date 2026-12-21"),
+            ("Ligar daqui a um mês", "Ligar | date 2026-11-21"),
+            ("Tomar remédio todo dia às 8 da noite", "Tomar remédio | at 20:00 | FREQ=DAILY"),
+            ("Pagar conta todo dia 5", "Pagar conta | FREQ=MONTHLY;BYMONTHDAY=5"),
+            ("Academia toda segunda e quarta", "Academia | FREQ=WEEKLY;BYDAY=MO,WE"),
+            ("Feira todas as segundas, quartas e sextas", "Feira | FREQ=WEEKLY;BYDAY=MO,WE,FR"),
+            ("Aula às segundas", "Aula | FREQ=WEEKLY;BYDAY=MO"),
+            ("Faxina a cada 2 semanas", "Faxina | FREQ=WEEKLY;INTERVAL=2"),
+            ("Trocar filtro !media", "Trocar filtro | !medium"),
+            ("Regar plantas !baixa #Casa", "Regar plantas | #l-casa | !low"),
+        ]);
+    }
+
+    #[test]
+    fn reads_the_documented_english_phrases() {
+        check(TODAY, Locale::En, &[
+            ("Pay rent every month on the 5th at 9am #home !high", "Pay rent | #l-home | !high | at 09:00 | FREQ=MONTHLY;BYMONTHDAY=5"),
+            ("Call mom tomorrow at 9:30 pm", "Call mom | date 2026-10-22 | at 21:30"),
+            ("Dinner next friday 7pm", "Dinner | date 2026-10-23 | at 19:00"),
+            ("Gym on fri", "Gym | date 2026-10-23"),
+            ("Flight on Oct 25", "Flight | date 2026-10-25"),
+            ("Invoice oct 25th, 2026", "Invoice | date 2026-10-25"),
+            ("Invoice 2026-10-25", "Invoice | date 2026-10-25"),
+            ("Report in 3 days", "Report | date 2026-10-24"),
+            ("Plan next week", "Plan | date 2026-10-28"),
+            ("Renew day after tomorrow", "Renew | date 2026-10-23"),
+            ("Taxes on the 5th", "Taxes | day 5"),
+            ("Taxes 5th", "Taxes | day 5"),
+            ("Meet at 9", "Meet | at 09:00"),
+            ("Meet 14:00", "Meet | at 14:00"),
+            ("Lunch noon", "Lunch | at 12:00"),
+            ("Walk in the afternoon", "Walk | at 15:00"),
+            ("Gym every day", "Gym | FREQ=DAILY"),
+            ("Water plants every 2 weeks", "Water plants | FREQ=WEEKLY;INTERVAL=2"),
+            ("Backup every other week", "Backup | FREQ=WEEKLY;INTERVAL=2"),
+            ("Standup every weekday at 10", "Standup | at 10:00 | FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR"),
+            ("Sync every monday and thursday", "Sync | FREQ=WEEKLY;BYDAY=MO,TH"),
+            ("Yoga on mondays", "Yoga | FREQ=WEEKLY;BYDAY=MO"),
+            ("Review yearly", "Review | FREQ=YEARLY"),
+            ("Ship it !1 #workstuff", "Ship it | #l-work | !high"),
+            ("Ship it !!", "Ship it | !medium"),
+        ]);
+    }
+
+    #[test]
+    fn leaves_ordinary_words_in_the_title() {
+        check(TODAY, Locale::En, &[
+            ("Buy 3 apples", "Buy 3 apples"),
+            ("Read chapter 5", "Read chapter 5"),
+            ("Fix #bug", "Fix #bug"),
+            ("Gym fri", "Gym fri"),
+            // One phrase of each kind; the second date stays in the title
+            ("Call today tomorrow", "Call tomorrow | date 2026-10-21"),
+        ]);
+    }
+
+    #[test]
+    fn reads_numeric_dates_in_the_order_of_the_locale() {
+        check(TODAY, Locale::PtBr, &[
+            ("Conta 03/04", "Conta | date 2027-04-03"),
+            ("Conta 13/04", "Conta | date 2027-04-13"),
+            ("Conta 10/25/2026", "Conta 10/25/2026"),
+            ("Conta 25/10/2026", "Conta | date 2026-10-25"),
+        ]);
+        check(TODAY, Locale::En, &[
+            ("Bill 03/04", "Bill | date 2027-03-04"),
+            ("Bill 13/04", "Bill 13/04"),
+            ("Bill 10/25/2026", "Bill | date 2026-10-25"),
+            ("Bill 25/10/2026", "Bill 25/10/2026"),
+        ]);
+    }
+
+    #[test]
+    fn rolls_dates_over_into_the_next_year() {
+        // Wednesday, the day before the last of the year
+        let today = "2026-12-30";
+        check(today, Locale::PtBr, &[
+            ("Festa 05/01", "Festa | date 2027-01-05"),
+            ("Natal 25/12", "Natal | date 2027-12-25"),
+            ("Prazo 30/12", "Prazo | date 2026-12-30"),
+            ("Viagem sexta", "Viagem | date 2027-01-01"),
+            ("Férias 10 de janeiro", "Férias | date 2027-01-10"),
+        ]);
+        check(today, Locale::En, &[
+            ("Review next month", "Review | date 2027-01-30"),
+            ("Ship next week", "Ship | date 2027-01-06"),
+            ("Pay in 2 days", "Pay | date 2027-01-01"),
+            ("Party jan 2nd", "Party | date 2027-01-02"),
+        ]);
+        // Feb 29 without a year is the next one there is
+        check("2027-03-01", Locale::PtBr, &[("Aniversário 29/02", "Aniversário | date 2028-02-29")]);
+    }
+
+    #[test]
+    fn due_dates_roll_over_into_the_next_year() {
+        let now = date("2026-12-31").and_hms_opt(18, 0, 0).unwrap();
+        let nine = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        let due = |text: &str| {
+            parse(text, now.date(), Locale::PtBr, &[])
+                .due(now, nine)
+                .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+        };
+
+        assert_eq!(due("Pagar dia 5").as_deref(), Some("2027-01-05 09:00"));
+        assert_eq!(due("Pagar dia 31").as_deref(), Some("2026-12-31 09:00"));
+        assert_eq!(due("Ligar às 9").as_deref(), Some("2027-01-01 09:00"));
+        assert_eq!(due("Pagar todo mês no dia 5").as_deref(), Some("2027-01-05 09:00"));
+        assert_eq!(due("Academia toda segunda às 7").as_deref(), Some("2027-01-04 07:00"));
+        assert_eq!(due("Ligar"), None);
+    }
+}
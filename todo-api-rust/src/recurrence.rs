@@ -0,0 +1,411 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+
+// Upper bound on steps of the rule (days, weeks, months or years), counted whether or
+// not a step yields a date, so a rule that never matches can't spin forever
+const MAX_ITERATIONS: u32 = 10_000;
+// Largest INTERVAL accepted; keeps every step far from the limits of chrono's dates
+const MAX_INTERVAL: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// Subset of an iCalendar RRULE: FREQ, INTERVAL, BYDAY, BYMONTHDAY, COUNT and UNTIL.
+/// Stored in `tasks.recurrence` as e.g. `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<Weekday>,
+    pub by_month_day: Option<u32>,
+    pub count: Option<u32>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl Recurrence {
+    pub fn new(freq: Frequency) -> Self {
+        Self {
+            freq,
+            interval: 1,
+            by_day: Vec::new(),
+            by_month_day: None,
+            count: None,
+            until: None,
+        }
+    }
+
+    /// Occurrences of the series anchored at `start` that fall in `[from, to]`.
+    /// Candidates are generated in the anchor's local time so "every day at 9am"
+    /// stays at 9am across DST changes.
+    pub fn occurrences<Tz: TimeZone>(
+        &self,
+        start: DateTime<Tz>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<DateTime<Utc>> {
+        let tz = start.timezone();
+        let local_start = start.naive_local();
+        let mut result = Vec::new();
+        let mut emitted = 0u32;
+
+        for candidate in self.candidates(local_start) {
+            let Some(at) = tz.from_local_datetime(&candidate).earliest() else {
+                continue;
+            };
+            let at = at.with_timezone(&Utc);
+
+            if at > to || self.until.is_some_and(|until| at > until) {
+                break;
+            }
+            if self.count.is_some_and(|count| emitted >= count) {
+                break;
+            }
+            emitted += 1;
+
+            if at >= from {
+                result.push(at);
+            }
+        }
+
+        result
+    }
+
+    fn candidates(&self, start: NaiveDateTime) -> impl Iterator<Item = NaiveDateTime> + '_ {
+        let time = start.time();
+
+        (0..MAX_ITERATIONS)
+            // `None` means the dates ran out of chrono's range: the series ends there
+            .map_while(move |step| self.step_days(start.date(), step))
+            .flat_map(move |days| {
+                days.into_iter()
+                    .map(move |day| NaiveDateTime::new(day, time))
+                    .filter(move |candidate| *candidate >= start)
+            })
+    }
+
+    // Dates of step `step` in order. Days that don't exist in a month or year (the 31st,
+    // Feb 29) are skipped, as in RFC 5545; `None` only when the arithmetic overflows.
+    fn step_days(&self, start: NaiveDate, step: u32) -> Option<Vec<NaiveDate>> {
+        let offset = step.checked_mul(self.interval.max(1))?;
+
+        let days = match self.freq {
+            Frequency::Daily => vec![start.checked_add_signed(Duration::days(offset.into()))?],
+            Frequency::Weekly if self.by_day.is_empty() => {
+                vec![start.checked_add_signed(Duration::weeks(offset.into()))?]
+            }
+            Frequency::Weekly => {
+                let monday = start
+                    .checked_sub_signed(Duration::days(start.weekday().num_days_from_monday().into()))?
+                    .checked_add_signed(Duration::weeks(offset.into()))?;
+                let mut days = self
+                    .by_day
+                    .iter()
+                    .map(|wd| monday.checked_add_signed(Duration::days(wd.num_days_from_monday().into())))
+                    .collect::<Option<Vec<NaiveDate>>>()?;
+                days.sort();
+                days
+            }
+            Frequency::Monthly => {
+                let month = start.with_day(1)?.checked_add_months(Months::new(offset))?;
+                let day = self.by_month_day.unwrap_or(start.day());
+                month.with_day(day).into_iter().collect()
+            }
+            Frequency::Yearly => {
+                let month = start.with_day(1)?.checked_add_months(Months::new(offset.checked_mul(12)?))?;
+                month.with_day(start.day()).into_iter().collect()
+            }
+        };
+
+        Some(days)
+    }
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    match value {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn weekday_code(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn parse_until(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = value.parse::<DateTime<Utc>>() {
+        return Some(dt);
+    }
+    let value = value.trim_end_matches('Z');
+    if let Ok(dt) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        return Some(dt.and_utc());
+    }
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .ok()
+        .map(|d| NaiveDateTime::new(d, NaiveTime::from_hms_opt(23, 59, 59).unwrap()).and_utc())
+}
+
+#[derive(Debug)]
+pub struct InvalidRecurrence;
+
+impl FromStr for Recurrence {
+    type Err = InvalidRecurrence;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().trim_start_matches("RRULE:");
+        let mut freq = None;
+        let mut rule = Recurrence::new(Frequency::Daily);
+
+        for part in s.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or(InvalidRecurrence)?;
+            let value = value.trim().to_uppercase();
+            match key.trim().to_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(InvalidRecurrence),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value.parse().map_err(|_| InvalidRecurrence)?;
+                    if !(1..=MAX_INTERVAL).contains(&rule.interval) {
+                        return Err(InvalidRecurrence);
+                    }
+                }
+                "BYDAY" => {
+                    rule.by_day = value
+                        .split(',')
+                        .map(|d| parse_weekday(d.trim()).ok_or(InvalidRecurrence))
+                        .collect::<Result<_, _>>()?;
+                }
+                "BYMONTHDAY" => {
+                    let day: u32 = value.parse().map_err(|_| InvalidRecurrence)?;
+                    if !(1..=31).contains(&day) {
+                        return Err(InvalidRecurrence);
+                    }
+                    rule.by_month_day = Some(day);
+                }
+                "COUNT" => rule.count = Some(value.parse().map_err(|_| InvalidRecurrence)?),
+                "UNTIL" => rule.until = Some(parse_until(&value).ok_or(InvalidRecurrence)?),
+                _ => return Err(InvalidRecurrence),
+            }
+        }
+
+        rule.freq = freq.ok_or(InvalidRecurrence)?;
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let freq = match self.freq {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={}", freq)?;
+        if self.interval > 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<&str> = self.by_day.iter().map(|d| weekday_code(*d)).collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if let Some(day) = self.by_month_day {
+            write!(f, ";BYMONTHDAY={}", day)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%SZ"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Normalizes an optional recurrence string from a request. Empty means "no recurrence".
+pub fn normalize(value: Option<&str>) -> Result<Option<String>, InvalidRecurrence> {
+    match value.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(value) => value.parse::<Recurrence>().map(|r| Some(r.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Tz;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    fn rule(value: &str) -> Recurrence {
+        value.parse().unwrap()
+    }
+
+    fn dates(occurrences: &[DateTime<Utc>]) -> Vec<String> {
+        occurrences.iter().map(|at| at.format("%Y-%m-%d").to_string()).collect()
+    }
+
+    #[test]
+    fn parses_and_prints_rules() {
+        for value in [
+            "FREQ=DAILY",
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH",
+            "FREQ=MONTHLY;BYMONTHDAY=31",
+            "FREQ=YEARLY;COUNT=3",
+            "FREQ=DAILY;UNTIL=20260301T120000Z",
+        ] {
+            assert_eq!(rule(value).to_string(), value);
+        }
+
+        let parsed = rule("RRULE:freq=weekly;byday=mo,we;interval=1");
+        assert_eq!(parsed.freq, Frequency::Weekly);
+        assert_eq!(parsed.by_day, vec![Weekday::Mon, Weekday::Wed]);
+        assert_eq!(parsed.to_string(), "FREQ=WEEKLY;BYDAY=MO,WE");
+        assert_eq!(rule("FREQ=DAILY;UNTIL=20260301").until, Some(utc("2026-03-01T23:59:59Z")));
+        assert_eq!(rule("FREQ=DAILY;INTERVAL=1000").interval, 1000);
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for value in [
+            "",
+            "INTERVAL=2",
+            "FREQ=HOURLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;INTERVAL=1001",
+            "FREQ=DAILY;INTERVAL=4000000000",
+            "FREQ=DAILY;INTERVAL=-1",
+            "FREQ=MONTHLY;BYMONTHDAY=0",
+            "FREQ=MONTHLY;BYMONTHDAY=32",
+            "FREQ=WEEKLY;BYDAY=XX",
+            "FREQ=DAILY;COUNT=many",
+            "FREQ=DAILY;UNTIL=tomorrow",
+            "FREQ=DAILY;BYHOUR=9",
+            "FREQ",
+        ] {
+            assert!(value.parse::<Recurrence>().is_err(), "{} should be rejected", value);
+        }
+    }
+
+    #[test]
+    fn normalizes_requests() {
+        assert_eq!(normalize(None).unwrap(), None);
+        assert_eq!(normalize(Some("  ")).unwrap(), None);
+        assert_eq!(normalize(Some("freq=daily;interval=1")).unwrap().as_deref(), Some("FREQ=DAILY"));
+        assert!(normalize(Some("FREQ=DAILY;INTERVAL=0")).is_err());
+    }
+
+    #[test]
+    fn expands_daily_and_weekly_rules() {
+        let start = utc("2026-03-02T09:00:00Z");
+        let daily = rule("FREQ=DAILY;INTERVAL=2;COUNT=3").occurrences(start, start, utc("2026-12-31T00:00:00Z"));
+        assert_eq!(dates(&daily), ["2026-03-02", "2026-03-04", "2026-03-06"]);
+
+        // Anchored on a Monday: Mondays and Thursdays of every other week
+        let weekly = rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=TH,MO")
+            .occurrences(start, start, utc("2026-03-20T00:00:00Z"));
+        assert_eq!(dates(&weekly), ["2026-03-02", "2026-03-05", "2026-03-16", "2026-03-19"]);
+        assert!(weekly.iter().all(|at| at.format("%H:%M").to_string() == "09:00"));
+    }
+
+    #[test]
+    fn counts_occurrences_before_the_range() {
+        let start = utc("2026-01-01T08:00:00Z");
+        let occurrences = rule("FREQ=DAILY;COUNT=5")
+            .occurrences(start, utc("2026-01-04T00:00:00Z"), utc("2026-01-31T00:00:00Z"));
+        assert_eq!(dates(&occurrences), ["2026-01-04", "2026-01-05"]);
+    }
+
+    #[test]
+    fn stops_at_until() {
+        let start = utc("2026-01-01T08:00:00Z");
+        let occurrences = rule("FREQ=WEEKLY;UNTIL=20260115")
+            .occurrences(start, start, utc("2026-12-31T00:00:00Z"));
+        assert_eq!(dates(&occurrences), ["2026-01-01", "2026-01-08", "2026-01-15"]);
+    }
+
+    #[test]
+    fn skips_month_days_that_do_not_exist() {
+        let start = utc("2026-01-31T10:00:00Z");
+        let occurrences = rule("FREQ=MONTHLY").occurrences(start, start, utc("2026-06-30T23:59:59Z"));
+        assert_eq!(dates(&occurrences), ["2026-01-31", "2026-03-31", "2026-05-31"]);
+
+        let anchor = utc("2026-01-05T10:00:00Z");
+        let occurrences = rule("FREQ=MONTHLY;BYMONTHDAY=30")
+            .occurrences(anchor, anchor, utc("2026-04-30T23:59:59Z"));
+        assert_eq!(dates(&occurrences), ["2026-01-30", "2026-03-30", "2026-04-30"]);
+    }
+
+    #[test]
+    fn expands_feb_29_only_in_leap_years() {
+        let start = utc("2024-02-29T12:00:00Z");
+        let occurrences = rule("FREQ=YEARLY").occurrences(start, start, utc("2033-01-01T00:00:00Z"));
+        assert_eq!(dates(&occurrences), ["2024-02-29", "2028-02-29", "2032-02-29"]);
+
+        let occurrences = rule("FREQ=YEARLY;COUNT=2").occurrences(start, start, utc("2100-01-01T00:00:00Z"));
+        assert_eq!(dates(&occurrences), ["2024-02-29", "2028-02-29"]);
+    }
+
+    #[test]
+    fn terminates_when_no_step_matches() {
+        // Every 12 months from February: the 30th never exists
+        let start = utc("2026-02-01T09:00:00Z");
+        let occurrences = rule("FREQ=MONTHLY;BYMONTHDAY=30;INTERVAL=12")
+            .occurrences(start, start, utc("9999-12-31T00:00:00Z"));
+        assert!(occurrences.is_empty());
+    }
+
+    #[test]
+    fn stops_instead_of_overflowing() {
+        let start = utc("2026-02-01T09:00:00Z");
+        let end = utc("9999-12-31T00:00:00Z");
+
+        let occurrences = rule("FREQ=DAILY;INTERVAL=1000").occurrences(start, start, end);
+        assert_eq!(occurrences.len(), 2913);
+
+        // Rules built in code skip the INTERVAL check
+        for freq in [Frequency::Daily, Frequency::Weekly, Frequency::Monthly, Frequency::Yearly] {
+            let rule = Recurrence { interval: u32::MAX, ..Recurrence::new(freq) };
+            assert_eq!(dates(&rule.occurrences(start, start, end)), ["2026-02-01"]);
+        }
+        let occurrences = rule("FREQ=YEARLY").occurrences(utc("9998-06-01T00:00:00Z"), start, end);
+        assert_eq!(dates(&occurrences), ["9998-06-01", "9999-06-01"]);
+    }
+
+    #[test]
+    fn keeps_local_time_across_dst() {
+        let tz: Tz = "Europe/Berlin".parse().unwrap();
+        let start = tz.with_ymd_and_hms(2026, 3, 28, 9, 0, 0).unwrap();
+        let occurrences = rule("FREQ=DAILY;COUNT=2")
+            .occurrences(start, utc("2026-03-01T00:00:00Z"), utc("2026-04-01T00:00:00Z"));
+        assert_eq!(occurrences, [utc("2026-03-28T08:00:00Z"), utc("2026-03-29T07:00:00Z")]);
+    }
+}
@@ -0,0 +1,545 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use minijinja::context;
+use serde_json::json;
+use sqlx::{FromRow, MySqlPool};
+
+use crate::config::Config;
+use crate::handlers::settings::load_settings;
+use crate::i18n::{self, Locale};
+use crate::mail::{self, MailKind};
+use crate::metrics::METRICS;
+use crate::models::{PushSubscription, WebhookEvent, PUSH_SUBSCRIPTION_COLUMNS};
+use crate::push::{self, Sent, VapidKey};
+use crate::webhooks;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+const BATCH_SIZE: i64 = 100;
+// A reminder this late is stale news; the server skips it rather than ring hours after
+const MAX_LATENESS_HOURS: i64 = 6;
+// How long push services keep a reminder for a device that is offline
+const PUSH_TTL_SECS: u32 = 6 * 60 * 60;
+
+/// A way the server delivers a reminder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReminderChannel {
+    Push,
+    Email,
+    Webhook,
+}
+
+impl ReminderChannel {
+    pub const ALL: [ReminderChannel; 3] = [ReminderChannel::Push, ReminderChannel::Email, ReminderChannel::Webhook];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReminderChannel::Push => "push",
+            ReminderChannel::Email => "email",
+            ReminderChannel::Webhook => "webhook",
+        }
+    }
+
+    /// `push,email webhook`; unknown names are skipped and `none` gives no channel.
+    pub fn parse_list(value: &str) -> Vec<Self> {
+        let mut channels = Vec::new();
+        for name in value.split(|c: char| c == ',' || c.is_whitespace()).filter(|n| !n.is_empty()) {
+            match name.parse() {
+                Ok(channel) if !channels.contains(&channel) => channels.push(channel),
+                Ok(_) => {}
+                Err(_) if name.eq_ignore_ascii_case("none") => {}
+                Err(_) => tracing::warn!(channel = name, "Unknown reminder channel ignored"),
+            }
+        }
+        channels
+    }
+}
+
+impl FromStr for ReminderChannel {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ReminderChannel::ALL
+            .into_iter()
+            .find(|channel| channel.as_str().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+// A reminder that is due: the task's own `reminder`, or a row of `task_reminders`
+#[derive(Debug, FromRow)]
+struct DueReminder {
+    task_id: String,
+    reminder_id: Option<String>,
+    title: String,
+    list_name: String,
+    user_id: String,
+    username: String,
+    email: String,
+    is_verified: bool,
+    due_at: Option<DateTime<Utc>>,
+    fires_at: DateTime<Utc>,
+}
+
+// What the dispatcher can use; channels without their setup are skipped
+struct Channels {
+    push: Option<(reqwest::Client, VapidKey)>,
+    email: bool,
+    webhook: bool,
+}
+
+impl Channels {
+    fn from_config(config: &Config) -> Self {
+        let enabled = |channel| config.reminder_channels.contains(&channel);
+
+        let push = if enabled(ReminderChannel::Push) {
+            let vapid = match config.vapid_private_key.as_deref().map(VapidKey::from_base64) {
+                Some(Ok(vapid)) => Some(vapid),
+                Some(Err(e)) => {
+                    tracing::warn!(error = %e, "Invalid VAPID_PRIVATE_KEY, reminders will not be pushed");
+                    None
+                }
+                None => {
+                    tracing::info!("VAPID_PRIVATE_KEY is not set, reminders will not be pushed");
+                    None
+                }
+            };
+            vapid.and_then(|vapid| match push::client(config) {
+                Ok(client) => Some((client, vapid)),
+                Err(e) => {
+                    tracing::error!(error = %e, "Push HTTP client unavailable, reminders will not be pushed");
+                    None
+                }
+            })
+        } else {
+            None
+        };
+
+        Self {
+            push,
+            email: enabled(ReminderChannel::Email),
+            webhook: enabled(ReminderChannel::Webhook),
+        }
+    }
+}
+
+/// Delivers due reminders through the channels in `REMINDER_CHANNELS`, so they ring even
+/// when no device has its own scheduler running. Each fire time is delivered once: the
+/// time is recorded in `reminder_delivered_for` or `task_reminders.delivered_for`, and a
+/// snooze or a new time makes the reminder due again.
+pub async fn run(pool: MySqlPool, config: Config) {
+    if config.reminder_channels.is_empty() {
+        tracing::info!("Reminder dispatcher disabled");
+        return;
+    }
+
+    let channels = Channels::from_config(&config);
+    tracing::info!(
+        push = channels.push.is_some(),
+        email = channels.email,
+        webhook = channels.webhook,
+        "Reminder dispatcher started"
+    );
+
+    loop {
+        let delivered = match deliver_batch(&pool, &config, &channels).await {
+            Ok(count) => count,
+            Err(e) => {
+                tracing::error!(error = %e, "Reminder dispatcher batch failed");
+                0
+            }
+        };
+
+        // A full batch likely means more is waiting
+        if delivered < BATCH_SIZE as usize {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+async fn deliver_batch(pool: &MySqlPool, config: &Config, channels: &Channels) -> Result<usize, sqlx::Error> {
+    let now = Utc::now();
+    let oldest = now - chrono::Duration::hours(MAX_LATENESS_HOURS);
+
+    // Same rows as `vw_pending_reminders`, limited to the lateness window
+    let due: Vec<DueReminder> = sqlx::query_as(
+        r#"
+        SELECT t.id AS task_id, NULL AS reminder_id, t.title, l.name AS list_name,
+               l.user_id, u.username, u.email, u.is_verified, t.due_at, t.reminder AS fires_at
+        FROM tasks t
+        JOIN todo_lists l ON t.list_id = l.id
+        JOIN users u ON l.user_id = u.id
+        WHERE t.deleted_at IS NULL AND l.deleted_at IS NULL AND t.completed = 0
+            AND t.reminder > ? AND t.reminder <= ?
+            AND NOT (t.reminder_delivered_for <=> t.reminder)
+        UNION ALL
+        SELECT p.task_id, p.reminder_id, p.title, p.list_name,
+               p.user_id, p.username, p.email, p.is_verified, p.due_at, p.fires_at
+        FROM (
+            SELECT t.id AS task_id, r.id AS reminder_id, t.title, l.name AS list_name,
+                   l.user_id, u.username, u.email, u.is_verified, t.due_at, r.delivered_for,
+                   COALESCE(r.snoozed_until, r.remind_at, t.due_at - INTERVAL r.minutes_before_due MINUTE) AS fires_at
+            FROM task_reminders r
+            JOIN tasks t ON r.task_id = t.id
+            JOIN todo_lists l ON t.list_id = l.id
+            JOIN users u ON l.user_id = u.id
+            WHERE t.deleted_at IS NULL AND l.deleted_at IS NULL AND t.completed = 0
+                AND r.dismissed_at IS NULL
+        ) p
+        WHERE p.fires_at > ? AND p.fires_at <= ?
+            AND NOT (p.delivered_for <=> p.fires_at)
+        ORDER BY fires_at
+        LIMIT ?
+        "#
+    )
+    .bind(oldest)
+    .bind(now)
+    .bind(oldest)
+    .bind(now)
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    let count = due.len();
+    for reminder in &due {
+        // Another instance may have got there first, or the reminder changed meanwhile
+        if claim(pool, reminder).await? {
+            dispatch(pool, config, channels, reminder).await;
+        }
+    }
+
+    Ok(count)
+}
+
+// Helper: Marks the fire time as delivered, if it still is the current one and nobody
+// else marked it. `updated_at` is kept so the mark doesn't show up as a change in sync.
+async fn claim(pool: &MySqlPool, reminder: &DueReminder) -> Result<bool, sqlx::Error> {
+    let result = match &reminder.reminder_id {
+        None => {
+            sqlx::query(
+                r#"
+                UPDATE tasks SET reminder_delivered_for = ?, updated_at = updated_at
+                WHERE id = ? AND reminder = ? AND NOT (reminder_delivered_for <=> ?)
+                "#
+            )
+            .bind(reminder.fires_at)
+            .bind(&reminder.task_id)
+            .bind(reminder.fires_at)
+            .bind(reminder.fires_at)
+            .execute(pool)
+            .await?
+        }
+        Some(reminder_id) => {
+            sqlx::query(
+                r#"
+                UPDATE task_reminders r JOIN tasks t ON r.task_id = t.id
+                SET r.delivered_for = ?
+                WHERE r.id = ? AND r.dismissed_at IS NULL
+                    AND COALESCE(r.snoozed_until, r.remind_at, t.due_at - INTERVAL r.minutes_before_due MINUTE) = ?
+                    AND NOT (r.delivered_for <=> ?)
+                "#
+            )
+            .bind(reminder.fires_at)
+            .bind(reminder_id)
+            .bind(reminder.fires_at)
+            .bind(reminder.fires_at)
+            .execute(pool)
+            .await?
+        }
+    };
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Helper: Sends one reminder through every channel. A failed channel doesn't stop the
+// others, and the reminder isn't retried: a late reminder is worse than a missed push.
+async fn dispatch(pool: &MySqlPool, config: &Config, channels: &Channels, reminder: &DueReminder) {
+    if let Some((client, vapid)) = &channels.push {
+        push_reminder(pool, config, client, vapid, reminder).await;
+    }
+
+    if channels.email && reminder.is_verified {
+        email_reminder(pool, config, reminder).await;
+    }
+
+    if channels.webhook {
+        webhooks::notify(pool, &reminder.user_id, WebhookEvent::ReminderDue, &reminder.task_id).await;
+        METRICS.reminder(ReminderChannel::Webhook.as_str(), "sent");
+    }
+}
+
+async fn push_reminder(
+    pool: &MySqlPool,
+    config: &Config,
+    client: &reqwest::Client,
+    vapid: &VapidKey,
+    reminder: &DueReminder,
+) {
+    let subscriptions: Vec<PushSubscription> = match sqlx::query_as(&format!(
+        "SELECT {} FROM push_subscriptions WHERE user_id = ?",
+        PUSH_SUBSCRIPTION_COLUMNS
+    ))
+    .bind(&reminder.user_id)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(subscriptions) => subscriptions,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to load push subscriptions");
+            return;
+        }
+    };
+
+    let payload = json!({
+        "type": "reminder",
+        "task_id": reminder.task_id,
+        "reminder_id": reminder.reminder_id,
+        "title": reminder.title,
+        "list": reminder.list_name,
+        "due_at": reminder.due_at,
+        "fires_at": reminder.fires_at,
+    })
+    .to_string();
+
+    for subscription in &subscriptions {
+        match push::send(client, config, vapid, subscription, payload.as_bytes(), PUSH_TTL_SECS).await {
+            Sent::Delivered => {
+                METRICS.reminder(ReminderChannel::Push.as_str(), "sent");
+                let _ = sqlx::query("UPDATE push_subscriptions SET last_used_at = ? WHERE id = ?")
+                    .bind(Utc::now())
+                    .bind(&subscription.id)
+                    .execute(pool)
+                    .await;
+            }
+            Sent::Expired => {
+                METRICS.reminder(ReminderChannel::Push.as_str(), "expired");
+                tracing::info!(subscription_id = %subscription.id, "Push subscription expired, deleting it");
+                let _ = sqlx::query("DELETE FROM push_subscriptions WHERE id = ?")
+                    .bind(&subscription.id)
+                    .execute(pool)
+                    .await;
+            }
+            Sent::Failed(error) => {
+                METRICS.reminder(ReminderChannel::Push.as_str(), "failed");
+                tracing::warn!(subscription_id = %subscription.id, error = %error, "Reminder push failed");
+            }
+        }
+    }
+}
+
+async fn email_reminder(pool: &MySqlPool, config: &Config, reminder: &DueReminder) {
+    let settings = match load_settings(pool, &reminder.user_id).await {
+        Ok(settings) if settings.reminder_email => settings,
+        Ok(_) => return,
+        Err(e) => {
+            tracing::error!(error = ?e, "Failed to load settings for a reminder email");
+            return;
+        }
+    };
+
+    let locale = Locale::from_tag(&settings.locale).unwrap_or(i18n::DEFAULT_LOCALE);
+    let tz = settings.tz();
+    // Same formats as exports
+    let pattern = match locale {
+        Locale::PtBr => "%d/%m/%Y %H:%M",
+        Locale::En => "%Y-%m-%d %H:%M",
+    };
+    let context = context! {
+        name => &reminder.username,
+        title => &reminder.title,
+        list => &reminder.list_name,
+        due => reminder.due_at.map(|due| due.with_timezone(&tz).format(pattern).to_string()),
+    };
+
+    let outcome = match mail::send(pool, config, MailKind::Reminder, locale, &reminder.email, context).await {
+        Ok(()) => "sent",
+        Err(e) => {
+            tracing::warn!(error = ?e, "Failed to queue a reminder email");
+            "failed"
+        }
+    };
+    METRICS.reminder(ReminderChannel::Email.as_str(), outcome);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use actix_web::{web, App, HttpServer};
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use chrono::{Duration, Timelike};
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use serde_json::Value;
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::handlers;
+    use crate::push::sink;
+    use crate::test_support;
+
+    // Push settings pointing at the stand-in push service, served on a free port
+    fn start_sink(config: Config) -> Config {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let key = EcKey::generate(&EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap()).unwrap();
+        let config = Config {
+            public_url: format!("http://{}", listener.local_addr().unwrap()),
+            push_test_sink: true,
+            vapid_private_key: Some(URL_SAFE_NO_PAD.encode(key.private_key().to_vec_padded(32).unwrap())),
+            reminder_channels: vec![ReminderChannel::Push],
+            ..config
+        };
+
+        let data = web::Data::new(config.clone());
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(data.clone())
+                .route("/push/sink/{id}", web::post().to(handlers::push::receive_sink))
+        })
+        .workers(1)
+        .listen(listener)
+        .unwrap()
+        .run();
+        actix_web::rt::spawn(server);
+
+        config
+    }
+
+    // Helper: Id of the inbox behind a sink endpoint
+    fn inbox(endpoint: &str) -> String {
+        endpoint.rsplit('/').next().unwrap().to_string()
+    }
+
+    fn payloads(inbox: &str) -> Vec<Value> {
+        sink::messages(inbox).unwrap().into_iter().map(|message| message.payload).collect()
+    }
+
+    #[test]
+    fn parses_channel_lists() {
+        assert_eq!(ReminderChannel::parse_list("push,email webhook"), ReminderChannel::ALL);
+        assert_eq!(ReminderChannel::parse_list("Email, email,,sms"), [ReminderChannel::Email]);
+        assert!(ReminderChannel::parse_list("none").is_empty());
+    }
+
+    #[actix_web::test]
+    async fn pushes_reach_the_test_sink() {
+        let config = start_sink(Config::for_tests());
+        let Channels { push: Some((client, vapid)), .. } = Channels::from_config(&config) else {
+            panic!("push channel not set up");
+        };
+        let created = sink::create(&config).unwrap();
+        let subscription = PushSubscription {
+            id: "subscription".to_string(),
+            endpoint: created.endpoint.clone(),
+            p256dh: created.keys.p256dh,
+            auth: created.keys.auth,
+            user_agent: None,
+            created_at: Utc::now(),
+            last_used_at: None,
+        };
+
+        let sent = push::send(&client, &config, &vapid, &subscription, br#"{"type":"reminder"}"#, PUSH_TTL_SECS).await;
+
+        assert!(matches!(sent, Sent::Delivered));
+        let messages = sink::messages(&inbox(&created.endpoint)).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].payload["type"], "reminder");
+        assert_eq!(messages[0].ttl, Some(PUSH_TTL_SECS));
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn fires_each_reminder_once() {
+        let (pool, config) = test_support::database().await;
+        let config = start_sink(config);
+        let channels = Channels::from_config(&config);
+        // DATETIME columns keep whole seconds
+        let now = Utc::now().with_nanosecond(0).unwrap();
+        let minutes_ago = |minutes: i64| now - Duration::minutes(minutes);
+
+        let (user_id, _) = test_support::create_user(&pool, "reminders", "!", true).await;
+        let list_id = test_support::create_list(&pool, &user_id, "Inbox").await;
+        let task_id = uuid::Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO tasks (id, list_id, title, completed, reminder, created_at) VALUES (?, ?, 'Call Ana', 0, ?, NOW())")
+            .bind(&task_id)
+            .bind(&list_id)
+            .bind(minutes_ago(1))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // Due, snoozed past now, and dismissed
+        let reminder_ids: Vec<String> = (0..3).map(|_| uuid::Uuid::new_v4().to_string()).collect();
+        for (id, (remind_at, snoozed_until, dismissed_at)) in reminder_ids.iter().zip([
+            (minutes_ago(2), None, None),
+            (minutes_ago(3), Some(now + Duration::hours(1)), None),
+            (minutes_ago(4), None, Some(now)),
+        ]) {
+            sqlx::query(
+                r#"
+                INSERT INTO task_reminders (id, task_id, remind_at, snoozed_until, dismissed_at, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, NOW(), NOW())
+                "#
+            )
+            .bind(id)
+            .bind(&task_id)
+            .bind(remind_at)
+            .bind(snoozed_until)
+            .bind(dismissed_at)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let created = sink::create(&config).unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO push_subscriptions (id, user_id, endpoint, endpoint_hash, p256dh, auth, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, NOW())
+            "#
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&user_id)
+        .bind(&created.endpoint)
+        .bind(format!("{:x}", Sha256::digest(created.endpoint.as_bytes())))
+        .bind(&created.keys.p256dh)
+        .bind(&created.keys.auth)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let inbox = inbox(&created.endpoint);
+
+        // A second pass finds nothing new
+        for _ in 0..2 {
+            deliver_batch(&pool, &config, &channels).await.unwrap();
+        }
+        let first = payloads(&inbox);
+
+        // The snooze runs out and the task's own reminder moves: both ring again
+        sqlx::query("UPDATE task_reminders SET snoozed_until = ? WHERE id = ?")
+            .bind(now - Duration::seconds(10))
+            .bind(&reminder_ids[1])
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE tasks SET reminder = ? WHERE id = ?")
+            .bind(now - Duration::seconds(30))
+            .bind(&task_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            deliver_batch(&pool, &config, &channels).await.unwrap();
+        }
+        let all = payloads(&inbox);
+        test_support::delete_user(&pool, &user_id).await;
+
+        let fired = |payloads: &[Value]| -> Vec<Value> { payloads.iter().map(|p| p["reminder_id"].clone()).collect() };
+        // Oldest fire time first; the task's own reminder has no id
+        assert_eq!(fired(&first), [Value::from(reminder_ids[0].as_str()), Value::Null]);
+        assert_eq!(fired(&all[first.len()..]), [Value::Null, Value::from(reminder_ids[1].as_str())]);
+        assert!(all.iter().all(|p| p["type"] == "reminder" && p["task_id"] == task_id.as_str()));
+    }
+}
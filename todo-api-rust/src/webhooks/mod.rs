@@ -0,0 +1,131 @@
+use std::sync::LazyLock;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::{MySqlConnection, MySqlPool};
+use tokio::sync::Notify;
+
+use crate::models::{ListResponse, TaskResponse, TodoList, Task, WebhookEvent};
+
+pub mod target;
+pub mod worker;
+
+/// `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`, keyed with the webhook secret.
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+// Wakes the worker so new deliveries don't wait for the next poll
+static WAKE: LazyLock<Notify> = LazyLock::new(Notify::new);
+
+/// Signature for `body` sent at `timestamp`. The timestamp is part of the signed string so
+/// receivers can reject replays.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("t={},v1={:x}", timestamp, mac.finalize().into_bytes())
+}
+
+// Helper: Current state of the user's entity, soft-deleted rows included. Reminders send
+// their task.
+async fn snapshot(
+    conn: &mut MySqlConnection,
+    user_id: &str,
+    event: WebhookEvent,
+    entity_id: &str,
+) -> Result<serde_json::Value, sqlx::Error> {
+    let data = match event.entity_type() {
+        "task" | "reminder" => {
+            let task: Option<Task> = sqlx::query_as(
+                "SELECT t.* FROM tasks t JOIN todo_lists l ON t.list_id = l.id WHERE t.id = ? AND l.user_id = ?"
+            )
+            .bind(entity_id)
+            .bind(user_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+            task.map(|task| serde_json::to_value(TaskResponse::from(task)))
+        }
+        _ => {
+            let list: Option<TodoList> = sqlx::query_as(
+                "SELECT id, user_id, name, color, created_at, updated_at, deleted_at FROM todo_lists WHERE id = ? AND user_id = ?"
+            )
+            .bind(entity_id)
+            .bind(user_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+            list.map(|list| serde_json::to_value(ListResponse::from(list)))
+        }
+    };
+
+    Ok(data
+        .and_then(Result::ok)
+        .unwrap_or_else(|| json!({ "id": entity_id })))
+}
+
+/// Queues one delivery per active webhook of `user_id` subscribed to `event`. Runs on the
+/// caller's connection, so inside a transaction the deliveries commit with the change.
+pub async fn enqueue(
+    conn: &mut MySqlConnection,
+    user_id: &str,
+    event: WebhookEvent,
+    entity_id: &str,
+) -> Result<(), sqlx::Error> {
+    let webhooks: Vec<(String, String)> = sqlx::query_as(
+        "SELECT id, events FROM webhooks WHERE user_id = ? AND is_active = 1"
+    )
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let subscribed: Vec<String> = webhooks
+        .into_iter()
+        .filter(|(_, events)| events.split_whitespace().any(|e| e == event.as_str()))
+        .map(|(id, _)| id)
+        .collect();
+
+    if subscribed.is_empty() {
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    let payload = json!({
+        "id": uuid::Uuid::new_v4().to_string(),
+        "event": event.as_str(),
+        "created_at": now,
+        "data": snapshot(conn, user_id, event, entity_id).await?,
+    })
+    .to_string();
+
+    for webhook_id in &subscribed {
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries (webhook_id, event, payload, status, next_attempt_at, created_at)
+            VALUES (?, ?, ?, 'pending', ?, ?)
+            "#
+        )
+        .bind(webhook_id)
+        .bind(event.as_str())
+        .bind(&payload)
+        .bind(now)
+        .bind(now)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    WAKE.notify_one();
+    Ok(())
+}
+
+/// `enqueue` outside a transaction. Webhooks are a side effect of a change that already
+/// happened, so a failure is logged rather than returned.
+pub async fn notify(pool: &MySqlPool, user_id: &str, event: WebhookEvent, entity_id: &str) {
+    let result = match pool.acquire().await {
+        Ok(mut conn) => enqueue(&mut conn, user_id, event, entity_id).await,
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = result {
+        tracing::warn!(error = %e, event = %event, "Failed to queue webhook deliveries");
+    }
+}
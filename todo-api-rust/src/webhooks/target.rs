@@ -0,0 +1,197 @@
+use std::error::Error;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::Url;
+
+use crate::config::Config;
+
+/// Returned by the guarded resolver when every address of a host is internal.
+#[derive(Debug)]
+pub struct InternalTarget;
+
+impl fmt::Display for InternalTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Target resolves to a private address")
+    }
+}
+
+impl Error for InternalTarget {}
+
+// Helper: IPv4 ranges a user must not reach through us
+fn is_internal_v4(v4: Ipv4Addr) -> bool {
+    let [a, b, c, _] = v4.octets();
+    v4.is_private()
+        || v4.is_loopback()
+        || v4.is_link_local()
+        || v4.is_broadcast()
+        || v4.is_multicast()
+        // "This network" 0.0.0.0/8, which Linux routes to the local host
+        || a == 0
+        // Carrier-grade NAT, 100.64.0.0/10
+        || (a == 100 && (b & 0xc0) == 64)
+        // IETF protocol assignments, 192.0.0.0/24
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking, 198.18.0.0/15
+        || (a == 198 && (b & 0xfe) == 18)
+        // Reserved, 240.0.0.0/4
+        || a >= 240
+}
+
+/// Loopback, private, link-local and similar ranges a user must not reach through us.
+/// IPv6 addresses that carry an IPv4 one (mapped, NAT64, 6to4) are judged by it.
+pub fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_internal_v4(v4),
+        IpAddr::V6(v6) => {
+            let segments = v6.segments();
+            let embedded = |high: u16, low: u16| Ipv4Addr::from(((high as u32) << 16) | low as u32);
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                // Unique local fc00::/7 and link-local fe80::/10
+                || (segments[0] & 0xfe00) == 0xfc00
+                || (segments[0] & 0xffc0) == 0xfe80
+                || v6.to_ipv4_mapped().is_some_and(is_internal_v4)
+                // NAT64 64:ff9b::/96
+                || (segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] && is_internal_v4(embedded(segments[6], segments[7])))
+                // 6to4 2002::/16
+                || (segments[0] == 0x2002 && is_internal_v4(embedded(segments[1], segments[2])))
+        }
+    }
+}
+
+// Resolves names and drops internal addresses, so the address that passed the check is
+// the one connected to, whatever the DNS answers between lookups.
+struct GuardedResolver {
+    // Reached even when internal, like the Web Push stand-in on a development machine
+    exempt_host: Option<String>,
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let exempt = self.exempt_host.as_deref() == Some(name.as_str());
+        let host = name.as_str().to_string();
+
+        Box::pin(async move {
+            let resolved: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            let allowed: Vec<SocketAddr> = resolved
+                .iter()
+                .copied()
+                .filter(|address| exempt || !is_internal(address.ip()))
+                .collect();
+
+            if allowed.is_empty() && !resolved.is_empty() {
+                return Err(Box::new(InternalTarget) as Box<dyn Error + Send + Sync>);
+            }
+            Ok(Box::new(allowed.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Client for user-supplied URLs (webhooks, Web Push endpoints). Redirects are not
+/// followed, and unless WEBHOOK_ALLOW_PRIVATE_TARGETS is set, names resolving only to
+/// internal addresses fail with `InternalTarget`.
+pub fn client(config: &Config, exempt_host: Option<String>, timeout: Duration) -> reqwest::Result<reqwest::Client> {
+    let builder = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(timeout);
+
+    if config.webhook_allow_private_targets {
+        builder.build()
+    } else {
+        builder.dns_resolver(Arc::new(GuardedResolver { exempt_host })).build()
+    }
+}
+
+/// Why `url` must not be called, for what the resolver never sees: a missing host, or an
+/// IP literal in an internal range.
+pub fn check_url(url: &Url, config: &Config) -> Result<(), String> {
+    let Some(host) = url.host_str() else {
+        return Err("Invalid URL: no host".to_string());
+    };
+    // IPv6 literals come bracketed in the URL
+    let literal = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>();
+
+    match literal {
+        Ok(ip) if !config.webhook_allow_private_targets && is_internal(ip) => Err(InternalTarget.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Whether a request failed because its target only resolves to internal addresses.
+pub fn is_blocked(error: &reqwest::Error) -> bool {
+    let mut source: Option<&(dyn Error + 'static)> = Some(error);
+    while let Some(error) = source {
+        if error.is::<InternalTarget>() {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_internal_ranges() {
+        let internal = [
+            "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1",
+            "0.0.0.0", "0.1.2.3", "192.0.0.8", "198.18.0.1", "198.19.255.255", "224.0.0.1", "240.0.0.1",
+            "255.255.255.255", "::1", "::", "fc00::1", "fd12::1", "fe80::1", "ff02::1", "::ffff:10.0.0.1",
+            "64:ff9b::7f00:1", "64:ff9b::a9fe:a9fe", "2002:7f00:1::1", "2002:c0a8:101::",
+        ];
+        let public = [
+            "8.8.8.8", "1.1.1.1", "100.128.0.1", "192.0.1.1", "198.20.0.1", "2606:4700::1111",
+            "::ffff:8.8.8.8", "64:ff9b::808:808", "2002:808:808::1",
+        ];
+
+        for ip in internal {
+            assert!(is_internal(ip.parse().unwrap()), "{} should be internal", ip);
+        }
+        for ip in public {
+            assert!(!is_internal(ip.parse().unwrap()), "{} should be public", ip);
+        }
+    }
+
+    #[test]
+    fn checks_ip_literals_in_urls() {
+        let config = Config::for_tests();
+        let check = |url: &str| check_url(&Url::parse(url).unwrap(), &config);
+
+        assert!(check("https://127.0.0.1/hook").is_err());
+        assert!(check("https://[::ffff:192.168.0.1]:8443/hook").is_err());
+        assert!(check("https://8.8.8.8/hook").is_ok());
+        // Names are left to the resolver
+        assert!(check("https://localhost/hook").is_ok());
+
+        let permissive = Config { webhook_allow_private_targets: true, ..Config::for_tests() };
+        assert!(check_url(&Url::parse("https://127.0.0.1/hook").unwrap(), &permissive).is_ok());
+    }
+
+    // The check happens where the connection is made, so a name can't pass it with one
+    // answer and connect with another
+    #[actix_web::test]
+    async fn resolver_refuses_names_of_internal_hosts() {
+        let config = Config::for_tests();
+        let timeout = Duration::from_secs(5);
+
+        let guarded = client(&config, None, timeout).unwrap();
+        let error = guarded.get("http://localhost:9/").send().await.unwrap_err();
+        assert!(is_blocked(&error), "{:?}", error);
+
+        // Exempt or allowed hosts get as far as connecting (nothing listens on port 9)
+        let exempt = client(&config, Some("localhost".to_string()), timeout).unwrap();
+        let error = exempt.get("http://localhost:9/").send().await.unwrap_err();
+        assert!(!is_blocked(&error), "{:?}", error);
+
+        let permissive = Config { webhook_allow_private_targets: true, ..Config::for_tests() };
+        let error = client(&permissive, None, timeout).unwrap().get("http://localhost:9/").send().await.unwrap_err();
+        assert!(!is_blocked(&error), "{:?}", error);
+    }
+}
@@ -0,0 +1,243 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use reqwest::header::{CONTENT_TYPE, USER_AGENT};
+use sqlx::{FromRow, MySqlPool};
+
+use super::target::{self, InternalTarget};
+use super::{sign, SIGNATURE_HEADER, WAKE};
+use crate::config::Config;
+use crate::metrics::METRICS;
+use crate::outbox::{self, backoff, Queue};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_ATTEMPTS: u32 = 8;
+// Enough of the receiver's answer to debug with, without storing whole error pages
+const MAX_RESPONSE_BODY: usize = 1000;
+
+#[derive(Debug, FromRow)]
+struct PendingDelivery {
+    id: u64,
+    event: String,
+    payload: String,
+    attempts: u32,
+    url: String,
+    secret: String,
+    is_active: bool,
+}
+
+// What one attempt produced
+struct Attempt {
+    response_status: Option<u16>,
+    response_body: Option<String>,
+    error: Option<String>,
+    retryable: bool,
+}
+
+impl Attempt {
+    fn failed(error: impl Into<String>, retryable: bool) -> Self {
+        Self {
+            response_status: None,
+            response_body: None,
+            error: Some(error.into()),
+            retryable,
+        }
+    }
+}
+
+enum Outcome {
+    Delivered,
+    Retry { at: DateTime<Utc> },
+    Failed,
+}
+
+/// Delivers `webhook_deliveries` until the process exits. Runs alongside the HTTP server.
+pub async fn run(pool: MySqlPool, config: Config) {
+    let client = match target::client(&config, None, REQUEST_TIMEOUT) {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!(error = %e, "Webhook HTTP client unavailable, deliveries will not be sent");
+            return;
+        }
+    };
+
+    tracing::info!("Webhook worker started");
+
+    outbox::drain(Queue::Webhooks, &WAKE, || deliver_batch(&pool, &client, &config)).await
+}
+
+async fn deliver_batch(pool: &MySqlPool, client: &reqwest::Client, config: &Config) -> Result<usize, sqlx::Error> {
+    let token = outbox::claim(pool, Queue::Webhooks, Utc::now()).await?;
+
+    let deliveries: Vec<PendingDelivery> = sqlx::query_as(
+        r#"
+        SELECT d.id, d.event, d.payload, d.attempts, w.url, w.secret, w.is_active
+        FROM webhook_deliveries d
+        JOIN webhooks w ON w.id = d.webhook_id
+        WHERE d.claim_token = ?
+        ORDER BY d.id
+        "#
+    )
+    .bind(&token)
+    .fetch_all(pool)
+    .await?;
+
+    for delivery in &deliveries {
+        let attempt = if delivery.is_active {
+            deliver(client, config, delivery).await
+        } else {
+            Attempt::failed("Webhook disabled", false)
+        };
+        record(pool, delivery, attempt).await?;
+    }
+
+    Ok(deliveries.len())
+}
+
+async fn deliver(client: &reqwest::Client, config: &Config, delivery: &PendingDelivery) -> Attempt {
+    let url = match reqwest::Url::parse(&delivery.url) {
+        Ok(url) => url,
+        Err(e) => return Attempt::failed(format!("Invalid URL: {}", e), false),
+    };
+
+    if let Err(error) = target::check_url(&url, config) {
+        return Attempt::failed(error, false);
+    }
+
+    let signature = sign(&delivery.secret, Utc::now().timestamp(), &delivery.payload);
+
+    let response = client
+        .post(url)
+        .header(CONTENT_TYPE, "application/json")
+        .header(USER_AGENT, "MyTudo-Webhooks/1.0")
+        .header("X-Webhook-Event", &delivery.event)
+        .header("X-Webhook-Delivery", delivery.id.to_string())
+        .header(SIGNATURE_HEADER, signature)
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+
+    let response = match response {
+        Ok(response) => response,
+        // Pointing the name at a public address later is a new webhook, not a retry
+        Err(e) if target::is_blocked(&e) => return Attempt::failed(InternalTarget.to_string(), false),
+        Err(e) => return Attempt::failed(e.to_string(), true),
+    };
+
+    let status = response.status();
+    let body = read_prefix(response).await;
+
+    Attempt {
+        response_status: Some(status.as_u16()),
+        response_body: Some(body),
+        error: (!status.is_success()).then(|| format!("HTTP {}", status.as_u16())),
+        // 410 Gone means the receiver wants no more of these
+        retryable: status != reqwest::StatusCode::GONE,
+    }
+}
+
+// Helper: Start of the response body. Stops reading at MAX_RESPONSE_BODY bytes, so a
+// receiver can't make us buffer an endless answer.
+async fn read_prefix(response: reqwest::Response) -> String {
+    let mut body = Vec::new();
+    let mut chunks = response.bytes_stream();
+
+    while let Some(Ok(chunk)) = chunks.next().await {
+        let room = MAX_RESPONSE_BODY - body.len();
+        body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        if body.len() == MAX_RESPONSE_BODY {
+            break;
+        }
+    }
+
+    // A character cut in half at the limit becomes U+FFFD
+    String::from_utf8_lossy(&body).into_owned()
+}
+
+// Helper: Persist the result of one attempt
+async fn record(pool: &MySqlPool, delivery: &PendingDelivery, attempt: Attempt) -> Result<(), sqlx::Error> {
+    let attempts = delivery.attempts + 1;
+    let now = Utc::now();
+
+    let outcome = match &attempt.error {
+        None => Outcome::Delivered,
+        Some(_) if attempt.retryable && attempts < MAX_ATTEMPTS => Outcome::Retry {
+            at: now + backoff(attempts),
+        },
+        Some(_) => Outcome::Failed,
+    };
+
+    let (status, next_attempt_at, delivered_at) = match outcome {
+        Outcome::Delivered => {
+            METRICS.webhook("delivered");
+            tracing::info!(id = delivery.id, event = %delivery.event, "Webhook delivered");
+            ("delivered", None, Some(now))
+        }
+        Outcome::Retry { at } => {
+            METRICS.webhook("retry");
+            tracing::warn!(id = delivery.id, event = %delivery.event, attempts, error = ?attempt.error, "Webhook failed, will retry");
+            ("pending", Some(at), None)
+        }
+        Outcome::Failed => {
+            METRICS.webhook("failed");
+            tracing::error!(id = delivery.id, event = %delivery.event, attempts, error = ?attempt.error, "Webhook failed permanently");
+            ("failed", None, None)
+        }
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = ?, attempts = ?, response_status = ?, response_body = ?, last_error = ?,
+            next_attempt_at = COALESCE(?, next_attempt_at), delivered_at = ?, claim_token = NULL
+        WHERE id = ?
+        "#
+    )
+    .bind(status)
+    .bind(attempts)
+    .bind(attempt.response_status)
+    .bind(&attempt.response_body)
+    .bind(&attempt.error)
+    .bind(next_attempt_at)
+    .bind(delivered_at)
+    .bind(delivery.id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use actix_web::{web, App, HttpResponse, HttpServer};
+    use futures_util::stream;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn reads_at_most_the_stored_prefix() {
+        // Answers with a body that never ends
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = HttpServer::new(|| {
+            App::new().default_service(web::to(|| async {
+                let chunk = web::Bytes::from("é".repeat(300));
+                HttpResponse::Ok().streaming(stream::repeat(chunk).map(Ok::<_, actix_web::Error>))
+            }))
+        })
+        .workers(1)
+        .listen(listener)
+        .unwrap()
+        .run();
+        actix_web::rt::spawn(server);
+
+        let response = reqwest::Client::new().get(&url).send().await.unwrap();
+        let body = tokio::time::timeout(Duration::from_secs(5), read_prefix(response)).await.unwrap();
+
+        assert_eq!(body.len(), MAX_RESPONSE_BODY);
+        assert!(body.chars().all(|c| c == 'é'));
+    }
+}
@@ -2,10 +2,11 @@
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
 use tauri::State;
-use bcrypt::{hash, verify, DEFAULT_COST};
 use uuid::Uuid;
 use chrono::Utc;
 
+mod password;
+
 // ============== MODELOS ==============
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -135,6 +136,14 @@ fn init_database(conn: &Connection) -> SqliteResult<()> {
 
 #[tauri::command]
 fn register(state: State<AppState>, request: RegisterRequest) -> ApiResponse<User> {
+    if let Err(message) = password::check_policy(&request.password) {
+        return ApiResponse {
+            success: false,
+            message,
+            data: None,
+        };
+    }
+
     let db = state.db.lock().unwrap();
     
     // Verificar se email já existe
@@ -155,9 +164,9 @@ fn register(state: State<AppState>, request: RegisterRequest) -> ApiResponse<Use
     }
 
     // Hash da senha
-    let password_hash = match hash(&request.password, DEFAULT_COST) {
-        Ok(h) => h,
-        Err(_) => {
+    let password_hash = match password::hash(&request.password) {
+        Some(h) => h,
+        None => {
             return ApiResponse {
                 success: false,
                 message: "Erro ao processar senha".to_string(),
@@ -210,28 +219,34 @@ fn login(state: State<AppState>, request: LoginRequest) -> ApiResponse<User> {
 
     match result {
         Ok((id, username, email, password_hash, created_at)) => {
-            match verify(&request.password, &password_hash) {
-                Ok(true) => {
-                    let user = User {
-                        id,
-                        username,
-                        email,
-                        created_at,
-                    };
-                    
-                    *state.current_user.lock().unwrap() = Some(user.clone());
-                    
-                    ApiResponse {
-                        success: true,
-                        message: "Login realizado com sucesso".to_string(),
-                        data: Some(user),
-                    }
-                }
-                _ => ApiResponse {
+            if !password::verify(&request.password, &password_hash) {
+                return ApiResponse {
                     success: false,
                     message: "Senha incorreta".to_string(),
                     data: None,
-                },
+                };
+            }
+
+            // Troca hashes bcrypt e de custo antigo pelo Argon2id atual; se falhar, o login segue
+            if password::needs_rehash(&password_hash) {
+                if let Some(new_hash) = password::hash(&request.password) {
+                    db.execute("UPDATE users SET password_hash = ? WHERE id = ?", [&new_hash, &id]).ok();
+                }
+            }
+
+            let user = User {
+                id,
+                username,
+                email,
+                created_at,
+            };
+            
+            *state.current_user.lock().unwrap() = Some(user.clone());
+            
+            ApiResponse {
+                success: true,
+                message: "Login realizado com sucesso".to_string(),
+                data: Some(user),
             }
         }
         Err(_) => ApiResponse {
@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+
+pub const MIN_LENGTH: usize = 8;
+pub const MAX_LENGTH: usize = 128;
+
+// Custo Argon2id recomendado pela OWASP (19 MiB, 2 passadas, 1 via), o mesmo padrão da API
+const MEMORY_KIB: u32 = 19 * 1024;
+const ITERATIONS: u32 = 2;
+const PARALLELISM: u32 = 1;
+
+const EMBEDDED_BLOCKLIST: &str = include_str!("../data/common-passwords.txt");
+
+static BLOCKLIST: OnceLock<HashSet<String>> = OnceLock::new();
+
+fn argon2() -> Argon2<'static> {
+    let params = Params::new(MEMORY_KIB, ITERATIONS, PARALLELISM, None).expect("parâmetros Argon2 válidos");
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+}
+
+/// Argon2id no formato PHC (`$argon2id$v=19$m=...`).
+pub fn hash(password: &str) -> Option<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    argon2()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .ok()
+}
+
+/// Confere a senha com um hash Argon2 ou com um bcrypt das versões anteriores do app.
+pub fn verify(password: &str, stored: &str) -> bool {
+    if stored.starts_with("$2") {
+        return bcrypt::verify(password, stored).unwrap_or(false);
+    }
+
+    // Os parâmetros vêm do próprio hash, então custos antigos continuam valendo
+    PasswordHash::new(stored)
+        .map(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+        .unwrap_or(false)
+}
+
+/// Verdadeiro para hashes bcrypt e Argon2 com parâmetros diferentes dos atuais.
+pub fn needs_rehash(stored: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(stored) else {
+        return true;
+    };
+    if parsed.algorithm != Algorithm::Argon2id.ident() || parsed.version != Some(Version::V0x13.into()) {
+        return true;
+    }
+
+    match Params::try_from(&parsed) {
+        Ok(current) => {
+            current.m_cost() != MEMORY_KIB || current.t_cost() != ITERATIONS || current.p_cost() != PARALLELISM
+        }
+        Err(_) => true,
+    }
+}
+
+fn blocklist() -> &'static HashSet<String> {
+    BLOCKLIST.get_or_init(|| {
+        EMBEDDED_BLOCKLIST
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_lowercase)
+            .collect()
+    })
+}
+
+// "aaaaaaaa", "12345678", "abcdefgh", "87654321"
+fn is_trivial(password: &str) -> bool {
+    let chars: Vec<char> = password.to_lowercase().chars().collect();
+    if chars.windows(2).all(|w| w[0] == w[1]) {
+        return true;
+    }
+
+    let steps: Vec<i64> = chars.windows(2).map(|w| w[1] as i64 - w[0] as i64).collect();
+    steps.iter().all(|&step| step == 1) || steps.iter().all(|&step| step == -1)
+}
+
+/// Tamanho, lista de senhas comuns e padrões triviais, nessa ordem, como na API.
+pub fn check_policy(password: &str) -> Result<(), String> {
+    let length = password.chars().count();
+    if length < MIN_LENGTH {
+        return Err(format!("Senha deve ter no mínimo {} caracteres", MIN_LENGTH));
+    }
+    if length > MAX_LENGTH {
+        return Err(format!("Senha deve ter no máximo {} caracteres", MAX_LENGTH));
+    }
+    if blocklist().contains(&password.to_lowercase()) {
+        return Err("Senha muito comum ou já vazada. Escolha outra.".to_string());
+    }
+    if is_trivial(password) {
+        return Err("Senha muito simples: evite sequências e caracteres repetidos".to_string());
+    }
+    Ok(())
+}
@@ -17,11 +17,14 @@ CREATE TABLE IF NOT EXISTS users (
     is_verified TINYINT(1) NOT NULL DEFAULT 0 COMMENT '0=não verificado, 1=verificado',
     verification_code VARCHAR(6) NULL COMMENT 'Código de verificação de 6 dígitos',
     code_expires_at DATETIME NULL COMMENT 'Expiração do código',
+    deletion_scheduled_at DATETIME NULL DEFAULT NULL COMMENT 'Exclusão agendada da conta (UTC)',
+    sessions_revoked_at DATETIME NULL DEFAULT NULL COMMENT 'Sessões (JWT) emitidas antes disto são recusadas (UTC)',
     created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT 'Data de criação',
     updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT 'Última atualização',
     
     UNIQUE INDEX idx_email (email),
-    INDEX idx_created (created_at)
+    INDEX idx_created (created_at),
+    INDEX idx_deletion_scheduled (deletion_scheduled_at)
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Tabela de usuários';
 
 -- Migração para adicionar campos de verificação (executar se tabela já existe)
@@ -29,6 +32,13 @@ CREATE TABLE IF NOT EXISTS users (
 -- ALTER TABLE users ADD COLUMN verification_code VARCHAR(6) NULL;
 -- ALTER TABLE users ADD COLUMN code_expires_at DATETIME NULL;
 
+-- Migração para exclusão de conta com carência (executar se tabela já existe)
+-- ALTER TABLE users ADD COLUMN deletion_scheduled_at DATETIME NULL DEFAULT NULL AFTER code_expires_at;
+-- ALTER TABLE users ADD INDEX idx_deletion_scheduled (deletion_scheduled_at);
+
+-- Migração para encerrar sessões ao desfazer uma troca de email (executar se tabela já existe)
+-- ALTER TABLE users ADD COLUMN sessions_revoked_at DATETIME NULL DEFAULT NULL AFTER deletion_scheduled_at;
+
 -- =====================================================
 -- TABELA: todo_lists (Listas de Tarefas)
 -- =====================================================
@@ -62,6 +72,10 @@ CREATE TABLE IF NOT EXISTS tasks (
     description TEXT NULL COMMENT 'Descrição detalhada',
     completed TINYINT(1) NOT NULL DEFAULT 0 COMMENT '0=pendente, 1=concluída',
     reminder DATETIME NULL DEFAULT NULL COMMENT 'Data/hora do lembrete',
+    reminder_delivered_for DATETIME NULL DEFAULT NULL COMMENT 'Lembrete já entregue pelo servidor',
+    due_at DATETIME NULL DEFAULT NULL COMMENT 'Data/hora de vencimento',
+    recurrence VARCHAR(255) NULL DEFAULT NULL COMMENT 'Regra de recorrência (RRULE)',
+    completed_at DATETIME NULL DEFAULT NULL COMMENT 'Data/hora da conclusão',
     created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT 'Data de criação',
     updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT 'Última atualização',
     deleted_at DATETIME NULL DEFAULT NULL COMMENT 'Data de exclusão (soft delete)',
@@ -71,6 +85,8 @@ CREATE TABLE IF NOT EXISTS tasks (
     INDEX idx_reminder (reminder),
     INDEX idx_deleted (deleted_at),
     INDEX idx_updated (updated_at),
+    INDEX idx_completed_at (completed_at),
+    INDEX idx_due_at (due_at),
     
     CONSTRAINT fk_tasks_list 
         FOREIGN KEY (list_id) 
@@ -79,6 +95,19 @@ CREATE TABLE IF NOT EXISTS tasks (
         ON UPDATE CASCADE
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Tarefas';
 
+-- Migração para adicionar data de conclusão (executar se tabela já existe)
+-- ALTER TABLE tasks ADD COLUMN completed_at DATETIME NULL DEFAULT NULL AFTER reminder;
+-- ALTER TABLE tasks ADD INDEX idx_completed_at (completed_at);
+-- UPDATE tasks SET completed_at = updated_at WHERE completed = 1 AND completed_at IS NULL;
+
+-- Migração para vencimento e recorrência (executar se tabela já existe)
+-- ALTER TABLE tasks ADD COLUMN due_at DATETIME NULL DEFAULT NULL AFTER reminder;
+-- ALTER TABLE tasks ADD COLUMN recurrence VARCHAR(255) NULL DEFAULT NULL AFTER due_at;
+-- ALTER TABLE tasks ADD INDEX idx_due_at (due_at);
+
+-- Migração para o envio de lembretes pelo servidor (executar se tabela já existe)
+-- ALTER TABLE tasks ADD COLUMN reminder_delivered_for DATETIME NULL DEFAULT NULL AFTER reminder;
+
 -- =====================================================
 -- TABELA: sync_log (Log de Sincronização)
 -- =====================================================
@@ -102,6 +131,343 @@ CREATE TABLE IF NOT EXISTS sync_log (
         ON UPDATE CASCADE
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Log de sincronização';
 
+-- =====================================================
+-- TABELA: user_settings (Configurações do Usuário)
+-- =====================================================
+CREATE TABLE IF NOT EXISTS user_settings (
+    user_id VARCHAR(36) PRIMARY KEY COMMENT 'ID do usuário',
+    time_zone VARCHAR(64) NOT NULL DEFAULT 'UTC' COMMENT 'Fuso horário IANA',
+    locale VARCHAR(10) NOT NULL DEFAULT 'pt-BR' COMMENT 'Idioma (pt-BR, en)',
+    week_start VARCHAR(10) NOT NULL DEFAULT 'monday' COMMENT 'Primeiro dia da semana',
+    default_reminder_time TIME NOT NULL DEFAULT '09:00:00' COMMENT 'Horário padrão de lembrete',
+    reminder_email TINYINT(1) NOT NULL DEFAULT 0 COMMENT '1=lembretes também por email',
+    digest_email TINYINT(1) NOT NULL DEFAULT 0 COMMENT '1=resumo diário por email',
+    digest_next_at DATETIME NULL DEFAULT NULL COMMENT 'Próximo envio do resumo diário (UTC)',
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT 'Última atualização',
+    
+    INDEX idx_digest_next (digest_next_at),
+    
+    CONSTRAINT fk_settings_user 
+        FOREIGN KEY (user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE 
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Configurações do usuário';
+
+-- Migração para lembretes por email (executar se tabela já existe)
+-- ALTER TABLE user_settings ADD COLUMN reminder_email TINYINT(1) NOT NULL DEFAULT 0 AFTER default_reminder_time;
+
+-- Migração para o resumo diário por email (executar se tabela já existe)
+-- ALTER TABLE user_settings ADD COLUMN digest_email TINYINT(1) NOT NULL DEFAULT 0 AFTER reminder_email;
+-- ALTER TABLE user_settings ADD COLUMN digest_next_at DATETIME NULL DEFAULT NULL AFTER digest_email;
+-- ALTER TABLE user_settings ADD INDEX idx_digest_next (digest_next_at);
+
+-- =====================================================
+-- TABELA: email_changes (Trocas de Email)
+-- =====================================================
+CREATE TABLE IF NOT EXISTS email_changes (
+    id VARCHAR(36) PRIMARY KEY COMMENT 'UUID da troca',
+    user_id VARCHAR(36) NOT NULL COMMENT 'ID do usuário',
+    old_email VARCHAR(255) NOT NULL COMMENT 'Email anterior',
+    new_email VARCHAR(255) NOT NULL COMMENT 'Novo email (só vale após confirmação)',
+    code VARCHAR(6) NOT NULL COMMENT 'Código enviado ao novo email',
+    code_expires_at DATETIME NOT NULL COMMENT 'Expiração do código (UTC)',
+    revert_token VARCHAR(64) NULL COMMENT 'Token do link de reversão enviado ao email anterior',
+    revert_expires_at DATETIME NULL DEFAULT NULL COMMENT 'Expiração do link de reversão (UTC)',
+    confirmed_at DATETIME NULL DEFAULT NULL COMMENT 'Confirmação pelo novo email (UTC)',
+    reverted_at DATETIME NULL DEFAULT NULL COMMENT 'Reversão pelo email anterior (UTC)',
+    created_at DATETIME NOT NULL COMMENT 'Data de criação (UTC)',
+    
+    UNIQUE INDEX idx_revert_token (revert_token),
+    INDEX idx_user_pending (user_id, confirmed_at),
+    
+    CONSTRAINT fk_email_changes_user 
+        FOREIGN KEY (user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE 
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Trocas de email pendentes e confirmadas';
+
+-- =====================================================
+-- TABELA: mail_outbox (Fila de Emails)
+-- =====================================================
+CREATE TABLE IF NOT EXISTS mail_outbox (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    kind VARCHAR(50) NOT NULL COMMENT 'Tipo (verification, password_reset, ...)',
+    recipient VARCHAR(255) NOT NULL COMMENT 'Destinatário',
+    subject VARCHAR(255) NOT NULL COMMENT 'Assunto',
+    html_body MEDIUMTEXT NOT NULL COMMENT 'Corpo HTML (limpo após o envio)',
+    text_body MEDIUMTEXT NULL COMMENT 'Corpo em texto puro (limpo após o envio)',
+    status ENUM('pending', 'sending', 'sent', 'failed') NOT NULL DEFAULT 'pending' COMMENT 'Situação',
+    attempts INT UNSIGNED NOT NULL DEFAULT 0 COMMENT 'Tentativas de envio',
+    last_error TEXT NULL COMMENT 'Último erro de envio',
+    claim_token VARCHAR(36) NULL COMMENT 'Lote do worker que está enviando',
+    claimed_at DATETIME NULL DEFAULT NULL COMMENT 'Início do envio (UTC)',
+    next_attempt_at DATETIME NOT NULL COMMENT 'Próxima tentativa (UTC)',
+    sent_at DATETIME NULL DEFAULT NULL COMMENT 'Data de envio (UTC)',
+    created_at DATETIME NOT NULL COMMENT 'Data de criação (UTC)',
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT 'Última atualização',
+    
+    INDEX idx_status_next (status, next_attempt_at),
+    INDEX idx_claim_token (claim_token)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Fila de emails';
+
+-- Migração para corpo em texto puro (executar se tabela já existe)
+-- ALTER TABLE mail_outbox ADD COLUMN text_body MEDIUMTEXT NULL AFTER html_body;
+
+-- =====================================================
+-- TABELA: api_tokens (Tokens de Acesso Pessoal)
+-- =====================================================
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id VARCHAR(36) PRIMARY KEY COMMENT 'UUID do token',
+    user_id VARCHAR(36) NOT NULL COMMENT 'ID do usuário',
+    name VARCHAR(100) NOT NULL COMMENT 'Nome dado pelo usuário',
+    token_hash CHAR(64) NOT NULL COMMENT 'SHA-256 do token (o token em si não é guardado)',
+    token_prefix VARCHAR(16) NOT NULL COMMENT 'Início do token, para identificação',
+    scopes VARCHAR(255) NOT NULL COMMENT 'Escopos separados por espaço (lists:read, tasks:write, ...)',
+    expires_at DATETIME NULL DEFAULT NULL COMMENT 'Expiração (UTC); NULL = não expira',
+    last_used_at DATETIME NULL DEFAULT NULL COMMENT 'Último uso (UTC)',
+    revoked_at DATETIME NULL DEFAULT NULL COMMENT 'Revogação (UTC)',
+    created_at DATETIME NOT NULL COMMENT 'Data de criação (UTC)',
+    
+    UNIQUE INDEX idx_token_hash (token_hash),
+    INDEX idx_user_revoked (user_id, revoked_at),
+    
+    CONSTRAINT fk_api_tokens_user 
+        FOREIGN KEY (user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE 
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Tokens de acesso pessoal com escopos';
+
+-- =====================================================
+-- TABELA: user_identities (Logins por OpenID Connect)
+-- =====================================================
+CREATE TABLE IF NOT EXISTS user_identities (
+    id VARCHAR(36) PRIMARY KEY COMMENT 'UUID do vínculo',
+    user_id VARCHAR(36) NOT NULL COMMENT 'ID do usuário',
+    provider VARCHAR(50) NOT NULL COMMENT 'Nome do provedor em OIDC_PROVIDERS',
+    subject VARCHAR(255) NOT NULL COMMENT 'Identificador do usuário no provedor (claim sub)',
+    email VARCHAR(255) NOT NULL COMMENT 'Email informado pelo provedor ao vincular',
+    created_at DATETIME NOT NULL COMMENT 'Data do vínculo (UTC)',
+    last_login_at DATETIME NULL DEFAULT NULL COMMENT 'Último login pelo provedor (UTC)',
+    
+    UNIQUE INDEX idx_provider_subject (provider, subject),
+    INDEX idx_user (user_id),
+    
+    CONSTRAINT fk_user_identities_user 
+        FOREIGN KEY (user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE 
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Contas de provedores OIDC vinculadas a usuários';
+
+-- =====================================================
+-- TABELA: oidc_login_states (Logins OIDC em andamento)
+-- =====================================================
+CREATE TABLE IF NOT EXISTS oidc_login_states (
+    state VARCHAR(64) PRIMARY KEY COMMENT 'Parâmetro state (proteção contra CSRF)',
+    provider VARCHAR(50) NOT NULL COMMENT 'Nome do provedor',
+    nonce VARCHAR(64) NOT NULL COMMENT 'Nonce esperado no ID token',
+    pkce_verifier VARCHAR(128) NOT NULL COMMENT 'Verificador PKCE',
+    expires_at DATETIME NOT NULL COMMENT 'Expiração (UTC)',
+    created_at DATETIME NOT NULL COMMENT 'Data de criação (UTC)',
+    
+    INDEX idx_expires (expires_at)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Estados de login OIDC (uso único)';
+
+-- =====================================================
+-- TABELA: webhooks (Webhooks de Saída)
+-- =====================================================
+CREATE TABLE IF NOT EXISTS webhooks (
+    id VARCHAR(36) PRIMARY KEY COMMENT 'UUID do webhook',
+    user_id VARCHAR(36) NOT NULL COMMENT 'ID do usuário',
+    url VARCHAR(2048) NOT NULL COMMENT 'Endereço HTTP(S) que recebe os eventos',
+    secret VARCHAR(100) NOT NULL COMMENT 'Chave da assinatura HMAC (X-Webhook-Signature)',
+    events VARCHAR(500) NOT NULL COMMENT 'Eventos separados por espaço (task.created, list.deleted, ...)',
+    is_active TINYINT(1) NOT NULL DEFAULT 1 COMMENT 'Desativado = entregas pendentes falham',
+    created_at DATETIME NOT NULL COMMENT 'Data de criação (UTC)',
+    updated_at DATETIME NOT NULL COMMENT 'Última atualização (UTC)',
+    
+    INDEX idx_user_active (user_id, is_active),
+    
+    CONSTRAINT fk_webhooks_user 
+        FOREIGN KEY (user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE 
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Webhooks cadastrados pelos usuários';
+
+-- =====================================================
+-- TABELA: webhook_deliveries (Fila e Histórico de Entregas)
+-- =====================================================
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    webhook_id VARCHAR(36) NOT NULL COMMENT 'ID do webhook',
+    event VARCHAR(50) NOT NULL COMMENT 'Evento (task.created, ...)',
+    payload MEDIUMTEXT NOT NULL COMMENT 'Corpo JSON enviado',
+    status ENUM('pending', 'sending', 'delivered', 'failed') NOT NULL DEFAULT 'pending' COMMENT 'Situação',
+    attempts INT UNSIGNED NOT NULL DEFAULT 0 COMMENT 'Tentativas de entrega',
+    response_status SMALLINT UNSIGNED NULL COMMENT 'Status HTTP da última resposta',
+    response_body TEXT NULL COMMENT 'Início do corpo da última resposta',
+    last_error TEXT NULL COMMENT 'Último erro de entrega',
+    claim_token VARCHAR(36) NULL COMMENT 'Lote do worker que está entregando',
+    claimed_at DATETIME NULL DEFAULT NULL COMMENT 'Início da entrega (UTC)',
+    next_attempt_at DATETIME NOT NULL COMMENT 'Próxima tentativa (UTC)',
+    delivered_at DATETIME NULL DEFAULT NULL COMMENT 'Data da entrega (UTC)',
+    created_at DATETIME NOT NULL COMMENT 'Data de criação (UTC)',
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT 'Última atualização',
+    
+    INDEX idx_status_next (status, next_attempt_at),
+    INDEX idx_claim_token (claim_token),
+    INDEX idx_webhook (webhook_id, id),
+    
+    CONSTRAINT fk_webhook_deliveries_webhook 
+        FOREIGN KEY (webhook_id) 
+        REFERENCES webhooks(id) 
+        ON DELETE CASCADE 
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Entregas de webhooks';
+
+-- =====================================================
+-- TABELA: calendar_feeds (Assinatura iCalendar)
+-- =====================================================
+CREATE TABLE IF NOT EXISTS calendar_feeds (
+    user_id VARCHAR(36) PRIMARY KEY COMMENT 'ID do usuário (um link por usuário)',
+    token_hash CHAR(64) NOT NULL COMMENT 'SHA-256 do segredo do link (o segredo em si não é guardado)',
+    created_at DATETIME NOT NULL COMMENT 'Geração do link (UTC)',
+    last_used_at DATETIME NULL DEFAULT NULL COMMENT 'Última leitura por um app de calendário (UTC)',
+    
+    UNIQUE INDEX idx_token_hash (token_hash),
+    
+    CONSTRAINT fk_calendar_feeds_user 
+        FOREIGN KEY (user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE 
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Links secretos de /calendar.ics';
+
+-- =====================================================
+-- TABELA: invitations (Convites por Email)
+-- =====================================================
+CREATE TABLE IF NOT EXISTS invitations (
+    id VARCHAR(36) PRIMARY KEY COMMENT 'ID do convite',
+    user_id VARCHAR(36) NOT NULL COMMENT 'Quem convidou',
+    email VARCHAR(255) NOT NULL COMMENT 'Email convidado',
+    created_at DATETIME NOT NULL COMMENT 'Data de envio (UTC)',
+    
+    INDEX idx_user_created (user_id, created_at),
+    INDEX idx_user_email (user_id, email),
+    
+    CONSTRAINT fk_invitations_user 
+        FOREIGN KEY (user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE 
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Convites enviados por POST /auth/invitations';
+
+-- =====================================================
+-- TABELA: inbound_addresses (Email para Tarefa)
+-- =====================================================
+CREATE TABLE IF NOT EXISTS inbound_addresses (
+    id VARCHAR(36) PRIMARY KEY COMMENT 'ID do endereço',
+    user_id VARCHAR(36) NOT NULL COMMENT 'ID do usuário',
+    list_id VARCHAR(36) NULL DEFAULT NULL COMMENT 'Lista de destino (NULL: a lista mais antiga do usuário)',
+    token CHAR(32) NOT NULL COMMENT 'Parte local do endereço (fica visível ao usuário, por isso não é um hash)',
+    created_at DATETIME NOT NULL COMMENT 'Data de criação (UTC)',
+    last_used_at DATETIME NULL DEFAULT NULL COMMENT 'Último email recebido (UTC)',
+    
+    UNIQUE INDEX idx_token (token),
+    UNIQUE INDEX idx_user_list (user_id, list_id),
+    
+    CONSTRAINT fk_inbound_addresses_user 
+        FOREIGN KEY (user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE 
+        ON UPDATE CASCADE,
+    CONSTRAINT fk_inbound_addresses_list 
+        FOREIGN KEY (list_id) 
+        REFERENCES todo_lists(id) 
+        ON DELETE CASCADE 
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Endereços de email que criam tarefas';
+
+-- =====================================================
+-- TABELA: task_attachments (Anexos de Tarefas)
+-- =====================================================
+CREATE TABLE IF NOT EXISTS task_attachments (
+    id VARCHAR(36) PRIMARY KEY COMMENT 'ID do anexo',
+    task_id VARCHAR(36) NOT NULL COMMENT 'ID da tarefa',
+    filename VARCHAR(255) NOT NULL COMMENT 'Nome do arquivo',
+    content_type VARCHAR(255) NOT NULL COMMENT 'Tipo MIME',
+    size INT UNSIGNED NOT NULL COMMENT 'Tamanho em bytes',
+    data LONGBLOB NOT NULL COMMENT 'Conteúdo do arquivo',
+    created_at DATETIME NOT NULL COMMENT 'Data de criação (UTC)',
+    
+    INDEX idx_task (task_id, created_at),
+    
+    CONSTRAINT fk_task_attachments_task 
+        FOREIGN KEY (task_id) 
+        REFERENCES tasks(id) 
+        ON DELETE CASCADE 
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Anexos de tarefas';
+
+-- =====================================================
+-- TABELA: task_reminders (Lembretes de Tarefas)
+-- =====================================================
+-- Cada tarefa pode ter vários lembretes: num horário fixo (remind_at) ou antes
+-- do prazo (minutes_before_due). Adiar e dispensar ficam registrados aqui para
+-- que todos os dispositivos parem de tocar juntos.
+CREATE TABLE IF NOT EXISTS task_reminders (
+    id VARCHAR(36) PRIMARY KEY COMMENT 'ID do lembrete',
+    task_id VARCHAR(36) NOT NULL COMMENT 'ID da tarefa',
+    remind_at DATETIME NULL DEFAULT NULL COMMENT 'Horário fixo (UTC)',
+    minutes_before_due INT UNSIGNED NULL DEFAULT NULL COMMENT 'Minutos antes do prazo',
+    snoozed_until DATETIME NULL DEFAULT NULL COMMENT 'Adiado até (UTC)',
+    dismissed_at DATETIME NULL DEFAULT NULL COMMENT 'Dispensado em (UTC)',
+    delivered_for DATETIME NULL DEFAULT NULL COMMENT 'Horário já entregue pelo servidor (UTC)',
+    created_at DATETIME NOT NULL COMMENT 'Data de criação (UTC)',
+    updated_at DATETIME NOT NULL COMMENT 'Última atualização (UTC)',
+    
+    INDEX idx_task (task_id),
+    
+    CONSTRAINT fk_task_reminders_task 
+        FOREIGN KEY (task_id) 
+        REFERENCES tasks(id) 
+        ON DELETE CASCADE 
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Lembretes de tarefas';
+
+-- Migração para o envio de lembretes pelo servidor (executar se tabela já existe)
+-- ALTER TABLE task_reminders ADD COLUMN delivered_for DATETIME NULL DEFAULT NULL AFTER dismissed_at;
+
+-- =====================================================
+-- TABELA: push_subscriptions (Inscrições Web Push)
+-- =====================================================
+-- Uma por navegador ou app; o servidor envia os lembretes para o endpoint
+-- com criptografia aes128gcm (RFC 8291) e assinatura VAPID (RFC 8292).
+CREATE TABLE IF NOT EXISTS push_subscriptions (
+    id VARCHAR(36) PRIMARY KEY COMMENT 'ID da inscrição',
+    user_id VARCHAR(36) NOT NULL COMMENT 'ID do usuário',
+    endpoint VARCHAR(2048) NOT NULL COMMENT 'URL do serviço de push',
+    endpoint_hash CHAR(64) NOT NULL COMMENT 'SHA-256 do endpoint',
+    p256dh VARCHAR(100) NOT NULL COMMENT 'Chave pública do navegador (base64url)',
+    auth VARCHAR(50) NOT NULL COMMENT 'Segredo de autenticação (base64url)',
+    user_agent VARCHAR(255) NULL COMMENT 'Navegador ou app',
+    created_at DATETIME NOT NULL COMMENT 'Data de criação (UTC)',
+    last_used_at DATETIME NULL DEFAULT NULL COMMENT 'Último envio aceito (UTC)',
+    
+    UNIQUE INDEX idx_endpoint (endpoint_hash),
+    INDEX idx_user (user_id),
+    
+    CONSTRAINT fk_push_subscriptions_user 
+        FOREIGN KEY (user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE 
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Inscrições Web Push';
+
 -- =====================================================
 -- VIEWS ÚTEIS (Opcional)
 -- =====================================================
@@ -123,13 +489,15 @@ LEFT JOIN tasks t ON t.list_id = l.id AND t.deleted_at IS NULL
 WHERE l.deleted_at IS NULL
 GROUP BY l.id, l.user_id, l.name, l.color, l.created_at, l.updated_at;
 
--- View: Tarefas com lembretes pendentes
+-- View: Lembretes vencidos e ainda não entregues pelo servidor
+-- (o lembrete da própria tarefa e os de task_reminders, já com adiamento)
 CREATE OR REPLACE VIEW vw_pending_reminders AS
 SELECT 
     t.id,
     t.list_id,
     t.title,
     t.description,
+    NULL AS reminder_id,
     t.reminder,
     t.created_at,
     l.user_id,
@@ -140,7 +508,42 @@ WHERE t.deleted_at IS NULL
     AND l.deleted_at IS NULL
     AND t.completed = 0 
     AND t.reminder IS NOT NULL 
-    AND t.reminder <= NOW();
+    AND t.reminder <= UTC_TIMESTAMP()
+    AND NOT (t.reminder_delivered_for <=> t.reminder)
+UNION ALL
+SELECT 
+    p.id,
+    p.list_id,
+    p.title,
+    p.description,
+    p.reminder_id,
+    p.reminder,
+    p.created_at,
+    p.user_id,
+    p.list_name
+FROM (
+    SELECT 
+        t.id,
+        t.list_id,
+        t.title,
+        t.description,
+        r.id AS reminder_id,
+        COALESCE(r.snoozed_until, r.remind_at, t.due_at - INTERVAL r.minutes_before_due MINUTE) AS reminder,
+        r.delivered_for,
+        t.created_at,
+        l.user_id,
+        l.name AS list_name
+    FROM task_reminders r
+    JOIN tasks t ON r.task_id = t.id
+    JOIN todo_lists l ON t.list_id = l.id
+    WHERE t.deleted_at IS NULL 
+        AND l.deleted_at IS NULL
+        AND t.completed = 0 
+        AND r.dismissed_at IS NULL
+) p
+WHERE p.reminder IS NOT NULL 
+    AND p.reminder <= UTC_TIMESTAMP()
+    AND NOT (p.delivered_for <=> p.reminder);
 
 -- =====================================================
 -- DADOS DE EXEMPLO (Opcional - Descomente para testar)